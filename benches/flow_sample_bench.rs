@@ -37,6 +37,9 @@ fn bench_parse_flow_sample(c: &mut Criterion) {
     c.bench_function("parse_flow_sample", |b| {
         b.iter(|| parser.parse_bytes(&data))
     });
+    c.bench_function("parse_flow_sample_ref", |b| {
+        b.iter(|| parser.parse_bytes_ref(&data))
+    });
 }
 
 criterion_group!(benches, bench_parse_flow_sample);
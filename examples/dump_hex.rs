@@ -14,7 +14,7 @@ use flowparser_sflow::flow_records::{
     RawPacketHeader, SampledEthernet, SampledIpv4, SampledIpv6,
 };
 use flowparser_sflow::samples::{
-    CounterSample, ExpandedCounterSample, ExpandedFlowSample, FlowSample,
+    CounterSample, DiscardedPacket, ExpandedCounterSample, ExpandedFlowSample, FlowSample,
 };
 use flowparser_sflow::{SflowDatagram, SflowParser, SflowSample};
 use pcap_parser::traits::PcapReaderIterator;
@@ -352,6 +352,24 @@ fn print_sample(idx: usize, sample: &SflowSample) {
                 print_counter_record(ri, rec);
             }
         }
+        SflowSample::Discard(DiscardedPacket {
+            sequence_number,
+            source_id_type,
+            source_id_index,
+            drops,
+            input,
+            output,
+            reason,
+            records,
+        }) => {
+            println!(
+                "Sample[{idx}]: Discard {{ seq={sequence_number}, src_id_type={source_id_type}, src_id_index={source_id_index}, drops={drops}, input={input}, output={output}, reason={reason:?}, records={} }}",
+                records.len()
+            );
+            for (ri, rec) in records.iter().enumerate() {
+                print_flow_record(ri, rec);
+            }
+        }
         SflowSample::Unknown {
             enterprise,
             format,
@@ -362,6 +380,14 @@ fn print_sample(idx: usize, sample: &SflowSample) {
                 data.len()
             );
         }
+        SflowSample::Custom(c) => {
+            println!(
+                "Sample[{idx}]: Custom {{ enterprise={}, format={}, value={:?} }}",
+                c.enterprise,
+                c.format,
+                c.value()
+            );
+        }
     }
 }
 
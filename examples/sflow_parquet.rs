@@ -0,0 +1,34 @@
+use flowparser_sflow::arrow::ParquetWriter;
+use flowparser_sflow::pcap::PcapSource;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: {} <pcap-file> <output-dir>", args[0]);
+        std::process::exit(1);
+    }
+
+    let source = PcapSource::open(&args[1]).expect("Failed to open pcap file");
+    let mut writer = ParquetWriter::builder()
+        .build(&args[2])
+        .expect("Failed to create output directory");
+    let mut rows = 0;
+
+    for item in source {
+        let (timestamp, _src, _dst, datagram) = match item {
+            Ok(item) => item,
+            Err(e) => {
+                eprintln!("Error reading pcap: {:?}", e);
+                break;
+            }
+        };
+        rows += writer
+            .write_datagram(&datagram, timestamp)
+            .expect("Failed to write Parquet");
+    }
+
+    for path in writer.close().expect("Failed to close Parquet files") {
+        println!("Wrote {}", path.display());
+    }
+    println!("Converted {} rows", rows);
+}
@@ -0,0 +1,380 @@
+//! Agent restart detection and boot time estimation.
+//!
+//! Every datagram carries the agent's uptime in milliseconds and a per
+//! sub-agent sequence number. [`AgentRegistry`] follows both and reports an
+//! [`AgentEvent`] when an agent reboots, when its 32-bit uptime wraps after
+//! roughly 49.7 days, or when a single sub-agent restarts its sequence without
+//! the agent rebooting. Counter baselines taken before a restart are no
+//! longer comparable with later values.
+//!
+//! ```
+//! use flowparser_sflow::agent::{AgentEvent, AgentRegistry};
+//! use flowparser_sflow::{AddressType, SflowDatagram};
+//! use std::net::Ipv4Addr;
+//! use std::time::{Duration, SystemTime};
+//!
+//! let mut registry = AgentRegistry::default();
+//! let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+//! let mut dg = SflowDatagram {
+//!     version: 5,
+//!     agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+//!     sub_agent_id: 0,
+//!     sequence_number: 500,
+//!     uptime: 3_600_000,
+//!     samples: vec![],
+//! };
+//! registry.observe(&dg, now);
+//! assert_eq!(
+//!     registry.boot_time(&dg.agent_address),
+//!     Some(now - Duration::from_secs(3600))
+//! );
+//!
+//! // One minute later the agent reports a lower uptime and sequence: it rebooted.
+//! dg.sequence_number = 1;
+//! dg.uptime = 20_000;
+//! let events = registry.observe(&dg, now + Duration::from_secs(60));
+//! assert!(matches!(events[0], AgentEvent::AgentRestarted { .. }));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::datagram::{AddressType, SflowDatagram};
+use crate::sequence::{AgentKey, SequenceStatus, SequenceTracker};
+
+const UPTIME_WRAP_MS: u64 = 1 << 32;
+
+/// A change in agent state detected by [`AgentRegistry::observe`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AgentEvent {
+    /// First datagram from this agent.
+    AgentDiscovered { agent_address: AddressType },
+    /// First datagram from this sub-agent.
+    SubAgentDiscovered { agent: AgentKey },
+    /// The agent rebooted: its uptime went backwards, or advanced less than
+    /// wall-clock time since the previous datagram. All of its sub-agents and
+    /// counters start over.
+    AgentRestarted {
+        agent_address: AddressType,
+        previous_uptime: u32,
+        uptime: u32,
+    },
+    /// The agent's uptime passed 2^32 ms and wrapped to a small value. The
+    /// agent did not reboot.
+    UptimeWrapped { agent_address: AddressType },
+    /// A sub-agent's sequence number started over while the agent's uptime
+    /// kept increasing, e.g. after a line card or process restart.
+    SubAgentReset {
+        agent: AgentKey,
+        previous_sequence: u32,
+        sequence_number: u32,
+    },
+}
+
+/// State kept for one agent address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentState {
+    /// Estimated wall-clock time of the last boot.
+    pub boot_time: SystemTime,
+    /// Uptime from the most recent datagram.
+    pub last_uptime: u32,
+    /// Receive time of the most recent datagram.
+    pub last_seen: SystemTime,
+    /// Number of uptime wraparounds since the last boot.
+    pub wraps: u32,
+    /// Number of reboots observed.
+    pub restarts: u32,
+}
+
+impl AgentState {
+    /// Time since boot, accounting for uptime wraparounds.
+    pub fn uptime(&self) -> Duration {
+        Duration::from_millis(
+            u64::from(self.wraps) * UPTIME_WRAP_MS + u64::from(self.last_uptime),
+        )
+    }
+}
+
+/// Agent restarts already accounted for in a sub-agent's sequence.
+#[derive(Debug, Clone)]
+struct SubAgentState {
+    restarts: u32,
+}
+
+/// Stateful registry of sFlow agents.
+///
+/// Uptime tracking is per agent address; sequence tracking is per
+/// `(agent_address, sub_agent_id)`. Receive times are supplied by the caller
+/// so that captures can be replayed with their original timestamps.
+/// Sub-agent sequences are classified like [`SequenceTracker`] datagram
+/// streams, and a [`SequenceStatus::Reset`] without a reboot is reported as
+/// [`AgentEvent::SubAgentReset`].
+#[derive(Debug, Clone)]
+pub struct AgentRegistry {
+    tolerance: Duration,
+    reorder_window: u32,
+    agents: HashMap<AddressType, AgentState>,
+    sub_agents: HashMap<AgentKey, SubAgentState>,
+    sequences: SequenceTracker,
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        AgentRegistry::builder().build()
+    }
+}
+
+impl AgentRegistry {
+    /// Create a builder for configuring the registry.
+    pub fn builder() -> AgentRegistryBuilder {
+        AgentRegistryBuilder {
+            tolerance: Duration::from_secs(10),
+            reorder_window: 64,
+        }
+    }
+
+    /// Record a datagram received at `now` and return any detected events.
+    pub fn observe(&mut self, datagram: &SflowDatagram, now: SystemTime) -> Vec<AgentEvent> {
+        self.observe_header(
+            &datagram.agent_address,
+            datagram.sub_agent_id,
+            datagram.sequence_number,
+            datagram.uptime,
+            now,
+        )
+    }
+
+    /// Record datagram header fields received at `now`, e.g. from a
+    /// [`LazyDatagram`](crate::LazyDatagram) or [`SflowDatagramRef`](crate::SflowDatagramRef).
+    pub fn observe_header(
+        &mut self,
+        agent_address: &AddressType,
+        sub_agent_id: u32,
+        sequence_number: u32,
+        uptime: u32,
+        now: SystemTime,
+    ) -> Vec<AgentEvent> {
+        let mut events = Vec::new();
+        let key = AgentKey {
+            agent_address: agent_address.clone(),
+            sub_agent_id,
+        };
+
+        let last_sequence = self.sequences.datagram_stats(&key).map(|s| s.last);
+        let restarts = match self.agents.get_mut(agent_address) {
+            None => {
+                self.agents.insert(
+                    agent_address.clone(),
+                    AgentState {
+                        boot_time: boot_estimate(now, 0, uptime),
+                        last_uptime: uptime,
+                        last_seen: now,
+                        wraps: 0,
+                        restarts: 0,
+                    },
+                );
+                events.push(AgentEvent::AgentDiscovered {
+                    agent_address: agent_address.clone(),
+                });
+                0
+            }
+            Some(state) => {
+                let late = last_sequence.is_some_and(|last| {
+                    last.wrapping_sub(sequence_number) <= self.reorder_window
+                });
+                if let Some(event) =
+                    update_uptime(state, agent_address, uptime, now, late, self.tolerance)
+                {
+                    events.push(event);
+                }
+                state.restarts
+            }
+        };
+
+        match self.sub_agents.get_mut(&key) {
+            None => {
+                self.sub_agents
+                    .insert(key.clone(), SubAgentState { restarts });
+                self.sequences
+                    .observe_datagram(key.clone(), sequence_number);
+                events.push(AgentEvent::SubAgentDiscovered { agent: key });
+            }
+            Some(sub) if sub.restarts != restarts => {
+                // The agent rebooted; the sequence restarting is expected.
+                sub.restarts = restarts;
+                self.sequences.remove_agent(&key);
+                self.sequences.observe_datagram(key, sequence_number);
+            }
+            Some(_) => {
+                let status = self
+                    .sequences
+                    .observe_datagram(key.clone(), sequence_number);
+                if let (SequenceStatus::Reset, Some(previous_sequence)) =
+                    (status, last_sequence)
+                {
+                    events.push(AgentEvent::SubAgentReset {
+                        agent: key,
+                        previous_sequence,
+                        sequence_number,
+                    });
+                }
+            }
+        }
+
+        events
+    }
+
+    /// State of an agent, if it has been seen.
+    pub fn agent(&self, agent_address: &AddressType) -> Option<&AgentState> {
+        self.agents.get(agent_address)
+    }
+
+    /// Iterate over all known agents.
+    pub fn agents(&self) -> impl Iterator<Item = (&AddressType, &AgentState)> {
+        self.agents.iter()
+    }
+
+    /// Estimated wall-clock boot time of an agent.
+    pub fn boot_time(&self, agent_address: &AddressType) -> Option<SystemTime> {
+        self.agents.get(agent_address).map(|s| s.boot_time)
+    }
+
+    /// Convert an agent uptime from the current boot into wall-clock time.
+    ///
+    /// Uptimes are assumed to fall in the current wraparound period.
+    pub fn timestamp(&self, agent_address: &AddressType, uptime: u32) -> Option<SystemTime> {
+        let state = self.agents.get(agent_address)?;
+        let ms = u64::from(state.wraps) * UPTIME_WRAP_MS + u64::from(uptime);
+        Some(state.boot_time + Duration::from_millis(ms))
+    }
+
+    /// Forget an agent and all of its sub-agents.
+    pub fn remove_agent(&mut self, agent_address: &AddressType) {
+        self.agents.remove(agent_address);
+        let sequences = &mut self.sequences;
+        self.sub_agents.retain(|key, _| {
+            let keep = key.agent_address != *agent_address;
+            if !keep {
+                sequences.remove_agent(key);
+            }
+            keep
+        });
+    }
+
+    /// Forget agents not heard from since `cutoff`.
+    pub fn expire(&mut self, cutoff: SystemTime) {
+        let stale: Vec<AddressType> = self
+            .agents
+            .iter()
+            .filter(|(_, state)| state.last_seen < cutoff)
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in stale {
+            self.remove_agent(&address);
+        }
+    }
+}
+
+/// Builder for configuring an [`AgentRegistry`].
+#[derive(Debug, Clone)]
+pub struct AgentRegistryBuilder {
+    tolerance: Duration,
+    reorder_window: u32,
+}
+
+impl AgentRegistryBuilder {
+    /// Allowed disagreement between agent uptime and receive time, covering
+    /// network delay and clock drift (default 10 seconds).
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Largest backward sequence step treated as a late datagram rather than
+    /// a reset (default 64). A step back to a number the sub-agent never sent
+    /// is a reset, as is a repeated number followed by the next one.
+    pub fn with_reorder_window(mut self, reorder_window: u32) -> Self {
+        self.reorder_window = reorder_window;
+        self
+    }
+
+    /// Build the configured [`AgentRegistry`].
+    pub fn build(self) -> AgentRegistry {
+        AgentRegistry {
+            tolerance: self.tolerance,
+            reorder_window: self.reorder_window,
+            agents: HashMap::new(),
+            sub_agents: HashMap::new(),
+            // Any forward step is a gap, however large
+            sequences: SequenceTracker::builder()
+                .with_max_gap(u32::MAX / 2)
+                .with_reorder_window(self.reorder_window)
+                .build(),
+        }
+    }
+}
+
+fn boot_estimate(now: SystemTime, wraps: u32, uptime: u32) -> SystemTime {
+    let ms = u64::from(wraps) * UPTIME_WRAP_MS + u64::from(uptime);
+    now.checked_sub(Duration::from_millis(ms))
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn update_uptime(
+    state: &mut AgentState,
+    agent_address: &AddressType,
+    uptime: u32,
+    now: SystemTime,
+    late: bool,
+    tolerance: Duration,
+) -> Option<AgentEvent> {
+    let elapsed_ms = now
+        .duration_since(state.last_seen)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    // Allow for network delay plus 0.1% clock drift between agent and collector.
+    let slack_ms = tolerance.as_millis() as u64 + elapsed_ms / 1000;
+    // Forward distance from the last uptime, through 2^32 if it wrapped.
+    let advanced = u64::from(uptime.wrapping_sub(state.last_uptime));
+    let wrapped = uptime < state.last_uptime;
+
+    if wrapped && late && u64::from(state.last_uptime - uptime) <= slack_ms {
+        // A delayed datagram; nothing changed.
+        return None;
+    }
+
+    let restarted = if wrapped {
+        advanced > elapsed_ms + slack_ms
+    } else {
+        // Uptime fell behind wall-clock time: the agent rebooted while silent.
+        elapsed_ms > advanced + slack_ms
+    };
+
+    let event = if restarted {
+        state.wraps = 0;
+        state.restarts += 1;
+        state.boot_time = boot_estimate(now, 0, uptime);
+        Some(AgentEvent::AgentRestarted {
+            agent_address: agent_address.clone(),
+            previous_uptime: state.last_uptime,
+            uptime,
+        })
+    } else if wrapped {
+        state.wraps += 1;
+        Some(AgentEvent::UptimeWrapped {
+            agent_address: agent_address.clone(),
+        })
+    } else {
+        None
+    };
+
+    // Network delay only makes the estimate later, so keep the earliest.
+    let estimate = boot_estimate(now, state.wraps, uptime);
+    if estimate < state.boot_time {
+        state.boot_time = estimate;
+    }
+    state.last_uptime = uptime;
+    state.last_seen = now;
+    event
+}
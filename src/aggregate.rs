@@ -0,0 +1,381 @@
+//! Time-windowed flow aggregation.
+//!
+//! [`FlowAggregator`] weighs every flow sample with a [`SamplingEstimator`],
+//! extracts a [`FlowKey`] reduced to the configured [`FlowField`]s and sums
+//! the scaled packets and bytes per key. Windows are aligned to multiples of
+//! the window length since the Unix epoch; when a receive time falls past the
+//! end of the open window, its flows are emitted as [`AggregatedFlow`]s.
+//!
+//! Addresses, ports and protocol come from sampled IPv4/IPv6 records or, with
+//! the `packet` feature, from the decoded raw packet header. VLANs come from
+//! extended switch records and AS numbers from extended gateway records.
+//!
+//! ```
+//! use flowparser_sflow::SflowParser;
+//! use flowparser_sflow::aggregate::{FlowAggregator, FlowField};
+//! use std::time::{Duration, SystemTime};
+//!
+//! let parser = SflowParser::default();
+//! let mut aggregator = FlowAggregator::builder()
+//!     .with_window(Duration::from_secs(60))
+//!     .with_fields(&[FlowField::SrcAs, FlowField::DstAs])
+//!     .build();
+//!
+//! let result = parser.parse_bytes(&[/* sflow datagram bytes */]);
+//! for datagram in &result.datagrams {
+//!     for flow in aggregator.observe(datagram, SystemTime::now()) {
+//!         println!("{:?} -> {:?}: {:.0} bytes", flow.key.src_as, flow.key.dst_as, flow.estimate.bytes);
+//!     }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+
+use crate::datagram::{AddressType, SflowDatagram};
+use crate::estimate::{SamplingEstimator, TrafficEstimate};
+use crate::flow_records::FlowRecord;
+use crate::samples::{Interface, NormalizedFlowSample};
+use crate::sequence::{AgentKey, SampleKey, SampleKind};
+
+/// A field that can be part of the aggregation key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FlowField {
+    AgentAddress,
+    InputIfIndex,
+    OutputIfIndex,
+    SrcAddr,
+    DstAddr,
+    Protocol,
+    SrcPort,
+    DstPort,
+    Tos,
+    SrcVlan,
+    DstVlan,
+    SrcAs,
+    DstAs,
+}
+
+impl FlowField {
+    /// Source and destination address, protocol and ports.
+    pub const FIVE_TUPLE: [FlowField; 5] = [
+        FlowField::SrcAddr,
+        FlowField::DstAddr,
+        FlowField::Protocol,
+        FlowField::SrcPort,
+        FlowField::DstPort,
+    ];
+}
+
+/// Aggregation key. Fields that are not configured, or not present in a
+/// sample, are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FlowKey {
+    pub agent_address: Option<AddressType>,
+    /// Input ifIndex, for samples with a single input interface.
+    pub input: Option<u32>,
+    /// Output ifIndex, for samples with a single output interface.
+    pub output: Option<u32>,
+    pub src_addr: Option<IpAddr>,
+    pub dst_addr: Option<IpAddr>,
+    pub protocol: Option<u8>,
+    pub src_port: Option<u16>,
+    pub dst_port: Option<u16>,
+    /// IPv4 type of service or IPv6 traffic class.
+    pub tos: Option<u8>,
+    pub src_vlan: Option<u32>,
+    pub dst_vlan: Option<u32>,
+    pub src_as: Option<u32>,
+    pub dst_as: Option<u32>,
+}
+
+impl FlowKey {
+    /// Extract every available field from a flow sample.
+    pub fn from_sample(agent_address: &AddressType, sample: &NormalizedFlowSample<'_>) -> Self {
+        let mut key = FlowKey {
+            agent_address: Some(agent_address.clone()),
+            input: single(sample.input),
+            output: single(sample.output),
+            ..FlowKey::default()
+        };
+        for record in sample.records {
+            match record {
+                FlowRecord::SampledIpv4(r) => {
+                    key.src_addr = Some(IpAddr::V4(r.src_ip));
+                    key.dst_addr = Some(IpAddr::V4(r.dst_ip));
+                    key.set_transport(r.protocol, r.src_port, r.dst_port, r.tos);
+                }
+                FlowRecord::SampledIpv6(r) => {
+                    key.src_addr = Some(IpAddr::V6(r.src_ip));
+                    key.dst_addr = Some(IpAddr::V6(r.dst_ip));
+                    key.set_transport(r.protocol, r.src_port, r.dst_port, r.priority);
+                }
+                FlowRecord::ExtendedSwitch(r) => {
+                    key.src_vlan = Some(r.src_vlan);
+                    key.dst_vlan = Some(r.dst_vlan);
+                }
+                FlowRecord::ExtendedGateway(r) => {
+                    key.src_as = Some(r.src_as);
+                    key.dst_as = Some(r.dst_as());
+                }
+                _ => {}
+            }
+        }
+        #[cfg(feature = "packet")]
+        if key.src_addr.is_none() {
+            key.set_from_header(sample.records);
+        }
+        key
+    }
+
+    /// Keep only the given fields, clearing all others.
+    pub fn select(&self, fields: &[FlowField]) -> Self {
+        let has = |field| fields.contains(&field);
+        FlowKey {
+            agent_address: self
+                .agent_address
+                .clone()
+                .filter(|_| has(FlowField::AgentAddress)),
+            input: self.input.filter(|_| has(FlowField::InputIfIndex)),
+            output: self.output.filter(|_| has(FlowField::OutputIfIndex)),
+            src_addr: self.src_addr.filter(|_| has(FlowField::SrcAddr)),
+            dst_addr: self.dst_addr.filter(|_| has(FlowField::DstAddr)),
+            protocol: self.protocol.filter(|_| has(FlowField::Protocol)),
+            src_port: self.src_port.filter(|_| has(FlowField::SrcPort)),
+            dst_port: self.dst_port.filter(|_| has(FlowField::DstPort)),
+            tos: self.tos.filter(|_| has(FlowField::Tos)),
+            src_vlan: self.src_vlan.filter(|_| has(FlowField::SrcVlan)),
+            dst_vlan: self.dst_vlan.filter(|_| has(FlowField::DstVlan)),
+            src_as: self.src_as.filter(|_| has(FlowField::SrcAs)),
+            dst_as: self.dst_as.filter(|_| has(FlowField::DstAs)),
+        }
+    }
+
+    fn set_transport(&mut self, protocol: u32, src_port: u32, dst_port: u32, tos: u32) {
+        self.protocol = u8::try_from(protocol).ok();
+        // Ports are only meaningful for TCP, UDP and SCTP.
+        if matches!(protocol, 6 | 17 | 132) {
+            self.src_port = u16::try_from(src_port).ok();
+            self.dst_port = u16::try_from(dst_port).ok();
+        }
+        self.tos = u8::try_from(tos).ok();
+    }
+
+    #[cfg(feature = "packet")]
+    fn set_from_header(&mut self, records: &[FlowRecord]) {
+        let Some(decoded) = records.iter().find_map(|record| match record {
+            FlowRecord::RawPacketHeader(r) => Some(r.decode()),
+            _ => None,
+        }) else {
+            return;
+        };
+        self.src_addr = decoded.src_ip;
+        self.dst_addr = decoded.dst_ip;
+        self.protocol = decoded.ip_protocol;
+        self.src_port = decoded.src_port;
+        self.dst_port = decoded.dst_port;
+        self.tos = decoded.tos;
+    }
+}
+
+fn single(interface: Interface) -> Option<u32> {
+    match interface {
+        Interface::Single(if_index) => Some(if_index),
+        _ => None,
+    }
+}
+
+/// Traffic of one flow key over one window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregatedFlow {
+    pub window_start: SystemTime,
+    pub window_end: SystemTime,
+    pub key: FlowKey,
+    pub estimate: TrafficEstimate,
+    /// Traffic of new keys beyond the configured maximum number of flows,
+    /// reported under an empty `key`.
+    pub overflow: bool,
+}
+
+/// Stateful aggregator of flow samples into windowed flows.
+#[derive(Debug, Clone)]
+pub struct FlowAggregator {
+    fields: Vec<FlowField>,
+    window: Duration,
+    max_flows: Option<usize>,
+    estimator: SamplingEstimator,
+    window_start: Option<SystemTime>,
+    flows: HashMap<FlowKey, TrafficEstimate>,
+    overflow: Option<TrafficEstimate>,
+}
+
+impl Default for FlowAggregator {
+    fn default() -> Self {
+        FlowAggregator::builder().build()
+    }
+}
+
+impl FlowAggregator {
+    /// Create a builder for configuring the aggregator.
+    pub fn builder() -> FlowAggregatorBuilder {
+        FlowAggregatorBuilder {
+            fields: FlowField::FIVE_TUPLE.to_vec(),
+            window: Duration::from_secs(60),
+            max_flows: None,
+            estimator: SamplingEstimator::default(),
+        }
+    }
+
+    /// Add the flow samples of a datagram received at `now`. Returns the
+    /// flows of the previous window if `now` is past its end.
+    pub fn observe(
+        &mut self,
+        datagram: &SflowDatagram,
+        now: SystemTime,
+    ) -> Vec<AggregatedFlow> {
+        let closed = self.close_expired(now);
+        if self.window_start.is_none() {
+            self.window_start = Some(self.align(now));
+        }
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        for sample in datagram.samples.iter().filter_map(|s| s.normalized_flow()) {
+            let sample_key = SampleKey {
+                agent: agent.clone(),
+                data_source: sample.data_source,
+                kind: SampleKind::Flow,
+            };
+            let Some(weight) = self.estimator.observe_sample(
+                sample_key,
+                sample.sequence_number,
+                sample.sampling_rate,
+                sample.sample_pool,
+                sample.drops,
+                sample.frame_length(),
+            ) else {
+                continue;
+            };
+            let key =
+                FlowKey::from_sample(&datagram.agent_address, &sample).select(&self.fields);
+            let estimate = if self
+                .max_flows
+                .is_some_and(|max| self.flows.len() >= max && !self.flows.contains_key(&key))
+            {
+                self.overflow.get_or_insert_default()
+            } else {
+                self.flows.entry(key).or_default()
+            };
+            estimate.add(weight.weight, weight.frame_length);
+        }
+        closed
+    }
+
+    /// Close the open window if `now` is past its end, returning its flows.
+    /// Call this periodically so windows close when no datagrams arrive.
+    pub fn close_expired(&mut self, now: SystemTime) -> Vec<AggregatedFlow> {
+        match self.window_start {
+            Some(start) if now >= start + self.window => self.flush(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Close the open window regardless of time, returning its flows
+    /// ordered by estimated bytes, largest first.
+    pub fn flush(&mut self) -> Vec<AggregatedFlow> {
+        let Some(window_start) = self.window_start.take() else {
+            return Vec::new();
+        };
+        let window_end = window_start + self.window;
+        let mut flows: Vec<AggregatedFlow> = self
+            .flows
+            .drain()
+            .map(|(key, estimate)| (key, estimate, false))
+            .chain(
+                self.overflow
+                    .take()
+                    .map(|estimate| (FlowKey::default(), estimate, true)),
+            )
+            .map(|(key, estimate, overflow)| AggregatedFlow {
+                window_start,
+                window_end,
+                key,
+                estimate,
+                overflow,
+            })
+            .collect();
+        flows.sort_by(|a, b| b.estimate.bytes.total_cmp(&a.estimate.bytes));
+        flows
+    }
+
+    /// Number of distinct keys in the open window, not counting overflow.
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Whether the open window has no flows.
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty() && self.overflow.is_none()
+    }
+
+    fn align(&self, now: SystemTime) -> SystemTime {
+        let window = self.window.as_nanos().max(1);
+        let since_epoch = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let offset = since_epoch.as_nanos() % window;
+        now - Duration::from_nanos(offset as u64)
+    }
+}
+
+/// Builder for configuring a [`FlowAggregator`].
+#[derive(Debug, Clone)]
+pub struct FlowAggregatorBuilder {
+    fields: Vec<FlowField>,
+    window: Duration,
+    max_flows: Option<usize>,
+    estimator: SamplingEstimator,
+}
+
+impl FlowAggregatorBuilder {
+    /// Fields making up the aggregation key (default [`FlowField::FIVE_TUPLE`]).
+    pub fn with_fields(mut self, fields: &[FlowField]) -> Self {
+        self.fields = fields.to_vec();
+        self
+    }
+
+    /// Window length (default 60 seconds).
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Maximum number of keys per window. Samples with new keys beyond the
+    /// limit are counted in one extra [`AggregatedFlow`] marked `overflow`.
+    pub fn with_max_flows(mut self, max_flows: usize) -> Self {
+        self.max_flows = Some(max_flows);
+        self
+    }
+
+    /// Estimator used to scale samples (default [`SamplingEstimator::default`]).
+    pub fn with_estimator(mut self, estimator: SamplingEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Build the configured [`FlowAggregator`].
+    pub fn build(self) -> FlowAggregator {
+        FlowAggregator {
+            fields: self.fields,
+            window: self.window,
+            max_flows: self.max_flows,
+            estimator: self.estimator,
+            window_start: None,
+            flows: HashMap::new(),
+            overflow: None,
+        }
+    }
+}
@@ -0,0 +1,635 @@
+//! Apache Arrow record batches and Parquet files.
+//!
+//! Enabled with the `arrow` cargo feature. [`FlowBatchBuilder`] flattens
+//! flow samples into rows of [`flow_schema`]: the datagram and sample header
+//! with the keys of the [`jsonl`](crate::jsonl) schema, plus the fields most
+//! analyses need from the sample's records, unified across record types:
+//!
+//! | Columns | Source |
+//! |---------|--------|
+//! | `frame_length`, `header_protocol` | Raw packet header, or sampled Ethernet/IPv4/IPv6 lengths |
+//! | `src_mac`, `dst_mac`, `eth_type` | Sampled Ethernet or the raw header |
+//! | `in_vlan`, `out_vlan` | Extended switch or the raw header's 802.1Q tag |
+//! | `src_ip`, `dst_ip`, `ip_protocol`, `tos`, `ttl`, `src_port`, `dst_port`, `tcp_flags`, `ip_size` | Sampled IPv4/IPv6 or the raw header |
+//! | `next_hop`, `src_mask_len`, `dst_mask_len` | Extended router |
+//! | `src_as`, `dst_as`, `src_peer_as` | Extended gateway |
+//!
+//! Raw packet headers are only decoded with the `packet` feature. Columns
+//! that a sample does not describe are null.
+//!
+//! [`CounterBatchBuilder`] keeps one table per [`CounterRecord`] type, named
+//! by its snake_case variant name (`generic_interface`, `host_cpu`, ...), or
+//! `custom_<enterprise>_<format>` for records decoded by a registered
+//! decoder.
+//! Each row has the datagram and counter sample header followed by the
+//! record's fields under their own names (see [`counter_schema`]): integers
+//! are `UInt64` (`Int64` for the signed temperature fields, negative values
+//! and all integers of custom records, whose signedness is not declared),
+//! floats `Float64`, and addresses, enums and strings `Utf8`, normalised as
+//! in the JSON Lines schema. Lists are stored as JSON text. Unknown records
+//! are skipped. A later value that does not fit its column's type is written
+//! as null and counted by [`CounterBatchBuilder::out_of_range`].
+//!
+//! [`ParquetWriter`] batches both and writes `flows.parquet` and one
+//! `<record_type>.parquet` per counter record type into a directory, ready
+//! for DuckDB (`SELECT * FROM 'out/flows.parquet'`), Polars or pandas. A
+//! Parquet file is only readable once its footer is written, so the writer
+//! must be [closed](ParquetWriter::close).
+//!
+//! ```
+//! use flowparser_sflow::arrow::ParquetWriter;
+//! use flowparser_sflow::SflowParser;
+//! use std::time::SystemTime;
+//!
+//! let dir = std::env::temp_dir().join("sflow-parquet-doc");
+//! let parser = SflowParser::default();
+//! let mut writer = ParquetWriter::builder().with_batch_size(8192).build(&dir).unwrap();
+//! for datagram in &parser.parse_bytes(&[/* sflow datagram bytes */]).datagrams {
+//!     writer.write_datagram(datagram, SystemTime::now()).unwrap();
+//! }
+//! let files = writer.close().unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::SystemTime;
+
+use arrow_array::builder::{
+    Float64Builder, Int64Builder, PrimitiveBuilder, StringBuilder, TimestampMillisecondBuilder,
+    UInt32Builder, UInt64Builder,
+};
+use arrow_array::{ArrayRef, ArrowPrimitiveType, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use serde_json::{Map, Value};
+
+use crate::SflowDatagram;
+use crate::counter_records::CounterRecord;
+use crate::flow_records::FlowRecord;
+use crate::jsonl::{counter_variant, data_source, enum_name, interface, normalize};
+use crate::sflowtool::FlowLine;
+
+pub use parquet::basic::Compression;
+
+/// Counter record fields that are signed integers.
+const SIGNED_FIELDS: &[(&str, &str)] = &[
+    ("sfp", "module_temperature"),
+    ("temperature", "minimum"),
+    ("temperature", "maximum"),
+];
+
+/// Columns shared by flow and counter tables.
+const HEADER_COLUMNS: &[(&str, DataType)] = &[
+    ("agent", DataType::Utf8),
+    ("sub_agent_id", DataType::UInt32),
+    ("datagram_sequence", DataType::UInt32),
+    ("uptime_ms", DataType::UInt32),
+    ("sample_sequence", DataType::UInt32),
+    ("source_id", DataType::Utf8),
+    ("source_id_type", DataType::UInt32),
+    ("source_id_index", DataType::UInt32),
+];
+
+/// Flow columns after the header columns.
+const FLOW_COLUMNS: &[(&str, DataType)] = &[
+    ("sampling_rate", DataType::UInt32),
+    ("sample_pool", DataType::UInt32),
+    ("drops", DataType::UInt32),
+    ("input_type", DataType::Utf8),
+    ("input", DataType::UInt32),
+    ("output_type", DataType::Utf8),
+    ("output", DataType::UInt32),
+    ("output_count", DataType::UInt32),
+    ("drop_reason", DataType::Utf8),
+    ("frame_length", DataType::UInt32),
+    ("header_protocol", DataType::Utf8),
+    ("src_mac", DataType::Utf8),
+    ("dst_mac", DataType::Utf8),
+    ("eth_type", DataType::UInt32),
+    ("in_vlan", DataType::UInt32),
+    ("out_vlan", DataType::UInt32),
+    ("src_ip", DataType::Utf8),
+    ("dst_ip", DataType::Utf8),
+    ("ip_protocol", DataType::UInt32),
+    ("tos", DataType::UInt32),
+    ("ttl", DataType::UInt32),
+    ("src_port", DataType::UInt32),
+    ("dst_port", DataType::UInt32),
+    ("tcp_flags", DataType::UInt32),
+    ("ip_size", DataType::UInt32),
+    ("next_hop", DataType::Utf8),
+    ("src_mask_len", DataType::UInt32),
+    ("dst_mask_len", DataType::UInt32),
+    ("src_as", DataType::UInt32),
+    ("dst_as", DataType::UInt32),
+    ("src_peer_as", DataType::UInt32),
+];
+
+static FLOW_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    schema(
+        HEADER_COLUMNS
+            .iter()
+            .chain(FLOW_COLUMNS)
+            .map(|(name, data_type)| (name.to_string(), data_type.clone())),
+    )
+});
+
+/// Schema of the rows built by [`FlowBatchBuilder`].
+pub fn flow_schema() -> SchemaRef {
+    Arc::clone(&FLOW_SCHEMA)
+}
+
+/// Schema of the table holding records of the same type as `record`.
+pub fn counter_schema(record: &CounterRecord) -> SchemaRef {
+    let custom = matches!(record, CounterRecord::Custom(_));
+    let (record_type, fields) = counter_variant(record);
+    let Value::Object(fields) = fields else {
+        return schema(header_columns());
+    };
+    let columns = fields.iter().map(|(name, value)| {
+        let data_type = match value {
+            Value::Number(n) if n.is_f64() => DataType::Float64,
+            Value::Number(n)
+                if custom
+                    || n.as_u64().is_none()
+                    || SIGNED_FIELDS.contains(&(record_type.as_str(), name)) =>
+            {
+                DataType::Int64
+            }
+            Value::Number(_) => DataType::UInt64,
+            _ => DataType::Utf8,
+        };
+        (name.clone(), data_type)
+    });
+    schema(header_columns().chain(columns))
+}
+
+/// Accumulates flow samples as rows of [`flow_schema`].
+#[derive(Debug)]
+pub struct FlowBatchBuilder {
+    table: Table,
+}
+
+impl Default for FlowBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowBatchBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        FlowBatchBuilder {
+            table: Table::new(flow_schema()),
+        }
+    }
+
+    /// Append a row per flow sample of `datagram`, received at `received`.
+    /// Returns the number of rows appended.
+    pub fn append_datagram(&mut self, datagram: &SflowDatagram, received: SystemTime) -> usize {
+        let header = header(datagram, received);
+        let mut rows = 0;
+        for sample in &datagram.samples {
+            let Some(flow) = sample.normalized_flow() else {
+                continue;
+            };
+            let mut row = header.clone();
+            row.insert("sample_sequence".into(), flow.sequence_number.into());
+            data_source(&mut row, flow.data_source);
+            row.insert("sampling_rate".into(), flow.sampling_rate.into());
+            row.insert("sample_pool".into(), flow.sample_pool.into());
+            row.insert("drops".into(), flow.drops.into());
+            interface(&mut row, "input", flow.input);
+            interface(&mut row, "output", flow.output);
+            if let Some(length) = flow.frame_length() {
+                row.insert("frame_length".into(), length.into());
+            }
+            packet_fields(&mut row, &FlowLine::from_sample(&flow));
+            for record in flow.records {
+                record_fields(&mut row, record);
+            }
+            self.table.push(&row);
+            rows += 1;
+        }
+        rows
+    }
+
+    /// Number of rows not yet returned by [`finish`](Self::finish).
+    pub fn len(&self) -> usize {
+        self.table.rows
+    }
+
+    /// Whether no rows are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.table.rows == 0
+    }
+
+    /// Return the buffered rows as a batch and start a new one.
+    pub fn finish(&mut self) -> RecordBatch {
+        self.table.finish()
+    }
+}
+
+/// Accumulates counter records in one table per record type.
+#[derive(Debug, Default)]
+pub struct CounterBatchBuilder {
+    tables: BTreeMap<String, Table>,
+}
+
+impl CounterBatchBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a row per counter record of `datagram`, received at
+    /// `received`. Returns the number of rows appended.
+    pub fn append_datagram(&mut self, datagram: &SflowDatagram, received: SystemTime) -> usize {
+        let header = header(datagram, received);
+        let mut rows = 0;
+        for sample in &datagram.samples {
+            let Some(counters) = sample.normalized_counter() else {
+                continue;
+            };
+            let mut context = header.clone();
+            context.insert("sample_sequence".into(), counters.sequence_number.into());
+            data_source(&mut context, counters.data_source);
+            for record in counters.records {
+                if let CounterRecord::Unknown { .. } = record {
+                    continue;
+                }
+                let (record_type, fields) = counter_variant(record);
+                let mut row = context.clone();
+                if let Value::Object(fields) = fields {
+                    row.extend(fields.into_iter().map(|(k, v)| (k, normalize(&v))));
+                }
+                self.tables
+                    .entry(record_type)
+                    .or_insert_with(|| Table::new(counter_schema(record)))
+                    .push(&row);
+                rows += 1;
+            }
+        }
+        rows
+    }
+
+    /// Number of rows not yet returned by [`finish`](Self::finish), over all
+    /// record types.
+    pub fn len(&self) -> usize {
+        self.tables.values().map(|t| t.rows).sum()
+    }
+
+    /// Whether no rows are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of values written as null because they did not fit the type of
+    /// their column, such as a negative value in a `UInt64` column, over all
+    /// record types.
+    pub fn out_of_range(&self) -> u64 {
+        self.tables.values().map(|t| t.out_of_range).sum()
+    }
+
+    /// Schema of the `record_type` table, once a record of that type has
+    /// been appended.
+    pub fn schema(&self, record_type: &str) -> Option<SchemaRef> {
+        self.tables.get(record_type).map(|t| Arc::clone(&t.schema))
+    }
+
+    /// Return the buffered rows as one batch per record type and start new
+    /// ones. Types without buffered rows are left out.
+    pub fn finish(&mut self) -> Vec<(String, RecordBatch)> {
+        self.tables
+            .iter_mut()
+            .filter(|(_, table)| table.rows > 0)
+            .map(|(record_type, table)| (record_type.clone(), table.finish()))
+            .collect()
+    }
+}
+
+/// Writes flow samples and counter records to Parquet files in a directory.
+pub struct ParquetWriter {
+    dir: PathBuf,
+    batch_size: usize,
+    properties: WriterProperties,
+    flows: FlowBatchBuilder,
+    counters: CounterBatchBuilder,
+    files: BTreeMap<String, ArrowWriter<File>>,
+}
+
+impl ParquetWriter {
+    /// Create a [`ParquetWriterBuilder`].
+    pub fn builder() -> ParquetWriterBuilder {
+        ParquetWriterBuilder {
+            batch_size: 65_536,
+            compression: Compression::SNAPPY,
+        }
+    }
+
+    /// Buffer the flow samples and counter records of `datagram`, received
+    /// at `received`, writing a batch once enough rows have accumulated.
+    /// Returns the number of rows buffered.
+    pub fn write_datagram(
+        &mut self,
+        datagram: &SflowDatagram,
+        received: SystemTime,
+    ) -> io::Result<usize> {
+        let rows = self.flows.append_datagram(datagram, received)
+            + self.counters.append_datagram(datagram, received);
+        if self.flows.len() >= self.batch_size {
+            self.write_flows()?;
+        }
+        if self.counters.len() >= self.batch_size {
+            self.write_counters()?;
+        }
+        Ok(rows)
+    }
+
+    /// Number of counter values written as null because they did not fit the
+    /// type of their column; see [`CounterBatchBuilder::out_of_range`].
+    pub fn out_of_range(&self) -> u64 {
+        self.counters.out_of_range()
+    }
+
+    /// Write the buffered rows and the file footers. Returns the paths of
+    /// the files written.
+    pub fn close(mut self) -> io::Result<Vec<PathBuf>> {
+        self.write_flows()?;
+        self.write_counters()?;
+        let mut paths = Vec::new();
+        for (name, writer) in std::mem::take(&mut self.files) {
+            writer.close().map_err(io::Error::other)?;
+            paths.push(self.path(&name));
+        }
+        Ok(paths)
+    }
+
+    fn write_flows(&mut self) -> io::Result<()> {
+        if self.flows.is_empty() {
+            return Ok(());
+        }
+        let batch = self.flows.finish();
+        self.write_batch("flows", &batch)
+    }
+
+    fn write_counters(&mut self) -> io::Result<()> {
+        for (record_type, batch) in self.counters.finish() {
+            self.write_batch(&record_type, &batch)?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self, name: &str, batch: &RecordBatch) -> io::Result<()> {
+        if !self.files.contains_key(name) {
+            let file = File::create(self.path(name))?;
+            let writer =
+                ArrowWriter::try_new(file, batch.schema(), Some(self.properties.clone()))
+                    .map_err(io::Error::other)?;
+            self.files.insert(name.to_string(), writer);
+        }
+        let writer = self.files.get_mut(name).expect("writer was inserted");
+        writer.write(batch).map_err(io::Error::other)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.parquet"))
+    }
+}
+
+impl std::fmt::Debug for ParquetWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParquetWriter")
+            .field("dir", &self.dir)
+            .field("batch_size", &self.batch_size)
+            .field("files", &self.files.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for configuring a [`ParquetWriter`].
+#[derive(Debug, Clone)]
+pub struct ParquetWriterBuilder {
+    batch_size: usize,
+    compression: Compression,
+}
+
+impl ParquetWriterBuilder {
+    /// Write a batch once this many flow or counter rows are buffered.
+    /// Defaults to 65536.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Compress column chunks with `compression`. Defaults to Snappy.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Build the configured [`ParquetWriter`], creating `dir` if needed.
+    /// Files are created when their first batch is written.
+    pub fn build(self, dir: impl AsRef<Path>) -> io::Result<ParquetWriter> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(ParquetWriter {
+            dir,
+            batch_size: self.batch_size,
+            properties: WriterProperties::builder()
+                .set_compression(self.compression)
+                .build(),
+            flows: FlowBatchBuilder::new(),
+            counters: CounterBatchBuilder::new(),
+            files: BTreeMap::new(),
+        })
+    }
+}
+
+/// Column builders for one schema.
+#[derive(Debug)]
+struct Table {
+    schema: SchemaRef,
+    columns: Vec<Column>,
+    rows: usize,
+    out_of_range: u64,
+}
+
+impl Table {
+    fn new(schema: SchemaRef) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| Column::new(f.data_type()))
+            .collect();
+        Table {
+            schema,
+            columns,
+            rows: 0,
+            out_of_range: 0,
+        }
+    }
+
+    /// Append `row`; columns without a key in `row` get a null.
+    fn push(&mut self, row: &Map<String, Value>) {
+        for (field, column) in self.schema.fields().iter().zip(&mut self.columns) {
+            if !column.push(row.get(field.name())) {
+                self.out_of_range += 1;
+            }
+        }
+        self.rows += 1;
+    }
+
+    fn finish(&mut self) -> RecordBatch {
+        let arrays = self.columns.iter_mut().map(Column::finish).collect();
+        self.rows = 0;
+        RecordBatch::try_new(Arc::clone(&self.schema), arrays)
+            .expect("columns are built from the schema")
+    }
+}
+
+#[derive(Debug)]
+enum Column {
+    Time(TimestampMillisecondBuilder),
+    UInt32(UInt32Builder),
+    UInt64(UInt64Builder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+}
+
+impl Column {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Timestamp(_, _) => {
+                Column::Time(TimestampMillisecondBuilder::new().with_timezone("UTC"))
+            }
+            DataType::UInt32 => Column::UInt32(UInt32Builder::new()),
+            DataType::UInt64 => Column::UInt64(UInt64Builder::new()),
+            DataType::Int64 => Column::Int64(Int64Builder::new()),
+            DataType::Float64 => Column::Float64(Float64Builder::new()),
+            _ => Column::Utf8(StringBuilder::new()),
+        }
+    }
+
+    /// Append `value`, or a null if it is missing or does not fit. Returns
+    /// false if a value was replaced by a null.
+    fn push(&mut self, value: Option<&Value>) -> bool {
+        let value = value.filter(|v| !v.is_null());
+        match self {
+            Column::Time(b) => append(b, value, Value::as_i64),
+            Column::UInt32(b) => {
+                append(b, value, |v| v.as_u64().and_then(|v| u32::try_from(v).ok()))
+            }
+            Column::UInt64(b) => append(b, value, Value::as_u64),
+            Column::Int64(b) => append(b, value, Value::as_i64),
+            Column::Float64(b) => append(b, value, Value::as_f64),
+            Column::Utf8(b) => {
+                b.append_option(value.map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                }));
+                true
+            }
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Column::Time(b) => Arc::new(b.finish()),
+            Column::UInt32(b) => Arc::new(b.finish()),
+            Column::UInt64(b) => Arc::new(b.finish()),
+            Column::Int64(b) => Arc::new(b.finish()),
+            Column::Float64(b) => Arc::new(b.finish()),
+            Column::Utf8(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+fn append<T: ArrowPrimitiveType>(
+    builder: &mut PrimitiveBuilder<T>,
+    value: Option<&Value>,
+    convert: impl Fn(&Value) -> Option<T::Native>,
+) -> bool {
+    let converted = value.and_then(convert);
+    builder.append_option(converted);
+    value.is_none() || converted.is_some()
+}
+
+fn schema(columns: impl Iterator<Item = (String, DataType)>) -> SchemaRef {
+    let time = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+    let fields = std::iter::once(Field::new("time", time, false))
+        .chain(columns.map(|(name, data_type)| Field::new(name, data_type, true)))
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new(fields))
+}
+
+fn header_columns() -> impl Iterator<Item = (String, DataType)> {
+    HEADER_COLUMNS
+        .iter()
+        .map(|(name, data_type)| (name.to_string(), data_type.clone()))
+}
+
+fn header(datagram: &SflowDatagram, received: SystemTime) -> Map<String, Value> {
+    let millis = received
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64);
+    let mut row = Map::new();
+    row.insert("time".into(), millis.into());
+    row.insert("agent".into(), datagram.agent_address.to_string().into());
+    row.insert("sub_agent_id".into(), datagram.sub_agent_id.into());
+    row.insert("datagram_sequence".into(), datagram.sequence_number.into());
+    row.insert("uptime_ms".into(), datagram.uptime.into());
+    row
+}
+
+/// Layer 2 to 4 fields, from sampled headers or a decoded raw header.
+fn packet_fields(row: &mut Map<String, Value>, packet: &FlowLine) {
+    if let (Some(src), Some(dst)) = (packet.src_mac, packet.dst_mac) {
+        row.insert("src_mac".into(), src.to_string().to_lowercase().into());
+        row.insert("dst_mac".into(), dst.to_string().to_lowercase().into());
+    }
+    if packet.eth_type != 0 {
+        row.insert("eth_type".into(), packet.eth_type.into());
+    }
+    if packet.in_vlan != 0 || packet.out_vlan != 0 {
+        row.insert("in_vlan".into(), packet.in_vlan.into());
+        row.insert("out_vlan".into(), packet.out_vlan.into());
+    }
+    if let (Some(src), Some(dst)) = (packet.src_ip, packet.dst_ip) {
+        row.insert("src_ip".into(), src.to_string().into());
+        row.insert("dst_ip".into(), dst.to_string().into());
+        row.insert("ip_protocol".into(), packet.protocol.into());
+        row.insert("tos".into(), packet.tos.into());
+        row.insert("ttl".into(), packet.ttl.into());
+        row.insert("src_port".into(), packet.src_port.into());
+        row.insert("dst_port".into(), packet.dst_port.into());
+        row.insert("tcp_flags".into(), packet.tcp_flags.into());
+        row.insert("ip_size".into(), packet.ip_size.into());
+    }
+}
+
+fn record_fields(row: &mut Map<String, Value>, record: &FlowRecord) {
+    match record {
+        FlowRecord::RawPacketHeader(r) => {
+            row.insert("header_protocol".into(), enum_name(&r.protocol()).into());
+        }
+        FlowRecord::ExtendedRouter(r) => {
+            row.insert("next_hop".into(), r.next_hop.to_string().into());
+            row.insert("src_mask_len".into(), r.src_mask_len.into());
+            row.insert("dst_mask_len".into(), r.dst_mask_len.into());
+        }
+        FlowRecord::ExtendedGateway(r) => {
+            row.entry("next_hop")
+                .or_insert_with(|| r.next_hop.to_string().into());
+            row.insert("src_as".into(), r.src_as.into());
+            row.insert("dst_as".into(), r.dst_as().into());
+            row.insert("src_peer_as".into(), r.src_peer_as.into());
+        }
+        _ => {}
+    }
+}
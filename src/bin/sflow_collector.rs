@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use flowparser_sflow::SflowParser;
+use flowparser_sflow::collector::{Collector, OutputFormat};
+use flowparser_sflow::prometheus::MetricsExporter;
+
+const USAGE: &str = "\
+Usage: sflow-collector [OPTIONS]
+
+Receive sFlow datagrams over UDP and write them to stdout or a file.
+
+Options:
+  -b, --bind ADDR            Listen on IP:PORT, [IPv6]:PORT, or PORT for both
+                             0.0.0.0 and [::] (repeatable, default 0.0.0.0:6343)
+  -r, --rcvbuf BYTES         Socket receive buffer size (SO_RCVBUF)
+  -f, --format FORMAT        json (default), jsonl, jsonl-samples, sflowtool, lines,
+                             influx, influx-flows, pcap, pcapng or none
+  -o, --output PATH          Write to PATH, or to a TCP listener such as Telegraf's
+                             socket_listener with tcp://HOST:PORT, instead of stdout
+  -a, --agent IP             Only accept datagrams from this agent (repeatable)
+  -x, --exclude-agent IP     Drop datagrams from this agent (repeatable)
+  -F, --forward IP:PORT      Replicate accepted datagrams to IP:PORT (repeatable)
+  -m, --metrics ADDR         Serve counter samples as Prometheus metrics on
+                             http://ADDR/metrics
+      --metrics-stale SECS   Drop metrics not updated for SECS seconds (default 300)
+      --max-samples N        Reject datagrams with more than N samples
+  -h, --help                 Print this help
+";
+
+fn main() -> ExitCode {
+    let collector = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(collector)) => collector,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("sflow-collector: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match collector.local_addrs() {
+        Ok(addrs) => {
+            for addr in addrs {
+                eprintln!("sflow-collector: listening on {addr}");
+            }
+            if let Some(addr) = collector.metrics_addr() {
+                eprintln!("sflow-collector: serving metrics on http://{addr}/metrics");
+            }
+        }
+        Err(e) => eprintln!("sflow-collector: {e}"),
+    }
+    match collector.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("sflow-collector: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Build a collector from the command line, or `None` for `--help`.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Collector>, String> {
+    let mut builder = Collector::builder();
+    let mut format = OutputFormat::Json;
+    let mut output = None;
+    let mut agents = Vec::new();
+    let mut excluded_agents = Vec::new();
+    let mut metrics = None;
+    let mut stale_after = Duration::from_secs(300);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-b" | "--bind" => {
+                for addr in parse_bind(&value()?)? {
+                    builder = builder.with_bind(addr);
+                }
+            }
+            "-r" | "--rcvbuf" => builder = builder.with_recv_buffer(parse(&value()?)?),
+            "-f" | "--format" => format = value()?.parse()?,
+            "-o" | "--output" => output = Some(value()?),
+            "-a" | "--agent" => agents.push(parse::<IpAddr>(&value()?)?),
+            "-x" | "--exclude-agent" => excluded_agents.push(parse::<IpAddr>(&value()?)?),
+            "-F" | "--forward" => builder = builder.with_forward(parse(&value()?)?),
+            "-m" | "--metrics" => metrics = Some(parse(&value()?)?),
+            "--metrics-stale" => stale_after = Duration::from_secs(parse(&value()?)?),
+            "--max-samples" => {
+                let parser = SflowParser::builder()
+                    .with_max_samples(parse(&value()?)?)
+                    .build();
+                builder = builder.with_parser(parser);
+            }
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+    let out: Box<dyn Write + Send> = match &output {
+        Some(path) => match path.strip_prefix("tcp://") {
+            Some(addr) => Box::new(BufWriter::new(
+                TcpStream::connect(addr).map_err(|e| format!("{path}: {e}"))?,
+            )),
+            None => Box::new(BufWriter::new(
+                File::create(path).map_err(|e| format!("{path}: {e}"))?,
+            )),
+        },
+        None => Box::new(io::stdout()),
+    };
+    if let Some(addr) = metrics {
+        let exporter = MetricsExporter::builder()
+            .with_stale_after(stale_after)
+            .build();
+        builder = builder.with_metrics(addr, exporter);
+    }
+    builder
+        .with_agents(&agents)
+        .with_excluded_agents(&excluded_agents)
+        .with_output(format, out)
+        .build()
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+fn parse_bind(value: &str) -> Result<Vec<SocketAddr>, String> {
+    match value.parse::<u16>() {
+        Ok(port) => Ok(vec![
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
+            SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)),
+        ]),
+        Err(_) => parse(value).map(|addr| vec![addr]),
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value: {value}"))
+}
@@ -0,0 +1,602 @@
+//! UDP collector used by the `sflow-collector` binary.
+//!
+//! Enabled with the `collector` cargo feature. A [`Collector`] listens on one
+//! or more UDP sockets, parses every datagram with [`SflowParser`], drops
+//! datagrams from unwanted agents, replicates the raw bytes of the remaining
+//! ones to other collectors and writes them to an output in one of the
+//! [`OutputFormat`]s.
+//!
+//! Agent filtering uses the agent address in the datagram header, not the
+//! UDP source address, so datagrams relayed by another collector are matched
+//! against the agent that produced them. Payloads that fail to parse are
+//! counted in [`CollectorStats::parse_errors`]; they are still forwarded when
+//! no agent filter is configured, since their agent cannot be checked.
+//!
+//! With [`CollectorBuilder::with_metrics`], counter samples of accepted
+//! datagrams also feed a [`MetricsExporter`] served over HTTP at `/metrics`
+//! in the Prometheus text format, or OpenMetrics when the scraper asks for
+//! it in its `Accept` header.
+//!
+//! ```no_run
+//! use flowparser_sflow::collector::{Collector, OutputFormat};
+//! use std::net::SocketAddr;
+//!
+//! let collector = Collector::builder()
+//!     .with_bind("0.0.0.0:6343".parse::<SocketAddr>().unwrap())
+//!     .with_bind("[::]:6343".parse::<SocketAddr>().unwrap())
+//!     .with_recv_buffer(8 << 20)
+//!     .with_agents(&["10.0.0.1".parse().unwrap()])
+//!     .with_forward("192.0.2.10:6343".parse().unwrap())
+//!     .with_output(OutputFormat::Json, std::io::stdout())
+//!     .build()
+//!     .unwrap();
+//! collector.run().unwrap();
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::influx::InfluxWriter;
+use crate::jsonl::{Granularity, JsonlWriter};
+use crate::pcap::{LINKTYPE_ETHERNET, PcapNgWriter, PcapWriter};
+use crate::prometheus::{ExpositionFormat, MetricsExporter};
+use crate::sflowtool::{DatagramInfo, datagram_lines, datagram_text};
+use crate::{AddressType, SflowDatagram, SflowParser};
+
+/// Largest UDP payload.
+const MAX_DATAGRAM_SIZE: usize = 65535;
+/// Received datagrams queued between the socket threads and the output.
+const QUEUE_SIZE: usize = 4096;
+/// Largest accepted HTTP request head.
+const MAX_REQUEST_SIZE: usize = 8192;
+/// Time allowed for a scraper to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest time written output stays buffered by default.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Output written for every accepted datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON object per line, the serde serialisation of [`SflowDatagram`].
+    Json,
+    /// One line per record or sample in the flat [`jsonl`](crate::jsonl)
+    /// schema.
+    Jsonl(Granularity),
+    /// sflowtool's verbose key/value format.
+    Sflowtool,
+    /// sflowtool's `-l` `FLOW,...`/`CNTR,...` line format.
+    SflowtoolLines,
+    /// InfluxDB line protocol for counter records, plus flow samples when
+    /// `flows` is set.
+    Influx { flows: bool },
+    /// Legacy pcap with the Ethernet raw packet headers of flow samples and
+    /// discarded packets.
+    Pcap,
+    /// pcapng with the raw packet headers of every supported link type.
+    PcapNg,
+    /// No output; only filtering and forwarding.
+    None,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    /// Parse `json`, `jsonl`, `jsonl-samples`, `sflowtool`, `lines`, `influx`,
+    /// `influx-flows`, `pcap`, `pcapng` or `none`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl(Granularity::Record)),
+            "jsonl-samples" => Ok(Self::Jsonl(Granularity::Sample)),
+            "sflowtool" => Ok(Self::Sflowtool),
+            "lines" => Ok(Self::SflowtoolLines),
+            "influx" => Ok(Self::Influx { flows: false }),
+            "influx-flows" => Ok(Self::Influx { flows: true }),
+            "pcap" => Ok(Self::Pcap),
+            "pcapng" => Ok(Self::PcapNg),
+            "none" => Ok(Self::None),
+            _ => Err(format!("unknown output format: {s}")),
+        }
+    }
+}
+
+/// Counters maintained by a [`Collector`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectorStats {
+    /// UDP payloads received.
+    pub received: u64,
+    /// Payloads that contained no parseable datagram.
+    pub parse_errors: u64,
+    /// Datagrams dropped by the agent filter.
+    pub filtered: u64,
+    /// Datagrams written to the output.
+    pub written: u64,
+    /// Copies sent to forwarding targets.
+    pub forwarded: u64,
+    /// Copies that could not be sent.
+    pub forward_errors: u64,
+}
+
+/// Destination of accepted datagrams.
+enum Sink {
+    Json(Box<dyn Write + Send>),
+    Jsonl(JsonlWriter<Box<dyn Write + Send>>),
+    Sflowtool(Box<dyn Write + Send>),
+    SflowtoolLines(Box<dyn Write + Send>),
+    Influx(InfluxWriter<Box<dyn Write + Send>>),
+    Pcap(PcapWriter<Box<dyn Write + Send>>),
+    PcapNg(PcapNgWriter<Box<dyn Write + Send>>),
+    None,
+}
+
+impl Sink {
+    fn new(format: OutputFormat, out: Box<dyn Write + Send>) -> io::Result<Self> {
+        Ok(match format {
+            OutputFormat::Json => Sink::Json(out),
+            OutputFormat::Jsonl(granularity) => {
+                Sink::Jsonl(JsonlWriter::new(out).with_granularity(granularity))
+            }
+            OutputFormat::Sflowtool => Sink::Sflowtool(out),
+            OutputFormat::SflowtoolLines => Sink::SflowtoolLines(out),
+            OutputFormat::Influx { flows } => {
+                Sink::Influx(InfluxWriter::new(out).with_flows(flows))
+            }
+            OutputFormat::Pcap => Sink::Pcap(PcapWriter::new(out, LINKTYPE_ETHERNET)?),
+            OutputFormat::PcapNg => Sink::PcapNg(PcapNgWriter::new(out)?),
+            OutputFormat::None => Sink::None,
+        })
+    }
+
+    fn write(&mut self, datagram: &SflowDatagram, info: &DatagramInfo) -> io::Result<()> {
+        match self {
+            Sink::Json(out) => {
+                serde_json::to_writer(&mut *out, datagram)?;
+                out.write_all(b"\n")
+            }
+            Sink::Jsonl(w) => w.write_datagram(datagram, info.received).map(|_| ()),
+            Sink::Sflowtool(out) => out.write_all(datagram_text(datagram, info).as_bytes()),
+            Sink::SflowtoolLines(out) => out.write_all(datagram_lines(datagram).as_bytes()),
+            Sink::Influx(w) => w.write_datagram(datagram, info.received).map(|_| ()),
+            Sink::Pcap(w) => w.write_datagram(datagram, info.received).map(|_| ()),
+            Sink::PcapNg(w) => w.write_datagram(datagram, info.received).map(|_| ()),
+            Sink::None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Json(out) | Sink::Sflowtool(out) | Sink::SflowtoolLines(out) => out.flush(),
+            Sink::Jsonl(w) => w.flush(),
+            Sink::Influx(w) => w.flush(),
+            Sink::Pcap(w) => w.flush(),
+            Sink::PcapNg(w) => w.flush(),
+            Sink::None => Ok(()),
+        }
+    }
+}
+
+/// A received UDP payload.
+type Received = (Vec<u8>, SocketAddr, SystemTime);
+
+/// Receives, filters, forwards and writes sFlow datagrams.
+///
+/// Each bound socket is read by its own thread in [`Collector::run`];
+/// datagrams are handled in arrival order on the calling thread. Output is
+/// flushed at most once per flush interval while datagrams arrive, once the
+/// interval has passed without any, and before `run` returns.
+pub struct Collector {
+    sockets: Vec<UdpSocket>,
+    parser: SflowParser,
+    agents: Vec<IpAddr>,
+    excluded_agents: Vec<IpAddr>,
+    forward: Vec<SocketAddr>,
+    forward_v4: Option<UdpSocket>,
+    forward_v6: Option<UdpSocket>,
+    sink: Sink,
+    flush_interval: Duration,
+    last_flush: Instant,
+    unflushed: bool,
+    metrics: Option<(TcpListener, Arc<Mutex<MetricsExporter>>)>,
+    stats: CollectorStats,
+}
+
+impl Collector {
+    /// Create a [`CollectorBuilder`].
+    pub fn builder() -> CollectorBuilder {
+        CollectorBuilder {
+            binds: Vec::new(),
+            recv_buffer: None,
+            parser: SflowParser::default(),
+            agents: Vec::new(),
+            excluded_agents: Vec::new(),
+            forward: Vec::new(),
+            output: None,
+            flush_interval: FLUSH_INTERVAL,
+            metrics: None,
+        }
+    }
+
+    /// Local addresses of the bound sockets, in the order they were added.
+    pub fn local_addrs(&self) -> io::Result<Vec<SocketAddr>> {
+        self.sockets.iter().map(UdpSocket::local_addr).collect()
+    }
+
+    /// Local address of the metrics endpoint, if enabled.
+    pub fn metrics_addr(&self) -> Option<SocketAddr> {
+        self.metrics
+            .as_ref()
+            .and_then(|(listener, _)| listener.local_addr().ok())
+    }
+
+    /// Counters since the collector was built.
+    pub fn stats(&self) -> CollectorStats {
+        self.stats
+    }
+
+    /// Whether datagrams from `agent` pass the agent filter.
+    pub fn accepts(&self, agent: &AddressType) -> bool {
+        let ip = match agent {
+            AddressType::IPv4(a) => IpAddr::V4(*a),
+            AddressType::IPv6(a) => IpAddr::V6(*a),
+        };
+        (self.agents.is_empty() || self.agents.contains(&ip))
+            && !self.excluded_agents.contains(&ip)
+    }
+
+    /// Handle one UDP payload received from `source` at `received`.
+    ///
+    /// Parses `data`, forwards it when at least one of its datagrams passes
+    /// the agent filter and writes the accepted datagrams to the output. Only
+    /// output errors are returned; failed forwards are counted in
+    /// [`CollectorStats::forward_errors`].
+    pub fn handle(
+        &mut self,
+        data: &[u8],
+        source: SocketAddr,
+        received: SystemTime,
+    ) -> io::Result<()> {
+        self.stats.received += 1;
+        let result = self.parser.parse_bytes(data);
+        if result.datagrams.is_empty() {
+            self.stats.parse_errors += 1;
+            if self.agents.is_empty() && self.excluded_agents.is_empty() {
+                self.forward(data);
+            }
+            return Ok(());
+        }
+
+        let total = result.datagrams.len();
+        let accepted: Vec<_> = result
+            .datagrams
+            .into_iter()
+            .filter(|d| self.accepts(&d.agent_address))
+            .collect();
+        self.stats.filtered += (total - accepted.len()) as u64;
+        if accepted.is_empty() {
+            return Ok(());
+        }
+        self.forward(data);
+
+        let info = DatagramInfo {
+            source: source.ip(),
+            size: data.len(),
+            received,
+        };
+        if let Some((_, exporter)) = &self.metrics {
+            let mut exporter = exporter.lock().unwrap_or_else(|e| e.into_inner());
+            for datagram in &accepted {
+                exporter.observe(datagram, received);
+            }
+        }
+        for datagram in &accepted {
+            self.sink.write(datagram, &info)?;
+            self.stats.written += 1;
+        }
+        self.unflushed = true;
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flush output written by [`handle`](Self::handle) that is still buffered.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.unflushed = false;
+        self.last_flush = Instant::now();
+        self.sink.flush()
+    }
+
+    /// Receive and handle datagrams until an output or socket error occurs.
+    pub fn run(mut self) -> io::Result<()> {
+        if let Some((listener, exporter)) = &self.metrics {
+            let listener = listener.try_clone()?;
+            let exporter = Arc::clone(exporter);
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    // A failed scrape only affects that connection.
+                    let _ = serve_metrics(stream, &exporter);
+                }
+            });
+        }
+        let (tx, rx) = mpsc::sync_channel::<io::Result<Received>>(QUEUE_SIZE);
+        for socket in std::mem::take(&mut self.sockets) {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+                loop {
+                    let item = socket
+                        .recv_from(&mut buf)
+                        .map(|(size, src)| (buf[..size].to_vec(), src, SystemTime::now()));
+                    let failed = item.is_err();
+                    if tx.send(item).is_err() || failed {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        loop {
+            let item = if self.unflushed {
+                let wait = self
+                    .flush_interval
+                    .saturating_sub(self.last_flush.elapsed());
+                match rx.recv_timeout(wait) {
+                    Ok(item) => item,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.flush()?;
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match rx.recv() {
+                    Ok(item) => item,
+                    Err(_) => break,
+                }
+            };
+            let handled =
+                item.and_then(|(data, source, received)| self.handle(&data, source, received));
+            if let Err(e) = handled {
+                // Keep what was already written; the original error matters more.
+                let _ = self.flush();
+                return Err(e);
+            }
+        }
+        self.flush()
+    }
+
+    fn forward(&mut self, data: &[u8]) {
+        for target in &self.forward {
+            let socket = match target {
+                SocketAddr::V4(_) => self.forward_v4.as_ref(),
+                SocketAddr::V6(_) => self.forward_v6.as_ref(),
+            };
+            match socket.map(|s| s.send_to(data, target)) {
+                Some(Ok(_)) => self.stats.forwarded += 1,
+                _ => self.stats.forward_errors += 1,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Collector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collector")
+            .field("sockets", &self.sockets)
+            .field("agents", &self.agents)
+            .field("excluded_agents", &self.excluded_agents)
+            .field("forward", &self.forward)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for configuring a [`Collector`].
+pub struct CollectorBuilder {
+    binds: Vec<SocketAddr>,
+    recv_buffer: Option<usize>,
+    parser: SflowParser,
+    agents: Vec<IpAddr>,
+    excluded_agents: Vec<IpAddr>,
+    forward: Vec<SocketAddr>,
+    output: Option<(OutputFormat, Box<dyn Write + Send>)>,
+    flush_interval: Duration,
+    metrics: Option<(SocketAddr, MetricsExporter)>,
+}
+
+impl CollectorBuilder {
+    /// Add an address to listen on. IPv6 sockets are bound IPv6-only so
+    /// `0.0.0.0` and `[::]` can share a port. Defaults to `0.0.0.0:6343`
+    /// when no address is added.
+    pub fn with_bind(mut self, addr: SocketAddr) -> Self {
+        self.binds.push(addr);
+        self
+    }
+
+    /// Request a socket receive buffer (`SO_RCVBUF`) of `bytes` on every
+    /// bound socket. The kernel may round or cap the value.
+    pub fn with_recv_buffer(mut self, bytes: usize) -> Self {
+        self.recv_buffer = Some(bytes);
+        self
+    }
+
+    /// Parser used for received payloads. Defaults to [`SflowParser::default`].
+    pub fn with_parser(mut self, parser: SflowParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    /// Only accept datagrams from these agent addresses. An empty list, the
+    /// default, accepts every agent.
+    pub fn with_agents(mut self, agents: &[IpAddr]) -> Self {
+        self.agents = agents.to_vec();
+        self
+    }
+
+    /// Drop datagrams from these agent addresses.
+    pub fn with_excluded_agents(mut self, agents: &[IpAddr]) -> Self {
+        self.excluded_agents = agents.to_vec();
+        self
+    }
+
+    /// Replicate the raw bytes of accepted datagrams to `target`.
+    pub fn with_forward(mut self, target: SocketAddr) -> Self {
+        self.forward.push(target);
+        self
+    }
+
+    /// Write accepted datagrams to `out` in `format`. Defaults to
+    /// [`OutputFormat::None`].
+    pub fn with_output<W: Write + Send + 'static>(
+        mut self,
+        format: OutputFormat,
+        out: W,
+    ) -> Self {
+        self.output = Some((format, Box::new(out)));
+        self
+    }
+
+    /// Longest time written output may stay buffered (default 1 second).
+    /// Zero flushes after every payload.
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Serve the counter samples of accepted datagrams as Prometheus metrics
+    /// on `http://addr/metrics`, using `exporter` for staleness settings.
+    pub fn with_metrics(mut self, addr: SocketAddr, exporter: MetricsExporter) -> Self {
+        self.metrics = Some((addr, exporter));
+        self
+    }
+
+    /// Bind the sockets and build the configured [`Collector`].
+    pub fn build(self) -> io::Result<Collector> {
+        let binds = if self.binds.is_empty() {
+            vec![SocketAddr::from((Ipv4Addr::UNSPECIFIED, 6343))]
+        } else {
+            self.binds
+        };
+        let sockets = binds
+            .iter()
+            .map(|addr| bind(*addr, self.recv_buffer))
+            .collect::<io::Result<Vec<_>>>()?;
+        let forward_v4 = if self.forward.iter().any(SocketAddr::is_ipv4) {
+            Some(UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?)
+        } else {
+            None
+        };
+        let forward_v6 = if self.forward.iter().any(SocketAddr::is_ipv6) {
+            Some(UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?)
+        } else {
+            None
+        };
+        let sink = match self.output {
+            Some((format, out)) => Sink::new(format, out)?,
+            None => Sink::None,
+        };
+        let metrics = match self.metrics {
+            Some((addr, exporter)) => {
+                Some((TcpListener::bind(addr)?, Arc::new(Mutex::new(exporter))))
+            }
+            None => None,
+        };
+        Ok(Collector {
+            sockets,
+            parser: self.parser,
+            agents: self.agents,
+            excluded_agents: self.excluded_agents,
+            forward: self.forward,
+            forward_v4,
+            forward_v6,
+            sink,
+            flush_interval: self.flush_interval,
+            last_flush: Instant::now(),
+            unflushed: false,
+            metrics,
+            stats: CollectorStats::default(),
+        })
+    }
+}
+
+impl fmt::Debug for CollectorBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CollectorBuilder")
+            .field("binds", &self.binds)
+            .field("recv_buffer", &self.recv_buffer)
+            .field("agents", &self.agents)
+            .field("excluded_agents", &self.excluded_agents)
+            .field("forward", &self.forward)
+            .field("output", &self.output.as_ref().map(|(format, _)| format))
+            .field("flush_interval", &self.flush_interval)
+            .field("metrics", &self.metrics.as_ref().map(|(addr, _)| addr))
+            .finish_non_exhaustive()
+    }
+}
+
+fn bind(addr: SocketAddr, recv_buffer: Option<usize>) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    if let Some(bytes) = recv_buffer {
+        socket.set_recv_buffer_size(bytes)?;
+    }
+    socket.bind(&addr.into())?;
+    Ok(socket.into())
+}
+
+/// Answer one HTTP request for `/metrics`.
+fn serve_metrics(mut stream: TcpStream, exporter: &Mutex<MetricsExporter>) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut lines = request.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    let path = path.map(|p| p.split('?').next().unwrap_or(p));
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => {
+            let openmetrics = lines.any(|line| {
+                line.to_ascii_lowercase().starts_with("accept:")
+                    && line.contains("application/openmetrics-text")
+            });
+            let format = if openmetrics {
+                ExpositionFormat::OpenMetrics
+            } else {
+                ExpositionFormat::Prometheus
+            };
+            let mut exporter = exporter.lock().unwrap_or_else(|e| e.into_inner());
+            exporter.expire(SystemTime::now());
+            ("200 OK", format.content_type(), exporter.render(format))
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
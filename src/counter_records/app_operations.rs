@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use crate::flow_records::parse_sflow_string;
+use crate::encode::write_u32;
+use crate::flow_records::{parse_sflow_str, write_sflow_string};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AppOperations {
@@ -21,8 +23,47 @@ pub struct AppOperations {
     pub status_ok: u32,
 }
 
-pub(crate) fn parse_app_operations(input: &[u8]) -> IResult<&[u8], AppOperations> {
-    let (input, application) = parse_sflow_string(input)?;
+/// Borrowed form of [`AppOperations`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppOperationsRef<'a> {
+    pub application: Cow<'a, str>,
+    pub success: u32,
+    pub other: u32,
+    pub timeout: u32,
+    pub internal_error: u32,
+    pub bad_request: u32,
+    pub forbidden: u32,
+    pub too_large: u32,
+    pub not_implemented: u32,
+    pub not_found: u32,
+    pub unavailable: u32,
+    pub unauthorized: u32,
+    pub status_ok: u32,
+}
+
+impl AppOperationsRef<'_> {
+    /// Convert into the owned [`AppOperations`], copying any borrowed data.
+    pub fn into_owned(self) -> AppOperations {
+        AppOperations {
+            application: self.application.into_owned(),
+            success: self.success,
+            other: self.other,
+            timeout: self.timeout,
+            internal_error: self.internal_error,
+            bad_request: self.bad_request,
+            forbidden: self.forbidden,
+            too_large: self.too_large,
+            not_implemented: self.not_implemented,
+            not_found: self.not_found,
+            unavailable: self.unavailable,
+            unauthorized: self.unauthorized,
+            status_ok: self.status_ok,
+        }
+    }
+}
+
+pub(crate) fn parse_app_operations_ref(input: &[u8]) -> IResult<&[u8], AppOperationsRef<'_>> {
+    let (input, application) = parse_sflow_str(input)?;
     let (input, success) = be_u32(input)?;
     let (input, other) = be_u32(input)?;
     let (input, timeout) = be_u32(input)?;
@@ -38,7 +79,7 @@ pub(crate) fn parse_app_operations(input: &[u8]) -> IResult<&[u8], AppOperations
 
     Ok((
         input,
-        AppOperations {
+        AppOperationsRef {
             application,
             success,
             other,
@@ -55,3 +96,19 @@ pub(crate) fn parse_app_operations(input: &[u8]) -> IResult<&[u8], AppOperations
         },
     ))
 }
+
+pub(crate) fn write_app_operations(out: &mut Vec<u8>, r: &AppOperations) {
+    write_sflow_string(out, &r.application);
+    write_u32(out, r.success);
+    write_u32(out, r.other);
+    write_u32(out, r.timeout);
+    write_u32(out, r.internal_error);
+    write_u32(out, r.bad_request);
+    write_u32(out, r.forbidden);
+    write_u32(out, r.too_large);
+    write_u32(out, r.not_implemented);
+    write_u32(out, r.not_found);
+    write_u32(out, r.unavailable);
+    write_u32(out, r.unauthorized);
+    write_u32(out, r.status_ok);
+}
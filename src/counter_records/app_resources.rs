@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AppResources {
     pub user_time: u32,
@@ -38,3 +40,14 @@ pub(crate) fn parse_app_resources(input: &[u8]) -> IResult<&[u8], AppResources>
         },
     ))
 }
+
+pub(crate) fn write_app_resources(out: &mut Vec<u8>, r: &AppResources) {
+    write_u32(out, r.user_time);
+    write_u32(out, r.system_time);
+    write_u64(out, r.mem_used);
+    write_u64(out, r.mem_max);
+    write_u32(out, r.fd_open);
+    write_u32(out, r.fd_max);
+    write_u32(out, r.conn_open);
+    write_u32(out, r.conn_max);
+}
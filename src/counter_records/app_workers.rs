@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AppWorkers {
     pub workers_active: u32,
@@ -29,3 +31,11 @@ pub(crate) fn parse_app_workers(input: &[u8]) -> IResult<&[u8], AppWorkers> {
         },
     ))
 }
+
+pub(crate) fn write_app_workers(out: &mut Vec<u8>, r: &AppWorkers) {
+    write_u32(out, r.workers_active);
+    write_u32(out, r.workers_idle);
+    write_u32(out, r.workers_max);
+    write_u32(out, r.req_delayed);
+    write_u32(out, r.req_dropped);
+}
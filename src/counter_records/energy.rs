@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Energy {
     /// Voltage in millivolts.
@@ -38,3 +40,12 @@ pub(crate) fn parse_energy(input: &[u8]) -> IResult<&[u8], Energy> {
         },
     ))
 }
+
+pub(crate) fn write_energy(out: &mut Vec<u8>, r: &Energy) {
+    write_u32(out, r.voltage);
+    write_u32(out, r.current);
+    write_u32(out, r.real_power);
+    write_u32(out, r.power_factor);
+    write_u32(out, r.energy);
+    write_u32(out, r.errors);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EthernetInterface {
     pub dot3_stats_alignment_errors: u32,
@@ -53,3 +55,19 @@ pub(crate) fn parse_ethernet_interface(input: &[u8]) -> IResult<&[u8], EthernetI
         },
     ))
 }
+
+pub(crate) fn write_ethernet_interface(out: &mut Vec<u8>, r: &EthernetInterface) {
+    write_u32(out, r.dot3_stats_alignment_errors);
+    write_u32(out, r.dot3_stats_fcs_errors);
+    write_u32(out, r.dot3_stats_single_collision_frames);
+    write_u32(out, r.dot3_stats_multiple_collision_frames);
+    write_u32(out, r.dot3_stats_sqe_test_errors);
+    write_u32(out, r.dot3_stats_deferred_transmissions);
+    write_u32(out, r.dot3_stats_late_collisions);
+    write_u32(out, r.dot3_stats_excessive_collisions);
+    write_u32(out, r.dot3_stats_internal_mac_transmit_errors);
+    write_u32(out, r.dot3_stats_carrier_sense_errors);
+    write_u32(out, r.dot3_stats_frame_too_longs);
+    write_u32(out, r.dot3_stats_internal_mac_receive_errors);
+    write_u32(out, r.dot3_stats_symbol_errors);
+}
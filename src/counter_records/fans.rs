@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Fans {
     /// Total number of fans.
@@ -26,3 +28,9 @@ pub(crate) fn parse_fans(input: &[u8]) -> IResult<&[u8], Fans> {
         },
     ))
 }
+
+pub(crate) fn write_fans(out: &mut Vec<u8>, r: &Fans) {
+    write_u32(out, r.total);
+    write_u32(out, r.failed);
+    write_u32(out, r.speed);
+}
@@ -2,6 +2,45 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
+/// Interface duplex/direction from `ifDirection` (RFC 2863 extension used by sFlow).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IfDirection {
+    Unknown,
+    FullDuplex,
+    HalfDuplex,
+    In,
+    Out,
+    Unrecognized(u32),
+}
+
+impl From<u32> for IfDirection {
+    fn from(v: u32) -> Self {
+        match v {
+            0 => IfDirection::Unknown,
+            1 => IfDirection::FullDuplex,
+            2 => IfDirection::HalfDuplex,
+            3 => IfDirection::In,
+            4 => IfDirection::Out,
+            v => IfDirection::Unrecognized(v),
+        }
+    }
+}
+
+impl From<IfDirection> for u32 {
+    fn from(v: IfDirection) -> Self {
+        match v {
+            IfDirection::Unknown => 0,
+            IfDirection::FullDuplex => 1,
+            IfDirection::HalfDuplex => 2,
+            IfDirection::In => 3,
+            IfDirection::Out => 4,
+            IfDirection::Unrecognized(v) => v,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GenericInterface {
     pub if_index: u32,
@@ -25,6 +64,23 @@ pub struct GenericInterface {
     pub if_promiscuous_mode: u32,
 }
 
+impl GenericInterface {
+    /// Decoded `if_direction`.
+    pub fn direction(&self) -> IfDirection {
+        IfDirection::from(self.if_direction)
+    }
+
+    /// Bit 0 of `if_status`: ifAdminStatus is up.
+    pub fn admin_up(&self) -> bool {
+        self.if_status & 1 != 0
+    }
+
+    /// Bit 1 of `if_status`: ifOperStatus is up.
+    pub fn oper_up(&self) -> bool {
+        self.if_status & 2 != 0
+    }
+}
+
 pub(crate) fn parse_generic_interface(input: &[u8]) -> IResult<&[u8], GenericInterface> {
     let (input, if_index) = be_u32(input)?;
     let (input, if_type) = be_u32(input)?;
@@ -71,3 +127,25 @@ pub(crate) fn parse_generic_interface(input: &[u8]) -> IResult<&[u8], GenericInt
         },
     ))
 }
+
+pub(crate) fn write_generic_interface(out: &mut Vec<u8>, r: &GenericInterface) {
+    write_u32(out, r.if_index);
+    write_u32(out, r.if_type);
+    write_u64(out, r.if_speed);
+    write_u32(out, r.if_direction);
+    write_u32(out, r.if_status);
+    write_u64(out, r.if_in_octets);
+    write_u32(out, r.if_in_ucast_pkts);
+    write_u32(out, r.if_in_multicast_pkts);
+    write_u32(out, r.if_in_broadcast_pkts);
+    write_u32(out, r.if_in_discards);
+    write_u32(out, r.if_in_errors);
+    write_u32(out, r.if_in_unknown_protos);
+    write_u64(out, r.if_out_octets);
+    write_u32(out, r.if_out_ucast_pkts);
+    write_u32(out, r.if_out_multicast_pkts);
+    write_u32(out, r.if_out_broadcast_pkts);
+    write_u32(out, r.if_out_discards);
+    write_u32(out, r.if_out_errors);
+    write_u32(out, r.if_promiscuous_mode);
+}
@@ -3,6 +3,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_mac, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostAdapter {
     pub if_index: u32,
@@ -61,3 +63,18 @@ pub(crate) fn parse_host_adapters(input: &[u8]) -> IResult<&[u8], HostAdapters>
 
     Ok((input, HostAdapters { adapters }))
 }
+
+fn write_host_adapter(out: &mut Vec<u8>, r: &HostAdapter) {
+    write_u32(out, r.if_index);
+    write_u32(out, r.mac_addresses.len() as u32);
+    for mac in &r.mac_addresses {
+        write_mac(out, mac);
+    }
+}
+
+pub(crate) fn write_host_adapters(out: &mut Vec<u8>, r: &HostAdapters) {
+    write_u32(out, r.adapters.len() as u32);
+    for adapter in &r.adapters {
+        write_host_adapter(out, adapter);
+    }
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_f32, be_u32};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_f32, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HostCpu {
     /// 1-minute load average.
@@ -68,3 +70,23 @@ pub(crate) fn parse_host_cpu(input: &[u8]) -> IResult<&[u8], HostCpu> {
         },
     ))
 }
+
+pub(crate) fn write_host_cpu(out: &mut Vec<u8>, r: &HostCpu) {
+    write_f32(out, r.load_one);
+    write_f32(out, r.load_five);
+    write_f32(out, r.load_fifteen);
+    write_u32(out, r.proc_run);
+    write_u32(out, r.proc_total);
+    write_u32(out, r.cpu_num);
+    write_u32(out, r.cpu_speed);
+    write_u32(out, r.uptime);
+    write_u32(out, r.cpu_user);
+    write_u32(out, r.cpu_nice);
+    write_u32(out, r.cpu_system);
+    write_u32(out, r.cpu_idle);
+    write_u32(out, r.cpu_wio);
+    write_u32(out, r.cpu_intr);
+    write_u32(out, r.cpu_sintr);
+    write_u32(out, r.interrupts);
+    write_u32(out, r.contexts);
+}
@@ -2,9 +2,11 @@ use nom::IResult;
 use nom::bytes::complete::take;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use uuid::Uuid;
 
-use crate::flow_records::parse_sflow_string;
+use crate::encode::write_u32;
+use crate::flow_records::{parse_sflow_str, write_sflow_string};
 
 /// Machine architecture type from the sFlow host structures specification.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -46,6 +48,27 @@ impl From<u32> for MachineType {
     }
 }
 
+impl From<MachineType> for u32 {
+    fn from(v: MachineType) -> Self {
+        match v {
+            MachineType::Unknown => 0,
+            MachineType::Other => 1,
+            MachineType::X86 => 2,
+            MachineType::X86_64 => 3,
+            MachineType::Ia64 => 4,
+            MachineType::Sparc => 5,
+            MachineType::Alpha => 6,
+            MachineType::Mips => 7,
+            MachineType::PowerPc => 8,
+            MachineType::M68k => 9,
+            MachineType::Arm => 10,
+            MachineType::HpPa => 11,
+            MachineType::S390 => 12,
+            MachineType::Unrecognized(v) => v,
+        }
+    }
+}
+
 /// Operating system name from the sFlow host structures specification.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OsName {
@@ -86,6 +109,27 @@ impl From<u32> for OsName {
     }
 }
 
+impl From<OsName> for u32 {
+    fn from(v: OsName) -> Self {
+        match v {
+            OsName::Unknown => 0,
+            OsName::Other => 1,
+            OsName::Linux => 2,
+            OsName::Windows => 3,
+            OsName::Darwin => 4,
+            OsName::HpUx => 5,
+            OsName::Aix => 6,
+            OsName::DragonflyBsd => 7,
+            OsName::FreeBsd => 8,
+            OsName::NetBsd => 9,
+            OsName::OpenBsd => 10,
+            OsName::Osf => 11,
+            OsName::Solaris => 12,
+            OsName::Unrecognized(v) => v,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostDescr {
     pub hostname: String,
@@ -95,17 +139,40 @@ pub struct HostDescr {
     pub os_release: String,
 }
 
-pub(crate) fn parse_host_descr(input: &[u8]) -> IResult<&[u8], HostDescr> {
-    let (input, hostname) = parse_sflow_string(input)?;
+/// Borrowed form of [`HostDescr`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostDescrRef<'a> {
+    pub hostname: Cow<'a, str>,
+    pub uuid: Uuid,
+    pub machine_type: MachineType,
+    pub os_name: OsName,
+    pub os_release: Cow<'a, str>,
+}
+
+impl HostDescrRef<'_> {
+    /// Convert into the owned [`HostDescr`], copying any borrowed data.
+    pub fn into_owned(self) -> HostDescr {
+        HostDescr {
+            hostname: self.hostname.into_owned(),
+            uuid: self.uuid,
+            machine_type: self.machine_type,
+            os_name: self.os_name,
+            os_release: self.os_release.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_host_descr_ref(input: &[u8]) -> IResult<&[u8], HostDescrRef<'_>> {
+    let (input, hostname) = parse_sflow_str(input)?;
     let (input, uuid_bytes) = take(16u8)(input)?;
     let uuid = Uuid::from_slice(uuid_bytes).unwrap_or(Uuid::nil());
     let (input, machine_type) = be_u32(input)?;
     let (input, os_name) = be_u32(input)?;
-    let (input, os_release) = parse_sflow_string(input)?;
+    let (input, os_release) = parse_sflow_str(input)?;
 
     Ok((
         input,
-        HostDescr {
+        HostDescrRef {
             hostname,
             uuid,
             machine_type: MachineType::from(machine_type),
@@ -114,3 +181,11 @@ pub(crate) fn parse_host_descr(input: &[u8]) -> IResult<&[u8], HostDescr> {
         },
     ))
 }
+
+pub(crate) fn write_host_descr(out: &mut Vec<u8>, r: &HostDescr) {
+    write_sflow_string(out, &r.hostname);
+    out.extend_from_slice(r.uuid.as_bytes());
+    write_u32(out, u32::from(r.machine_type));
+    write_u32(out, u32::from(r.os_name));
+    write_sflow_string(out, &r.os_release);
+}
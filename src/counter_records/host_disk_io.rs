@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostDiskIo {
     pub disk_total: u64,
@@ -42,3 +44,15 @@ pub(crate) fn parse_host_disk_io(input: &[u8]) -> IResult<&[u8], HostDiskIo> {
         },
     ))
 }
+
+pub(crate) fn write_host_disk_io(out: &mut Vec<u8>, r: &HostDiskIo) {
+    write_u64(out, r.disk_total);
+    write_u64(out, r.disk_free);
+    write_u32(out, r.part_max_used);
+    write_u32(out, r.reads);
+    write_u64(out, r.bytes_read);
+    write_u32(out, r.read_time);
+    write_u32(out, r.writes);
+    write_u64(out, r.bytes_written);
+    write_u32(out, r.write_time);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostMemory {
     pub mem_total: u64,
@@ -47,3 +49,17 @@ pub(crate) fn parse_host_memory(input: &[u8]) -> IResult<&[u8], HostMemory> {
         },
     ))
 }
+
+pub(crate) fn write_host_memory(out: &mut Vec<u8>, r: &HostMemory) {
+    write_u64(out, r.mem_total);
+    write_u64(out, r.mem_free);
+    write_u64(out, r.mem_shared);
+    write_u64(out, r.mem_buffers);
+    write_u64(out, r.mem_cached);
+    write_u64(out, r.swap_total);
+    write_u64(out, r.swap_free);
+    write_u32(out, r.page_in);
+    write_u32(out, r.page_out);
+    write_u32(out, r.swap_in);
+    write_u32(out, r.swap_out);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostNetIo {
     pub bytes_in: u64,
@@ -38,3 +40,14 @@ pub(crate) fn parse_host_net_io(input: &[u8]) -> IResult<&[u8], HostNetIo> {
         },
     ))
 }
+
+pub(crate) fn write_host_net_io(out: &mut Vec<u8>, r: &HostNetIo) {
+    write_u64(out, r.bytes_in);
+    write_u32(out, r.packets_in);
+    write_u32(out, r.errs_in);
+    write_u32(out, r.drops_in);
+    write_u64(out, r.bytes_out);
+    write_u32(out, r.packets_out);
+    write_u32(out, r.errs_out);
+    write_u32(out, r.drops_out);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostParent {
     pub container_type: u32,
@@ -20,3 +22,8 @@ pub(crate) fn parse_host_parent(input: &[u8]) -> IResult<&[u8], HostParent> {
         },
     ))
 }
+
+pub(crate) fn write_host_parent(out: &mut Vec<u8>, r: &HostParent) {
+    write_u32(out, r.container_type);
+    write_u32(out, r.container_index);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HttpCounters {
     pub method_option_count: u32,
@@ -59,3 +61,21 @@ pub(crate) fn parse_http_counters(input: &[u8]) -> IResult<&[u8], HttpCounters>
         },
     ))
 }
+
+pub(crate) fn write_http_counters(out: &mut Vec<u8>, r: &HttpCounters) {
+    write_u32(out, r.method_option_count);
+    write_u32(out, r.method_get_count);
+    write_u32(out, r.method_head_count);
+    write_u32(out, r.method_post_count);
+    write_u32(out, r.method_put_count);
+    write_u32(out, r.method_delete_count);
+    write_u32(out, r.method_trace_count);
+    write_u32(out, r.method_connect_count);
+    write_u32(out, r.method_other_count);
+    write_u32(out, r.status_1xx_count);
+    write_u32(out, r.status_2xx_count);
+    write_u32(out, r.status_3xx_count);
+    write_u32(out, r.status_4xx_count);
+    write_u32(out, r.status_5xx_count);
+    write_u32(out, r.status_other_count);
+}
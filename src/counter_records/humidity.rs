@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Humidity {
     /// Relative humidity percentage.
@@ -13,3 +15,7 @@ pub(crate) fn parse_humidity(input: &[u8]) -> IResult<&[u8], Humidity> {
 
     Ok((input, Humidity { relative_humidity }))
 }
+
+pub(crate) fn write_humidity(out: &mut Vec<u8>, r: &Humidity) {
+    write_u32(out, r.relative_humidity);
+}
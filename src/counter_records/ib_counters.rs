@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IbCounters {
     pub port_xmit_data: u64,
@@ -62,3 +64,22 @@ pub(crate) fn parse_ib_counters(input: &[u8]) -> IResult<&[u8], IbCounters> {
         },
     ))
 }
+
+pub(crate) fn write_ib_counters(out: &mut Vec<u8>, r: &IbCounters) {
+    write_u64(out, r.port_xmit_data);
+    write_u64(out, r.port_rcv_data);
+    write_u64(out, r.port_xmit_pkts);
+    write_u64(out, r.port_rcv_pkts);
+    write_u32(out, r.symbol_error_counter);
+    write_u32(out, r.link_error_recovery_counter);
+    write_u32(out, r.link_downed_counter);
+    write_u32(out, r.port_rcv_errors);
+    write_u32(out, r.port_rcv_remote_physical_errors);
+    write_u32(out, r.port_rcv_switch_relay_errors);
+    write_u32(out, r.port_xmit_discards);
+    write_u32(out, r.port_xmit_constraint_errors);
+    write_u32(out, r.port_rcv_constraint_errors);
+    write_u32(out, r.local_link_integrity_errors);
+    write_u32(out, r.excessive_buffer_overrun_errors);
+    write_u32(out, r.vl15_dropped);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Ieee80211Counters {
     pub transmitted_fragments: u32,
@@ -74,3 +76,26 @@ pub(crate) fn parse_ieee80211_counters(input: &[u8]) -> IResult<&[u8], Ieee80211
         },
     ))
 }
+
+pub(crate) fn write_ieee80211_counters(out: &mut Vec<u8>, r: &Ieee80211Counters) {
+    write_u32(out, r.transmitted_fragments);
+    write_u32(out, r.multicast_transmitted_frames);
+    write_u32(out, r.failures);
+    write_u32(out, r.retries);
+    write_u32(out, r.multiple_retries);
+    write_u32(out, r.frame_duplicates);
+    write_u32(out, r.rts_successes);
+    write_u32(out, r.rts_failures);
+    write_u32(out, r.ack_failures);
+    write_u32(out, r.received_fragments);
+    write_u32(out, r.multicast_received_frames);
+    write_u32(out, r.fcs_errors);
+    write_u32(out, r.transmitted_frames);
+    write_u32(out, r.wep_undecryptables);
+    write_u32(out, r.qos_discarded_fragments);
+    write_u32(out, r.associated_stations);
+    write_u32(out, r.qos_cf_polls_received);
+    write_u32(out, r.qos_cf_polls_unused);
+    write_u32(out, r.qos_cf_polls_unusable);
+    write_u32(out, r.qos_cf_polls_lost);
+}
@@ -1,7 +1,8 @@
 use nom::IResult;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use crate::flow_records::parse_sflow_string;
+use crate::flow_records::{parse_sflow_str, write_sflow_string};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JmxRuntime {
@@ -10,17 +11,42 @@ pub struct JmxRuntime {
     pub vm_version: String,
 }
 
-pub(crate) fn parse_jmx_runtime(input: &[u8]) -> IResult<&[u8], JmxRuntime> {
-    let (input, vm_name) = parse_sflow_string(input)?;
-    let (input, vm_vendor) = parse_sflow_string(input)?;
-    let (input, vm_version) = parse_sflow_string(input)?;
+/// Borrowed form of [`JmxRuntime`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JmxRuntimeRef<'a> {
+    pub vm_name: Cow<'a, str>,
+    pub vm_vendor: Cow<'a, str>,
+    pub vm_version: Cow<'a, str>,
+}
+
+impl JmxRuntimeRef<'_> {
+    /// Convert into the owned [`JmxRuntime`], copying any borrowed data.
+    pub fn into_owned(self) -> JmxRuntime {
+        JmxRuntime {
+            vm_name: self.vm_name.into_owned(),
+            vm_vendor: self.vm_vendor.into_owned(),
+            vm_version: self.vm_version.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_jmx_runtime_ref(input: &[u8]) -> IResult<&[u8], JmxRuntimeRef<'_>> {
+    let (input, vm_name) = parse_sflow_str(input)?;
+    let (input, vm_vendor) = parse_sflow_str(input)?;
+    let (input, vm_version) = parse_sflow_str(input)?;
 
     Ok((
         input,
-        JmxRuntime {
+        JmxRuntimeRef {
             vm_name,
             vm_vendor,
             vm_version,
         },
     ))
 }
+
+pub(crate) fn write_jmx_runtime(out: &mut Vec<u8>, r: &JmxRuntime) {
+    write_sflow_string(out, &r.vm_name);
+    write_sflow_string(out, &r.vm_vendor);
+    write_sflow_string(out, &r.vm_version);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JvmStatistics {
     pub heap_initial: u64,
@@ -71,3 +73,25 @@ pub(crate) fn parse_jvm_statistics(input: &[u8]) -> IResult<&[u8], JvmStatistics
         },
     ))
 }
+
+pub(crate) fn write_jvm_statistics(out: &mut Vec<u8>, r: &JvmStatistics) {
+    write_u64(out, r.heap_initial);
+    write_u64(out, r.heap_used);
+    write_u64(out, r.heap_committed);
+    write_u64(out, r.heap_max);
+    write_u64(out, r.non_heap_initial);
+    write_u64(out, r.non_heap_used);
+    write_u64(out, r.non_heap_committed);
+    write_u64(out, r.non_heap_max);
+    write_u32(out, r.gc_count);
+    write_u32(out, r.gc_time);
+    write_u32(out, r.classes_loaded);
+    write_u32(out, r.classes_total);
+    write_u32(out, r.classes_unloaded);
+    write_u32(out, r.compilation_time);
+    write_u32(out, r.threads_live);
+    write_u32(out, r.threads_daemon);
+    write_u32(out, r.threads_started);
+    write_u32(out, r.fds_open);
+    write_u32(out, r.fds_max);
+}
@@ -4,6 +4,8 @@ use nom::bytes::complete::take;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_mac, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LagPortStats {
     pub actor_system_id: MacAddress,
@@ -62,3 +64,19 @@ pub(crate) fn parse_lag_port_stats(input: &[u8]) -> IResult<&[u8], LagPortStats>
         },
     ))
 }
+
+pub(crate) fn write_lag_port_stats(out: &mut Vec<u8>, r: &LagPortStats) {
+    write_mac(out, &r.actor_system_id);
+    write_mac(out, &r.partner_system_id);
+    write_u32(out, r.attachment_individual);
+    // collector_state is not retained by the parser; emit zeroes
+    write_u32(out, 0);
+    write_u32(out, r.lacpdu_rx);
+    write_u32(out, r.marker_pdu_rx);
+    write_u32(out, r.marker_response_pdu_rx);
+    write_u32(out, r.unknown_rx);
+    write_u32(out, r.illegal_rx);
+    write_u32(out, r.lacpdu_tx);
+    write_u32(out, r.marker_pdu_tx);
+    write_u32(out, r.marker_response_pdu_tx);
+}
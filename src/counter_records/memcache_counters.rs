@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MemcacheCounters {
     pub cmd_set: u32,
@@ -107,3 +109,37 @@ pub(crate) fn parse_memcache_counters(input: &[u8]) -> IResult<&[u8], MemcacheCo
         },
     ))
 }
+
+pub(crate) fn write_memcache_counters(out: &mut Vec<u8>, r: &MemcacheCounters) {
+    write_u32(out, r.cmd_set);
+    write_u32(out, r.cmd_touch);
+    write_u32(out, r.cmd_flush);
+    write_u32(out, r.get_hits);
+    write_u32(out, r.get_misses);
+    write_u32(out, r.delete_hits);
+    write_u32(out, r.delete_misses);
+    write_u32(out, r.incr_hits);
+    write_u32(out, r.incr_misses);
+    write_u32(out, r.decr_hits);
+    write_u32(out, r.decr_misses);
+    write_u32(out, r.cas_hits);
+    write_u32(out, r.cas_misses);
+    write_u32(out, r.cas_badval);
+    write_u32(out, r.auth_cmds);
+    write_u32(out, r.auth_errors);
+    write_u32(out, r.threads);
+    write_u32(out, r.conn_yields);
+    write_u32(out, r.listen_disabled_num);
+    write_u32(out, r.curr_connections);
+    write_u32(out, r.rejected_connections);
+    write_u32(out, r.total_connections);
+    write_u32(out, r.connection_structures);
+    write_u32(out, r.evictions);
+    write_u32(out, r.reclaimed);
+    write_u32(out, r.curr_items);
+    write_u32(out, r.total_items);
+    write_u64(out, r.bytes_read);
+    write_u64(out, r.bytes_written);
+    write_u64(out, r.bytes);
+    write_u64(out, r.limit_maxbytes);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mib2IcmpGroup {
     pub icmp_in_msgs: u32,
@@ -92,3 +94,32 @@ pub(crate) fn parse_mib2_icmp_group(input: &[u8]) -> IResult<&[u8], Mib2IcmpGrou
         },
     ))
 }
+
+pub(crate) fn write_mib2_icmp_group(out: &mut Vec<u8>, r: &Mib2IcmpGroup) {
+    write_u32(out, r.icmp_in_msgs);
+    write_u32(out, r.icmp_in_errors);
+    write_u32(out, r.icmp_in_dest_unreachs);
+    write_u32(out, r.icmp_in_time_excds);
+    write_u32(out, r.icmp_in_parm_probs);
+    write_u32(out, r.icmp_in_src_quenchs);
+    write_u32(out, r.icmp_in_redirects);
+    write_u32(out, r.icmp_in_echos);
+    write_u32(out, r.icmp_in_echo_reps);
+    write_u32(out, r.icmp_in_timestamps);
+    write_u32(out, r.icmp_in_timestamp_reps);
+    write_u32(out, r.icmp_in_addr_masks);
+    write_u32(out, r.icmp_in_addr_mask_reps);
+    write_u32(out, r.icmp_out_msgs);
+    write_u32(out, r.icmp_out_errors);
+    write_u32(out, r.icmp_out_dest_unreachs);
+    write_u32(out, r.icmp_out_time_excds);
+    write_u32(out, r.icmp_out_parm_probs);
+    write_u32(out, r.icmp_out_src_quenchs);
+    write_u32(out, r.icmp_out_redirects);
+    write_u32(out, r.icmp_out_echos);
+    write_u32(out, r.icmp_out_echo_reps);
+    write_u32(out, r.icmp_out_timestamps);
+    write_u32(out, r.icmp_out_timestamp_reps);
+    write_u32(out, r.icmp_out_addr_masks);
+    write_u32(out, r.icmp_out_addr_mask_reps);
+}
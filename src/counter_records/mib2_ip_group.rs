@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mib2IpGroup {
     pub ip_forwarding: u32,
@@ -71,3 +73,25 @@ pub(crate) fn parse_mib2_ip_group(input: &[u8]) -> IResult<&[u8], Mib2IpGroup> {
         },
     ))
 }
+
+pub(crate) fn write_mib2_ip_group(out: &mut Vec<u8>, r: &Mib2IpGroup) {
+    write_u32(out, r.ip_forwarding);
+    write_u32(out, r.ip_default_ttl);
+    write_u32(out, r.ip_in_receives);
+    write_u32(out, r.ip_in_hdr_errors);
+    write_u32(out, r.ip_in_addr_errors);
+    write_u32(out, r.ip_forw_datagrams);
+    write_u32(out, r.ip_in_unknown_protos);
+    write_u32(out, r.ip_in_discards);
+    write_u32(out, r.ip_in_delivers);
+    write_u32(out, r.ip_out_requests);
+    write_u32(out, r.ip_out_discards);
+    write_u32(out, r.ip_out_no_routes);
+    write_u32(out, r.ip_reasm_timeout);
+    write_u32(out, r.ip_reasm_reqds);
+    write_u32(out, r.ip_reasm_oks);
+    write_u32(out, r.ip_reasm_fails);
+    write_u32(out, r.ip_frag_oks);
+    write_u32(out, r.ip_frag_fails);
+    write_u32(out, r.ip_frag_creates);
+}
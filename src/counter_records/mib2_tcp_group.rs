@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mib2TcpGroup {
     pub tcp_rto_algorithm: u32,
@@ -59,3 +61,21 @@ pub(crate) fn parse_mib2_tcp_group(input: &[u8]) -> IResult<&[u8], Mib2TcpGroup>
         },
     ))
 }
+
+pub(crate) fn write_mib2_tcp_group(out: &mut Vec<u8>, r: &Mib2TcpGroup) {
+    write_u32(out, r.tcp_rto_algorithm);
+    write_u32(out, r.tcp_rto_min);
+    write_u32(out, r.tcp_rto_max);
+    write_u32(out, r.tcp_max_conn);
+    write_u32(out, r.tcp_active_opens);
+    write_u32(out, r.tcp_passive_opens);
+    write_u32(out, r.tcp_attempt_fails);
+    write_u32(out, r.tcp_estab_resets);
+    write_u32(out, r.tcp_curr_estab);
+    write_u32(out, r.tcp_in_segs);
+    write_u32(out, r.tcp_out_segs);
+    write_u32(out, r.tcp_retrans_segs);
+    write_u32(out, r.tcp_in_errs);
+    write_u32(out, r.tcp_out_rsts);
+    write_u32(out, r.tcp_in_csum_errs);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mib2UdpGroup {
     pub udp_in_datagrams: u32,
@@ -35,3 +37,13 @@ pub(crate) fn parse_mib2_udp_group(input: &[u8]) -> IResult<&[u8], Mib2UdpGroup>
         },
     ))
 }
+
+pub(crate) fn write_mib2_udp_group(out: &mut Vec<u8>, r: &Mib2UdpGroup) {
+    write_u32(out, r.udp_in_datagrams);
+    write_u32(out, r.udp_no_ports);
+    write_u32(out, r.udp_in_errors);
+    write_u32(out, r.udp_out_datagrams);
+    write_u32(out, r.udp_rcvbuf_errors);
+    write_u32(out, r.udp_sndbuf_errors);
+    write_u32(out, r.udp_in_csum_errors);
+}
@@ -44,19 +44,22 @@ pub mod vlan;
 pub mod xen_vif;
 
 use nom::IResult;
-use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-pub use app_operations::AppOperations;
+use crate::custom::Custom;
+use crate::encode::{write_tagged, write_u32};
+use crate::flow_records::split_record;
+
+pub use app_operations::{AppOperations, AppOperationsRef};
 pub use app_resources::AppResources;
 pub use app_workers::AppWorkers;
 pub use energy::Energy;
 pub use ethernet_interface::EthernetInterface;
 pub use fans::Fans;
-pub use generic_interface::GenericInterface;
+pub use generic_interface::{GenericInterface, IfDirection};
 pub use host_adapters::HostAdapters;
 pub use host_cpu::HostCpu;
-pub use host_descr::{HostDescr, MachineType, OsName};
+pub use host_descr::{HostDescr, HostDescrRef, MachineType, OsName};
 pub use host_disk_io::HostDiskIo;
 pub use host_memory::HostMemory;
 pub use host_net_io::HostNetIo;
@@ -65,7 +68,7 @@ pub use http_counters::HttpCounters;
 pub use humidity::Humidity;
 pub use ib_counters::IbCounters;
 pub use ieee80211_counters::Ieee80211Counters;
-pub use jmx_runtime::JmxRuntime;
+pub use jmx_runtime::{JmxRuntime, JmxRuntimeRef};
 pub use jvm_statistics::JvmStatistics;
 pub use lag_port_stats::LagPortStats;
 pub use memcache_counters::MemcacheCounters;
@@ -75,7 +78,7 @@ pub use mib2_tcp_group::Mib2TcpGroup;
 pub use mib2_udp_group::Mib2UdpGroup;
 pub use of_port::OfPort;
 pub use ovs_dp_stats::OvsDpStats;
-pub use port_name::PortName;
+pub use port_name::{PortName, PortNameRef};
 pub use processor::Processor;
 pub use queue_length::QueueLength;
 pub use radio_utilization::RadioUtilization;
@@ -195,231 +198,544 @@ pub enum CounterRecord {
         /// Raw record data.
         data: Vec<u8>,
     },
+    /// Enterprise-specific counter record decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
 }
 
-pub(crate) fn parse_counter_records(
-    mut input: &[u8],
-    num_records: u32,
-) -> IResult<&[u8], Vec<CounterRecord>> {
-    // Cap capacity to prevent DoS: each record needs at least 8 bytes (format + length)
-    let cap = (num_records as usize).min(input.len() / 8);
-    let mut records = Vec::with_capacity(cap);
+/// Borrowed form of [`CounterRecord`].
+///
+/// Records carrying strings or opaque bytes borrow them from the input
+/// buffer; the remaining variants hold the same types as [`CounterRecord`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum CounterRecordRef<'a> {
+    /// Generic interface counters (enterprise=0, format=1).
+    GenericInterface(GenericInterface),
+    /// Ethernet-specific interface counters (enterprise=0, format=2).
+    EthernetInterface(EthernetInterface),
+    /// Token Ring interface counters (enterprise=0, format=3).
+    TokenRing(TokenRing),
+    /// 100VG-AnyLAN counters (enterprise=0, format=4).
+    VgCounters(VgCounters),
+    /// VLAN counters (enterprise=0, format=5).
+    Vlan(Vlan),
+    /// IEEE 802.11 counters (enterprise=0, format=6).
+    Ieee80211Counters(Ieee80211Counters),
+    /// LAG port statistics (enterprise=0, format=7).
+    LagPortStats(LagPortStats),
+    /// Slow path counts (enterprise=0, format=8).
+    SlowPathCounts(SlowPathCounts),
+    /// InfiniBand counters (enterprise=0, format=9).
+    IbCounters(IbCounters),
+    /// SFP/optical transceiver counters (enterprise=0, format=10).
+    Sfp(Sfp),
+    /// Processor/CPU counters (enterprise=0, format=1001).
+    Processor(Processor),
+    /// Radio utilization counters (enterprise=0, format=1002).
+    RadioUtilization(RadioUtilization),
+    /// Queue length histogram (enterprise=0, format=1003).
+    QueueLength(QueueLength),
+    /// OpenFlow port mapping (enterprise=0, format=1004).
+    OfPort(OfPort),
+    /// Port name (enterprise=0, format=1005).
+    PortName(PortNameRef<'a>),
+    /// Host description (enterprise=0, format=2000).
+    HostDescr(HostDescrRef<'a>),
+    /// Host network adapters (enterprise=0, format=2001).
+    HostAdapters(HostAdapters),
+    /// Host parent (virtualization) (enterprise=0, format=2002).
+    HostParent(HostParent),
+    /// Host CPU counters (enterprise=0, format=2003).
+    HostCpu(HostCpu),
+    /// Host memory counters (enterprise=0, format=2004).
+    HostMemory(HostMemory),
+    /// Host disk I/O counters (enterprise=0, format=2005).
+    HostDiskIo(HostDiskIo),
+    /// Host network I/O counters (enterprise=0, format=2006).
+    HostNetIo(HostNetIo),
+    /// Virtual node statistics (enterprise=0, format=2100).
+    VirtNode(VirtNode),
+    /// Virtual CPU statistics (enterprise=0, format=2101).
+    VirtCpu(VirtCpu),
+    /// Virtual memory statistics (enterprise=0, format=2102).
+    VirtMemory(VirtMemory),
+    /// Virtual disk I/O statistics (enterprise=0, format=2103).
+    VirtDiskIo(VirtDiskIo),
+    /// Virtual network I/O statistics (enterprise=0, format=2104).
+    VirtNetIo(VirtNetIo),
+    /// MIB-II IP group counters (enterprise=0, format=2007).
+    Mib2IpGroup(Mib2IpGroup),
+    /// MIB-II ICMP group counters (enterprise=0, format=2008).
+    Mib2IcmpGroup(Mib2IcmpGroup),
+    /// MIB-II TCP group counters (enterprise=0, format=2009).
+    Mib2TcpGroup(Mib2TcpGroup),
+    /// MIB-II UDP group counters (enterprise=0, format=2010).
+    Mib2UdpGroup(Mib2UdpGroup),
+    /// JMX runtime information (enterprise=0, format=2105).
+    JmxRuntime(JmxRuntimeRef<'a>),
+    /// JVM statistics counters (enterprise=0, format=2106).
+    JvmStatistics(JvmStatistics),
+    /// HTTP method and status counters (enterprise=0, format=2201).
+    HttpCounters(HttpCounters),
+    /// Application operations counters (enterprise=0, format=2202).
+    AppOperations(AppOperationsRef<'a>),
+    /// Application resource counters (enterprise=0, format=2203).
+    AppResources(AppResources),
+    /// Memcache counters (enterprise=0, format=2204).
+    MemcacheCounters(MemcacheCounters),
+    /// Application worker counters (enterprise=0, format=2206).
+    AppWorkers(AppWorkers),
+    /// Open vSwitch datapath statistics (enterprise=0, format=2207).
+    OvsDpStats(OvsDpStats),
+    /// Energy consumption counters (enterprise=0, format=3000).
+    Energy(Energy),
+    /// Temperature sensor counters (enterprise=0, format=3001).
+    Temperature(Temperature),
+    /// Humidity sensor counters (enterprise=0, format=3002).
+    Humidity(Humidity),
+    /// Fan status counters (enterprise=0, format=3003).
+    Fans(Fans),
+    /// XenServer virtual interface metadata (enterprise=4300, format=2).
+    XenVif(XenVif),
+    /// Unrecognized counter record type, preserved as raw bytes.
+    Unknown {
+        /// Enterprise code from the record header.
+        enterprise: u32,
+        /// Format code from the record header.
+        format: u32,
+        /// Raw record data.
+        data: &'a [u8],
+    },
+    /// Enterprise-specific counter record decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
+}
 
-    for _ in 0..num_records {
-        let (rest, data_format) = be_u32(input)?;
-        let enterprise = data_format >> 12;
-        let format = data_format & 0xFFF;
+impl CounterRecordRef<'_> {
+    /// Convert into the owned [`CounterRecord`], copying any borrowed data.
+    pub fn into_owned(self) -> CounterRecord {
+        match self {
+            CounterRecordRef::GenericInterface(r) => CounterRecord::GenericInterface(r),
+            CounterRecordRef::EthernetInterface(r) => CounterRecord::EthernetInterface(r),
+            CounterRecordRef::TokenRing(r) => CounterRecord::TokenRing(r),
+            CounterRecordRef::VgCounters(r) => CounterRecord::VgCounters(r),
+            CounterRecordRef::Vlan(r) => CounterRecord::Vlan(r),
+            CounterRecordRef::Ieee80211Counters(r) => CounterRecord::Ieee80211Counters(r),
+            CounterRecordRef::LagPortStats(r) => CounterRecord::LagPortStats(r),
+            CounterRecordRef::SlowPathCounts(r) => CounterRecord::SlowPathCounts(r),
+            CounterRecordRef::IbCounters(r) => CounterRecord::IbCounters(r),
+            CounterRecordRef::Sfp(r) => CounterRecord::Sfp(r),
+            CounterRecordRef::Processor(r) => CounterRecord::Processor(r),
+            CounterRecordRef::RadioUtilization(r) => CounterRecord::RadioUtilization(r),
+            CounterRecordRef::QueueLength(r) => CounterRecord::QueueLength(r),
+            CounterRecordRef::OfPort(r) => CounterRecord::OfPort(r),
+            CounterRecordRef::PortName(r) => CounterRecord::PortName(r.into_owned()),
+            CounterRecordRef::HostDescr(r) => CounterRecord::HostDescr(r.into_owned()),
+            CounterRecordRef::HostAdapters(r) => CounterRecord::HostAdapters(r),
+            CounterRecordRef::HostParent(r) => CounterRecord::HostParent(r),
+            CounterRecordRef::HostCpu(r) => CounterRecord::HostCpu(r),
+            CounterRecordRef::HostMemory(r) => CounterRecord::HostMemory(r),
+            CounterRecordRef::HostDiskIo(r) => CounterRecord::HostDiskIo(r),
+            CounterRecordRef::HostNetIo(r) => CounterRecord::HostNetIo(r),
+            CounterRecordRef::VirtNode(r) => CounterRecord::VirtNode(r),
+            CounterRecordRef::VirtCpu(r) => CounterRecord::VirtCpu(r),
+            CounterRecordRef::VirtMemory(r) => CounterRecord::VirtMemory(r),
+            CounterRecordRef::VirtDiskIo(r) => CounterRecord::VirtDiskIo(r),
+            CounterRecordRef::VirtNetIo(r) => CounterRecord::VirtNetIo(r),
+            CounterRecordRef::Mib2IpGroup(r) => CounterRecord::Mib2IpGroup(r),
+            CounterRecordRef::Mib2IcmpGroup(r) => CounterRecord::Mib2IcmpGroup(r),
+            CounterRecordRef::Mib2TcpGroup(r) => CounterRecord::Mib2TcpGroup(r),
+            CounterRecordRef::Mib2UdpGroup(r) => CounterRecord::Mib2UdpGroup(r),
+            CounterRecordRef::JmxRuntime(r) => CounterRecord::JmxRuntime(r.into_owned()),
+            CounterRecordRef::JvmStatistics(r) => CounterRecord::JvmStatistics(r),
+            CounterRecordRef::HttpCounters(r) => CounterRecord::HttpCounters(r),
+            CounterRecordRef::AppOperations(r) => CounterRecord::AppOperations(r.into_owned()),
+            CounterRecordRef::AppResources(r) => CounterRecord::AppResources(r),
+            CounterRecordRef::MemcacheCounters(r) => CounterRecord::MemcacheCounters(r),
+            CounterRecordRef::AppWorkers(r) => CounterRecord::AppWorkers(r),
+            CounterRecordRef::OvsDpStats(r) => CounterRecord::OvsDpStats(r),
+            CounterRecordRef::Energy(r) => CounterRecord::Energy(r),
+            CounterRecordRef::Temperature(r) => CounterRecord::Temperature(r),
+            CounterRecordRef::Humidity(r) => CounterRecord::Humidity(r),
+            CounterRecordRef::Fans(r) => CounterRecord::Fans(r),
+            CounterRecordRef::XenVif(r) => CounterRecord::XenVif(r),
+            CounterRecordRef::Unknown {
+                enterprise,
+                format,
+                data,
+            } => CounterRecord::Unknown {
+                enterprise,
+                format,
+                data: data.to_vec(),
+            },
+            CounterRecordRef::Custom(c) => CounterRecord::Custom(c),
+        }
+    }
+}
 
-        let (rest, record_length) = be_u32(rest)?;
-        let record_length = record_length as usize;
+impl<'a> From<CounterRecordRef<'a>> for CounterRecord {
+    fn from(r: CounterRecordRef<'a>) -> Self {
+        r.into_owned()
+    }
+}
 
-        if rest.len() < record_length {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                rest,
-                nom::error::ErrorKind::Eof,
-            )));
+impl CounterRecord {
+    /// The `(enterprise, format)` pair identifying this record type on the wire.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        match self {
+            CounterRecord::GenericInterface(_) => (0, 1),
+            CounterRecord::EthernetInterface(_) => (0, 2),
+            CounterRecord::TokenRing(_) => (0, 3),
+            CounterRecord::VgCounters(_) => (0, 4),
+            CounterRecord::Vlan(_) => (0, 5),
+            CounterRecord::Ieee80211Counters(_) => (0, 6),
+            CounterRecord::LagPortStats(_) => (0, 7),
+            CounterRecord::SlowPathCounts(_) => (0, 8),
+            CounterRecord::IbCounters(_) => (0, 9),
+            CounterRecord::Sfp(_) => (0, 10),
+            CounterRecord::Processor(_) => (0, 1001),
+            CounterRecord::QueueLength(_) => (0, 1003),
+            CounterRecord::RadioUtilization(_) => (0, 1002),
+            CounterRecord::OfPort(_) => (0, 1004),
+            CounterRecord::PortName(_) => (0, 1005),
+            CounterRecord::HostDescr(_) => (0, 2000),
+            CounterRecord::HostAdapters(_) => (0, 2001),
+            CounterRecord::HostParent(_) => (0, 2002),
+            CounterRecord::HostCpu(_) => (0, 2003),
+            CounterRecord::HostMemory(_) => (0, 2004),
+            CounterRecord::HostDiskIo(_) => (0, 2005),
+            CounterRecord::HostNetIo(_) => (0, 2006),
+            CounterRecord::VirtNode(_) => (0, 2100),
+            CounterRecord::VirtCpu(_) => (0, 2101),
+            CounterRecord::VirtMemory(_) => (0, 2102),
+            CounterRecord::VirtDiskIo(_) => (0, 2103),
+            CounterRecord::VirtNetIo(_) => (0, 2104),
+            CounterRecord::Mib2IpGroup(_) => (0, 2007),
+            CounterRecord::Mib2IcmpGroup(_) => (0, 2008),
+            CounterRecord::Mib2TcpGroup(_) => (0, 2009),
+            CounterRecord::Mib2UdpGroup(_) => (0, 2010),
+            CounterRecord::JmxRuntime(_) => (0, 2105),
+            CounterRecord::JvmStatistics(_) => (0, 2106),
+            CounterRecord::HttpCounters(_) => (0, 2201),
+            CounterRecord::AppOperations(_) => (0, 2202),
+            CounterRecord::AppResources(_) => (0, 2203),
+            CounterRecord::MemcacheCounters(_) => (0, 2204),
+            CounterRecord::AppWorkers(_) => (0, 2206),
+            CounterRecord::OvsDpStats(_) => (0, 2207),
+            CounterRecord::Energy(_) => (0, 3000),
+            CounterRecord::Temperature(_) => (0, 3001),
+            CounterRecord::Humidity(_) => (0, 3002),
+            CounterRecord::Fans(_) => (0, 3003),
+            CounterRecord::XenVif(_) => (4300, 2),
+            CounterRecord::Unknown {
+                enterprise, format, ..
+            } => (*enterprise, *format),
+            CounterRecord::Custom(c) => (c.enterprise, c.format),
         }
+    }
+
+    /// Append the XDR encoding of this record, including its
+    /// `data_format` and `length` header, to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let (enterprise, format) = self.enterprise_format();
+        write_tagged(out, enterprise, format, |out| self.encode_body(out));
+    }
+
+    /// Encode this record, including its header, into a new buffer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
 
-        let record_data = &rest[..record_length];
-        let after_record = &rest[record_length..];
+    fn encode_body(&self, out: &mut Vec<u8>) {
+        match self {
+            CounterRecord::GenericInterface(r) => {
+                generic_interface::write_generic_interface(out, r)
+            }
+            CounterRecord::EthernetInterface(r) => {
+                ethernet_interface::write_ethernet_interface(out, r)
+            }
+            CounterRecord::TokenRing(r) => token_ring::write_token_ring(out, r),
+            CounterRecord::VgCounters(r) => vg_counters::write_vg_counters(out, r),
+            CounterRecord::Vlan(r) => vlan::write_vlan(out, r),
+            CounterRecord::Ieee80211Counters(r) => {
+                ieee80211_counters::write_ieee80211_counters(out, r)
+            }
+            CounterRecord::LagPortStats(r) => lag_port_stats::write_lag_port_stats(out, r),
+            CounterRecord::SlowPathCounts(r) => {
+                slow_path_counts::write_slow_path_counts(out, r)
+            }
+            CounterRecord::IbCounters(r) => ib_counters::write_ib_counters(out, r),
+            CounterRecord::Sfp(r) => sfp::write_sfp(out, r),
+            CounterRecord::Processor(r) => processor::write_processor(out, r),
+            CounterRecord::QueueLength(r) => queue_length::write_queue_length(out, r),
+            CounterRecord::RadioUtilization(r) => {
+                radio_utilization::write_radio_utilization(out, r)
+            }
+            CounterRecord::OfPort(r) => of_port::write_of_port(out, r),
+            CounterRecord::PortName(r) => port_name::write_port_name(out, r),
+            CounterRecord::HostDescr(r) => host_descr::write_host_descr(out, r),
+            CounterRecord::HostAdapters(r) => host_adapters::write_host_adapters(out, r),
+            CounterRecord::HostParent(r) => host_parent::write_host_parent(out, r),
+            CounterRecord::HostCpu(r) => host_cpu::write_host_cpu(out, r),
+            CounterRecord::HostMemory(r) => host_memory::write_host_memory(out, r),
+            CounterRecord::HostDiskIo(r) => host_disk_io::write_host_disk_io(out, r),
+            CounterRecord::HostNetIo(r) => host_net_io::write_host_net_io(out, r),
+            CounterRecord::VirtNode(r) => virt_node::write_virt_node(out, r),
+            CounterRecord::VirtCpu(r) => virt_cpu::write_virt_cpu(out, r),
+            CounterRecord::VirtMemory(r) => virt_memory::write_virt_memory(out, r),
+            CounterRecord::VirtDiskIo(r) => virt_disk_io::write_virt_disk_io(out, r),
+            CounterRecord::VirtNetIo(r) => virt_net_io::write_virt_net_io(out, r),
+            CounterRecord::Mib2IpGroup(r) => mib2_ip_group::write_mib2_ip_group(out, r),
+            CounterRecord::Mib2IcmpGroup(r) => mib2_icmp_group::write_mib2_icmp_group(out, r),
+            CounterRecord::Mib2TcpGroup(r) => mib2_tcp_group::write_mib2_tcp_group(out, r),
+            CounterRecord::Mib2UdpGroup(r) => mib2_udp_group::write_mib2_udp_group(out, r),
+            CounterRecord::JmxRuntime(r) => jmx_runtime::write_jmx_runtime(out, r),
+            CounterRecord::JvmStatistics(r) => jvm_statistics::write_jvm_statistics(out, r),
+            CounterRecord::HttpCounters(r) => http_counters::write_http_counters(out, r),
+            CounterRecord::AppOperations(r) => app_operations::write_app_operations(out, r),
+            CounterRecord::AppResources(r) => app_resources::write_app_resources(out, r),
+            CounterRecord::MemcacheCounters(r) => {
+                memcache_counters::write_memcache_counters(out, r)
+            }
+            CounterRecord::AppWorkers(r) => app_workers::write_app_workers(out, r),
+            CounterRecord::OvsDpStats(r) => ovs_dp_stats::write_ovs_dp_stats(out, r),
+            CounterRecord::Energy(r) => energy::write_energy(out, r),
+            CounterRecord::Temperature(r) => temperature::write_temperature(out, r),
+            CounterRecord::Humidity(r) => humidity::write_humidity(out, r),
+            CounterRecord::Fans(r) => fans::write_fans(out, r),
+            CounterRecord::XenVif(r) => xen_vif::write_xen_vif(out, r),
+            CounterRecord::Unknown { data, .. } => out.extend_from_slice(data),
+            CounterRecord::Custom(c) => out.extend_from_slice(&c.data),
+        }
+    }
+}
 
-        let record = if enterprise == 0 {
-            match format {
-                1 => {
-                    let (_, r) = generic_interface::parse_generic_interface(record_data)?;
-                    CounterRecord::GenericInterface(r)
-                }
-                2 => {
-                    let (_, r) = ethernet_interface::parse_ethernet_interface(record_data)?;
-                    CounterRecord::EthernetInterface(r)
-                }
-                3 => {
-                    let (_, r) = token_ring::parse_token_ring(record_data)?;
-                    CounterRecord::TokenRing(r)
-                }
-                4 => {
-                    let (_, r) = vg_counters::parse_vg_counters(record_data)?;
-                    CounterRecord::VgCounters(r)
-                }
-                5 => {
-                    let (_, r) = vlan::parse_vlan(record_data)?;
-                    CounterRecord::Vlan(r)
-                }
-                6 => {
-                    let (_, r) = ieee80211_counters::parse_ieee80211_counters(record_data)?;
-                    CounterRecord::Ieee80211Counters(r)
-                }
-                7 => {
-                    let (_, r) = lag_port_stats::parse_lag_port_stats(record_data)?;
-                    CounterRecord::LagPortStats(r)
-                }
-                8 => {
-                    let (_, r) = slow_path_counts::parse_slow_path_counts(record_data)?;
-                    CounterRecord::SlowPathCounts(r)
-                }
-                9 => {
-                    let (_, r) = ib_counters::parse_ib_counters(record_data)?;
-                    CounterRecord::IbCounters(r)
-                }
-                10 => {
-                    let (_, r) = sfp::parse_sfp(record_data)?;
-                    CounterRecord::Sfp(r)
-                }
-                1001 => {
-                    let (_, r) = processor::parse_processor(record_data)?;
-                    CounterRecord::Processor(r)
-                }
-                1003 => {
-                    let (_, r) = queue_length::parse_queue_length(record_data)?;
-                    CounterRecord::QueueLength(r)
-                }
-                1002 => {
-                    let (_, r) = radio_utilization::parse_radio_utilization(record_data)?;
-                    CounterRecord::RadioUtilization(r)
-                }
-                1004 => {
-                    let (_, r) = of_port::parse_of_port(record_data)?;
-                    CounterRecord::OfPort(r)
-                }
-                1005 => {
-                    let (_, r) = port_name::parse_port_name(record_data)?;
-                    CounterRecord::PortName(r)
-                }
-                2000 => {
-                    let (_, r) = host_descr::parse_host_descr(record_data)?;
-                    CounterRecord::HostDescr(r)
-                }
-                2001 => {
-                    let (_, r) = host_adapters::parse_host_adapters(record_data)?;
-                    CounterRecord::HostAdapters(r)
-                }
-                2002 => {
-                    let (_, r) = host_parent::parse_host_parent(record_data)?;
-                    CounterRecord::HostParent(r)
-                }
-                2003 => {
-                    let (_, r) = host_cpu::parse_host_cpu(record_data)?;
-                    CounterRecord::HostCpu(r)
-                }
-                2004 => {
-                    let (_, r) = host_memory::parse_host_memory(record_data)?;
-                    CounterRecord::HostMemory(r)
-                }
-                2005 => {
-                    let (_, r) = host_disk_io::parse_host_disk_io(record_data)?;
-                    CounterRecord::HostDiskIo(r)
-                }
-                2006 => {
-                    let (_, r) = host_net_io::parse_host_net_io(record_data)?;
-                    CounterRecord::HostNetIo(r)
-                }
-                2100 => {
-                    let (_, r) = virt_node::parse_virt_node(record_data)?;
-                    CounterRecord::VirtNode(r)
-                }
-                2101 => {
-                    let (_, r) = virt_cpu::parse_virt_cpu(record_data)?;
-                    CounterRecord::VirtCpu(r)
-                }
-                2102 => {
-                    let (_, r) = virt_memory::parse_virt_memory(record_data)?;
-                    CounterRecord::VirtMemory(r)
-                }
-                2103 => {
-                    let (_, r) = virt_disk_io::parse_virt_disk_io(record_data)?;
-                    CounterRecord::VirtDiskIo(r)
-                }
-                2104 => {
-                    let (_, r) = virt_net_io::parse_virt_net_io(record_data)?;
-                    CounterRecord::VirtNetIo(r)
-                }
-                2007 => {
-                    let (_, r) = mib2_ip_group::parse_mib2_ip_group(record_data)?;
-                    CounterRecord::Mib2IpGroup(r)
-                }
-                2008 => {
-                    let (_, r) = mib2_icmp_group::parse_mib2_icmp_group(record_data)?;
-                    CounterRecord::Mib2IcmpGroup(r)
-                }
-                2009 => {
-                    let (_, r) = mib2_tcp_group::parse_mib2_tcp_group(record_data)?;
-                    CounterRecord::Mib2TcpGroup(r)
-                }
-                2010 => {
-                    let (_, r) = mib2_udp_group::parse_mib2_udp_group(record_data)?;
-                    CounterRecord::Mib2UdpGroup(r)
-                }
-                2105 => {
-                    let (_, r) = jmx_runtime::parse_jmx_runtime(record_data)?;
-                    CounterRecord::JmxRuntime(r)
-                }
-                2106 => {
-                    let (_, r) = jvm_statistics::parse_jvm_statistics(record_data)?;
-                    CounterRecord::JvmStatistics(r)
-                }
-                2201 => {
-                    let (_, r) = http_counters::parse_http_counters(record_data)?;
-                    CounterRecord::HttpCounters(r)
-                }
-                2202 => {
-                    let (_, r) = app_operations::parse_app_operations(record_data)?;
-                    CounterRecord::AppOperations(r)
-                }
-                2203 => {
-                    let (_, r) = app_resources::parse_app_resources(record_data)?;
-                    CounterRecord::AppResources(r)
-                }
-                2204 => {
-                    let (_, r) = memcache_counters::parse_memcache_counters(record_data)?;
-                    CounterRecord::MemcacheCounters(r)
-                }
-                2206 => {
-                    let (_, r) = app_workers::parse_app_workers(record_data)?;
-                    CounterRecord::AppWorkers(r)
-                }
-                2207 => {
-                    let (_, r) = ovs_dp_stats::parse_ovs_dp_stats(record_data)?;
-                    CounterRecord::OvsDpStats(r)
-                }
-                3000 => {
-                    let (_, r) = energy::parse_energy(record_data)?;
-                    CounterRecord::Energy(r)
-                }
-                3001 => {
-                    let (_, r) = temperature::parse_temperature(record_data)?;
-                    CounterRecord::Temperature(r)
-                }
-                3002 => {
-                    let (_, r) = humidity::parse_humidity(record_data)?;
-                    CounterRecord::Humidity(r)
-                }
-                3003 => {
-                    let (_, r) = fans::parse_fans(record_data)?;
-                    CounterRecord::Fans(r)
-                }
-                _ => CounterRecord::Unknown {
-                    enterprise,
-                    format,
-                    data: record_data.to_vec(),
-                },
-            }
-        } else {
-            match (enterprise, format) {
-                (4300, 2) => {
-                    let (_, r) = xen_vif::parse_xen_vif(record_data)?;
-                    CounterRecord::XenVif(r)
-                }
-                _ => CounterRecord::Unknown {
-                    enterprise,
-                    format,
-                    data: record_data.to_vec(),
-                },
-            }
-        };
+/// Parse `num_records` records into [`CounterRecord`] or [`CounterRecordRef`],
+/// converting each one as it is decoded.
+pub(crate) fn parse_counter_records<'a, R: From<CounterRecordRef<'a>>>(
+    mut input: &'a [u8],
+    num_records: u32,
+) -> IResult<&'a [u8], Vec<R>> {
+    // Cap capacity to prevent DoS: each record needs at least 8 bytes (format + length)
+    let cap = (num_records as usize).min(input.len() / 8);
+    let mut records = Vec::with_capacity(cap);
 
-        records.push(record);
+    for _ in 0..num_records {
+        let (after_record, (enterprise, format, record_data)) = split_record(input)?;
+        records.push(parse_counter_record_ref(enterprise, format, record_data)?.into());
         input = after_record;
     }
 
     Ok((input, records))
 }
+
+/// Decode a single counter record body identified by `enterprise` and `format`.
+pub(crate) fn parse_counter_record_ref(
+    enterprise: u32,
+    format: u32,
+    record_data: &[u8],
+) -> Result<CounterRecordRef<'_>, nom::Err<nom::error::Error<&[u8]>>> {
+    let record = if enterprise == 0 {
+        match format {
+            1 => {
+                let (_, r) = generic_interface::parse_generic_interface(record_data)?;
+                CounterRecordRef::GenericInterface(r)
+            }
+            2 => {
+                let (_, r) = ethernet_interface::parse_ethernet_interface(record_data)?;
+                CounterRecordRef::EthernetInterface(r)
+            }
+            3 => {
+                let (_, r) = token_ring::parse_token_ring(record_data)?;
+                CounterRecordRef::TokenRing(r)
+            }
+            4 => {
+                let (_, r) = vg_counters::parse_vg_counters(record_data)?;
+                CounterRecordRef::VgCounters(r)
+            }
+            5 => {
+                let (_, r) = vlan::parse_vlan(record_data)?;
+                CounterRecordRef::Vlan(r)
+            }
+            6 => {
+                let (_, r) = ieee80211_counters::parse_ieee80211_counters(record_data)?;
+                CounterRecordRef::Ieee80211Counters(r)
+            }
+            7 => {
+                let (_, r) = lag_port_stats::parse_lag_port_stats(record_data)?;
+                CounterRecordRef::LagPortStats(r)
+            }
+            8 => {
+                let (_, r) = slow_path_counts::parse_slow_path_counts(record_data)?;
+                CounterRecordRef::SlowPathCounts(r)
+            }
+            9 => {
+                let (_, r) = ib_counters::parse_ib_counters(record_data)?;
+                CounterRecordRef::IbCounters(r)
+            }
+            10 => {
+                let (_, r) = sfp::parse_sfp(record_data)?;
+                CounterRecordRef::Sfp(r)
+            }
+            1001 => {
+                let (_, r) = processor::parse_processor(record_data)?;
+                CounterRecordRef::Processor(r)
+            }
+            1003 => {
+                let (_, r) = queue_length::parse_queue_length(record_data)?;
+                CounterRecordRef::QueueLength(r)
+            }
+            1002 => {
+                let (_, r) = radio_utilization::parse_radio_utilization(record_data)?;
+                CounterRecordRef::RadioUtilization(r)
+            }
+            1004 => {
+                let (_, r) = of_port::parse_of_port(record_data)?;
+                CounterRecordRef::OfPort(r)
+            }
+            1005 => {
+                let (_, r) = port_name::parse_port_name_ref(record_data)?;
+                CounterRecordRef::PortName(r)
+            }
+            2000 => {
+                let (_, r) = host_descr::parse_host_descr_ref(record_data)?;
+                CounterRecordRef::HostDescr(r)
+            }
+            2001 => {
+                let (_, r) = host_adapters::parse_host_adapters(record_data)?;
+                CounterRecordRef::HostAdapters(r)
+            }
+            2002 => {
+                let (_, r) = host_parent::parse_host_parent(record_data)?;
+                CounterRecordRef::HostParent(r)
+            }
+            2003 => {
+                let (_, r) = host_cpu::parse_host_cpu(record_data)?;
+                CounterRecordRef::HostCpu(r)
+            }
+            2004 => {
+                let (_, r) = host_memory::parse_host_memory(record_data)?;
+                CounterRecordRef::HostMemory(r)
+            }
+            2005 => {
+                let (_, r) = host_disk_io::parse_host_disk_io(record_data)?;
+                CounterRecordRef::HostDiskIo(r)
+            }
+            2006 => {
+                let (_, r) = host_net_io::parse_host_net_io(record_data)?;
+                CounterRecordRef::HostNetIo(r)
+            }
+            2100 => {
+                let (_, r) = virt_node::parse_virt_node(record_data)?;
+                CounterRecordRef::VirtNode(r)
+            }
+            2101 => {
+                let (_, r) = virt_cpu::parse_virt_cpu(record_data)?;
+                CounterRecordRef::VirtCpu(r)
+            }
+            2102 => {
+                let (_, r) = virt_memory::parse_virt_memory(record_data)?;
+                CounterRecordRef::VirtMemory(r)
+            }
+            2103 => {
+                let (_, r) = virt_disk_io::parse_virt_disk_io(record_data)?;
+                CounterRecordRef::VirtDiskIo(r)
+            }
+            2104 => {
+                let (_, r) = virt_net_io::parse_virt_net_io(record_data)?;
+                CounterRecordRef::VirtNetIo(r)
+            }
+            2007 => {
+                let (_, r) = mib2_ip_group::parse_mib2_ip_group(record_data)?;
+                CounterRecordRef::Mib2IpGroup(r)
+            }
+            2008 => {
+                let (_, r) = mib2_icmp_group::parse_mib2_icmp_group(record_data)?;
+                CounterRecordRef::Mib2IcmpGroup(r)
+            }
+            2009 => {
+                let (_, r) = mib2_tcp_group::parse_mib2_tcp_group(record_data)?;
+                CounterRecordRef::Mib2TcpGroup(r)
+            }
+            2010 => {
+                let (_, r) = mib2_udp_group::parse_mib2_udp_group(record_data)?;
+                CounterRecordRef::Mib2UdpGroup(r)
+            }
+            2105 => {
+                let (_, r) = jmx_runtime::parse_jmx_runtime_ref(record_data)?;
+                CounterRecordRef::JmxRuntime(r)
+            }
+            2106 => {
+                let (_, r) = jvm_statistics::parse_jvm_statistics(record_data)?;
+                CounterRecordRef::JvmStatistics(r)
+            }
+            2201 => {
+                let (_, r) = http_counters::parse_http_counters(record_data)?;
+                CounterRecordRef::HttpCounters(r)
+            }
+            2202 => {
+                let (_, r) = app_operations::parse_app_operations_ref(record_data)?;
+                CounterRecordRef::AppOperations(r)
+            }
+            2203 => {
+                let (_, r) = app_resources::parse_app_resources(record_data)?;
+                CounterRecordRef::AppResources(r)
+            }
+            2204 => {
+                let (_, r) = memcache_counters::parse_memcache_counters(record_data)?;
+                CounterRecordRef::MemcacheCounters(r)
+            }
+            2206 => {
+                let (_, r) = app_workers::parse_app_workers(record_data)?;
+                CounterRecordRef::AppWorkers(r)
+            }
+            2207 => {
+                let (_, r) = ovs_dp_stats::parse_ovs_dp_stats(record_data)?;
+                CounterRecordRef::OvsDpStats(r)
+            }
+            3000 => {
+                let (_, r) = energy::parse_energy(record_data)?;
+                CounterRecordRef::Energy(r)
+            }
+            3001 => {
+                let (_, r) = temperature::parse_temperature(record_data)?;
+                CounterRecordRef::Temperature(r)
+            }
+            3002 => {
+                let (_, r) = humidity::parse_humidity(record_data)?;
+                CounterRecordRef::Humidity(r)
+            }
+            3003 => {
+                let (_, r) = fans::parse_fans(record_data)?;
+                CounterRecordRef::Fans(r)
+            }
+            _ => CounterRecordRef::Unknown {
+                enterprise,
+                format,
+                data: record_data,
+            },
+        }
+    } else {
+        match (enterprise, format) {
+            (4300, 2) => {
+                let (_, r) = xen_vif::parse_xen_vif(record_data)?;
+                CounterRecordRef::XenVif(r)
+            }
+            _ => CounterRecordRef::Unknown {
+                enterprise,
+                format,
+                data: record_data,
+            },
+        }
+    };
+    Ok(record)
+}
+
+pub(crate) fn write_counter_records(out: &mut Vec<u8>, records: &[CounterRecord]) {
+    write_u32(out, records.len() as u32);
+    for record in records {
+        record.encode(out);
+    }
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OfPort {
     pub datapath_id: u64,
@@ -20,3 +22,8 @@ pub(crate) fn parse_of_port(input: &[u8]) -> IResult<&[u8], OfPort> {
         },
     ))
 }
+
+pub(crate) fn write_of_port(out: &mut Vec<u8>, r: &OfPort) {
+    write_u64(out, r.datapath_id);
+    write_u32(out, r.port_no);
+}
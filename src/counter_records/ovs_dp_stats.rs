@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OvsDpStats {
     pub n_hit: u32,
@@ -32,3 +34,12 @@ pub(crate) fn parse_ovs_dp_stats(input: &[u8]) -> IResult<&[u8], OvsDpStats> {
         },
     ))
 }
+
+pub(crate) fn write_ovs_dp_stats(out: &mut Vec<u8>, r: &OvsDpStats) {
+    write_u32(out, r.n_hit);
+    write_u32(out, r.n_missed);
+    write_u32(out, r.n_lost);
+    write_u32(out, r.n_mask_hit);
+    write_u32(out, r.n_flows);
+    write_u32(out, r.n_masks);
+}
@@ -1,15 +1,35 @@
 use nom::IResult;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use crate::flow_records::parse_sflow_string;
+use crate::flow_records::{parse_sflow_str, write_sflow_string};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PortName {
     pub name: String,
 }
 
-pub(crate) fn parse_port_name(input: &[u8]) -> IResult<&[u8], PortName> {
-    let (input, name) = parse_sflow_string(input)?;
+/// Borrowed form of [`PortName`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PortNameRef<'a> {
+    pub name: Cow<'a, str>,
+}
+
+impl PortNameRef<'_> {
+    /// Convert into the owned [`PortName`], copying any borrowed data.
+    pub fn into_owned(self) -> PortName {
+        PortName {
+            name: self.name.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_port_name_ref(input: &[u8]) -> IResult<&[u8], PortNameRef<'_>> {
+    let (input, name) = parse_sflow_str(input)?;
+
+    Ok((input, PortNameRef { name }))
+}
 
-    Ok((input, PortName { name }))
+pub(crate) fn write_port_name(out: &mut Vec<u8>, r: &PortName) {
+    write_sflow_string(out, &r.name);
 }
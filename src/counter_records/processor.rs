@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Processor {
     pub cpu_5s: u32,
@@ -29,3 +31,11 @@ pub(crate) fn parse_processor(input: &[u8]) -> IResult<&[u8], Processor> {
         },
     ))
 }
+
+pub(crate) fn write_processor(out: &mut Vec<u8>, r: &Processor) {
+    write_u32(out, r.cpu_5s);
+    write_u32(out, r.cpu_1m);
+    write_u32(out, r.cpu_5m);
+    write_u64(out, r.total_memory);
+    write_u64(out, r.free_memory);
+}
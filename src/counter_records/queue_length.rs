@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct QueueLength {
     pub queue_index: u32,
@@ -53,3 +55,19 @@ pub(crate) fn parse_queue_length(input: &[u8]) -> IResult<&[u8], QueueLength> {
         },
     ))
 }
+
+pub(crate) fn write_queue_length(out: &mut Vec<u8>, r: &QueueLength) {
+    write_u32(out, r.queue_index);
+    write_u32(out, r.segment_size);
+    write_u32(out, r.queue_segments);
+    write_u32(out, r.queue_length_0);
+    write_u32(out, r.queue_length_1);
+    write_u32(out, r.queue_length_2);
+    write_u32(out, r.queue_length_4);
+    write_u32(out, r.queue_length_8);
+    write_u32(out, r.queue_length_32);
+    write_u32(out, r.queue_length_128);
+    write_u32(out, r.queue_length_1024);
+    write_u32(out, r.queue_length_more);
+    write_u32(out, r.dropped);
+}
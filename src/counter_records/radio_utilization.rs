@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RadioUtilization {
     pub elapsed_time: u32,
@@ -23,3 +25,9 @@ pub(crate) fn parse_radio_utilization(input: &[u8]) -> IResult<&[u8], RadioUtili
         },
     ))
 }
+
+pub(crate) fn write_radio_utilization(out: &mut Vec<u8>, r: &RadioUtilization) {
+    write_u32(out, r.elapsed_time);
+    write_u32(out, r.on_channel_time);
+    write_u32(out, r.on_channel_busy_time);
+}
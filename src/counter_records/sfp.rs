@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_i32, be_u32};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_i32, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SfpLane {
     pub tx_bias_current: u32,
@@ -82,3 +84,27 @@ pub(crate) fn parse_sfp(input: &[u8]) -> IResult<&[u8], Sfp> {
         },
     ))
 }
+
+fn write_sfp_lane(out: &mut Vec<u8>, r: &SfpLane) {
+    write_u32(out, r.tx_bias_current);
+    write_u32(out, r.tx_power);
+    write_u32(out, r.tx_power_min);
+    write_u32(out, r.tx_power_max);
+    write_u32(out, r.tx_wavelength);
+    write_u32(out, r.rx_power);
+    write_u32(out, r.rx_power_min);
+    write_u32(out, r.rx_power_max);
+    write_u32(out, r.rx_wavelength);
+    write_u32(out, r.bias_current);
+}
+
+/// The lane count is taken from `lanes`, not `module_num_lanes`.
+pub(crate) fn write_sfp(out: &mut Vec<u8>, r: &Sfp) {
+    write_u32(out, r.module_id);
+    write_u32(out, r.lanes.len() as u32);
+    write_u32(out, r.module_supply_voltage);
+    write_i32(out, r.module_temperature);
+    for lane in &r.lanes {
+        write_sfp_lane(out, lane);
+    }
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SlowPathCounts {
     pub unknown: u32,
@@ -32,3 +34,12 @@ pub(crate) fn parse_slow_path_counts(input: &[u8]) -> IResult<&[u8], SlowPathCou
         },
     ))
 }
+
+pub(crate) fn write_slow_path_counts(out: &mut Vec<u8>, r: &SlowPathCounts) {
+    write_u32(out, r.unknown);
+    write_u32(out, r.other);
+    write_u32(out, r.cam_miss);
+    write_u32(out, r.cam_full);
+    write_u32(out, r.no_hw_support);
+    write_u32(out, r.cntrl);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_i32, be_u32};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_i32, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Temperature {
     /// Minimum temperature reading in degrees Celsius.
@@ -26,3 +28,9 @@ pub(crate) fn parse_temperature(input: &[u8]) -> IResult<&[u8], Temperature> {
         },
     ))
 }
+
+pub(crate) fn write_temperature(out: &mut Vec<u8>, r: &Temperature) {
+    write_i32(out, r.minimum);
+    write_i32(out, r.maximum);
+    write_u32(out, r.errors);
+}
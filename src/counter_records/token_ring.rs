@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenRing {
     pub dot5_stats_line_errors: u32,
@@ -68,3 +70,24 @@ pub(crate) fn parse_token_ring(input: &[u8]) -> IResult<&[u8], TokenRing> {
         },
     ))
 }
+
+pub(crate) fn write_token_ring(out: &mut Vec<u8>, r: &TokenRing) {
+    write_u32(out, r.dot5_stats_line_errors);
+    write_u32(out, r.dot5_stats_burst_errors);
+    write_u32(out, r.dot5_stats_ac_errors);
+    write_u32(out, r.dot5_stats_abort_trans_errors);
+    write_u32(out, r.dot5_stats_internal_errors);
+    write_u32(out, r.dot5_stats_lost_frame_errors);
+    write_u32(out, r.dot5_stats_receive_congestions);
+    write_u32(out, r.dot5_stats_frame_copied_errors);
+    write_u32(out, r.dot5_stats_token_errors);
+    write_u32(out, r.dot5_stats_soft_errors);
+    write_u32(out, r.dot5_stats_hard_errors);
+    write_u32(out, r.dot5_stats_signal_loss);
+    write_u32(out, r.dot5_stats_transmit_beacons);
+    write_u32(out, r.dot5_stats_recoverys);
+    write_u32(out, r.dot5_stats_lobe_wires);
+    write_u32(out, r.dot5_stats_removes);
+    write_u32(out, r.dot5_stats_singles);
+    write_u32(out, r.dot5_stats_freq_errors);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VgCounters {
     pub in_high_priority_frames: u32,
@@ -62,3 +64,22 @@ pub(crate) fn parse_vg_counters(input: &[u8]) -> IResult<&[u8], VgCounters> {
         },
     ))
 }
+
+pub(crate) fn write_vg_counters(out: &mut Vec<u8>, r: &VgCounters) {
+    write_u32(out, r.in_high_priority_frames);
+    write_u64(out, r.in_high_priority_octets);
+    write_u32(out, r.in_norm_priority_frames);
+    write_u64(out, r.in_norm_priority_octets);
+    write_u32(out, r.in_ipm_errors);
+    write_u32(out, r.in_oversize_frame_errors);
+    write_u32(out, r.in_data_errors);
+    write_u32(out, r.in_null_addressed_frames);
+    write_u32(out, r.out_high_priority_frames);
+    write_u64(out, r.out_high_priority_octets);
+    write_u32(out, r.out_norm_priority_frames);
+    write_u64(out, r.out_norm_priority_octets);
+    write_u64(out, r.in_hc_high_priority_octets);
+    write_u64(out, r.in_hc_norm_priority_octets);
+    write_u64(out, r.out_hc_high_priority_octets);
+    write_u64(out, r.out_hc_norm_priority_octets);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 /// Virtual domain state from libvirt's virDomainState.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VirtDomainState {
@@ -32,6 +34,22 @@ impl From<u32> for VirtDomainState {
     }
 }
 
+impl From<VirtDomainState> for u32 {
+    fn from(v: VirtDomainState) -> Self {
+        match v {
+            VirtDomainState::NoState => 0,
+            VirtDomainState::Running => 1,
+            VirtDomainState::Blocked => 2,
+            VirtDomainState::Paused => 3,
+            VirtDomainState::Shutdown => 4,
+            VirtDomainState::Shutoff => 5,
+            VirtDomainState::Crashed => 6,
+            VirtDomainState::PmSuspended => 7,
+            VirtDomainState::Unrecognized(v) => v,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtCpu {
     /// Virtual domain state.
@@ -56,3 +74,9 @@ pub(crate) fn parse_virt_cpu(input: &[u8]) -> IResult<&[u8], VirtCpu> {
         },
     ))
 }
+
+pub(crate) fn write_virt_cpu(out: &mut Vec<u8>, r: &VirtCpu) {
+    write_u32(out, u32::from(r.state));
+    write_u32(out, r.cpu_time);
+    write_u32(out, r.nr_virt_cpu);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtDiskIo {
     /// Logical disk size in bytes.
@@ -46,3 +48,14 @@ pub(crate) fn parse_virt_disk_io(input: &[u8]) -> IResult<&[u8], VirtDiskIo> {
         },
     ))
 }
+
+pub(crate) fn write_virt_disk_io(out: &mut Vec<u8>, r: &VirtDiskIo) {
+    write_u64(out, r.capacity);
+    write_u64(out, r.allocation);
+    write_u64(out, r.available);
+    write_u32(out, r.rd_req);
+    write_u64(out, r.rd_bytes);
+    write_u32(out, r.wr_req);
+    write_u64(out, r.wr_bytes);
+    write_u32(out, r.errs);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u64;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u64;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtMemory {
     /// Memory used by domain in bytes.
@@ -16,3 +18,8 @@ pub(crate) fn parse_virt_memory(input: &[u8]) -> IResult<&[u8], VirtMemory> {
 
     Ok((input, VirtMemory { memory, max_memory }))
 }
+
+pub(crate) fn write_virt_memory(out: &mut Vec<u8>, r: &VirtMemory) {
+    write_u64(out, r.memory);
+    write_u64(out, r.max_memory);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtNetIo {
     pub rx_bytes: u64,
@@ -38,3 +40,14 @@ pub(crate) fn parse_virt_net_io(input: &[u8]) -> IResult<&[u8], VirtNetIo> {
         },
     ))
 }
+
+pub(crate) fn write_virt_net_io(out: &mut Vec<u8>, r: &VirtNetIo) {
+    write_u64(out, r.rx_bytes);
+    write_u32(out, r.rx_packets);
+    write_u32(out, r.rx_errs);
+    write_u32(out, r.rx_drop);
+    write_u64(out, r.tx_bytes);
+    write_u32(out, r.tx_packets);
+    write_u32(out, r.tx_errs);
+    write_u32(out, r.tx_drop);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtNode {
     pub mhz: u32,
@@ -29,3 +31,11 @@ pub(crate) fn parse_virt_node(input: &[u8]) -> IResult<&[u8], VirtNode> {
         },
     ))
 }
+
+pub(crate) fn write_virt_node(out: &mut Vec<u8>, r: &VirtNode) {
+    write_u32(out, r.mhz);
+    write_u32(out, r.cpus);
+    write_u64(out, r.memory);
+    write_u64(out, r.memory_free);
+    write_u32(out, r.num_domains);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_u32, write_u64};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Vlan {
     pub vlan_id: u32,
@@ -32,3 +34,12 @@ pub(crate) fn parse_vlan(input: &[u8]) -> IResult<&[u8], Vlan> {
         },
     ))
 }
+
+pub(crate) fn write_vlan(out: &mut Vec<u8>, r: &Vlan) {
+    write_u32(out, r.vlan_id);
+    write_u64(out, r.octets);
+    write_u32(out, r.ucast_pkts);
+    write_u32(out, r.multicast_pkts);
+    write_u32(out, r.broadcast_pkts);
+    write_u32(out, r.discards);
+}
@@ -3,6 +3,7 @@ use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
 use crate::datagram::AddressType;
+use crate::encode::write_u32;
 
 /// XenServer virtual interface (VIF) counter record (enterprise=4300, format=2).
 ///
@@ -42,3 +43,16 @@ pub(crate) fn parse_xen_vif(input: &[u8]) -> IResult<&[u8], XenVif> {
         },
     ))
 }
+
+/// Only IPv4 VM addresses fit this record; IPv6 addresses are written as 0.0.0.0.
+pub(crate) fn write_xen_vif(out: &mut Vec<u8>, r: &XenVif) {
+    write_u32(out, r.vif_index);
+    let addr = match r.vm_address {
+        AddressType::IPv4(a) => u32::from(a),
+        AddressType::IPv6(_) => 0,
+    };
+    write_u32(out, addr);
+    write_u32(out, r.domain_id);
+    write_u32(out, r.network_index);
+    write_u32(out, r.flags);
+}
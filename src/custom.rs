@@ -0,0 +1,379 @@
+//! Application-provided decoders for enterprise-specific data.
+//!
+//! Samples and records the crate does not decode itself are returned as
+//! `Unknown` variants with their raw bytes. Decoders registered on
+//! [`SflowParserBuilder`](crate::SflowParserBuilder) for an
+//! `(enterprise, format)` pair turn those into `Custom` variants
+//! ([`FlowRecord::Custom`], [`CounterRecord::Custom`] and
+//! [`SflowSample::Custom`]) holding a [`Custom`] with the decoded value.
+//! Built-in formats, including the XenServer VIF counters (4300:2), are
+//! always decoded by the crate.
+//!
+//! A decoder receives the body of the record or sample and returns `None`
+//! when it cannot decode it, in which case the data stays `Unknown`. The
+//! decoded value can be any type that is `Debug`, `PartialEq`, `Serialize`,
+//! `Send` and `Sync`, and is read back with [`Custom::downcast_ref`].
+//!
+//! ```
+//! use flowparser_sflow::custom::Custom;
+//! use flowparser_sflow::{CounterRecord, SflowParser};
+//! use serde::Serialize;
+//!
+//! #[derive(Debug, PartialEq, Serialize)]
+//! struct Temperature {
+//!     celsius: u32,
+//! }
+//!
+//! let parser = SflowParser::builder()
+//!     .with_counter_decoder(9999, 1, |data: &[u8]| {
+//!         let celsius = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+//!         Some(Temperature { celsius })
+//!     })
+//!     .build();
+//!
+//! for datagram in parser.parse_bytes(&[/* sflow datagram bytes */]).datagrams {
+//!     for sample in &datagram.samples {
+//!         let Some(counters) = sample.normalized_counter() else { continue };
+//!         for record in counters.records {
+//!             if let CounterRecord::Custom(custom) = record
+//!                 && let Some(t) = custom.downcast_ref::<Temperature>()
+//!             {
+//!                 println!("{} C", t.celsius);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::counter_records::{CounterRecord, CounterRecordRef};
+use crate::flow_records::{FlowRecord, FlowRecordRef};
+use crate::samples::{SflowSample, SflowSampleRef};
+
+/// A value produced by a registered decoder.
+///
+/// Implemented for every type that is `Debug`, `PartialEq`, `Serialize`,
+/// `Send`, `Sync` and `'static`.
+pub trait CustomValue: Any + fmt::Debug + Send + Sync {
+    /// The value as [`Any`], for downcasting.
+    fn as_any(&self) -> &dyn Any;
+
+    /// The value as JSON, used to serialize the enclosing [`Custom`].
+    fn to_json(&self) -> Value;
+
+    /// Compare with another value: with `==` when both have the same type,
+    /// and by their JSON form otherwise.
+    fn eq_value(&self, other: &dyn CustomValue) -> bool;
+}
+
+impl<T> CustomValue for T
+where
+    T: Any + fmt::Debug + PartialEq + Serialize + Send + Sync,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    fn eq_value(&self, other: &dyn CustomValue) -> bool {
+        match other.as_any().downcast_ref::<T>() {
+            Some(other) => self == other,
+            None => self.to_json() == other.to_json(),
+        }
+    }
+}
+
+/// An enterprise-specific sample or record decoded by a registered decoder.
+///
+/// The raw body is kept in `data` so the enclosing datagram encodes back to
+/// the same bytes. Serialized, the value is written as JSON under `value`;
+/// deserializing yields a `Custom` whose value is a [`serde_json::Value`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CustomRepr", into = "CustomRepr")]
+pub struct Custom {
+    /// Enterprise code from the sample or record header.
+    pub enterprise: u32,
+    /// Format code from the sample or record header.
+    pub format: u32,
+    /// Raw sample or record data.
+    pub data: Vec<u8>,
+    value: Arc<dyn CustomValue>,
+}
+
+impl Custom {
+    /// Wrap a decoded `value` of the `enterprise`/`format` body `data`.
+    pub fn new(enterprise: u32, format: u32, data: Vec<u8>, value: impl CustomValue) -> Self {
+        Self {
+            enterprise,
+            format,
+            data,
+            value: Arc::new(value),
+        }
+    }
+
+    /// The decoded value.
+    pub fn value(&self) -> &dyn CustomValue {
+        self.value.as_ref()
+    }
+
+    /// The decoded value as a `T`, or `None` if it has another type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.as_any().downcast_ref()
+    }
+
+    /// Record type name and fields for the output modules:
+    /// `custom_<enterprise>_<format>` and the value as JSON.
+    pub(crate) fn variant(&self) -> (String, Value) {
+        (
+            format!("custom_{}_{}", self.enterprise, self.format),
+            self.value.to_json(),
+        )
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        self.enterprise == other.enterprise
+            && self.format == other.format
+            && self.data == other.data
+            && self.value.eq_value(other.value.as_ref())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Custom")]
+struct CustomRepr {
+    enterprise: u32,
+    format: u32,
+    data: Vec<u8>,
+    value: Value,
+}
+
+impl From<CustomRepr> for Custom {
+    fn from(repr: CustomRepr) -> Self {
+        Custom::new(repr.enterprise, repr.format, repr.data, repr.value)
+    }
+}
+
+impl From<Custom> for CustomRepr {
+    fn from(custom: Custom) -> Self {
+        CustomRepr {
+            value: custom.value.to_json(),
+            enterprise: custom.enterprise,
+            format: custom.format,
+            data: custom.data,
+        }
+    }
+}
+
+type DecodeFn = dyn Fn(&[u8]) -> Option<Arc<dyn CustomValue>> + Send + Sync;
+
+/// Decoders registered with [`SflowParserBuilder`](crate::SflowParserBuilder),
+/// keyed by `(enterprise, format)`.
+///
+/// [`SflowParser::decoders`](crate::SflowParser::decoders) exposes the
+/// registry so bodies from [`lazy`](crate::lazy) decoding can be decoded
+/// with the same functions.
+#[derive(Clone, Default)]
+pub struct DecoderRegistry {
+    flows: HashMap<(u32, u32), Arc<DecodeFn>>,
+    counters: HashMap<(u32, u32), Arc<DecodeFn>>,
+    samples: HashMap<(u32, u32), Arc<DecodeFn>>,
+}
+
+impl fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = |map: &HashMap<(u32, u32), Arc<DecodeFn>>| {
+            let mut keys: Vec<_> = map.keys().copied().collect();
+            keys.sort_unstable();
+            keys
+        };
+        f.debug_struct("DecoderRegistry")
+            .field("flows", &keys(&self.flows))
+            .field("counters", &keys(&self.counters))
+            .field("samples", &keys(&self.samples))
+            .finish()
+    }
+}
+
+impl DecoderRegistry {
+    /// Whether no decoders are registered.
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty() && self.counters.is_empty() && self.samples.is_empty()
+    }
+
+    /// Decode a flow record body, or `None` if no decoder is registered for
+    /// `enterprise`/`format` or the decoder rejects `data`.
+    pub fn flow_record(&self, enterprise: u32, format: u32, data: &[u8]) -> Option<Custom> {
+        decode(&self.flows, enterprise, format, data)
+    }
+
+    /// Decode a counter record body; see [`flow_record`](Self::flow_record).
+    pub fn counter_record(&self, enterprise: u32, format: u32, data: &[u8]) -> Option<Custom> {
+        decode(&self.counters, enterprise, format, data)
+    }
+
+    /// Decode a sample body; see [`flow_record`](Self::flow_record).
+    pub fn sample(&self, enterprise: u32, format: u32, data: &[u8]) -> Option<Custom> {
+        decode(&self.samples, enterprise, format, data)
+    }
+
+    pub(crate) fn register_flow<T, F>(&mut self, enterprise: u32, format: u32, decode: F)
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.flows.insert((enterprise, format), erase(decode));
+    }
+
+    pub(crate) fn register_counter<T, F>(&mut self, enterprise: u32, format: u32, decode: F)
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.counters.insert((enterprise, format), erase(decode));
+    }
+
+    pub(crate) fn register_sample<T, F>(&mut self, enterprise: u32, format: u32, decode: F)
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.samples.insert((enterprise, format), erase(decode));
+    }
+
+    /// Replace the `Unknown` samples and records of `samples` that have a
+    /// registered decoder.
+    pub(crate) fn apply(&self, samples: &mut [SflowSample]) {
+        for sample in samples {
+            match sample {
+                SflowSample::Flow(s) => self.apply_flows(&mut s.records),
+                SflowSample::ExpandedFlow(s) => self.apply_flows(&mut s.records),
+                SflowSample::Discard(s) => self.apply_flows(&mut s.records),
+                SflowSample::Counter(s) => self.apply_counters(&mut s.records),
+                SflowSample::ExpandedCounter(s) => self.apply_counters(&mut s.records),
+                SflowSample::Unknown {
+                    enterprise,
+                    format,
+                    data,
+                } => {
+                    if let Some(custom) = self.sample(*enterprise, *format, data) {
+                        *sample = SflowSample::Custom(custom);
+                    }
+                }
+                SflowSample::Custom(_) => {}
+            }
+        }
+    }
+
+    /// Borrowed counterpart of [`apply`](Self::apply).
+    pub(crate) fn apply_ref(&self, samples: &mut [SflowSampleRef<'_>]) {
+        for sample in samples {
+            match sample {
+                SflowSampleRef::Flow(s) => self.apply_flows_ref(&mut s.records),
+                SflowSampleRef::ExpandedFlow(s) => self.apply_flows_ref(&mut s.records),
+                SflowSampleRef::Discard(s) => self.apply_flows_ref(&mut s.records),
+                SflowSampleRef::Counter(s) => self.apply_counters_ref(&mut s.records),
+                SflowSampleRef::ExpandedCounter(s) => self.apply_counters_ref(&mut s.records),
+                SflowSampleRef::Unknown {
+                    enterprise,
+                    format,
+                    data,
+                } => {
+                    if let Some(custom) = self.sample(*enterprise, *format, data) {
+                        *sample = SflowSampleRef::Custom(custom);
+                    }
+                }
+                SflowSampleRef::Custom(_) => {}
+            }
+        }
+    }
+
+    fn apply_flows(&self, records: &mut [FlowRecord]) {
+        for record in records {
+            if let FlowRecord::Unknown {
+                enterprise,
+                format,
+                data,
+            } = record
+                && let Some(custom) = self.flow_record(*enterprise, *format, data)
+            {
+                *record = FlowRecord::Custom(custom);
+            }
+        }
+    }
+
+    fn apply_flows_ref(&self, records: &mut [FlowRecordRef<'_>]) {
+        for record in records {
+            if let FlowRecordRef::Unknown {
+                enterprise,
+                format,
+                data,
+            } = record
+                && let Some(custom) = self.flow_record(*enterprise, *format, data)
+            {
+                *record = FlowRecordRef::Custom(custom);
+            }
+        }
+    }
+
+    fn apply_counters(&self, records: &mut [CounterRecord]) {
+        for record in records {
+            if let CounterRecord::Unknown {
+                enterprise,
+                format,
+                data,
+            } = record
+                && let Some(custom) = self.counter_record(*enterprise, *format, data)
+            {
+                *record = CounterRecord::Custom(custom);
+            }
+        }
+    }
+
+    fn apply_counters_ref(&self, records: &mut [CounterRecordRef<'_>]) {
+        for record in records {
+            if let CounterRecordRef::Unknown {
+                enterprise,
+                format,
+                data,
+            } = record
+                && let Some(custom) = self.counter_record(*enterprise, *format, data)
+            {
+                *record = CounterRecordRef::Custom(custom);
+            }
+        }
+    }
+}
+
+fn erase<T, F>(decode: F) -> Arc<DecodeFn>
+where
+    T: CustomValue,
+    F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+{
+    Arc::new(move |data: &[u8]| decode(data).map(|v| Arc::new(v) as Arc<dyn CustomValue>))
+}
+
+fn decode(
+    decoders: &HashMap<(u32, u32), Arc<DecodeFn>>,
+    enterprise: u32,
+    format: u32,
+    data: &[u8],
+) -> Option<Custom> {
+    let decode = decoders.get(&(enterprise, format))?;
+    Some(Custom {
+        enterprise,
+        format,
+        data: data.to_vec(),
+        value: decode(data)?,
+    })
+}
@@ -1,16 +1,21 @@
 use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
+use crate::encode::{write_ipv6, write_u32};
 use crate::error::{ParseContext, ParseErrorKind, SflowError};
-use crate::samples::{SflowSample, parse_samples};
+use crate::samples::{
+    ParsedSample, SflowSample, SflowSampleRef, parse_legacy_samples, parse_samples,
+    write_samples,
+};
 
 /// An sFlow address, either IPv4 or IPv6.
 ///
 /// Used for both agent addresses in the datagram header and
 /// next-hop addresses in extended router/gateway records.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AddressType {
     /// IPv4 address.
     IPv4(Ipv4Addr),
@@ -18,13 +23,26 @@ pub enum AddressType {
     IPv6(Ipv6Addr),
 }
 
-/// A parsed sFlow v5 datagram containing header fields and samples.
+impl fmt::Display for AddressType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressType::IPv4(a) => a.fmt(f),
+            AddressType::IPv6(a) => a.fmt(f),
+        }
+    }
+}
+
+/// A parsed sFlow datagram containing header fields and samples.
 ///
 /// Each datagram is sent by an sFlow agent and contains a header
 /// identifying the agent, plus zero or more flow or counter samples.
+/// Version 2 and 4 datagrams are decoded into the same v5 model; see
+/// [`version`](Self::version).
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SflowDatagram {
-    /// sFlow version (always 5).
+    /// sFlow version: 5, or 2 or 4 for legacy agents. Legacy datagrams have
+    /// no sub-agent, so `sub_agent_id` is 0, and their samples are mapped to
+    /// [`SflowSample::Flow`] and [`SflowSample::Counter`].
     pub version: u32,
     /// IP address of the sFlow agent.
     pub agent_address: AddressType,
@@ -38,6 +56,100 @@ pub struct SflowDatagram {
     pub samples: Vec<SflowSample>,
 }
 
+/// Borrowed form of [`SflowDatagram`], returned by
+/// [`SflowParser::parse_bytes_ref`](crate::SflowParser::parse_bytes_ref).
+///
+/// Raw packet headers, strings and unknown sample or record bodies borrow
+/// from the input buffer instead of being copied.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SflowDatagramRef<'a> {
+    /// sFlow version: 5, or 2 or 4 for legacy agents.
+    pub version: u32,
+    /// IP address of the sFlow agent.
+    pub agent_address: AddressType,
+    /// Sub-agent identifier (disambiguates multiple data sources).
+    pub sub_agent_id: u32,
+    /// Sequence number incremented per datagram from this agent.
+    pub sequence_number: u32,
+    /// Agent uptime in milliseconds since boot.
+    pub uptime: u32,
+    /// Flow and counter samples contained in this datagram.
+    pub samples: Vec<SflowSampleRef<'a>>,
+}
+
+impl SflowDatagramRef<'_> {
+    /// Convert into the owned [`SflowDatagram`], copying any borrowed data.
+    pub fn into_owned(self) -> SflowDatagram {
+        SflowDatagram {
+            version: self.version,
+            agent_address: self.agent_address,
+            sub_agent_id: self.sub_agent_id,
+            sequence_number: self.sequence_number,
+            uptime: self.uptime,
+            samples: self
+                .samples
+                .into_iter()
+                .map(SflowSampleRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl SflowDatagram {
+    /// Append the sFlow v5 XDR encoding of this datagram to `out`.
+    ///
+    /// Sample and record counts and lengths are derived from the contained
+    /// values, so `parse_bytes(&dg.to_bytes())` yields a datagram equal to `dg`.
+    /// Datagrams decoded from v2 or v4 are written as v5, so only their
+    /// `version` differs after a round trip.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        write_u32(out, 5);
+        write_address(out, &self.agent_address);
+        write_u32(out, self.sub_agent_id);
+        write_u32(out, self.sequence_number);
+        write_u32(out, self.uptime);
+        write_samples(out, &self.samples);
+    }
+
+    /// Encode this datagram into a new buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flowparser_sflow::{AddressType, SflowDatagram, SflowParser};
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let dg = SflowDatagram {
+    ///     version: 5,
+    ///     agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+    ///     sub_agent_id: 0,
+    ///     sequence_number: 1,
+    ///     uptime: 1000,
+    ///     samples: vec![],
+    /// };
+    /// let result = SflowParser::default().parse_bytes(&dg.to_bytes());
+    /// assert_eq!(result.datagrams, vec![dg]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+pub(crate) fn write_address(out: &mut Vec<u8>, addr: &AddressType) {
+    match addr {
+        AddressType::IPv4(a) => {
+            write_u32(out, 1);
+            write_u32(out, u32::from(*a));
+        }
+        AddressType::IPv6(a) => {
+            write_u32(out, 2);
+            write_ipv6(out, a);
+        }
+    }
+}
+
 pub(crate) fn parse_address(input: &[u8]) -> IResult<&[u8], AddressType> {
     let (input, addr_type) = be_u32(input)?;
     match addr_type {
@@ -64,6 +176,65 @@ pub(crate) fn parse_datagram(
     input: &[u8],
     max_samples: Option<u32>,
 ) -> Result<(&[u8], SflowDatagram), SflowError> {
+    let (input, header) = parse_datagram_header(input, max_samples)?;
+    let (input, samples) = parse_datagram_samples(input, &header)?;
+    Ok((
+        input,
+        SflowDatagram {
+            version: header.version,
+            agent_address: header.agent_address,
+            sub_agent_id: header.sub_agent_id,
+            sequence_number: header.sequence_number,
+            uptime: header.uptime,
+            samples,
+        },
+    ))
+}
+
+pub(crate) fn parse_datagram_ref(
+    input: &[u8],
+    max_samples: Option<u32>,
+) -> Result<(&[u8], SflowDatagramRef<'_>), SflowError> {
+    let (input, header) = parse_datagram_header(input, max_samples)?;
+    let (input, samples) = parse_datagram_samples(input, &header)?;
+    Ok((
+        input,
+        SflowDatagramRef {
+            version: header.version,
+            agent_address: header.agent_address,
+            sub_agent_id: header.sub_agent_id,
+            sequence_number: header.sequence_number,
+            uptime: header.uptime,
+            samples,
+        },
+    ))
+}
+
+fn parse_datagram_samples<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+    header: &DatagramHeader,
+) -> Result<(&'a [u8], Vec<S>), SflowError> {
+    if header.version == 5 {
+        parse_samples(input, header.num_samples)
+    } else {
+        parse_legacy_samples(input, header.num_samples, header.version)
+    }
+}
+
+/// Datagram header fields preceding the sample list.
+pub(crate) struct DatagramHeader {
+    pub(crate) version: u32,
+    pub(crate) agent_address: AddressType,
+    pub(crate) sub_agent_id: u32,
+    pub(crate) sequence_number: u32,
+    pub(crate) uptime: u32,
+    pub(crate) num_samples: u32,
+}
+
+pub(crate) fn parse_datagram_header(
+    input: &[u8],
+    max_samples: Option<u32>,
+) -> Result<(&[u8], DatagramHeader), SflowError> {
     let original = input;
 
     let (input, version) = be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
@@ -74,7 +245,7 @@ pub(crate) fn parse_datagram(
         }
     })?;
 
-    if version != 5 {
+    if !matches!(version, 2 | 4 | 5) {
         return Err(SflowError::UnsupportedVersion { version });
     }
 
@@ -87,14 +258,18 @@ pub(crate) fn parse_datagram(
             }
         })?;
 
-    let (input, sub_agent_id) =
+    // Sub-agents were introduced in v5
+    let (input, sub_agent_id) = if version == 5 {
         be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
             SflowError::Incomplete {
                 available: input.len(),
                 expected: None,
                 context: ParseContext::SubAgentId,
             }
-        })?;
+        })?
+    } else {
+        (input, 0)
+    };
 
     let (input, sequence_number) =
         be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
@@ -132,17 +307,15 @@ pub(crate) fn parse_datagram(
         });
     }
 
-    let (input, samples) = parse_samples(input, num_samples)?;
-
     Ok((
         input,
-        SflowDatagram {
+        DatagramHeader {
             version,
             agent_address,
             sub_agent_id,
             sequence_number,
             uptime,
-            samples,
+            num_samples,
         },
     ))
 }
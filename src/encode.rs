@@ -0,0 +1,60 @@
+//! XDR primitives shared by the record, sample and datagram encoders.
+
+use mac_address::MacAddress;
+use std::net::Ipv6Addr;
+
+pub(crate) fn write_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub(crate) fn write_u64(out: &mut Vec<u8>, v: u64) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub(crate) fn write_i32(out: &mut Vec<u8>, v: i32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub(crate) fn write_f32(out: &mut Vec<u8>, v: f32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+/// Write zero bytes to pad `len` bytes of data to a 4-byte boundary.
+pub(crate) fn write_padding(out: &mut Vec<u8>, len: usize) {
+    let padding = (4 - (len % 4)) % 4;
+    out.extend_from_slice(&[0u8; 3][..padding]);
+}
+
+/// Write XDR variable-length opaque data (length-prefixed, padded to 4-byte boundary).
+pub(crate) fn write_opaque(out: &mut Vec<u8>, data: &[u8]) {
+    write_u32(out, data.len() as u32);
+    out.extend_from_slice(data);
+    write_padding(out, data.len());
+}
+
+/// Write a MAC address padded to 8 bytes (6 bytes MAC + 2 bytes padding).
+pub(crate) fn write_mac(out: &mut Vec<u8>, mac: &MacAddress) {
+    out.extend_from_slice(&mac.bytes());
+    out.extend_from_slice(&[0u8; 2]);
+}
+
+pub(crate) fn write_ipv6(out: &mut Vec<u8>, addr: &Ipv6Addr) {
+    out.extend_from_slice(&addr.octets());
+}
+
+/// Write a `data_format` + `length` header followed by the body produced by `f`.
+///
+/// The length is back-patched once the body has been written.
+pub(crate) fn write_tagged(
+    out: &mut Vec<u8>,
+    enterprise: u32,
+    format: u32,
+    f: impl FnOnce(&mut Vec<u8>),
+) {
+    write_u32(out, (enterprise << 12) | (format & 0xFFF));
+    let len_pos = out.len();
+    write_u32(out, 0);
+    f(out);
+    let len = (out.len() - len_pos - 4) as u32;
+    out[len_pos..len_pos + 4].copy_from_slice(&len.to_be_bytes());
+}
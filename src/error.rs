@@ -19,6 +19,7 @@ pub enum ParseContext {
     CounterSample,
     ExpandedFlowSample,
     ExpandedCounterSample,
+    DiscardedPacket,
 }
 
 impl fmt::Display for ParseContext {
@@ -38,6 +39,7 @@ impl fmt::Display for ParseContext {
             ParseContext::CounterSample => "counter sample",
             ParseContext::ExpandedFlowSample => "expanded flow sample",
             ParseContext::ExpandedCounterSample => "expanded counter sample",
+            ParseContext::DiscardedPacket => "discarded packet",
         };
         f.write_str(s)
     }
@@ -162,11 +164,17 @@ pub enum SflowError {
         /// The parsing phase where the error occurred.
         context: ParseContext,
     },
-    /// The datagram version is not sFlow v5.
+    /// The datagram version is not sFlow v2, v4 or v5.
     UnsupportedVersion {
         /// The version number found in the datagram header.
         version: u32,
     },
+    /// Lazy decoding was asked for a v2 or v4 datagram. Their samples have
+    /// no length fields to skip over, so only eager parsing decodes them.
+    LazyUnsupportedVersion {
+        /// The version number found in the datagram header.
+        version: u32,
+    },
     /// A structural parse error at a known offset.
     ParseError {
         /// Byte offset from the start of the datagram.
@@ -209,7 +217,16 @@ impl fmt::Display for SflowError {
                 )
             }
             SflowError::UnsupportedVersion { version } => {
-                write!(f, "Unsupported sFlow version: {version} (expected 5)")
+                write!(
+                    f,
+                    "Unsupported sFlow version: {version} (expected 2, 4 or 5)"
+                )
+            }
+            SflowError::LazyUnsupportedVersion { version } => {
+                write!(
+                    f,
+                    "sFlow version {version} cannot be decoded lazily (expected 5)"
+                )
             }
             SflowError::ParseError {
                 offset,
@@ -0,0 +1,370 @@
+//! Traffic estimates from sampled flows.
+//!
+//! Each flow sample stands for roughly `sampling_rate` packets. Agents may
+//! change their rate at any time, datagrams get lost in transit and samples
+//! are dropped when an agent runs out of resources, so [`SamplingEstimator`]
+//! prefers the sample pool: the number of packets that could have been
+//! sampled since the previous sample on the same data source. That weight
+//! covers rate changes, lost samples and `drops` without further correction.
+//! Samples without a usable previous one fall back to `sampling_rate`, scaled
+//! up by any increase in `drops`.
+//!
+//! Following the sFlow sampling theory, an estimate built from `c` samples
+//! has a relative error of at most `z * sqrt(1 / c)`, where `z` is the normal
+//! quantile of the confidence level ([`Z_95`] for 95%).
+//!
+//! ```
+//! use flowparser_sflow::SflowParser;
+//! use flowparser_sflow::estimate::{SamplingEstimator, Z_95};
+//!
+//! let parser = SflowParser::default();
+//! let mut estimator = SamplingEstimator::default();
+//!
+//! let result = parser.parse_bytes(&[/* sflow datagram bytes */]);
+//! for datagram in &result.datagrams {
+//!     estimator.observe(datagram);
+//! }
+//! let total = estimator.total();
+//! let (low, high) = total.bytes_interval(Z_95);
+//! println!("{:.0} bytes ({low:.0}..{high:.0}) from {} samples", total.bytes, total.samples);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::datagram::{AddressType, SflowDatagram, SflowDatagramRef};
+use crate::samples::DataSource;
+use crate::sequence::{AgentKey, SampleKey, SampleKind};
+
+/// Normal quantile for a 95% confidence interval.
+pub const Z_95: f64 = 1.959_964;
+/// Normal quantile for a 99% confidence interval.
+pub const Z_99: f64 = 2.575_829;
+
+/// Estimated packets and bytes behind a set of flow samples.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct TrafficEstimate {
+    /// Number of flow samples contributing to the estimate.
+    pub samples: u64,
+    pub packets: f64,
+    pub bytes: f64,
+}
+
+impl TrafficEstimate {
+    /// Add one sample standing for `weight` packets of `frame_length` bytes.
+    pub fn add(&mut self, weight: f64, frame_length: Option<u32>) {
+        self.samples += 1;
+        self.packets += weight;
+        self.bytes += weight * f64::from(frame_length.unwrap_or(0));
+    }
+
+    /// Add another estimate to this one.
+    pub fn merge(&mut self, other: &TrafficEstimate) {
+        self.samples += other.samples;
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+    }
+
+    /// Upper bound on the relative error at normal quantile `z`, i.e.
+    /// `z * sqrt(1 / samples)`. Infinite when there are no samples.
+    pub fn relative_error(&self, z: f64) -> f64 {
+        if self.samples == 0 {
+            return f64::INFINITY;
+        }
+        z / (self.samples as f64).sqrt()
+    }
+
+    /// Confidence interval for `packets` at normal quantile `z`.
+    pub fn packets_interval(&self, z: f64) -> (f64, f64) {
+        interval(self.packets, self.relative_error(z))
+    }
+
+    /// Confidence interval for `bytes` at normal quantile `z`.
+    ///
+    /// Uses the packet count bound, which assumes packet sizes do not vary
+    /// much within the estimated traffic.
+    pub fn bytes_interval(&self, z: f64) -> (f64, f64) {
+        interval(self.bytes, self.relative_error(z))
+    }
+}
+
+fn interval(value: f64, relative_error: f64) -> (f64, f64) {
+    if relative_error.is_infinite() {
+        return (0.0, f64::INFINITY);
+    }
+    (
+        value * (1.0 - relative_error).max(0.0),
+        value * (1.0 + relative_error),
+    )
+}
+
+/// Where the weight of a sample came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeightSource {
+    /// `sampling_rate`, multiplied by one plus the increase in `drops`.
+    SamplingRate,
+    /// The increase in `sample_pool` since the previous sample.
+    SamplePool,
+}
+
+/// Number of packets a single flow sample stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SampleWeight {
+    pub weight: f64,
+    pub source: WeightSource,
+    /// Sampled packet length, see
+    /// [`NormalizedFlowSample::frame_length`](crate::samples::NormalizedFlowSample::frame_length).
+    pub frame_length: Option<u32>,
+}
+
+impl SampleWeight {
+    /// Estimated bytes behind the sample.
+    pub fn bytes(&self) -> f64 {
+        self.weight * f64::from(self.frame_length.unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StreamState {
+    sequence_number: u32,
+    sample_pool: u32,
+    drops: u32,
+    effective_rate: Option<f64>,
+    estimate: TrafficEstimate,
+}
+
+/// Stateful estimator scaling flow samples into traffic totals.
+///
+/// State is kept per agent and data source.
+#[derive(Debug, Clone)]
+pub struct SamplingEstimator {
+    use_sample_pool: bool,
+    max_gap: u32,
+    streams: HashMap<SampleKey, StreamState>,
+}
+
+impl Default for SamplingEstimator {
+    fn default() -> Self {
+        SamplingEstimator::builder().build()
+    }
+}
+
+impl SamplingEstimator {
+    /// Create a builder for configuring the estimator.
+    pub fn builder() -> SamplingEstimatorBuilder {
+        SamplingEstimatorBuilder {
+            use_sample_pool: true,
+            max_gap: 10_000,
+        }
+    }
+
+    /// Weigh every flow sample in a datagram and add it to the estimates.
+    pub fn observe(&mut self, datagram: &SflowDatagram) -> Vec<(SampleKey, SampleWeight)> {
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        datagram
+            .samples
+            .iter()
+            .filter_map(|sample| sample.normalized_flow())
+            .filter_map(|s| {
+                let key = flow_key(&agent, s.data_source);
+                let weight = self.observe_sample(
+                    key.clone(),
+                    s.sequence_number,
+                    s.sampling_rate,
+                    s.sample_pool,
+                    s.drops,
+                    s.frame_length(),
+                )?;
+                Some((key, weight))
+            })
+            .collect()
+    }
+
+    /// Borrowed counterpart of [`observe`](Self::observe).
+    pub fn observe_ref(
+        &mut self,
+        datagram: &SflowDatagramRef<'_>,
+    ) -> Vec<(SampleKey, SampleWeight)> {
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        datagram
+            .samples
+            .iter()
+            .filter_map(|sample| sample.normalized_flow())
+            .filter_map(|s| {
+                let key = flow_key(&agent, s.data_source);
+                let weight = self.observe_sample(
+                    key.clone(),
+                    s.sequence_number,
+                    s.sampling_rate,
+                    s.sample_pool,
+                    s.drops,
+                    s.frame_length(),
+                )?;
+                Some((key, weight))
+            })
+            .collect()
+    }
+
+    /// Weigh one flow sample from its header fields.
+    ///
+    /// Returns `None` for a duplicate, or for a late sample already covered
+    /// by the sample pool of a newer one; neither is counted.
+    pub fn observe_sample(
+        &mut self,
+        key: SampleKey,
+        sequence_number: u32,
+        sampling_rate: u32,
+        sample_pool: u32,
+        drops: u32,
+        frame_length: Option<u32>,
+    ) -> Option<SampleWeight> {
+        let nominal = f64::from(sampling_rate.max(1));
+        let Some(state) = self.streams.get_mut(&key) else {
+            let mut estimate = TrafficEstimate::default();
+            estimate.add(nominal, frame_length);
+            self.streams.insert(
+                key,
+                StreamState {
+                    sequence_number,
+                    sample_pool,
+                    drops,
+                    effective_rate: None,
+                    estimate,
+                },
+            );
+            return Some(SampleWeight {
+                weight: nominal,
+                source: WeightSource::SamplingRate,
+                frame_length,
+            });
+        };
+
+        let ahead = sequence_number.wrapping_sub(state.sequence_number);
+        let behind = state.sequence_number.wrapping_sub(sequence_number);
+        let (weight, source) = if ahead == 0 {
+            return None;
+        } else if ahead <= self.max_gap {
+            let pool = sample_pool.wrapping_sub(state.sample_pool);
+            let dropped = drops.wrapping_sub(state.drops);
+            // Packets per generated or dropped sample over the interval.
+            let rate = f64::from(pool) / (f64::from(ahead) + f64::from(dropped));
+            state.effective_rate = (pool > 0).then_some(rate);
+            state.sequence_number = sequence_number;
+            state.sample_pool = sample_pool;
+            state.drops = drops;
+            if self.use_sample_pool && pool > 0 {
+                (f64::from(pool), WeightSource::SamplePool)
+            } else {
+                (
+                    nominal * (1.0 + f64::from(dropped)),
+                    WeightSource::SamplingRate,
+                )
+            }
+        } else if behind <= self.max_gap {
+            // A late sample: the pool of the newer sample already covers it.
+            if self.use_sample_pool {
+                return None;
+            }
+            (nominal, WeightSource::SamplingRate)
+        } else {
+            // The stream restarted.
+            state.sequence_number = sequence_number;
+            state.sample_pool = sample_pool;
+            state.drops = drops;
+            state.effective_rate = None;
+            (nominal, WeightSource::SamplingRate)
+        };
+
+        state.estimate.add(weight, frame_length);
+        Some(SampleWeight {
+            weight,
+            source,
+            frame_length,
+        })
+    }
+
+    /// Estimate for one data source, if any samples were seen.
+    pub fn estimate(&self, key: &SampleKey) -> Option<&TrafficEstimate> {
+        self.streams.get(key).map(|s| &s.estimate)
+    }
+
+    /// Iterate over the estimates of all data sources.
+    pub fn estimates(&self) -> impl Iterator<Item = (&SampleKey, &TrafficEstimate)> {
+        self.streams.iter().map(|(key, s)| (key, &s.estimate))
+    }
+
+    /// Sum of the estimates of all data sources.
+    pub fn total(&self) -> TrafficEstimate {
+        let mut total = TrafficEstimate::default();
+        for state in self.streams.values() {
+            total.merge(&state.estimate);
+        }
+        total
+    }
+
+    /// Packets per sample actually achieved by a data source over its last
+    /// interval, derived from the sample pool, sequence numbers and drops.
+    pub fn effective_rate(&self, key: &SampleKey) -> Option<f64> {
+        self.streams.get(key)?.effective_rate
+    }
+
+    /// Reset all estimates to zero, keeping the per-stream state needed to
+    /// weigh the next samples, e.g. at the end of a reporting interval.
+    pub fn clear_estimates(&mut self) {
+        for state in self.streams.values_mut() {
+            state.estimate = TrafficEstimate::default();
+        }
+    }
+
+    /// Forget all streams of an agent.
+    pub fn remove_agent(&mut self, agent_address: &AddressType) {
+        self.streams
+            .retain(|key, _| key.agent.agent_address != *agent_address);
+    }
+}
+
+/// Builder for configuring a [`SamplingEstimator`].
+#[derive(Debug, Clone)]
+pub struct SamplingEstimatorBuilder {
+    use_sample_pool: bool,
+    max_gap: u32,
+}
+
+impl SamplingEstimatorBuilder {
+    /// Weigh samples by `sample_pool` increases (default `true`). When
+    /// disabled, every sample is weighted by its `sampling_rate` and `drops`.
+    pub fn with_sample_pool(mut self, use_sample_pool: bool) -> Self {
+        self.use_sample_pool = use_sample_pool;
+        self
+    }
+
+    /// Largest sample sequence jump still bridged by the sample pool
+    /// (default 10000). Larger jumps are treated as resets.
+    pub fn with_max_gap(mut self, max_gap: u32) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Build the configured [`SamplingEstimator`].
+    pub fn build(self) -> SamplingEstimator {
+        SamplingEstimator {
+            use_sample_pool: self.use_sample_pool,
+            max_gap: self.max_gap,
+            streams: HashMap::new(),
+        }
+    }
+}
+
+fn flow_key(agent: &AgentKey, data_source: DataSource) -> SampleKey {
+    SampleKey {
+        agent: agent.clone(),
+        data_source,
+        kind: SampleKind::Flow,
+    }
+}
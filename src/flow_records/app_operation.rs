@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::{write_u32, write_u64};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AppOperation {
@@ -14,9 +16,34 @@ pub struct AppOperation {
     pub status: u32,
 }
 
-pub(crate) fn parse_app_operation(input: &[u8]) -> IResult<&[u8], AppOperation> {
-    let (input, context) = parse_sflow_string(input)?;
-    let (input, status_descr) = parse_sflow_string(input)?;
+/// Borrowed form of [`AppOperation`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppOperationRef<'a> {
+    pub context: Cow<'a, str>,
+    pub status_descr: Cow<'a, str>,
+    pub req_bytes: u64,
+    pub resp_bytes: u64,
+    pub duration_us: u32,
+    pub status: u32,
+}
+
+impl AppOperationRef<'_> {
+    /// Convert into the owned [`AppOperation`], copying any borrowed data.
+    pub fn into_owned(self) -> AppOperation {
+        AppOperation {
+            context: self.context.into_owned(),
+            status_descr: self.status_descr.into_owned(),
+            req_bytes: self.req_bytes,
+            resp_bytes: self.resp_bytes,
+            duration_us: self.duration_us,
+            status: self.status,
+        }
+    }
+}
+
+pub(crate) fn parse_app_operation_ref(input: &[u8]) -> IResult<&[u8], AppOperationRef<'_>> {
+    let (input, context) = parse_sflow_str(input)?;
+    let (input, status_descr) = parse_sflow_str(input)?;
     let (input, req_bytes) = be_u64(input)?;
     let (input, resp_bytes) = be_u64(input)?;
     let (input, duration_us) = be_u32(input)?;
@@ -24,7 +51,7 @@ pub(crate) fn parse_app_operation(input: &[u8]) -> IResult<&[u8], AppOperation>
 
     Ok((
         input,
-        AppOperation {
+        AppOperationRef {
             context,
             status_descr,
             req_bytes,
@@ -34,3 +61,12 @@ pub(crate) fn parse_app_operation(input: &[u8]) -> IResult<&[u8], AppOperation>
         },
     ))
 }
+
+pub(crate) fn write_app_operation(out: &mut Vec<u8>, r: &AppOperation) {
+    write_sflow_string(out, &r.context);
+    write_sflow_string(out, &r.status_descr);
+    write_u64(out, r.req_bytes);
+    write_u64(out, r.resp_bytes);
+    write_u32(out, r.duration_us);
+    write_u32(out, r.status);
+}
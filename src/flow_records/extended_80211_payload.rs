@@ -3,15 +3,34 @@ use nom::bytes::complete::take;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_opaque, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Extended80211Payload {
     pub cipher_suite: u32,
     pub data: Vec<u8>,
 }
 
-pub(crate) fn parse_extended_80211_payload(
+/// Borrowed form of [`Extended80211Payload`]; bytes borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extended80211PayloadRef<'a> {
+    pub cipher_suite: u32,
+    pub data: &'a [u8],
+}
+
+impl Extended80211PayloadRef<'_> {
+    /// Convert into the owned [`Extended80211Payload`], copying any borrowed data.
+    pub fn into_owned(self) -> Extended80211Payload {
+        Extended80211Payload {
+            cipher_suite: self.cipher_suite,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+pub(crate) fn parse_extended_80211_payload_ref(
     input: &[u8],
-) -> IResult<&[u8], Extended80211Payload> {
+) -> IResult<&[u8], Extended80211PayloadRef<'_>> {
     let (input, cipher_suite) = be_u32(input)?;
     let (input, length) = be_u32(input)?;
     let (input, bytes) = take(length as usize)(input)?;
@@ -20,9 +39,14 @@ pub(crate) fn parse_extended_80211_payload(
 
     Ok((
         input,
-        Extended80211Payload {
+        Extended80211PayloadRef {
             cipher_suite,
-            data: bytes.to_vec(),
+            data: bytes,
         },
     ))
 }
+
+pub(crate) fn write_extended_80211_payload(out: &mut Vec<u8>, r: &Extended80211Payload) {
+    write_u32(out, r.cipher_suite);
+    write_opaque(out, &r.data);
+}
@@ -2,8 +2,10 @@ use mac_address::MacAddress;
 use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::{write_u32, write_u64};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Extended80211Rx {
@@ -17,6 +19,35 @@ pub struct Extended80211Rx {
     pub packet_duration_us: u32,
 }
 
+/// Borrowed form of [`Extended80211Rx`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extended80211RxRef<'a> {
+    pub ssid: Cow<'a, str>,
+    pub bssid: MacAddress,
+    pub version: u32,
+    pub channel: u32,
+    pub speed: u64,
+    pub rsni: u32,
+    pub rcpi: u32,
+    pub packet_duration_us: u32,
+}
+
+impl Extended80211RxRef<'_> {
+    /// Convert into the owned [`Extended80211Rx`], copying any borrowed data.
+    pub fn into_owned(self) -> Extended80211Rx {
+        Extended80211Rx {
+            ssid: self.ssid.into_owned(),
+            bssid: self.bssid,
+            version: self.version,
+            channel: self.channel,
+            speed: self.speed,
+            rsni: self.rsni,
+            rcpi: self.rcpi,
+            packet_duration_us: self.packet_duration_us,
+        }
+    }
+}
+
 fn parse_mac(input: &[u8]) -> IResult<&[u8], MacAddress> {
     if input.len() < 6 {
         return Err(nom::Err::Error(nom::error::Error::new(
@@ -28,8 +59,10 @@ fn parse_mac(input: &[u8]) -> IResult<&[u8], MacAddress> {
     Ok((&input[6..], MacAddress::new(bytes)))
 }
 
-pub(crate) fn parse_extended_80211_rx(input: &[u8]) -> IResult<&[u8], Extended80211Rx> {
-    let (input, ssid) = parse_sflow_string(input)?;
+pub(crate) fn parse_extended_80211_rx_ref(
+    input: &[u8],
+) -> IResult<&[u8], Extended80211RxRef<'_>> {
+    let (input, ssid) = parse_sflow_str(input)?;
     let (input, bssid) = parse_mac(input)?;
     let (input, version) = be_u32(input)?;
     let (input, channel) = be_u32(input)?;
@@ -40,7 +73,7 @@ pub(crate) fn parse_extended_80211_rx(input: &[u8]) -> IResult<&[u8], Extended80
 
     Ok((
         input,
-        Extended80211Rx {
+        Extended80211RxRef {
             ssid,
             bssid,
             version,
@@ -52,3 +85,14 @@ pub(crate) fn parse_extended_80211_rx(input: &[u8]) -> IResult<&[u8], Extended80
         },
     ))
 }
+
+pub(crate) fn write_extended_80211_rx(out: &mut Vec<u8>, r: &Extended80211Rx) {
+    write_sflow_string(out, &r.ssid);
+    out.extend_from_slice(&r.bssid.bytes());
+    write_u32(out, r.version);
+    write_u32(out, r.channel);
+    write_u64(out, r.speed);
+    write_u32(out, r.rsni);
+    write_u32(out, r.rcpi);
+    write_u32(out, r.packet_duration_us);
+}
@@ -2,8 +2,10 @@ use mac_address::MacAddress;
 use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::{write_u32, write_u64};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Extended80211Tx {
@@ -18,6 +20,37 @@ pub struct Extended80211Tx {
     pub power: u32,
 }
 
+/// Borrowed form of [`Extended80211Tx`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extended80211TxRef<'a> {
+    pub ssid: Cow<'a, str>,
+    pub bssid: MacAddress,
+    pub version: u32,
+    pub transmissions: u32,
+    pub packet_duration_us: u32,
+    pub retrans_duration_us: u32,
+    pub channel: u32,
+    pub speed: u64,
+    pub power: u32,
+}
+
+impl Extended80211TxRef<'_> {
+    /// Convert into the owned [`Extended80211Tx`], copying any borrowed data.
+    pub fn into_owned(self) -> Extended80211Tx {
+        Extended80211Tx {
+            ssid: self.ssid.into_owned(),
+            bssid: self.bssid,
+            version: self.version,
+            transmissions: self.transmissions,
+            packet_duration_us: self.packet_duration_us,
+            retrans_duration_us: self.retrans_duration_us,
+            channel: self.channel,
+            speed: self.speed,
+            power: self.power,
+        }
+    }
+}
+
 fn parse_mac(input: &[u8]) -> IResult<&[u8], MacAddress> {
     if input.len() < 6 {
         return Err(nom::Err::Error(nom::error::Error::new(
@@ -29,8 +62,10 @@ fn parse_mac(input: &[u8]) -> IResult<&[u8], MacAddress> {
     Ok((&input[6..], MacAddress::new(bytes)))
 }
 
-pub(crate) fn parse_extended_80211_tx(input: &[u8]) -> IResult<&[u8], Extended80211Tx> {
-    let (input, ssid) = parse_sflow_string(input)?;
+pub(crate) fn parse_extended_80211_tx_ref(
+    input: &[u8],
+) -> IResult<&[u8], Extended80211TxRef<'_>> {
+    let (input, ssid) = parse_sflow_str(input)?;
     let (input, bssid) = parse_mac(input)?;
     let (input, version) = be_u32(input)?;
     let (input, transmissions) = be_u32(input)?;
@@ -42,7 +77,7 @@ pub(crate) fn parse_extended_80211_tx(input: &[u8]) -> IResult<&[u8], Extended80
 
     Ok((
         input,
-        Extended80211Tx {
+        Extended80211TxRef {
             ssid,
             bssid,
             version,
@@ -55,3 +90,15 @@ pub(crate) fn parse_extended_80211_tx(input: &[u8]) -> IResult<&[u8], Extended80
         },
     ))
 }
+
+pub(crate) fn write_extended_80211_tx(out: &mut Vec<u8>, r: &Extended80211Tx) {
+    write_sflow_string(out, &r.ssid);
+    out.extend_from_slice(&r.bssid.bytes());
+    write_u32(out, r.version);
+    write_u32(out, r.transmissions);
+    write_u32(out, r.packet_duration_us);
+    write_u32(out, r.retrans_duration_us);
+    write_u32(out, r.channel);
+    write_u64(out, r.speed);
+    write_u32(out, r.power);
+}
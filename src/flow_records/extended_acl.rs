@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedAcl {
@@ -11,17 +13,42 @@ pub struct ExtendedAcl {
     pub direction: u32,
 }
 
-pub(crate) fn parse_extended_acl(input: &[u8]) -> IResult<&[u8], ExtendedAcl> {
+/// Borrowed form of [`ExtendedAcl`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedAclRef<'a> {
+    pub number: u32,
+    pub name: Cow<'a, str>,
+    pub direction: u32,
+}
+
+impl ExtendedAclRef<'_> {
+    /// Convert into the owned [`ExtendedAcl`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedAcl {
+        ExtendedAcl {
+            number: self.number,
+            name: self.name.into_owned(),
+            direction: self.direction,
+        }
+    }
+}
+
+pub(crate) fn parse_extended_acl_ref(input: &[u8]) -> IResult<&[u8], ExtendedAclRef<'_>> {
     let (input, number) = be_u32(input)?;
-    let (input, name) = parse_sflow_string(input)?;
+    let (input, name) = parse_sflow_str(input)?;
     let (input, direction) = be_u32(input)?;
 
     Ok((
         input,
-        ExtendedAcl {
+        ExtendedAclRef {
             number,
             name,
             direction,
         },
     ))
 }
+
+pub(crate) fn write_extended_acl(out: &mut Vec<u8>, r: &ExtendedAcl) {
+    write_u32(out, r.number);
+    write_sflow_string(out, &r.name);
+    write_u32(out, r.direction);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedDecapsulateEgress {
     pub inner_header_offset: u32,
@@ -37,3 +39,17 @@ pub(crate) fn parse_extended_decapsulate_ingress(
         },
     ))
 }
+
+pub(crate) fn write_extended_decapsulate_egress(
+    out: &mut Vec<u8>,
+    r: &ExtendedDecapsulateEgress,
+) {
+    write_u32(out, r.inner_header_offset);
+}
+
+pub(crate) fn write_extended_decapsulate_ingress(
+    out: &mut Vec<u8>,
+    r: &ExtendedDecapsulateIngress,
+) {
+    write_u32(out, r.inner_header_offset);
+}
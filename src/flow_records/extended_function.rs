@@ -1,15 +1,37 @@
 use nom::IResult;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedFunction {
     pub symbol: String,
 }
 
-pub(crate) fn parse_extended_function(input: &[u8]) -> IResult<&[u8], ExtendedFunction> {
-    let (input, symbol) = parse_sflow_string(input)?;
+/// Borrowed form of [`ExtendedFunction`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedFunctionRef<'a> {
+    pub symbol: Cow<'a, str>,
+}
+
+impl ExtendedFunctionRef<'_> {
+    /// Convert into the owned [`ExtendedFunction`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedFunction {
+        ExtendedFunction {
+            symbol: self.symbol.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_extended_function_ref(
+    input: &[u8],
+) -> IResult<&[u8], ExtendedFunctionRef<'_>> {
+    let (input, symbol) = parse_sflow_str(input)?;
+
+    Ok((input, ExtendedFunctionRef { symbol }))
+}
 
-    Ok((input, ExtendedFunction { symbol }))
+pub(crate) fn write_extended_function(out: &mut Vec<u8>, r: &ExtendedFunction) {
+    write_sflow_string(out, &r.symbol);
 }
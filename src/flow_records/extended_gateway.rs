@@ -2,7 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-use crate::datagram::{AddressType, parse_address};
+use crate::datagram::{AddressType, parse_address, write_address};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AsPathSegment {
@@ -20,7 +21,19 @@ pub struct ExtendedGateway {
     pub communities: Vec<u32>,
 }
 
-fn parse_as_path_segment(input: &[u8]) -> IResult<&[u8], AsPathSegment> {
+impl ExtendedGateway {
+    /// Destination AS: the last AS in the path, or `as_number` when the path
+    /// is empty and the destination is in the router's own AS.
+    pub fn dst_as(&self) -> u32 {
+        self.as_path_segments
+            .iter()
+            .rev()
+            .find_map(|segment| segment.values.last().copied())
+            .unwrap_or(self.as_number)
+    }
+}
+
+pub(crate) fn parse_as_path_segment(input: &[u8]) -> IResult<&[u8], AsPathSegment> {
     let (input, segment_type) = be_u32(input)?;
     let (input, count) = be_u32(input)?;
     // Cap capacity: each value is 4 bytes
@@ -81,3 +94,26 @@ pub(crate) fn parse_extended_gateway(input: &[u8]) -> IResult<&[u8], ExtendedGat
         },
     ))
 }
+
+fn write_as_path_segment(out: &mut Vec<u8>, r: &AsPathSegment) {
+    write_u32(out, r.segment_type);
+    write_u32(out, r.values.len() as u32);
+    for val in &r.values {
+        write_u32(out, *val);
+    }
+}
+
+pub(crate) fn write_extended_gateway(out: &mut Vec<u8>, r: &ExtendedGateway) {
+    write_address(out, &r.next_hop);
+    write_u32(out, r.as_number);
+    write_u32(out, r.src_as);
+    write_u32(out, r.src_peer_as);
+    write_u32(out, r.as_path_segments.len() as u32);
+    for segment in &r.as_path_segments {
+        write_as_path_segment(out, segment);
+    }
+    write_u32(out, r.communities.len() as u32);
+    for val in &r.communities {
+        write_u32(out, *val);
+    }
+}
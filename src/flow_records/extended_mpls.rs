@@ -2,7 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-use crate::datagram::{AddressType, parse_address};
+use crate::datagram::{AddressType, parse_address, write_address};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedMpls {
@@ -38,3 +39,16 @@ pub(crate) fn parse_extended_mpls(input: &[u8]) -> IResult<&[u8], ExtendedMpls>
         },
     ))
 }
+
+fn write_label_stack(out: &mut Vec<u8>, labels: &[u32]) {
+    write_u32(out, labels.len() as u32);
+    for val in labels {
+        write_u32(out, *val);
+    }
+}
+
+pub(crate) fn write_extended_mpls(out: &mut Vec<u8>, r: &ExtendedMpls) {
+    write_address(out, &r.next_hop);
+    write_label_stack(out, &r.in_label_stack);
+    write_label_stack(out, &r.out_label_stack);
+}
@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedMplsFtn {
@@ -10,15 +12,39 @@ pub struct ExtendedMplsFtn {
     pub mpls_ftn_mask: u32,
 }
 
-pub(crate) fn parse_extended_mpls_ftn(input: &[u8]) -> IResult<&[u8], ExtendedMplsFtn> {
-    let (input, mpls_ftn_descr) = parse_sflow_string(input)?;
+/// Borrowed form of [`ExtendedMplsFtn`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedMplsFtnRef<'a> {
+    pub mpls_ftn_descr: Cow<'a, str>,
+    pub mpls_ftn_mask: u32,
+}
+
+impl ExtendedMplsFtnRef<'_> {
+    /// Convert into the owned [`ExtendedMplsFtn`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedMplsFtn {
+        ExtendedMplsFtn {
+            mpls_ftn_descr: self.mpls_ftn_descr.into_owned(),
+            mpls_ftn_mask: self.mpls_ftn_mask,
+        }
+    }
+}
+
+pub(crate) fn parse_extended_mpls_ftn_ref(
+    input: &[u8],
+) -> IResult<&[u8], ExtendedMplsFtnRef<'_>> {
+    let (input, mpls_ftn_descr) = parse_sflow_str(input)?;
     let (input, mpls_ftn_mask) = be_u32(input)?;
 
     Ok((
         input,
-        ExtendedMplsFtn {
+        ExtendedMplsFtnRef {
             mpls_ftn_descr,
             mpls_ftn_mask,
         },
     ))
 }
+
+pub(crate) fn write_extended_mpls_ftn(out: &mut Vec<u8>, r: &ExtendedMplsFtn) {
+    write_sflow_string(out, &r.mpls_ftn_descr);
+    write_u32(out, r.mpls_ftn_mask);
+}
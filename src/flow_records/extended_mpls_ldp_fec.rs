@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedMplsLdpFec {
     pub mpls_fec_addr_prefix_length: u32,
@@ -17,3 +19,7 @@ pub(crate) fn parse_extended_mpls_ldp_fec(input: &[u8]) -> IResult<&[u8], Extend
         },
     ))
 }
+
+pub(crate) fn write_extended_mpls_ldp_fec(out: &mut Vec<u8>, r: &ExtendedMplsLdpFec) {
+    write_u32(out, r.mpls_fec_addr_prefix_length);
+}
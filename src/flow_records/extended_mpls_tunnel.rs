@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedMplsTunnel {
@@ -11,17 +13,44 @@ pub struct ExtendedMplsTunnel {
     pub tunnel_cos: u32,
 }
 
-pub(crate) fn parse_extended_mpls_tunnel(input: &[u8]) -> IResult<&[u8], ExtendedMplsTunnel> {
-    let (input, tunnel_lsp_name) = parse_sflow_string(input)?;
+/// Borrowed form of [`ExtendedMplsTunnel`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedMplsTunnelRef<'a> {
+    pub tunnel_lsp_name: Cow<'a, str>,
+    pub tunnel_id: u32,
+    pub tunnel_cos: u32,
+}
+
+impl ExtendedMplsTunnelRef<'_> {
+    /// Convert into the owned [`ExtendedMplsTunnel`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedMplsTunnel {
+        ExtendedMplsTunnel {
+            tunnel_lsp_name: self.tunnel_lsp_name.into_owned(),
+            tunnel_id: self.tunnel_id,
+            tunnel_cos: self.tunnel_cos,
+        }
+    }
+}
+
+pub(crate) fn parse_extended_mpls_tunnel_ref(
+    input: &[u8],
+) -> IResult<&[u8], ExtendedMplsTunnelRef<'_>> {
+    let (input, tunnel_lsp_name) = parse_sflow_str(input)?;
     let (input, tunnel_id) = be_u32(input)?;
     let (input, tunnel_cos) = be_u32(input)?;
 
     Ok((
         input,
-        ExtendedMplsTunnel {
+        ExtendedMplsTunnelRef {
             tunnel_lsp_name,
             tunnel_id,
             tunnel_cos,
         },
     ))
 }
+
+pub(crate) fn write_extended_mpls_tunnel(out: &mut Vec<u8>, r: &ExtendedMplsTunnel) {
+    write_sflow_string(out, &r.tunnel_lsp_name);
+    write_u32(out, r.tunnel_id);
+    write_u32(out, r.tunnel_cos);
+}
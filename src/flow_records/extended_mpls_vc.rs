@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedMplsVc {
@@ -11,17 +13,44 @@ pub struct ExtendedMplsVc {
     pub vc_label_cos: u32,
 }
 
-pub(crate) fn parse_extended_mpls_vc(input: &[u8]) -> IResult<&[u8], ExtendedMplsVc> {
-    let (input, vc_instance_name) = parse_sflow_string(input)?;
+/// Borrowed form of [`ExtendedMplsVc`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedMplsVcRef<'a> {
+    pub vc_instance_name: Cow<'a, str>,
+    pub vll_vc_id: u32,
+    pub vc_label_cos: u32,
+}
+
+impl ExtendedMplsVcRef<'_> {
+    /// Convert into the owned [`ExtendedMplsVc`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedMplsVc {
+        ExtendedMplsVc {
+            vc_instance_name: self.vc_instance_name.into_owned(),
+            vll_vc_id: self.vll_vc_id,
+            vc_label_cos: self.vc_label_cos,
+        }
+    }
+}
+
+pub(crate) fn parse_extended_mpls_vc_ref(
+    input: &[u8],
+) -> IResult<&[u8], ExtendedMplsVcRef<'_>> {
+    let (input, vc_instance_name) = parse_sflow_str(input)?;
     let (input, vll_vc_id) = be_u32(input)?;
     let (input, vc_label_cos) = be_u32(input)?;
 
     Ok((
         input,
-        ExtendedMplsVc {
+        ExtendedMplsVcRef {
             vc_instance_name,
             vll_vc_id,
             vc_label_cos,
         },
     ))
 }
+
+pub(crate) fn write_extended_mpls_vc(out: &mut Vec<u8>, r: &ExtendedMplsVc) {
+    write_sflow_string(out, &r.vc_instance_name);
+    write_u32(out, r.vll_vc_id);
+    write_u32(out, r.vc_label_cos);
+}
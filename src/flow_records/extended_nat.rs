@@ -1,7 +1,7 @@
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
-use crate::datagram::{AddressType, parse_address};
+use crate::datagram::{AddressType, parse_address, write_address};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedNat {
@@ -21,3 +21,8 @@ pub(crate) fn parse_extended_nat(input: &[u8]) -> IResult<&[u8], ExtendedNat> {
         },
     ))
 }
+
+pub(crate) fn write_extended_nat(out: &mut Vec<u8>, r: &ExtendedNat) {
+    write_address(out, &r.src_address);
+    write_address(out, &r.dst_address);
+}
@@ -1,7 +1,8 @@
 use nom::IResult;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedProxyRequest {
@@ -9,11 +10,33 @@ pub struct ExtendedProxyRequest {
     pub host: String,
 }
 
-pub(crate) fn parse_extended_proxy_request(
+/// Borrowed form of [`ExtendedProxyRequest`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedProxyRequestRef<'a> {
+    pub uri: Cow<'a, str>,
+    pub host: Cow<'a, str>,
+}
+
+impl ExtendedProxyRequestRef<'_> {
+    /// Convert into the owned [`ExtendedProxyRequest`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedProxyRequest {
+        ExtendedProxyRequest {
+            uri: self.uri.into_owned(),
+            host: self.host.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_extended_proxy_request_ref(
     input: &[u8],
-) -> IResult<&[u8], ExtendedProxyRequest> {
-    let (input, uri) = parse_sflow_string(input)?;
-    let (input, host) = parse_sflow_string(input)?;
+) -> IResult<&[u8], ExtendedProxyRequestRef<'_>> {
+    let (input, uri) = parse_sflow_str(input)?;
+    let (input, host) = parse_sflow_str(input)?;
+
+    Ok((input, ExtendedProxyRequestRef { uri, host }))
+}
 
-    Ok((input, ExtendedProxyRequest { uri, host }))
+pub(crate) fn write_extended_proxy_request(out: &mut Vec<u8>, r: &ExtendedProxyRequest) {
+    write_sflow_string(out, &r.uri);
+    write_sflow_string(out, &r.host);
 }
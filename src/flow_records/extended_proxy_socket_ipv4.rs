@@ -3,6 +3,8 @@ use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedProxySocketIpv4 {
     pub protocol: u32,
@@ -32,3 +34,11 @@ pub(crate) fn parse_extended_proxy_socket_ipv4(
         },
     ))
 }
+
+pub(crate) fn write_extended_proxy_socket_ipv4(out: &mut Vec<u8>, r: &ExtendedProxySocketIpv4) {
+    write_u32(out, r.protocol);
+    write_u32(out, u32::from(r.local_ip));
+    write_u32(out, u32::from(r.remote_ip));
+    write_u32(out, r.local_port);
+    write_u32(out, r.remote_port);
+}
@@ -3,6 +3,8 @@ use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 use std::net::Ipv6Addr;
 
+use crate::encode::{write_ipv6, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedProxySocketIpv6 {
     pub protocol: u32,
@@ -41,3 +43,11 @@ pub(crate) fn parse_extended_proxy_socket_ipv6(
         },
     ))
 }
+
+pub(crate) fn write_extended_proxy_socket_ipv6(out: &mut Vec<u8>, r: &ExtendedProxySocketIpv6) {
+    write_u32(out, r.protocol);
+    write_ipv6(out, &r.local_ip);
+    write_ipv6(out, &r.remote_ip);
+    write_u32(out, r.local_port);
+    write_u32(out, r.remote_port);
+}
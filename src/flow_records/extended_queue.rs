@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedQueue {
     pub queue_depth: u32,
@@ -12,3 +14,7 @@ pub(crate) fn parse_extended_queue(input: &[u8]) -> IResult<&[u8], ExtendedQueue
 
     Ok((input, ExtendedQueue { queue_depth }))
 }
+
+pub(crate) fn write_extended_queue(out: &mut Vec<u8>, r: &ExtendedQueue) {
+    write_u32(out, r.queue_depth);
+}
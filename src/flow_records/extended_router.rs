@@ -2,7 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-use crate::datagram::{AddressType, parse_address};
+use crate::datagram::{AddressType, parse_address, write_address};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedRouter {
@@ -25,3 +26,9 @@ pub(crate) fn parse_extended_router(input: &[u8]) -> IResult<&[u8], ExtendedRout
         },
     ))
 }
+
+pub(crate) fn write_extended_router(out: &mut Vec<u8>, r: &ExtendedRouter) {
+    write_address(out, &r.next_hop);
+    write_u32(out, r.src_mask_len);
+    write_u32(out, r.dst_mask_len);
+}
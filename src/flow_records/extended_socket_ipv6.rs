@@ -3,6 +3,8 @@ use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 use std::net::Ipv6Addr;
 
+use crate::encode::{write_ipv6, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedSocketIpv6 {
     pub protocol: u32,
@@ -39,3 +41,11 @@ pub(crate) fn parse_extended_socket_ipv6(input: &[u8]) -> IResult<&[u8], Extende
         },
     ))
 }
+
+pub(crate) fn write_extended_socket_ipv6(out: &mut Vec<u8>, r: &ExtendedSocketIpv6) {
+    write_u32(out, r.protocol);
+    write_ipv6(out, &r.local_ip);
+    write_ipv6(out, &r.remote_ip);
+    write_u32(out, r.local_port);
+    write_u32(out, r.remote_port);
+}
@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedSwitch {
     pub src_vlan: u32,
@@ -26,3 +28,10 @@ pub(crate) fn parse_extended_switch(input: &[u8]) -> IResult<&[u8], ExtendedSwit
         },
     ))
 }
+
+pub(crate) fn write_extended_switch(out: &mut Vec<u8>, r: &ExtendedSwitch) {
+    write_u32(out, r.src_vlan);
+    write_u32(out, r.src_priority);
+    write_u32(out, r.dst_vlan);
+    write_u32(out, r.dst_priority);
+}
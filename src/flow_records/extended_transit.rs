@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedTransit {
     pub transit_delay_ns: u32,
@@ -12,3 +14,7 @@ pub(crate) fn parse_extended_transit(input: &[u8]) -> IResult<&[u8], ExtendedTra
 
     Ok((input, ExtendedTransit { transit_delay_ns }))
 }
+
+pub(crate) fn write_extended_transit(out: &mut Vec<u8>, r: &ExtendedTransit) {
+    write_u32(out, r.transit_delay_ns);
+}
@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedUrl {
@@ -11,17 +13,42 @@ pub struct ExtendedUrl {
     pub host: String,
 }
 
-pub(crate) fn parse_extended_url(input: &[u8]) -> IResult<&[u8], ExtendedUrl> {
+/// Borrowed form of [`ExtendedUrl`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedUrlRef<'a> {
+    pub direction: u32,
+    pub url: Cow<'a, str>,
+    pub host: Cow<'a, str>,
+}
+
+impl ExtendedUrlRef<'_> {
+    /// Convert into the owned [`ExtendedUrl`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedUrl {
+        ExtendedUrl {
+            direction: self.direction,
+            url: self.url.into_owned(),
+            host: self.host.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_extended_url_ref(input: &[u8]) -> IResult<&[u8], ExtendedUrlRef<'_>> {
     let (input, direction) = be_u32(input)?;
-    let (input, url) = parse_sflow_string(input)?;
-    let (input, host) = parse_sflow_string(input)?;
+    let (input, url) = parse_sflow_str(input)?;
+    let (input, host) = parse_sflow_str(input)?;
 
     Ok((
         input,
-        ExtendedUrl {
+        ExtendedUrlRef {
             direction,
             url,
             host,
         },
     ))
 }
+
+pub(crate) fn write_extended_url(out: &mut Vec<u8>, r: &ExtendedUrl) {
+    write_u32(out, r.direction);
+    write_sflow_string(out, &r.url);
+    write_sflow_string(out, &r.host);
+}
@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedUser {
@@ -12,15 +14,36 @@ pub struct ExtendedUser {
     pub dst_user: String,
 }
 
-pub(crate) fn parse_extended_user(input: &[u8]) -> IResult<&[u8], ExtendedUser> {
+/// Borrowed form of [`ExtendedUser`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtendedUserRef<'a> {
+    pub src_charset: u32,
+    pub src_user: Cow<'a, str>,
+    pub dst_charset: u32,
+    pub dst_user: Cow<'a, str>,
+}
+
+impl ExtendedUserRef<'_> {
+    /// Convert into the owned [`ExtendedUser`], copying any borrowed data.
+    pub fn into_owned(self) -> ExtendedUser {
+        ExtendedUser {
+            src_charset: self.src_charset,
+            src_user: self.src_user.into_owned(),
+            dst_charset: self.dst_charset,
+            dst_user: self.dst_user.into_owned(),
+        }
+    }
+}
+
+pub(crate) fn parse_extended_user_ref(input: &[u8]) -> IResult<&[u8], ExtendedUserRef<'_>> {
     let (input, src_charset) = be_u32(input)?;
-    let (input, src_user) = parse_sflow_string(input)?;
+    let (input, src_user) = parse_sflow_str(input)?;
     let (input, dst_charset) = be_u32(input)?;
-    let (input, dst_user) = parse_sflow_string(input)?;
+    let (input, dst_user) = parse_sflow_str(input)?;
 
     Ok((
         input,
-        ExtendedUser {
+        ExtendedUserRef {
             src_charset,
             src_user,
             dst_charset,
@@ -28,3 +51,10 @@ pub(crate) fn parse_extended_user(input: &[u8]) -> IResult<&[u8], ExtendedUser>
         },
     ))
 }
+
+pub(crate) fn write_extended_user(out: &mut Vec<u8>, r: &ExtendedUser) {
+    write_u32(out, r.src_charset);
+    write_sflow_string(out, &r.src_user);
+    write_u32(out, r.dst_charset);
+    write_sflow_string(out, &r.dst_user);
+}
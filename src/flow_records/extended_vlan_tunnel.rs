@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedVlanTunnel {
     pub stack: Vec<u32>,
@@ -20,3 +22,10 @@ pub(crate) fn parse_extended_vlan_tunnel(input: &[u8]) -> IResult<&[u8], Extende
 
     Ok((input, ExtendedVlanTunnel { stack }))
 }
+
+pub(crate) fn write_extended_vlan_tunnel(out: &mut Vec<u8>, r: &ExtendedVlanTunnel) {
+    write_u32(out, r.stack.len() as u32);
+    for val in &r.stack {
+        write_u32(out, *val);
+    }
+}
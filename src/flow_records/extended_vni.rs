@@ -2,6 +2,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtendedVniEgress {
     pub vni: u32,
@@ -23,3 +25,11 @@ pub(crate) fn parse_extended_vni_ingress(input: &[u8]) -> IResult<&[u8], Extende
 
     Ok((input, ExtendedVniIngress { vni }))
 }
+
+pub(crate) fn write_extended_vni_egress(out: &mut Vec<u8>, r: &ExtendedVniEgress) {
+    write_u32(out, r.vni);
+}
+
+pub(crate) fn write_extended_vni_ingress(out: &mut Vec<u8>, r: &ExtendedVniIngress) {
+    write_u32(out, r.vni);
+}
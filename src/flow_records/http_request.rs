@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::{write_u32, write_u64};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HttpRequest {
@@ -21,16 +23,55 @@ pub struct HttpRequest {
     pub status: u32,
 }
 
-pub(crate) fn parse_http_request(input: &[u8]) -> IResult<&[u8], HttpRequest> {
+/// Borrowed form of [`HttpRequest`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HttpRequestRef<'a> {
+    pub method: u32,
+    pub protocol: u32,
+    pub uri: Cow<'a, str>,
+    pub host: Cow<'a, str>,
+    pub referer: Cow<'a, str>,
+    pub useragent: Cow<'a, str>,
+    pub xff: Cow<'a, str>,
+    pub authuser: Cow<'a, str>,
+    pub mime_type: Cow<'a, str>,
+    pub req_bytes: u64,
+    pub resp_bytes: u64,
+    pub duration_us: u32,
+    pub status: u32,
+}
+
+impl HttpRequestRef<'_> {
+    /// Convert into the owned [`HttpRequest`], copying any borrowed data.
+    pub fn into_owned(self) -> HttpRequest {
+        HttpRequest {
+            method: self.method,
+            protocol: self.protocol,
+            uri: self.uri.into_owned(),
+            host: self.host.into_owned(),
+            referer: self.referer.into_owned(),
+            useragent: self.useragent.into_owned(),
+            xff: self.xff.into_owned(),
+            authuser: self.authuser.into_owned(),
+            mime_type: self.mime_type.into_owned(),
+            req_bytes: self.req_bytes,
+            resp_bytes: self.resp_bytes,
+            duration_us: self.duration_us,
+            status: self.status,
+        }
+    }
+}
+
+pub(crate) fn parse_http_request_ref(input: &[u8]) -> IResult<&[u8], HttpRequestRef<'_>> {
     let (input, method) = be_u32(input)?;
     let (input, protocol) = be_u32(input)?;
-    let (input, uri) = parse_sflow_string(input)?;
-    let (input, host) = parse_sflow_string(input)?;
-    let (input, referer) = parse_sflow_string(input)?;
-    let (input, useragent) = parse_sflow_string(input)?;
-    let (input, xff) = parse_sflow_string(input)?;
-    let (input, authuser) = parse_sflow_string(input)?;
-    let (input, mime_type) = parse_sflow_string(input)?;
+    let (input, uri) = parse_sflow_str(input)?;
+    let (input, host) = parse_sflow_str(input)?;
+    let (input, referer) = parse_sflow_str(input)?;
+    let (input, useragent) = parse_sflow_str(input)?;
+    let (input, xff) = parse_sflow_str(input)?;
+    let (input, authuser) = parse_sflow_str(input)?;
+    let (input, mime_type) = parse_sflow_str(input)?;
     let (input, req_bytes) = be_u64(input)?;
     let (input, resp_bytes) = be_u64(input)?;
     let (input, duration_us) = be_u32(input)?;
@@ -38,7 +79,7 @@ pub(crate) fn parse_http_request(input: &[u8]) -> IResult<&[u8], HttpRequest> {
 
     Ok((
         input,
-        HttpRequest {
+        HttpRequestRef {
             method,
             protocol,
             uri,
@@ -55,3 +96,19 @@ pub(crate) fn parse_http_request(input: &[u8]) -> IResult<&[u8], HttpRequest> {
         },
     ))
 }
+
+pub(crate) fn write_http_request(out: &mut Vec<u8>, r: &HttpRequest) {
+    write_u32(out, r.method);
+    write_u32(out, r.protocol);
+    write_sflow_string(out, &r.uri);
+    write_sflow_string(out, &r.host);
+    write_sflow_string(out, &r.referer);
+    write_sflow_string(out, &r.useragent);
+    write_sflow_string(out, &r.xff);
+    write_sflow_string(out, &r.authuser);
+    write_sflow_string(out, &r.mime_type);
+    write_u64(out, r.req_bytes);
+    write_u64(out, r.resp_bytes);
+    write_u32(out, r.duration_us);
+    write_u32(out, r.status);
+}
@@ -1,8 +1,10 @@
 use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-use super::parse_sflow_string;
+use super::{parse_sflow_str, write_sflow_string};
+use crate::encode::write_u32;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MemcacheOperation {
@@ -15,10 +17,39 @@ pub struct MemcacheOperation {
     pub status: u32,
 }
 
-pub(crate) fn parse_memcache_operation(input: &[u8]) -> IResult<&[u8], MemcacheOperation> {
+/// Borrowed form of [`MemcacheOperation`]; strings borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemcacheOperationRef<'a> {
+    pub protocol: u32,
+    pub cmd: u32,
+    pub key: Cow<'a, str>,
+    pub nkeys: u32,
+    pub value_bytes: u32,
+    pub duration_us: u32,
+    pub status: u32,
+}
+
+impl MemcacheOperationRef<'_> {
+    /// Convert into the owned [`MemcacheOperation`], copying any borrowed data.
+    pub fn into_owned(self) -> MemcacheOperation {
+        MemcacheOperation {
+            protocol: self.protocol,
+            cmd: self.cmd,
+            key: self.key.into_owned(),
+            nkeys: self.nkeys,
+            value_bytes: self.value_bytes,
+            duration_us: self.duration_us,
+            status: self.status,
+        }
+    }
+}
+
+pub(crate) fn parse_memcache_operation_ref(
+    input: &[u8],
+) -> IResult<&[u8], MemcacheOperationRef<'_>> {
     let (input, protocol) = be_u32(input)?;
     let (input, cmd) = be_u32(input)?;
-    let (input, key) = parse_sflow_string(input)?;
+    let (input, key) = parse_sflow_str(input)?;
     let (input, nkeys) = be_u32(input)?;
     let (input, value_bytes) = be_u32(input)?;
     let (input, duration_us) = be_u32(input)?;
@@ -26,7 +57,7 @@ pub(crate) fn parse_memcache_operation(input: &[u8]) -> IResult<&[u8], MemcacheO
 
     Ok((
         input,
-        MemcacheOperation {
+        MemcacheOperationRef {
             protocol,
             cmd,
             key,
@@ -37,3 +68,13 @@ pub(crate) fn parse_memcache_operation(input: &[u8]) -> IResult<&[u8], MemcacheO
         },
     ))
 }
+
+pub(crate) fn write_memcache_operation(out: &mut Vec<u8>, r: &MemcacheOperation) {
+    write_u32(out, r.protocol);
+    write_u32(out, r.cmd);
+    write_sflow_string(out, &r.key);
+    write_u32(out, r.nkeys);
+    write_u32(out, r.value_bytes);
+    write_u32(out, r.duration_us);
+    write_u32(out, r.status);
+}
@@ -38,23 +38,27 @@ use nom::IResult;
 use nom::bytes::complete::take;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-pub use app_operation::AppOperation;
-pub use extended_80211_payload::Extended80211Payload;
-pub use extended_80211_rx::Extended80211Rx;
-pub use extended_80211_tx::Extended80211Tx;
-pub use extended_acl::ExtendedAcl;
+use crate::custom::Custom;
+use crate::encode::{write_opaque, write_tagged, write_u32};
+
+pub use app_operation::{AppOperation, AppOperationRef};
+pub use extended_80211_payload::{Extended80211Payload, Extended80211PayloadRef};
+pub use extended_80211_rx::{Extended80211Rx, Extended80211RxRef};
+pub use extended_80211_tx::{Extended80211Tx, Extended80211TxRef};
+pub use extended_acl::{ExtendedAcl, ExtendedAclRef};
 pub use extended_decapsulate::{ExtendedDecapsulateEgress, ExtendedDecapsulateIngress};
 pub use extended_egress_queue::ExtendedEgressQueue;
-pub use extended_function::ExtendedFunction;
+pub use extended_function::{ExtendedFunction, ExtendedFunctionRef};
 pub use extended_gateway::ExtendedGateway;
 pub use extended_mpls::ExtendedMpls;
-pub use extended_mpls_ftn::ExtendedMplsFtn;
+pub use extended_mpls_ftn::{ExtendedMplsFtn, ExtendedMplsFtnRef};
 pub use extended_mpls_ldp_fec::ExtendedMplsLdpFec;
-pub use extended_mpls_tunnel::ExtendedMplsTunnel;
-pub use extended_mpls_vc::ExtendedMplsVc;
+pub use extended_mpls_tunnel::{ExtendedMplsTunnel, ExtendedMplsTunnelRef};
+pub use extended_mpls_vc::{ExtendedMplsVc, ExtendedMplsVcRef};
 pub use extended_nat::ExtendedNat;
-pub use extended_proxy_request::ExtendedProxyRequest;
+pub use extended_proxy_request::{ExtendedProxyRequest, ExtendedProxyRequestRef};
 pub use extended_proxy_socket_ipv4::ExtendedProxySocketIpv4;
 pub use extended_proxy_socket_ipv6::ExtendedProxySocketIpv6;
 pub use extended_queue::ExtendedQueue;
@@ -63,14 +67,14 @@ pub use extended_socket_ipv4::ExtendedSocketIpv4;
 pub use extended_socket_ipv6::ExtendedSocketIpv6;
 pub use extended_switch::ExtendedSwitch;
 pub use extended_transit::ExtendedTransit;
-pub use extended_url::ExtendedUrl;
-pub use extended_user::ExtendedUser;
+pub use extended_url::{ExtendedUrl, ExtendedUrlRef};
+pub use extended_user::{ExtendedUser, ExtendedUserRef};
 pub use extended_vlan_tunnel::ExtendedVlanTunnel;
 pub use extended_vni::{ExtendedVniEgress, ExtendedVniIngress};
-pub use http_request::HttpRequest;
-pub use jvm_runtime::JvmRuntime;
-pub use memcache_operation::MemcacheOperation;
-pub use raw_packet_header::RawPacketHeader;
+pub use http_request::{HttpRequest, HttpRequestRef};
+pub use jvm_runtime::{JvmRuntime, JvmRuntimeRef};
+pub use memcache_operation::{MemcacheOperation, MemcacheOperationRef};
+pub use raw_packet_header::{HeaderProtocol, RawPacketHeader, RawPacketHeaderRef};
 pub use sampled_ethernet::SampledEthernet;
 pub use sampled_ipv4::SampledIpv4;
 pub use sampled_ipv6::SampledIpv6;
@@ -79,7 +83,7 @@ pub use sampled_ipv6::SampledIpv6;
 ///
 /// Flow records describe properties of a sampled packet, ranging from
 /// raw header bytes to decoded L2/L3/L4 fields and extended routing data.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FlowRecord {
     /// Raw packet header bytes (enterprise=0, format=1).
     RawPacketHeader(RawPacketHeader),
@@ -176,248 +180,661 @@ pub enum FlowRecord {
         /// Raw record data.
         data: Vec<u8>,
     },
+    /// Enterprise-specific flow record decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
+}
+
+/// Borrowed form of [`FlowRecord`].
+///
+/// Records carrying strings or opaque bytes borrow them from the input
+/// buffer; the remaining variants hold the same types as [`FlowRecord`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum FlowRecordRef<'a> {
+    /// Raw packet header bytes (enterprise=0, format=1).
+    RawPacketHeader(RawPacketHeaderRef<'a>),
+    /// Sampled Ethernet frame header (enterprise=0, format=2).
+    SampledEthernet(SampledEthernet),
+    /// Sampled IPv4 packet header (enterprise=0, format=3).
+    SampledIpv4(SampledIpv4),
+    /// Sampled IPv6 packet header (enterprise=0, format=4).
+    SampledIpv6(SampledIpv6),
+    /// Extended switch data — VLAN and priority (enterprise=0, format=1001).
+    ExtendedSwitch(ExtendedSwitch),
+    /// Extended router data — next hop and masks (enterprise=0, format=1002).
+    ExtendedRouter(ExtendedRouter),
+    /// Extended gateway data — BGP AS path and communities (enterprise=0, format=1003).
+    ExtendedGateway(ExtendedGateway),
+    /// Extended user data — source and destination user identifiers (enterprise=0, format=1004).
+    ExtendedUser(ExtendedUserRef<'a>),
+    /// Extended URL data — URL and host strings (enterprise=0, format=1005).
+    ExtendedUrl(ExtendedUrlRef<'a>),
+    /// Extended MPLS data — next hop and label stacks (enterprise=0, format=1006).
+    ExtendedMpls(ExtendedMpls),
+    /// Extended NAT data — translated source and destination addresses (enterprise=0, format=1007).
+    ExtendedNat(ExtendedNat),
+    /// Extended MPLS tunnel data (enterprise=0, format=1008).
+    ExtendedMplsTunnel(ExtendedMplsTunnelRef<'a>),
+    /// Extended MPLS virtual circuit data (enterprise=0, format=1009).
+    ExtendedMplsVc(ExtendedMplsVcRef<'a>),
+    /// Extended MPLS FEC to NHLFE mapping (enterprise=0, format=1010).
+    ExtendedMplsFtn(ExtendedMplsFtnRef<'a>),
+    /// Extended MPLS LDP FEC data (enterprise=0, format=1011).
+    ExtendedMplsLdpFec(ExtendedMplsLdpFec),
+    /// Extended VLAN tunnel data — 802.1Q-in-Q stack (enterprise=0, format=1012).
+    ExtendedVlanTunnel(ExtendedVlanTunnel),
+    /// Extended 802.11 payload data (enterprise=0, format=1013).
+    Extended80211Payload(Extended80211PayloadRef<'a>),
+    /// Extended 802.11 receive data (enterprise=0, format=1014).
+    Extended80211Rx(Extended80211RxRef<'a>),
+    /// Extended 802.11 transmit data (enterprise=0, format=1015).
+    Extended80211Tx(Extended80211TxRef<'a>),
+    /// Extended L2 tunnel egress — reuses sampled Ethernet (enterprise=0, format=1021).
+    ExtendedL2TunnelEgress(SampledEthernet),
+    /// Extended L2 tunnel ingress — reuses sampled Ethernet (enterprise=0, format=1022).
+    ExtendedL2TunnelIngress(SampledEthernet),
+    /// Extended IPv4 tunnel egress — reuses sampled IPv4 (enterprise=0, format=1023).
+    ExtendedIpv4TunnelEgress(SampledIpv4),
+    /// Extended IPv4 tunnel ingress — reuses sampled IPv4 (enterprise=0, format=1024).
+    ExtendedIpv4TunnelIngress(SampledIpv4),
+    /// Extended IPv6 tunnel egress — reuses sampled IPv6 (enterprise=0, format=1025).
+    ExtendedIpv6TunnelEgress(SampledIpv6),
+    /// Extended IPv6 tunnel ingress — reuses sampled IPv6 (enterprise=0, format=1026).
+    ExtendedIpv6TunnelIngress(SampledIpv6),
+    /// Extended decapsulate egress data (enterprise=0, format=1027).
+    ExtendedDecapsulateEgress(ExtendedDecapsulateEgress),
+    /// Extended decapsulate ingress data (enterprise=0, format=1028).
+    ExtendedDecapsulateIngress(ExtendedDecapsulateIngress),
+    /// Extended VNI egress data (enterprise=0, format=1029).
+    ExtendedVniEgress(ExtendedVniEgress),
+    /// Extended VNI ingress data (enterprise=0, format=1030).
+    ExtendedVniIngress(ExtendedVniIngress),
+    /// Extended egress queue identifier (enterprise=0, format=1036).
+    ExtendedEgressQueue(ExtendedEgressQueue),
+    /// Extended ACL data (enterprise=0, format=1037).
+    ExtendedAcl(ExtendedAclRef<'a>),
+    /// Extended function/symbol data (enterprise=0, format=1038).
+    ExtendedFunction(ExtendedFunctionRef<'a>),
+    /// Extended transit delay data (enterprise=0, format=1039).
+    ExtendedTransit(ExtendedTransit),
+    /// Extended queue depth data (enterprise=0, format=1040).
+    ExtendedQueue(ExtendedQueue),
+    /// Extended socket IPv4 data (enterprise=0, format=2100).
+    ExtendedSocketIpv4(ExtendedSocketIpv4),
+    /// Extended socket IPv6 data (enterprise=0, format=2101).
+    ExtendedSocketIpv6(ExtendedSocketIpv6),
+    /// Extended proxy socket IPv4 data (enterprise=0, format=2102).
+    ExtendedProxySocketIpv4(ExtendedProxySocketIpv4),
+    /// Extended proxy socket IPv6 data (enterprise=0, format=2103).
+    ExtendedProxySocketIpv6(ExtendedProxySocketIpv6),
+    /// JVM runtime information (enterprise=0, format=2105).
+    JvmRuntime(JvmRuntimeRef<'a>),
+    /// Memcache operation data (enterprise=0, format=2200).
+    MemcacheOperation(MemcacheOperationRef<'a>),
+    /// Application operation data (enterprise=0, format=2202).
+    AppOperation(AppOperationRef<'a>),
+    /// HTTP request data (enterprise=0, format=2206).
+    HttpRequest(HttpRequestRef<'a>),
+    /// Extended proxy request data (enterprise=0, format=2207).
+    ExtendedProxyRequest(ExtendedProxyRequestRef<'a>),
+    /// Unrecognized flow record type, preserved as raw bytes.
+    Unknown {
+        /// Enterprise code from the record header.
+        enterprise: u32,
+        /// Format code from the record header.
+        format: u32,
+        /// Raw record data.
+        data: &'a [u8],
+    },
+    /// Enterprise-specific flow record decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
+}
+
+impl FlowRecordRef<'_> {
+    /// Borrowed counterpart of [`FlowRecord::frame_length`].
+    pub fn frame_length(&self) -> Option<u32> {
+        match self {
+            FlowRecordRef::RawPacketHeader(r) => Some(r.frame_length),
+            FlowRecordRef::SampledEthernet(r) => Some(r.length),
+            FlowRecordRef::SampledIpv4(r) => Some(r.length),
+            FlowRecordRef::SampledIpv6(r) => Some(r.length),
+            _ => None,
+        }
+    }
+
+    /// Convert into the owned [`FlowRecord`], copying any borrowed data.
+    pub fn into_owned(self) -> FlowRecord {
+        match self {
+            FlowRecordRef::RawPacketHeader(r) => FlowRecord::RawPacketHeader(r.into_owned()),
+            FlowRecordRef::SampledEthernet(r) => FlowRecord::SampledEthernet(r),
+            FlowRecordRef::SampledIpv4(r) => FlowRecord::SampledIpv4(r),
+            FlowRecordRef::SampledIpv6(r) => FlowRecord::SampledIpv6(r),
+            FlowRecordRef::ExtendedSwitch(r) => FlowRecord::ExtendedSwitch(r),
+            FlowRecordRef::ExtendedRouter(r) => FlowRecord::ExtendedRouter(r),
+            FlowRecordRef::ExtendedGateway(r) => FlowRecord::ExtendedGateway(r),
+            FlowRecordRef::ExtendedUser(r) => FlowRecord::ExtendedUser(r.into_owned()),
+            FlowRecordRef::ExtendedUrl(r) => FlowRecord::ExtendedUrl(r.into_owned()),
+            FlowRecordRef::ExtendedMpls(r) => FlowRecord::ExtendedMpls(r),
+            FlowRecordRef::ExtendedNat(r) => FlowRecord::ExtendedNat(r),
+            FlowRecordRef::ExtendedMplsTunnel(r) => {
+                FlowRecord::ExtendedMplsTunnel(r.into_owned())
+            }
+            FlowRecordRef::ExtendedMplsVc(r) => FlowRecord::ExtendedMplsVc(r.into_owned()),
+            FlowRecordRef::ExtendedMplsFtn(r) => FlowRecord::ExtendedMplsFtn(r.into_owned()),
+            FlowRecordRef::ExtendedMplsLdpFec(r) => FlowRecord::ExtendedMplsLdpFec(r),
+            FlowRecordRef::ExtendedVlanTunnel(r) => FlowRecord::ExtendedVlanTunnel(r),
+            FlowRecordRef::Extended80211Payload(r) => {
+                FlowRecord::Extended80211Payload(r.into_owned())
+            }
+            FlowRecordRef::Extended80211Rx(r) => FlowRecord::Extended80211Rx(r.into_owned()),
+            FlowRecordRef::Extended80211Tx(r) => FlowRecord::Extended80211Tx(r.into_owned()),
+            FlowRecordRef::ExtendedL2TunnelEgress(r) => FlowRecord::ExtendedL2TunnelEgress(r),
+            FlowRecordRef::ExtendedL2TunnelIngress(r) => FlowRecord::ExtendedL2TunnelIngress(r),
+            FlowRecordRef::ExtendedIpv4TunnelEgress(r) => {
+                FlowRecord::ExtendedIpv4TunnelEgress(r)
+            }
+            FlowRecordRef::ExtendedIpv4TunnelIngress(r) => {
+                FlowRecord::ExtendedIpv4TunnelIngress(r)
+            }
+            FlowRecordRef::ExtendedIpv6TunnelEgress(r) => {
+                FlowRecord::ExtendedIpv6TunnelEgress(r)
+            }
+            FlowRecordRef::ExtendedIpv6TunnelIngress(r) => {
+                FlowRecord::ExtendedIpv6TunnelIngress(r)
+            }
+            FlowRecordRef::ExtendedDecapsulateEgress(r) => {
+                FlowRecord::ExtendedDecapsulateEgress(r)
+            }
+            FlowRecordRef::ExtendedDecapsulateIngress(r) => {
+                FlowRecord::ExtendedDecapsulateIngress(r)
+            }
+            FlowRecordRef::ExtendedVniEgress(r) => FlowRecord::ExtendedVniEgress(r),
+            FlowRecordRef::ExtendedVniIngress(r) => FlowRecord::ExtendedVniIngress(r),
+            FlowRecordRef::ExtendedEgressQueue(r) => FlowRecord::ExtendedEgressQueue(r),
+            FlowRecordRef::ExtendedAcl(r) => FlowRecord::ExtendedAcl(r.into_owned()),
+            FlowRecordRef::ExtendedFunction(r) => FlowRecord::ExtendedFunction(r.into_owned()),
+            FlowRecordRef::ExtendedTransit(r) => FlowRecord::ExtendedTransit(r),
+            FlowRecordRef::ExtendedQueue(r) => FlowRecord::ExtendedQueue(r),
+            FlowRecordRef::ExtendedSocketIpv4(r) => FlowRecord::ExtendedSocketIpv4(r),
+            FlowRecordRef::ExtendedSocketIpv6(r) => FlowRecord::ExtendedSocketIpv6(r),
+            FlowRecordRef::ExtendedProxySocketIpv4(r) => FlowRecord::ExtendedProxySocketIpv4(r),
+            FlowRecordRef::ExtendedProxySocketIpv6(r) => FlowRecord::ExtendedProxySocketIpv6(r),
+            FlowRecordRef::JvmRuntime(r) => FlowRecord::JvmRuntime(r.into_owned()),
+            FlowRecordRef::MemcacheOperation(r) => {
+                FlowRecord::MemcacheOperation(r.into_owned())
+            }
+            FlowRecordRef::AppOperation(r) => FlowRecord::AppOperation(r.into_owned()),
+            FlowRecordRef::HttpRequest(r) => FlowRecord::HttpRequest(r.into_owned()),
+            FlowRecordRef::ExtendedProxyRequest(r) => {
+                FlowRecord::ExtendedProxyRequest(r.into_owned())
+            }
+            FlowRecordRef::Unknown {
+                enterprise,
+                format,
+                data,
+            } => FlowRecord::Unknown {
+                enterprise,
+                format,
+                data: data.to_vec(),
+            },
+            FlowRecordRef::Custom(c) => FlowRecord::Custom(c),
+        }
+    }
+}
+
+impl<'a> From<FlowRecordRef<'a>> for FlowRecord {
+    fn from(r: FlowRecordRef<'a>) -> Self {
+        r.into_owned()
+    }
+}
+
+impl FlowRecord {
+    /// The `(enterprise, format)` pair identifying this record type on the wire.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        match self {
+            FlowRecord::RawPacketHeader(_) => (0, 1),
+            FlowRecord::SampledEthernet(_) => (0, 2),
+            FlowRecord::SampledIpv4(_) => (0, 3),
+            FlowRecord::SampledIpv6(_) => (0, 4),
+            FlowRecord::ExtendedSwitch(_) => (0, 1001),
+            FlowRecord::ExtendedRouter(_) => (0, 1002),
+            FlowRecord::ExtendedGateway(_) => (0, 1003),
+            FlowRecord::ExtendedUser(_) => (0, 1004),
+            FlowRecord::ExtendedUrl(_) => (0, 1005),
+            FlowRecord::ExtendedMpls(_) => (0, 1006),
+            FlowRecord::ExtendedNat(_) => (0, 1007),
+            FlowRecord::ExtendedMplsTunnel(_) => (0, 1008),
+            FlowRecord::ExtendedMplsVc(_) => (0, 1009),
+            FlowRecord::ExtendedMplsFtn(_) => (0, 1010),
+            FlowRecord::ExtendedMplsLdpFec(_) => (0, 1011),
+            FlowRecord::ExtendedVlanTunnel(_) => (0, 1012),
+            FlowRecord::Extended80211Payload(_) => (0, 1013),
+            FlowRecord::Extended80211Rx(_) => (0, 1014),
+            FlowRecord::Extended80211Tx(_) => (0, 1015),
+            FlowRecord::ExtendedL2TunnelEgress(_) => (0, 1021),
+            FlowRecord::ExtendedL2TunnelIngress(_) => (0, 1022),
+            FlowRecord::ExtendedIpv4TunnelEgress(_) => (0, 1023),
+            FlowRecord::ExtendedIpv4TunnelIngress(_) => (0, 1024),
+            FlowRecord::ExtendedIpv6TunnelEgress(_) => (0, 1025),
+            FlowRecord::ExtendedIpv6TunnelIngress(_) => (0, 1026),
+            FlowRecord::ExtendedDecapsulateEgress(_) => (0, 1027),
+            FlowRecord::ExtendedDecapsulateIngress(_) => (0, 1028),
+            FlowRecord::ExtendedVniEgress(_) => (0, 1029),
+            FlowRecord::ExtendedVniIngress(_) => (0, 1030),
+            FlowRecord::ExtendedEgressQueue(_) => (0, 1036),
+            FlowRecord::ExtendedAcl(_) => (0, 1037),
+            FlowRecord::ExtendedFunction(_) => (0, 1038),
+            FlowRecord::ExtendedTransit(_) => (0, 1039),
+            FlowRecord::ExtendedQueue(_) => (0, 1040),
+            FlowRecord::ExtendedSocketIpv4(_) => (0, 2100),
+            FlowRecord::ExtendedSocketIpv6(_) => (0, 2101),
+            FlowRecord::ExtendedProxySocketIpv4(_) => (0, 2102),
+            FlowRecord::ExtendedProxySocketIpv6(_) => (0, 2103),
+            FlowRecord::JvmRuntime(_) => (0, 2105),
+            FlowRecord::MemcacheOperation(_) => (0, 2200),
+            FlowRecord::AppOperation(_) => (0, 2202),
+            FlowRecord::HttpRequest(_) => (0, 2206),
+            FlowRecord::ExtendedProxyRequest(_) => (0, 2207),
+            FlowRecord::Unknown {
+                enterprise, format, ..
+            } => (*enterprise, *format),
+            FlowRecord::Custom(c) => (c.enterprise, c.format),
+        }
+    }
+
+    /// Length of the sampled packet from a raw packet header (`frame_length`)
+    /// or a sampled Ethernet, IPv4 or IPv6 record (`length`). `None` for
+    /// other records.
+    pub fn frame_length(&self) -> Option<u32> {
+        match self {
+            FlowRecord::RawPacketHeader(r) => Some(r.frame_length),
+            FlowRecord::SampledEthernet(r) => Some(r.length),
+            FlowRecord::SampledIpv4(r) => Some(r.length),
+            FlowRecord::SampledIpv6(r) => Some(r.length),
+            _ => None,
+        }
+    }
+
+    /// Append the XDR encoding of this record, including its
+    /// `data_format` and `length` header, to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let (enterprise, format) = self.enterprise_format();
+        write_tagged(out, enterprise, format, |out| self.encode_body(out));
+    }
+
+    /// Encode this record, including its header, into a new buffer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    fn encode_body(&self, out: &mut Vec<u8>) {
+        match self {
+            FlowRecord::RawPacketHeader(r) => {
+                raw_packet_header::write_raw_packet_header(out, r)
+            }
+            FlowRecord::SampledEthernet(r) => sampled_ethernet::write_sampled_ethernet(out, r),
+            FlowRecord::SampledIpv4(r) => sampled_ipv4::write_sampled_ipv4(out, r),
+            FlowRecord::SampledIpv6(r) => sampled_ipv6::write_sampled_ipv6(out, r),
+            FlowRecord::ExtendedSwitch(r) => extended_switch::write_extended_switch(out, r),
+            FlowRecord::ExtendedRouter(r) => extended_router::write_extended_router(out, r),
+            FlowRecord::ExtendedGateway(r) => extended_gateway::write_extended_gateway(out, r),
+            FlowRecord::ExtendedUser(r) => extended_user::write_extended_user(out, r),
+            FlowRecord::ExtendedUrl(r) => extended_url::write_extended_url(out, r),
+            FlowRecord::ExtendedMpls(r) => extended_mpls::write_extended_mpls(out, r),
+            FlowRecord::ExtendedNat(r) => extended_nat::write_extended_nat(out, r),
+            FlowRecord::ExtendedMplsTunnel(r) => {
+                extended_mpls_tunnel::write_extended_mpls_tunnel(out, r)
+            }
+            FlowRecord::ExtendedMplsVc(r) => extended_mpls_vc::write_extended_mpls_vc(out, r),
+            FlowRecord::ExtendedMplsFtn(r) => {
+                extended_mpls_ftn::write_extended_mpls_ftn(out, r)
+            }
+            FlowRecord::ExtendedMplsLdpFec(r) => {
+                extended_mpls_ldp_fec::write_extended_mpls_ldp_fec(out, r)
+            }
+            FlowRecord::ExtendedVlanTunnel(r) => {
+                extended_vlan_tunnel::write_extended_vlan_tunnel(out, r)
+            }
+            FlowRecord::Extended80211Payload(r) => {
+                extended_80211_payload::write_extended_80211_payload(out, r)
+            }
+            FlowRecord::Extended80211Rx(r) => {
+                extended_80211_rx::write_extended_80211_rx(out, r)
+            }
+            FlowRecord::Extended80211Tx(r) => {
+                extended_80211_tx::write_extended_80211_tx(out, r)
+            }
+            FlowRecord::ExtendedL2TunnelEgress(r) => {
+                sampled_ethernet::write_sampled_ethernet(out, r)
+            }
+            FlowRecord::ExtendedL2TunnelIngress(r) => {
+                sampled_ethernet::write_sampled_ethernet(out, r)
+            }
+            FlowRecord::ExtendedIpv4TunnelEgress(r) => sampled_ipv4::write_sampled_ipv4(out, r),
+            FlowRecord::ExtendedIpv4TunnelIngress(r) => {
+                sampled_ipv4::write_sampled_ipv4(out, r)
+            }
+            FlowRecord::ExtendedIpv6TunnelEgress(r) => sampled_ipv6::write_sampled_ipv6(out, r),
+            FlowRecord::ExtendedIpv6TunnelIngress(r) => {
+                sampled_ipv6::write_sampled_ipv6(out, r)
+            }
+            FlowRecord::ExtendedDecapsulateEgress(r) => {
+                extended_decapsulate::write_extended_decapsulate_egress(out, r)
+            }
+            FlowRecord::ExtendedDecapsulateIngress(r) => {
+                extended_decapsulate::write_extended_decapsulate_ingress(out, r)
+            }
+            FlowRecord::ExtendedVniEgress(r) => extended_vni::write_extended_vni_egress(out, r),
+            FlowRecord::ExtendedVniIngress(r) => {
+                extended_vni::write_extended_vni_ingress(out, r)
+            }
+            FlowRecord::ExtendedEgressQueue(r) => {
+                extended_egress_queue::write_extended_egress_queue(out, r)
+            }
+            FlowRecord::ExtendedAcl(r) => extended_acl::write_extended_acl(out, r),
+            FlowRecord::ExtendedFunction(r) => {
+                extended_function::write_extended_function(out, r)
+            }
+            FlowRecord::ExtendedTransit(r) => extended_transit::write_extended_transit(out, r),
+            FlowRecord::ExtendedQueue(r) => extended_queue::write_extended_queue(out, r),
+            FlowRecord::ExtendedSocketIpv4(r) => {
+                extended_socket_ipv4::write_extended_socket_ipv4(out, r)
+            }
+            FlowRecord::ExtendedSocketIpv6(r) => {
+                extended_socket_ipv6::write_extended_socket_ipv6(out, r)
+            }
+            FlowRecord::ExtendedProxySocketIpv4(r) => {
+                extended_proxy_socket_ipv4::write_extended_proxy_socket_ipv4(out, r)
+            }
+            FlowRecord::ExtendedProxySocketIpv6(r) => {
+                extended_proxy_socket_ipv6::write_extended_proxy_socket_ipv6(out, r)
+            }
+            FlowRecord::JvmRuntime(r) => jvm_runtime::write_jvm_runtime(out, r),
+            FlowRecord::MemcacheOperation(r) => {
+                memcache_operation::write_memcache_operation(out, r)
+            }
+            FlowRecord::AppOperation(r) => app_operation::write_app_operation(out, r),
+            FlowRecord::HttpRequest(r) => http_request::write_http_request(out, r),
+            FlowRecord::ExtendedProxyRequest(r) => {
+                extended_proxy_request::write_extended_proxy_request(out, r)
+            }
+            FlowRecord::Unknown { data, .. } => out.extend_from_slice(data),
+            FlowRecord::Custom(c) => out.extend_from_slice(&c.data),
+        }
+    }
 }
 
 /// Parse an XDR-encoded sFlow string (length-prefixed, padded to 4-byte boundary).
 ///
+/// The string borrows from `input` when it is valid UTF-8.
 /// Note: Invalid UTF-8 bytes are replaced with U+FFFD (replacement character).
-pub(crate) fn parse_sflow_string(input: &[u8]) -> IResult<&[u8], String> {
+pub(crate) fn parse_sflow_str(input: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
     let (input, length) = be_u32(input)?;
     let (input, bytes) = take(length as usize)(input)?;
     // Pad to 4-byte boundary
     let padding = (4 - (length as usize % 4)) % 4;
     let (input, _) = take(padding)(input)?;
-    let s = String::from_utf8_lossy(bytes).into_owned();
-    Ok((input, s))
+    Ok((input, String::from_utf8_lossy(bytes)))
+}
+
+/// An undecoded record or sample: `(enterprise, format, body)`.
+pub(crate) type Tagged<'a> = (u32, u32, &'a [u8]);
+
+/// Split the next record off `input`, returning its `(enterprise, format, data)`
+/// without decoding the body.
+pub(crate) fn split_record(input: &[u8]) -> IResult<&[u8], Tagged<'_>> {
+    let (rest, data_format) = be_u32(input)?;
+    let enterprise = data_format >> 12;
+    let format = data_format & 0xFFF;
+
+    let (rest, record_length) = be_u32(rest)?;
+    let record_length = record_length as usize;
+
+    if rest.len() < record_length {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            rest,
+            nom::error::ErrorKind::Eof,
+        )));
+    }
+
+    Ok((
+        &rest[record_length..],
+        (enterprise, format, &rest[..record_length]),
+    ))
+}
+
+/// Write an XDR-encoded sFlow string (length-prefixed, padded to 4-byte boundary).
+pub(crate) fn write_sflow_string(out: &mut Vec<u8>, s: &str) {
+    write_opaque(out, s.as_bytes());
 }
 
-pub(crate) fn parse_flow_records(
-    mut input: &[u8],
+/// Parse `num_records` records into [`FlowRecord`] or [`FlowRecordRef`],
+/// converting each one as it is decoded.
+pub(crate) fn parse_flow_records<'a, R: From<FlowRecordRef<'a>>>(
+    mut input: &'a [u8],
     num_records: u32,
-) -> IResult<&[u8], Vec<FlowRecord>> {
+) -> IResult<&'a [u8], Vec<R>> {
     // Cap capacity to prevent DoS: each record needs at least 8 bytes (format + length)
     let cap = (num_records as usize).min(input.len() / 8);
     let mut records = Vec::with_capacity(cap);
 
     for _ in 0..num_records {
-        let (rest, data_format) = be_u32(input)?;
-        let enterprise = data_format >> 12;
-        let format = data_format & 0xFFF;
-
-        let (rest, record_length) = be_u32(rest)?;
-        let record_length = record_length as usize;
-
-        if rest.len() < record_length {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                rest,
-                nom::error::ErrorKind::Eof,
-            )));
-        }
+        let (after_record, (enterprise, format, record_data)) = split_record(input)?;
+        records.push(parse_flow_record_ref(enterprise, format, record_data)?.into());
+        input = after_record;
+    }
 
-        let record_data = &rest[..record_length];
-        let after_record = &rest[record_length..];
+    Ok((input, records))
+}
 
-        let record = if enterprise == 0 {
-            match format {
-                1 => {
-                    let (_, r) = raw_packet_header::parse_raw_packet_header(record_data)?;
-                    FlowRecord::RawPacketHeader(r)
-                }
-                2 => {
-                    let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
-                    FlowRecord::SampledEthernet(r)
-                }
-                3 => {
-                    let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
-                    FlowRecord::SampledIpv4(r)
-                }
-                4 => {
-                    let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
-                    FlowRecord::SampledIpv6(r)
-                }
-                1001 => {
-                    let (_, r) = extended_switch::parse_extended_switch(record_data)?;
-                    FlowRecord::ExtendedSwitch(r)
-                }
-                1002 => {
-                    let (_, r) = extended_router::parse_extended_router(record_data)?;
-                    FlowRecord::ExtendedRouter(r)
-                }
-                1003 => {
-                    let (_, r) = extended_gateway::parse_extended_gateway(record_data)?;
-                    FlowRecord::ExtendedGateway(r)
-                }
-                1004 => {
-                    let (_, r) = extended_user::parse_extended_user(record_data)?;
-                    FlowRecord::ExtendedUser(r)
-                }
-                1005 => {
-                    let (_, r) = extended_url::parse_extended_url(record_data)?;
-                    FlowRecord::ExtendedUrl(r)
-                }
-                1006 => {
-                    let (_, r) = extended_mpls::parse_extended_mpls(record_data)?;
-                    FlowRecord::ExtendedMpls(r)
-                }
-                1007 => {
-                    let (_, r) = extended_nat::parse_extended_nat(record_data)?;
-                    FlowRecord::ExtendedNat(r)
-                }
-                1008 => {
-                    let (_, r) = extended_mpls_tunnel::parse_extended_mpls_tunnel(record_data)?;
-                    FlowRecord::ExtendedMplsTunnel(r)
-                }
-                1009 => {
-                    let (_, r) = extended_mpls_vc::parse_extended_mpls_vc(record_data)?;
-                    FlowRecord::ExtendedMplsVc(r)
-                }
-                1010 => {
-                    let (_, r) = extended_mpls_ftn::parse_extended_mpls_ftn(record_data)?;
-                    FlowRecord::ExtendedMplsFtn(r)
-                }
-                1011 => {
-                    let (_, r) =
-                        extended_mpls_ldp_fec::parse_extended_mpls_ldp_fec(record_data)?;
-                    FlowRecord::ExtendedMplsLdpFec(r)
-                }
-                1012 => {
-                    let (_, r) = extended_vlan_tunnel::parse_extended_vlan_tunnel(record_data)?;
-                    FlowRecord::ExtendedVlanTunnel(r)
-                }
-                1013 => {
-                    let (_, r) =
-                        extended_80211_payload::parse_extended_80211_payload(record_data)?;
-                    FlowRecord::Extended80211Payload(r)
-                }
-                1014 => {
-                    let (_, r) = extended_80211_rx::parse_extended_80211_rx(record_data)?;
-                    FlowRecord::Extended80211Rx(r)
-                }
-                1015 => {
-                    let (_, r) = extended_80211_tx::parse_extended_80211_tx(record_data)?;
-                    FlowRecord::Extended80211Tx(r)
-                }
-                1021 => {
-                    let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
-                    FlowRecord::ExtendedL2TunnelEgress(r)
-                }
-                1022 => {
-                    let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
-                    FlowRecord::ExtendedL2TunnelIngress(r)
-                }
-                1023 => {
-                    let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
-                    FlowRecord::ExtendedIpv4TunnelEgress(r)
-                }
-                1024 => {
-                    let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
-                    FlowRecord::ExtendedIpv4TunnelIngress(r)
-                }
-                1025 => {
-                    let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
-                    FlowRecord::ExtendedIpv6TunnelEgress(r)
-                }
-                1026 => {
-                    let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
-                    FlowRecord::ExtendedIpv6TunnelIngress(r)
-                }
-                1027 => {
-                    let (_, r) =
-                        extended_decapsulate::parse_extended_decapsulate_egress(record_data)?;
-                    FlowRecord::ExtendedDecapsulateEgress(r)
-                }
-                1028 => {
-                    let (_, r) =
-                        extended_decapsulate::parse_extended_decapsulate_ingress(record_data)?;
-                    FlowRecord::ExtendedDecapsulateIngress(r)
-                }
-                1029 => {
-                    let (_, r) = extended_vni::parse_extended_vni_egress(record_data)?;
-                    FlowRecord::ExtendedVniEgress(r)
-                }
-                1030 => {
-                    let (_, r) = extended_vni::parse_extended_vni_ingress(record_data)?;
-                    FlowRecord::ExtendedVniIngress(r)
-                }
-                1036 => {
-                    let (_, r) =
-                        extended_egress_queue::parse_extended_egress_queue(record_data)?;
-                    FlowRecord::ExtendedEgressQueue(r)
-                }
-                1037 => {
-                    let (_, r) = extended_acl::parse_extended_acl(record_data)?;
-                    FlowRecord::ExtendedAcl(r)
-                }
-                1038 => {
-                    let (_, r) = extended_function::parse_extended_function(record_data)?;
-                    FlowRecord::ExtendedFunction(r)
-                }
-                1039 => {
-                    let (_, r) = extended_transit::parse_extended_transit(record_data)?;
-                    FlowRecord::ExtendedTransit(r)
-                }
-                1040 => {
-                    let (_, r) = extended_queue::parse_extended_queue(record_data)?;
-                    FlowRecord::ExtendedQueue(r)
-                }
-                2100 => {
-                    let (_, r) = extended_socket_ipv4::parse_extended_socket_ipv4(record_data)?;
-                    FlowRecord::ExtendedSocketIpv4(r)
-                }
-                2101 => {
-                    let (_, r) = extended_socket_ipv6::parse_extended_socket_ipv6(record_data)?;
-                    FlowRecord::ExtendedSocketIpv6(r)
-                }
-                2102 => {
-                    let (_, r) = extended_proxy_socket_ipv4::parse_extended_proxy_socket_ipv4(
-                        record_data,
-                    )?;
-                    FlowRecord::ExtendedProxySocketIpv4(r)
-                }
-                2103 => {
-                    let (_, r) = extended_proxy_socket_ipv6::parse_extended_proxy_socket_ipv6(
-                        record_data,
-                    )?;
-                    FlowRecord::ExtendedProxySocketIpv6(r)
-                }
-                2105 => {
-                    let (_, r) = jvm_runtime::parse_jvm_runtime(record_data)?;
-                    FlowRecord::JvmRuntime(r)
-                }
-                2200 => {
-                    let (_, r) = memcache_operation::parse_memcache_operation(record_data)?;
-                    FlowRecord::MemcacheOperation(r)
-                }
-                2202 => {
-                    let (_, r) = app_operation::parse_app_operation(record_data)?;
-                    FlowRecord::AppOperation(r)
-                }
-                2206 => {
-                    let (_, r) = http_request::parse_http_request(record_data)?;
-                    FlowRecord::HttpRequest(r)
-                }
-                2207 => {
-                    let (_, r) =
-                        extended_proxy_request::parse_extended_proxy_request(record_data)?;
-                    FlowRecord::ExtendedProxyRequest(r)
-                }
-                _ => FlowRecord::Unknown {
-                    enterprise,
-                    format,
-                    data: record_data.to_vec(),
-                },
-            }
-        } else {
-            FlowRecord::Unknown {
+/// Decode a single flow record body identified by `enterprise` and `format`.
+pub(crate) fn parse_flow_record_ref(
+    enterprise: u32,
+    format: u32,
+    record_data: &[u8],
+) -> Result<FlowRecordRef<'_>, nom::Err<nom::error::Error<&[u8]>>> {
+    let record = if enterprise == 0 {
+        match format {
+            1 => {
+                let (_, r) = raw_packet_header::parse_raw_packet_header_ref(record_data)?;
+                FlowRecordRef::RawPacketHeader(r)
+            }
+            2 => {
+                let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
+                FlowRecordRef::SampledEthernet(r)
+            }
+            3 => {
+                let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
+                FlowRecordRef::SampledIpv4(r)
+            }
+            4 => {
+                let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
+                FlowRecordRef::SampledIpv6(r)
+            }
+            1001 => {
+                let (_, r) = extended_switch::parse_extended_switch(record_data)?;
+                FlowRecordRef::ExtendedSwitch(r)
+            }
+            1002 => {
+                let (_, r) = extended_router::parse_extended_router(record_data)?;
+                FlowRecordRef::ExtendedRouter(r)
+            }
+            1003 => {
+                let (_, r) = extended_gateway::parse_extended_gateway(record_data)?;
+                FlowRecordRef::ExtendedGateway(r)
+            }
+            1004 => {
+                let (_, r) = extended_user::parse_extended_user_ref(record_data)?;
+                FlowRecordRef::ExtendedUser(r)
+            }
+            1005 => {
+                let (_, r) = extended_url::parse_extended_url_ref(record_data)?;
+                FlowRecordRef::ExtendedUrl(r)
+            }
+            1006 => {
+                let (_, r) = extended_mpls::parse_extended_mpls(record_data)?;
+                FlowRecordRef::ExtendedMpls(r)
+            }
+            1007 => {
+                let (_, r) = extended_nat::parse_extended_nat(record_data)?;
+                FlowRecordRef::ExtendedNat(r)
+            }
+            1008 => {
+                let (_, r) = extended_mpls_tunnel::parse_extended_mpls_tunnel_ref(record_data)?;
+                FlowRecordRef::ExtendedMplsTunnel(r)
+            }
+            1009 => {
+                let (_, r) = extended_mpls_vc::parse_extended_mpls_vc_ref(record_data)?;
+                FlowRecordRef::ExtendedMplsVc(r)
+            }
+            1010 => {
+                let (_, r) = extended_mpls_ftn::parse_extended_mpls_ftn_ref(record_data)?;
+                FlowRecordRef::ExtendedMplsFtn(r)
+            }
+            1011 => {
+                let (_, r) = extended_mpls_ldp_fec::parse_extended_mpls_ldp_fec(record_data)?;
+                FlowRecordRef::ExtendedMplsLdpFec(r)
+            }
+            1012 => {
+                let (_, r) = extended_vlan_tunnel::parse_extended_vlan_tunnel(record_data)?;
+                FlowRecordRef::ExtendedVlanTunnel(r)
+            }
+            1013 => {
+                let (_, r) =
+                    extended_80211_payload::parse_extended_80211_payload_ref(record_data)?;
+                FlowRecordRef::Extended80211Payload(r)
+            }
+            1014 => {
+                let (_, r) = extended_80211_rx::parse_extended_80211_rx_ref(record_data)?;
+                FlowRecordRef::Extended80211Rx(r)
+            }
+            1015 => {
+                let (_, r) = extended_80211_tx::parse_extended_80211_tx_ref(record_data)?;
+                FlowRecordRef::Extended80211Tx(r)
+            }
+            1021 => {
+                let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
+                FlowRecordRef::ExtendedL2TunnelEgress(r)
+            }
+            1022 => {
+                let (_, r) = sampled_ethernet::parse_sampled_ethernet(record_data)?;
+                FlowRecordRef::ExtendedL2TunnelIngress(r)
+            }
+            1023 => {
+                let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
+                FlowRecordRef::ExtendedIpv4TunnelEgress(r)
+            }
+            1024 => {
+                let (_, r) = sampled_ipv4::parse_sampled_ipv4(record_data)?;
+                FlowRecordRef::ExtendedIpv4TunnelIngress(r)
+            }
+            1025 => {
+                let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
+                FlowRecordRef::ExtendedIpv6TunnelEgress(r)
+            }
+            1026 => {
+                let (_, r) = sampled_ipv6::parse_sampled_ipv6(record_data)?;
+                FlowRecordRef::ExtendedIpv6TunnelIngress(r)
+            }
+            1027 => {
+                let (_, r) =
+                    extended_decapsulate::parse_extended_decapsulate_egress(record_data)?;
+                FlowRecordRef::ExtendedDecapsulateEgress(r)
+            }
+            1028 => {
+                let (_, r) =
+                    extended_decapsulate::parse_extended_decapsulate_ingress(record_data)?;
+                FlowRecordRef::ExtendedDecapsulateIngress(r)
+            }
+            1029 => {
+                let (_, r) = extended_vni::parse_extended_vni_egress(record_data)?;
+                FlowRecordRef::ExtendedVniEgress(r)
+            }
+            1030 => {
+                let (_, r) = extended_vni::parse_extended_vni_ingress(record_data)?;
+                FlowRecordRef::ExtendedVniIngress(r)
+            }
+            1036 => {
+                let (_, r) = extended_egress_queue::parse_extended_egress_queue(record_data)?;
+                FlowRecordRef::ExtendedEgressQueue(r)
+            }
+            1037 => {
+                let (_, r) = extended_acl::parse_extended_acl_ref(record_data)?;
+                FlowRecordRef::ExtendedAcl(r)
+            }
+            1038 => {
+                let (_, r) = extended_function::parse_extended_function_ref(record_data)?;
+                FlowRecordRef::ExtendedFunction(r)
+            }
+            1039 => {
+                let (_, r) = extended_transit::parse_extended_transit(record_data)?;
+                FlowRecordRef::ExtendedTransit(r)
+            }
+            1040 => {
+                let (_, r) = extended_queue::parse_extended_queue(record_data)?;
+                FlowRecordRef::ExtendedQueue(r)
+            }
+            2100 => {
+                let (_, r) = extended_socket_ipv4::parse_extended_socket_ipv4(record_data)?;
+                FlowRecordRef::ExtendedSocketIpv4(r)
+            }
+            2101 => {
+                let (_, r) = extended_socket_ipv6::parse_extended_socket_ipv6(record_data)?;
+                FlowRecordRef::ExtendedSocketIpv6(r)
+            }
+            2102 => {
+                let (_, r) =
+                    extended_proxy_socket_ipv4::parse_extended_proxy_socket_ipv4(record_data)?;
+                FlowRecordRef::ExtendedProxySocketIpv4(r)
+            }
+            2103 => {
+                let (_, r) =
+                    extended_proxy_socket_ipv6::parse_extended_proxy_socket_ipv6(record_data)?;
+                FlowRecordRef::ExtendedProxySocketIpv6(r)
+            }
+            2105 => {
+                let (_, r) = jvm_runtime::parse_jvm_runtime_ref(record_data)?;
+                FlowRecordRef::JvmRuntime(r)
+            }
+            2200 => {
+                let (_, r) = memcache_operation::parse_memcache_operation_ref(record_data)?;
+                FlowRecordRef::MemcacheOperation(r)
+            }
+            2202 => {
+                let (_, r) = app_operation::parse_app_operation_ref(record_data)?;
+                FlowRecordRef::AppOperation(r)
+            }
+            2206 => {
+                let (_, r) = http_request::parse_http_request_ref(record_data)?;
+                FlowRecordRef::HttpRequest(r)
+            }
+            2207 => {
+                let (_, r) =
+                    extended_proxy_request::parse_extended_proxy_request_ref(record_data)?;
+                FlowRecordRef::ExtendedProxyRequest(r)
+            }
+            _ => FlowRecordRef::Unknown {
                 enterprise,
                 format,
-                data: record_data.to_vec(),
-            }
-        };
+                data: record_data,
+            },
+        }
+    } else {
+        FlowRecordRef::Unknown {
+            enterprise,
+            format,
+            data: record_data,
+        }
+    };
+    Ok(record)
+}
 
-        records.push(record);
-        input = after_record;
+pub(crate) fn write_flow_records(out: &mut Vec<u8>, records: &[FlowRecord]) {
+    write_u32(out, records.len() as u32);
+    for record in records {
+        record.encode(out);
     }
-
-    Ok((input, records))
 }
@@ -3,6 +3,81 @@ use nom::bytes::complete::take;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_opaque, write_u32};
+
+/// Header protocol of a raw packet header record, from the sFlow v5 specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HeaderProtocol {
+    EthernetIso88023,
+    Iso88024TokenBus,
+    Iso88025TokenRing,
+    Fddi,
+    FrameRelay,
+    X25,
+    Ppp,
+    Smds,
+    Aal5,
+    Aal5Ip,
+    Ipv4,
+    Ipv6,
+    Mpls,
+    Pos,
+    Ieee80211Mac,
+    Ieee80211Ampdu,
+    Ieee80211AmsduSubframe,
+    Unrecognized(u32),
+}
+
+impl From<u32> for HeaderProtocol {
+    fn from(v: u32) -> Self {
+        match v {
+            1 => HeaderProtocol::EthernetIso88023,
+            2 => HeaderProtocol::Iso88024TokenBus,
+            3 => HeaderProtocol::Iso88025TokenRing,
+            4 => HeaderProtocol::Fddi,
+            5 => HeaderProtocol::FrameRelay,
+            6 => HeaderProtocol::X25,
+            7 => HeaderProtocol::Ppp,
+            8 => HeaderProtocol::Smds,
+            9 => HeaderProtocol::Aal5,
+            10 => HeaderProtocol::Aal5Ip,
+            11 => HeaderProtocol::Ipv4,
+            12 => HeaderProtocol::Ipv6,
+            13 => HeaderProtocol::Mpls,
+            14 => HeaderProtocol::Pos,
+            15 => HeaderProtocol::Ieee80211Mac,
+            16 => HeaderProtocol::Ieee80211Ampdu,
+            17 => HeaderProtocol::Ieee80211AmsduSubframe,
+            v => HeaderProtocol::Unrecognized(v),
+        }
+    }
+}
+
+impl From<HeaderProtocol> for u32 {
+    fn from(v: HeaderProtocol) -> Self {
+        match v {
+            HeaderProtocol::EthernetIso88023 => 1,
+            HeaderProtocol::Iso88024TokenBus => 2,
+            HeaderProtocol::Iso88025TokenRing => 3,
+            HeaderProtocol::Fddi => 4,
+            HeaderProtocol::FrameRelay => 5,
+            HeaderProtocol::X25 => 6,
+            HeaderProtocol::Ppp => 7,
+            HeaderProtocol::Smds => 8,
+            HeaderProtocol::Aal5 => 9,
+            HeaderProtocol::Aal5Ip => 10,
+            HeaderProtocol::Ipv4 => 11,
+            HeaderProtocol::Ipv6 => 12,
+            HeaderProtocol::Mpls => 13,
+            HeaderProtocol::Pos => 14,
+            HeaderProtocol::Ieee80211Mac => 15,
+            HeaderProtocol::Ieee80211Ampdu => 16,
+            HeaderProtocol::Ieee80211AmsduSubframe => 17,
+            HeaderProtocol::Unrecognized(v) => v,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawPacketHeader {
     pub header_protocol: u32,
@@ -12,7 +87,73 @@ pub struct RawPacketHeader {
     pub header: Vec<u8>,
 }
 
-pub(crate) fn parse_raw_packet_header(input: &[u8]) -> IResult<&[u8], RawPacketHeader> {
+impl RawPacketHeader {
+    /// The header protocol as a [`HeaderProtocol`].
+    pub fn protocol(&self) -> HeaderProtocol {
+        HeaderProtocol::from(self.header_protocol)
+    }
+
+    /// Decode the sampled header bytes into L2/L3/L4 fields.
+    ///
+    /// Decoding stops at `header_length`; see [`decode_header`](crate::packet::decode_header).
+    #[cfg(feature = "packet")]
+    pub fn decode(&self) -> crate::packet::DecodedHeader {
+        crate::packet::decode_header(
+            self.protocol(),
+            sampled_bytes(&self.header, self.header_length),
+        )
+    }
+}
+
+/// Borrowed form of [`RawPacketHeader`]; bytes borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RawPacketHeaderRef<'a> {
+    pub header_protocol: u32,
+    pub frame_length: u32,
+    pub stripped: u32,
+    pub header_length: u32,
+    pub header: &'a [u8],
+}
+
+impl RawPacketHeaderRef<'_> {
+    /// Convert into the owned [`RawPacketHeader`], copying any borrowed data.
+    pub fn into_owned(self) -> RawPacketHeader {
+        RawPacketHeader {
+            header_protocol: self.header_protocol,
+            frame_length: self.frame_length,
+            stripped: self.stripped,
+            header_length: self.header_length,
+            header: self.header.to_vec(),
+        }
+    }
+
+    /// The header protocol as a [`HeaderProtocol`].
+    pub fn protocol(&self) -> HeaderProtocol {
+        HeaderProtocol::from(self.header_protocol)
+    }
+
+    /// Decode the sampled header bytes into L2/L3/L4 fields.
+    ///
+    /// Decoding stops at `header_length`; see [`decode_header`](crate::packet::decode_header).
+    #[cfg(feature = "packet")]
+    pub fn decode(&self) -> crate::packet::DecodedHeader {
+        crate::packet::decode_header(
+            self.protocol(),
+            sampled_bytes(self.header, self.header_length),
+        )
+    }
+}
+
+/// The captured bytes of `header`, cut off at `header_length`.
+#[cfg(feature = "packet")]
+fn sampled_bytes(header: &[u8], header_length: u32) -> &[u8] {
+    let len = (header_length as usize).min(header.len());
+    &header[..len]
+}
+
+pub(crate) fn parse_raw_packet_header_ref(
+    input: &[u8],
+) -> IResult<&[u8], RawPacketHeaderRef<'_>> {
     let (input, header_protocol) = be_u32(input)?;
     let (input, frame_length) = be_u32(input)?;
     let (input, stripped) = be_u32(input)?;
@@ -24,12 +165,20 @@ pub(crate) fn parse_raw_packet_header(input: &[u8]) -> IResult<&[u8], RawPacketH
 
     Ok((
         input,
-        RawPacketHeader {
+        RawPacketHeaderRef {
             header_protocol,
             frame_length,
             stripped,
             header_length,
-            header: header.to_vec(),
+            header,
         },
     ))
 }
+
+/// The header length is taken from `header`, not `header_length`.
+pub(crate) fn write_raw_packet_header(out: &mut Vec<u8>, r: &RawPacketHeader) {
+    write_u32(out, r.header_protocol);
+    write_u32(out, r.frame_length);
+    write_u32(out, r.stripped);
+    write_opaque(out, &r.header);
+}
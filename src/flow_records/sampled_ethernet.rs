@@ -3,6 +3,8 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::encode::{write_mac, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SampledEthernet {
     pub length: u32,
@@ -40,3 +42,10 @@ pub(crate) fn parse_sampled_ethernet(input: &[u8]) -> IResult<&[u8], SampledEthe
         },
     ))
 }
+
+pub(crate) fn write_sampled_ethernet(out: &mut Vec<u8>, r: &SampledEthernet) {
+    write_u32(out, r.length);
+    write_mac(out, &r.src_mac);
+    write_mac(out, &r.dst_mac);
+    write_u32(out, r.eth_type);
+}
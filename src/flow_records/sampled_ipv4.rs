@@ -3,6 +3,8 @@ use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
 
+use crate::encode::write_u32;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SampledIpv4 {
     pub length: u32,
@@ -39,3 +41,14 @@ pub(crate) fn parse_sampled_ipv4(input: &[u8]) -> IResult<&[u8], SampledIpv4> {
         },
     ))
 }
+
+pub(crate) fn write_sampled_ipv4(out: &mut Vec<u8>, r: &SampledIpv4) {
+    write_u32(out, r.length);
+    write_u32(out, r.protocol);
+    write_u32(out, u32::from(r.src_ip));
+    write_u32(out, u32::from(r.dst_ip));
+    write_u32(out, r.src_port);
+    write_u32(out, r.dst_port);
+    write_u32(out, r.tcp_flags);
+    write_u32(out, r.tos);
+}
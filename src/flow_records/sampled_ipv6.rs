@@ -3,6 +3,8 @@ use nom::number::complete::{be_u32, be_u64};
 use serde::{Deserialize, Serialize};
 use std::net::Ipv6Addr;
 
+use crate::encode::{write_ipv6, write_u32};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SampledIpv6 {
     pub length: u32,
@@ -48,3 +50,14 @@ pub(crate) fn parse_sampled_ipv6(input: &[u8]) -> IResult<&[u8], SampledIpv6> {
         },
     ))
 }
+
+pub(crate) fn write_sampled_ipv6(out: &mut Vec<u8>, r: &SampledIpv6) {
+    write_u32(out, r.length);
+    write_u32(out, r.protocol);
+    write_ipv6(out, &r.src_ip);
+    write_ipv6(out, &r.dst_ip);
+    write_u32(out, r.src_port);
+    write_u32(out, r.dst_port);
+    write_u32(out, r.tcp_flags);
+    write_u32(out, r.priority);
+}
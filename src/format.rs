@@ -0,0 +1,130 @@
+//! Helpers shared by the sflowtool, JSON Lines, InfluxDB, Prometheus and
+//! Arrow output formats.
+
+use serde::de::{DeserializeOwned, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Serialize `record` to JSON text and parse it back, so f32 fields keep
+/// their own precision instead of being widened through f64.
+pub(crate) fn reparse<T: DeserializeOwned>(record: &impl Serialize) -> Option<T> {
+    serde_json::to_string(record)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// A JSON value whose single-member objects may be enum variants.
+pub(crate) trait Tagged {
+    /// The name and value of a single-member object.
+    fn single_member(&self) -> Option<(&str, &Self)>;
+}
+
+impl Tagged for Value {
+    fn single_member(&self) -> Option<(&str, &Self)> {
+        match self {
+            Value::Object(map) if map.len() == 1 => map
+                .iter()
+                .next()
+                .map(|(name, inner)| (name.as_str(), inner)),
+            _ => None,
+        }
+    }
+}
+
+/// Skip the `{"Variant": value}` wrappers of externally tagged enums.
+pub(crate) fn unwrap_variant<T: Tagged>(value: &T) -> &T {
+    match value.single_member() {
+        Some((name, inner)) if name.starts_with(|c: char| c.is_ascii_uppercase()) => {
+            unwrap_variant(inner)
+        }
+        _ => value,
+    }
+}
+
+/// A JSON value that keeps object members in the order they were written.
+pub(crate) enum Ordered {
+    Scalar(Value),
+    Array(Vec<Ordered>),
+    Object(Vec<(String, Ordered)>),
+}
+
+impl Tagged for Ordered {
+    fn single_member(&self) -> Option<(&str, &Self)> {
+        match self {
+            Ordered::Object(members) if members.len() == 1 => {
+                Some((members[0].0.as_str(), &members[0].1))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Ordered {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OrderedVisitor)
+    }
+}
+
+struct OrderedVisitor;
+
+impl<'de> Visitor<'de> for OrderedVisitor {
+    type Value = Ordered;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::Bool(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Ordered, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Ordered::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Ordered, A::Error> {
+        let mut members = Vec::new();
+        while let Some(member) = map.next_entry()? {
+            members.push(member);
+        }
+        Ok(Ordered::Object(members))
+    }
+}
+
+/// Civil `(year, month, day)` of `days` since 1970-01-01 (Howard Hinnant's
+/// algorithm).
+pub(crate) fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
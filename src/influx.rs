@@ -0,0 +1,307 @@
+//! InfluxDB line protocol output.
+//!
+//! [`counter_lines`] renders every counter record of a datagram as one line
+//! whose measurement is `sflow_<record_type>`, where `record_type` is the
+//! snake_case [`CounterRecord`] variant name (`generic_interface`,
+//! `host_cpu`, ..., or `custom_<enterprise>_<format>` for records decoded
+//! by a registered decoder). Every numeric member of the record becomes a field:
+//! integers with the `i` suffix and floats as is. 64-bit counters above
+//! `i64::MAX`, usually the all-ones value agents report for unknown counters,
+//! are left out so that every field keeps one type.
+//! Nested structs are flattened with `_` and fields are sorted by name;
+//! strings, addresses and lists are left out, as are records without numeric
+//! members.
+//!
+//! Lines are tagged with `agent`, `sub_agent` and `source` (the data source
+//! as `type:index`). Interface data sources add `ifindex`, and `ifname` when
+//! the same sample carries a port name record. The timestamp is the receive
+//! time in nanoseconds, so the output can be sent straight to Telegraf's
+//! `socket_listener` input or the InfluxDB write API.
+//!
+//! [`flow_lines`] optionally adds one `sflow_flow` line per flow sample with
+//! the same tags and the fields `sampling_rate`, `sample_pool`, `drops`,
+//! `input`, `output`, `frame_length`, `packets` and `bytes` (the sample
+//! scaled by the sampling rate), plus the Ethernet, IP and transport header
+//! fields of the `sflowtool -l` format when the sample describes them. IP
+//! and MAC addresses are string fields rather than tags to keep series
+//! cardinality bounded.
+//!
+//! ```
+//! use flowparser_sflow::influx::InfluxWriter;
+//! use flowparser_sflow::SflowParser;
+//! use std::time::SystemTime;
+//!
+//! let parser = SflowParser::default();
+//! let mut writer = InfluxWriter::new(Vec::new()).with_flows(true);
+//! for datagram in &parser.parse_bytes(&[/* sflow datagram bytes */]).datagrams {
+//!     writer.write_datagram(datagram, SystemTime::now()).unwrap();
+//! }
+//! let lines = writer.into_inner();
+//! ```
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+use crate::SflowDatagram;
+use crate::counter_records::CounterRecord;
+use crate::format::unwrap_variant;
+use crate::jsonl::counter_variant;
+use crate::samples::DataSource;
+use crate::sflowtool::FlowLine;
+
+/// Render the counter records of `datagram`, received at `received`, one
+/// line each.
+pub fn counter_lines(datagram: &SflowDatagram, received: SystemTime) -> Vec<String> {
+    let timestamp = timestamp(received);
+    let mut lines = Vec::new();
+    for sample in &datagram.samples {
+        let Some(counters) = sample.normalized_counter() else {
+            continue;
+        };
+        let mut tags = tags(datagram, counters.data_source);
+        if let DataSource::IfIndex(_) = counters.data_source
+            && let Some(name) = counters.records.iter().find_map(|r| match r {
+                CounterRecord::PortName(p) => Some(p.name.clone()),
+                _ => None,
+            })
+        {
+            tags.push(("ifname", name));
+        }
+        let tags = render_tags(&tags);
+        for record in counters.records {
+            if let CounterRecord::Unknown { .. } = record {
+                continue;
+            }
+            let (record_type, value) = counter_variant(record);
+            let mut fields = Vec::new();
+            numeric_fields(&mut fields, "", &value);
+            if !fields.is_empty() {
+                lines.push(line(
+                    &format!("sflow_{record_type}"),
+                    &tags,
+                    &fields,
+                    timestamp,
+                ));
+            }
+        }
+    }
+    lines
+}
+
+/// Render the flow samples of `datagram`, received at `received`, as one
+/// `sflow_flow` line each.
+pub fn flow_lines(datagram: &SflowDatagram, received: SystemTime) -> Vec<String> {
+    let timestamp = timestamp(received);
+    let mut lines = Vec::new();
+    for sample in &datagram.samples {
+        let Some(flow) = sample.normalized_flow() else {
+            continue;
+        };
+        let tags = render_tags(&tags(datagram, flow.data_source));
+        let packet = FlowLine::from_sample(&flow);
+        let rate = u64::from(flow.sampling_rate);
+        let mut fields = vec![
+            ("sampling_rate".to_string(), format!("{rate}i")),
+            ("sample_pool".to_string(), format!("{}i", flow.sample_pool)),
+            ("drops".to_string(), format!("{}i", flow.drops)),
+            ("input".to_string(), format!("{}i", packet.input)),
+            ("output".to_string(), format!("{}i", packet.output)),
+            ("packets".to_string(), format!("{rate}i")),
+        ];
+        let mut int =
+            |name: &str, value: u64| fields.push((name.to_string(), format!("{value}i")));
+        if let Some(length) = flow.frame_length() {
+            int("frame_length", u64::from(length));
+            int("bytes", u64::from(length) * rate);
+        }
+        if packet.src_mac.is_some() || packet.eth_type != 0 {
+            int("eth_type", u64::from(packet.eth_type));
+        }
+        if packet.in_vlan != 0 || packet.out_vlan != 0 {
+            int("in_vlan", u64::from(packet.in_vlan));
+            int("out_vlan", u64::from(packet.out_vlan));
+        }
+        if packet.src_ip.is_some() {
+            int("protocol", u64::from(packet.protocol));
+            int("src_port", u64::from(packet.src_port));
+            int("dst_port", u64::from(packet.dst_port));
+            int("tcp_flags", u64::from(packet.tcp_flags));
+            int("tos", u64::from(packet.tos));
+            int("ttl", u64::from(packet.ttl));
+            int("ip_size", u64::from(packet.ip_size));
+        }
+        let strings = [
+            (
+                "src_mac",
+                packet.src_mac.map(|m| m.to_string().to_lowercase()),
+            ),
+            (
+                "dst_mac",
+                packet.dst_mac.map(|m| m.to_string().to_lowercase()),
+            ),
+            ("src_ip", packet.src_ip.map(|ip| ip.to_string())),
+            ("dst_ip", packet.dst_ip.map(|ip| ip.to_string())),
+        ];
+        for (name, value) in strings {
+            if let Some(value) = value {
+                fields.push((name.to_string(), string_field(&value)));
+            }
+        }
+        lines.push(line("sflow_flow", &tags, &fields, timestamp));
+    }
+    lines
+}
+
+/// Writes line protocol for each datagram.
+#[derive(Debug)]
+pub struct InfluxWriter<W: Write> {
+    out: W,
+    flows: bool,
+    lines: u64,
+}
+
+impl<W: Write> InfluxWriter<W> {
+    /// Create a writer emitting counter records only.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            flows: false,
+            lines: 0,
+        }
+    }
+
+    /// Also emit an `sflow_flow` line per flow sample.
+    pub fn with_flows(mut self, flows: bool) -> Self {
+        self.flows = flows;
+        self
+    }
+
+    /// Write the lines of `datagram`, received at `received`. Returns the
+    /// number of lines written.
+    pub fn write_datagram(
+        &mut self,
+        datagram: &SflowDatagram,
+        received: SystemTime,
+    ) -> io::Result<usize> {
+        let mut lines = counter_lines(datagram, received);
+        if self.flows {
+            lines.extend(flow_lines(datagram, received));
+        }
+        let mut buf = String::new();
+        for line in &lines {
+            buf.push_str(line);
+            buf.push('\n');
+        }
+        self.out.write_all(buf.as_bytes())?;
+        self.lines += lines.len() as u64;
+        Ok(lines.len())
+    }
+
+    /// Number of lines written so far.
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+fn tags(datagram: &SflowDatagram, source: DataSource) -> Vec<(&'static str, String)> {
+    let mut tags = vec![
+        ("agent", datagram.agent_address.to_string()),
+        ("sub_agent", datagram.sub_agent_id.to_string()),
+        ("source", source.to_string()),
+    ];
+    if let DataSource::IfIndex(index) = source {
+        tags.push(("ifindex", index.to_string()));
+    }
+    tags
+}
+
+/// Tags sorted by key, as InfluxDB recommends. Empty values are not allowed
+/// and are left out.
+fn render_tags(tags: &[(&str, String)]) -> String {
+    let mut tags: Vec<_> = tags.iter().filter(|(_, value)| !value.is_empty()).collect();
+    tags.sort_by_key(|(key, _)| *key);
+    let mut out = String::new();
+    for (key, value) in tags {
+        let _ = write!(out, ",{}={}", escape(key, ",= "), escape(value, ",= "));
+    }
+    out
+}
+
+fn numeric_fields(fields: &mut Vec<(String, String)>, prefix: &str, value: &Value) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    for (name, field) in map {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}_{name}")
+        };
+        match unwrap_variant(field) {
+            // The suffix must not depend on the value, or the field would
+            // change type between points and InfluxDB would reject it
+            Value::Number(n) => {
+                if let Some(v) = n.as_i64() {
+                    fields.push((key, format!("{v}i")));
+                } else if n.is_f64() {
+                    fields.push((key, n.to_string()));
+                }
+            }
+            nested @ Value::Object(_) => numeric_fields(fields, &key, nested),
+            _ => {}
+        }
+    }
+}
+
+fn line(measurement: &str, tags: &str, fields: &[(String, String)], timestamp: u128) -> String {
+    let mut out = escape(measurement, ", ");
+    out.push_str(tags);
+    for (i, (key, value)) in fields.iter().enumerate() {
+        out.push(if i == 0 { ' ' } else { ',' });
+        let _ = write!(out, "{}={value}", escape(key, ",= "));
+    }
+    let _ = write!(out, " {timestamp}");
+    out
+}
+
+/// Backslash-escape `special` characters. Line breaks cannot be escaped in
+/// line protocol and are written as `\n`.
+fn escape(value: &str, special: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c if special.contains(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn string_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn timestamp(received: SystemTime) -> u128 {
+    received
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
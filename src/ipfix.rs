@@ -0,0 +1,564 @@
+//! IPFIX and NetFlow v9 export of flow samples.
+//!
+//! [`FlowExporter`] turns every flow sample with an IPv4 or IPv6 packet into
+//! one IPFIX (RFC 7011) or NetFlow v9 (RFC 3954) data record describing the
+//! single sampled packet: `octetDeltaCount` is the sampled frame length and
+//! `packetDeltaCount` is 1. Each record carries `samplingInterval` (34) and
+//! `samplingAlgorithm` (35, random), so collectors scale the counts the same
+//! way they do for natively sampled NetFlow. IPFIX records also carry
+//! `selectorId` (302) with the sample's data source as
+//! `source_id_type << 32 | index`. NetFlow v9 has no equivalent: its
+//! `FLOW_SAMPLER_ID` (48) refers to a sampler defined in an options template,
+//! which is not sent, so v9 records identify the data source only through
+//! their interfaces.
+//!
+//! Addresses, ports, protocol, ToS and TCP flags come from sampled IPv4/IPv6
+//! records or, with the `packet` feature, the decoded raw packet header.
+//! Extended switch, router and gateway records add VLANs, next hop, prefix
+//! lengths and AS numbers. Samples without IP information are skipped.
+//!
+//! Templates are sent in the first message and then every
+//! `with_template_refresh` messages, as required for UDP transport.
+//!
+//! ```
+//! use flowparser_sflow::SflowParser;
+//! use flowparser_sflow::ipfix::{ExportFormat, FlowExporter};
+//! use std::time::SystemTime;
+//!
+//! let parser = SflowParser::default();
+//! let mut exporter = FlowExporter::builder()
+//!     .with_format(ExportFormat::Ipfix)
+//!     .with_observation_domain_id(1)
+//!     .build();
+//!
+//! let result = parser.parse_bytes(&[/* sflow datagram bytes */]);
+//! for datagram in &result.datagrams {
+//!     for message in exporter.export(datagram, SystemTime::now()) {
+//!         // socket.send_to(&message, collector)?;
+//!         let _ = message;
+//!     }
+//! }
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::SystemTime;
+
+use crate::datagram::{AddressType, SflowDatagram};
+use crate::flow_records::FlowRecord;
+use crate::samples::{Interface, NormalizedFlowSample};
+
+/// Template ID of IPv4 flow records.
+pub const IPV4_TEMPLATE_ID: u16 = 256;
+/// Template ID of IPv6 flow records.
+pub const IPV6_TEMPLATE_ID: u16 = 257;
+
+const IPFIX_VERSION: u16 = 10;
+const NETFLOW_V9_VERSION: u16 = 9;
+const IPFIX_HEADER_LEN: usize = 16;
+const NETFLOW_V9_HEADER_LEN: usize = 20;
+const IPFIX_TEMPLATE_SET_ID: u16 = 2;
+const NETFLOW_V9_TEMPLATE_SET_ID: u16 = 0;
+/// samplingAlgorithm value for random n-out-of-N sampling.
+const RANDOM_SAMPLING: u8 = 2;
+
+/// Information element IDs used in the templates.
+mod ie {
+    pub const OCTET_DELTA_COUNT: u16 = 1;
+    pub const PACKET_DELTA_COUNT: u16 = 2;
+    pub const PROTOCOL_IDENTIFIER: u16 = 4;
+    pub const IP_CLASS_OF_SERVICE: u16 = 5;
+    pub const TCP_CONTROL_BITS: u16 = 6;
+    pub const SOURCE_TRANSPORT_PORT: u16 = 7;
+    pub const SOURCE_IPV4_ADDRESS: u16 = 8;
+    pub const SOURCE_IPV4_PREFIX_LENGTH: u16 = 9;
+    pub const INGRESS_INTERFACE: u16 = 10;
+    pub const DESTINATION_TRANSPORT_PORT: u16 = 11;
+    pub const DESTINATION_IPV4_ADDRESS: u16 = 12;
+    pub const DESTINATION_IPV4_PREFIX_LENGTH: u16 = 13;
+    pub const EGRESS_INTERFACE: u16 = 14;
+    pub const IP_NEXT_HOP_IPV4_ADDRESS: u16 = 15;
+    pub const BGP_SOURCE_AS_NUMBER: u16 = 16;
+    pub const BGP_DESTINATION_AS_NUMBER: u16 = 17;
+    pub const LAST_SWITCHED: u16 = 21;
+    pub const FIRST_SWITCHED: u16 = 22;
+    pub const SOURCE_IPV6_ADDRESS: u16 = 27;
+    pub const DESTINATION_IPV6_ADDRESS: u16 = 28;
+    pub const SOURCE_IPV6_PREFIX_LENGTH: u16 = 29;
+    pub const DESTINATION_IPV6_PREFIX_LENGTH: u16 = 30;
+    pub const SAMPLING_INTERVAL: u16 = 34;
+    pub const SAMPLING_ALGORITHM: u16 = 35;
+    pub const VLAN_ID: u16 = 58;
+    pub const POST_VLAN_ID: u16 = 59;
+    pub const IP_NEXT_HOP_IPV6_ADDRESS: u16 = 62;
+    pub const FLOW_START_MILLISECONDS: u16 = 152;
+    pub const FLOW_END_MILLISECONDS: u16 = 153;
+    pub const SELECTOR_ID: u16 = 302;
+}
+
+/// Wire format produced by a [`FlowExporter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// IPFIX (RFC 7011), version 10.
+    Ipfix,
+    /// NetFlow version 9 (RFC 3954).
+    NetflowV9,
+}
+
+/// One sampled packet, flattened from its flow sample.
+#[derive(Debug, Clone, Default)]
+struct ExportRecord {
+    src_addr: Option<IpAddr>,
+    dst_addr: Option<IpAddr>,
+    protocol: u8,
+    tos: u8,
+    tcp_flags: u16,
+    src_port: u16,
+    dst_port: u16,
+    input: u32,
+    output: u32,
+    next_hop: Option<IpAddr>,
+    src_mask_len: u8,
+    dst_mask_len: u8,
+    src_as: u32,
+    dst_as: u32,
+    src_vlan: u16,
+    dst_vlan: u16,
+    frame_length: u64,
+    sampling_rate: u32,
+    selector_id: u64,
+}
+
+impl ExportRecord {
+    fn from_sample(sample: &NormalizedFlowSample<'_>) -> Self {
+        let mut record = ExportRecord {
+            input: interface_index(sample.input),
+            output: interface_index(sample.output),
+            frame_length: u64::from(sample.frame_length().unwrap_or(0)),
+            sampling_rate: sample.sampling_rate,
+            selector_id: u64::from(sample.data_source.source_id_type()) << 32
+                | u64::from(sample.data_source.index()),
+            ..ExportRecord::default()
+        };
+        for r in sample.records {
+            match r {
+                FlowRecord::SampledIpv4(r) => {
+                    record.src_addr = Some(IpAddr::V4(r.src_ip));
+                    record.dst_addr = Some(IpAddr::V4(r.dst_ip));
+                    record.set_transport(r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+                    record.tos = r.tos as u8;
+                }
+                FlowRecord::SampledIpv6(r) => {
+                    record.src_addr = Some(IpAddr::V6(r.src_ip));
+                    record.dst_addr = Some(IpAddr::V6(r.dst_ip));
+                    record.set_transport(r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+                    record.tos = r.priority as u8;
+                }
+                FlowRecord::ExtendedSwitch(r) => {
+                    record.src_vlan = r.src_vlan as u16;
+                    record.dst_vlan = r.dst_vlan as u16;
+                }
+                FlowRecord::ExtendedRouter(r) => {
+                    record.next_hop = Some(match r.next_hop {
+                        AddressType::IPv4(addr) => IpAddr::V4(addr),
+                        AddressType::IPv6(addr) => IpAddr::V6(addr),
+                    });
+                    record.src_mask_len = r.src_mask_len as u8;
+                    record.dst_mask_len = r.dst_mask_len as u8;
+                }
+                FlowRecord::ExtendedGateway(r) => {
+                    record.src_as = r.src_as;
+                    record.dst_as = r.dst_as();
+                }
+                _ => {}
+            }
+        }
+        #[cfg(feature = "packet")]
+        if record.src_addr.is_none() {
+            record.set_from_header(sample.records);
+        }
+        record
+    }
+
+    fn set_transport(&mut self, protocol: u32, src_port: u32, dst_port: u32, tcp_flags: u32) {
+        self.protocol = protocol as u8;
+        self.src_port = src_port as u16;
+        self.dst_port = dst_port as u16;
+        self.tcp_flags = tcp_flags as u16;
+    }
+
+    #[cfg(feature = "packet")]
+    fn set_from_header(&mut self, records: &[FlowRecord]) {
+        let Some(decoded) = records.iter().find_map(|record| match record {
+            FlowRecord::RawPacketHeader(r) => Some(r.decode()),
+            _ => None,
+        }) else {
+            return;
+        };
+        self.src_addr = decoded.src_ip;
+        self.dst_addr = decoded.dst_ip;
+        self.protocol = decoded.ip_protocol.unwrap_or(0);
+        self.tos = decoded.tos.unwrap_or(0);
+        self.tcp_flags = decoded.tcp_flags.unwrap_or(0);
+        self.src_port = decoded.src_port.unwrap_or(0);
+        self.dst_port = decoded.dst_port.unwrap_or(0);
+        if self.src_vlan == 0
+            && let Some(tag) = decoded.vlan_tags.first()
+        {
+            self.src_vlan = tag.vid;
+        }
+    }
+
+    fn is_ipv6(&self) -> Option<bool> {
+        match (self.src_addr?, self.dst_addr?) {
+            (IpAddr::V4(_), IpAddr::V4(_)) => Some(false),
+            (IpAddr::V6(_), IpAddr::V6(_)) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+fn interface_index(interface: Interface) -> u32 {
+    match interface {
+        Interface::Single(if_index) => if_index,
+        _ => 0,
+    }
+}
+
+/// Template fields as `(information element, length)`.
+fn template_fields(format: ExportFormat, ipv6: bool) -> Vec<(u16, u16)> {
+    let mut fields = if ipv6 {
+        vec![
+            (ie::SOURCE_IPV6_ADDRESS, 16),
+            (ie::DESTINATION_IPV6_ADDRESS, 16),
+            (ie::IP_NEXT_HOP_IPV6_ADDRESS, 16),
+            (ie::SOURCE_IPV6_PREFIX_LENGTH, 1),
+            (ie::DESTINATION_IPV6_PREFIX_LENGTH, 1),
+        ]
+    } else {
+        vec![
+            (ie::SOURCE_IPV4_ADDRESS, 4),
+            (ie::DESTINATION_IPV4_ADDRESS, 4),
+            (ie::IP_NEXT_HOP_IPV4_ADDRESS, 4),
+            (ie::SOURCE_IPV4_PREFIX_LENGTH, 1),
+            (ie::DESTINATION_IPV4_PREFIX_LENGTH, 1),
+        ]
+    };
+    fields.extend_from_slice(&[
+        (ie::PROTOCOL_IDENTIFIER, 1),
+        (ie::IP_CLASS_OF_SERVICE, 1),
+        (ie::TCP_CONTROL_BITS, 2),
+        (ie::SOURCE_TRANSPORT_PORT, 2),
+        (ie::DESTINATION_TRANSPORT_PORT, 2),
+        (ie::INGRESS_INTERFACE, 4),
+        (ie::EGRESS_INTERFACE, 4),
+        (ie::BGP_SOURCE_AS_NUMBER, 4),
+        (ie::BGP_DESTINATION_AS_NUMBER, 4),
+        (ie::VLAN_ID, 2),
+        (ie::POST_VLAN_ID, 2),
+        (ie::OCTET_DELTA_COUNT, 8),
+        (ie::PACKET_DELTA_COUNT, 8),
+        (ie::SAMPLING_INTERVAL, 4),
+        (ie::SAMPLING_ALGORITHM, 1),
+    ]);
+    match format {
+        ExportFormat::Ipfix => fields.extend_from_slice(&[
+            (ie::SELECTOR_ID, 8),
+            (ie::FLOW_START_MILLISECONDS, 8),
+            (ie::FLOW_END_MILLISECONDS, 8),
+        ]),
+        ExportFormat::NetflowV9 => {
+            fields.extend_from_slice(&[(ie::FIRST_SWITCHED, 4), (ie::LAST_SWITCHED, 4)])
+        }
+    }
+    fields
+}
+
+/// Timestamps written into a record.
+#[derive(Clone, Copy)]
+struct Times {
+    /// Receive time in milliseconds since the Unix epoch (IPFIX).
+    unix_ms: u64,
+    /// Agent uptime in milliseconds (NetFlow v9 sysUpTime).
+    uptime: u32,
+}
+
+fn write_field(out: &mut Vec<u8>, id: u16, r: &ExportRecord, times: Times) {
+    let ipv4 = |addr: Option<IpAddr>| match addr {
+        Some(IpAddr::V4(addr)) => addr,
+        _ => Ipv4Addr::UNSPECIFIED,
+    };
+    let ipv6 = |addr: Option<IpAddr>| match addr {
+        Some(IpAddr::V6(addr)) => addr,
+        _ => Ipv6Addr::UNSPECIFIED,
+    };
+    match id {
+        ie::SOURCE_IPV4_ADDRESS => out.extend_from_slice(&ipv4(r.src_addr).octets()),
+        ie::DESTINATION_IPV4_ADDRESS => out.extend_from_slice(&ipv4(r.dst_addr).octets()),
+        ie::IP_NEXT_HOP_IPV4_ADDRESS => out.extend_from_slice(&ipv4(r.next_hop).octets()),
+        ie::SOURCE_IPV6_ADDRESS => out.extend_from_slice(&ipv6(r.src_addr).octets()),
+        ie::DESTINATION_IPV6_ADDRESS => out.extend_from_slice(&ipv6(r.dst_addr).octets()),
+        ie::IP_NEXT_HOP_IPV6_ADDRESS => out.extend_from_slice(&ipv6(r.next_hop).octets()),
+        ie::SOURCE_IPV4_PREFIX_LENGTH | ie::SOURCE_IPV6_PREFIX_LENGTH => {
+            out.push(r.src_mask_len)
+        }
+        ie::DESTINATION_IPV4_PREFIX_LENGTH | ie::DESTINATION_IPV6_PREFIX_LENGTH => {
+            out.push(r.dst_mask_len)
+        }
+        ie::PROTOCOL_IDENTIFIER => out.push(r.protocol),
+        ie::IP_CLASS_OF_SERVICE => out.push(r.tos),
+        ie::TCP_CONTROL_BITS => out.extend_from_slice(&r.tcp_flags.to_be_bytes()),
+        ie::SOURCE_TRANSPORT_PORT => out.extend_from_slice(&r.src_port.to_be_bytes()),
+        ie::DESTINATION_TRANSPORT_PORT => out.extend_from_slice(&r.dst_port.to_be_bytes()),
+        ie::INGRESS_INTERFACE => out.extend_from_slice(&r.input.to_be_bytes()),
+        ie::EGRESS_INTERFACE => out.extend_from_slice(&r.output.to_be_bytes()),
+        ie::BGP_SOURCE_AS_NUMBER => out.extend_from_slice(&r.src_as.to_be_bytes()),
+        ie::BGP_DESTINATION_AS_NUMBER => out.extend_from_slice(&r.dst_as.to_be_bytes()),
+        ie::VLAN_ID => out.extend_from_slice(&r.src_vlan.to_be_bytes()),
+        ie::POST_VLAN_ID => out.extend_from_slice(&r.dst_vlan.to_be_bytes()),
+        ie::OCTET_DELTA_COUNT => out.extend_from_slice(&r.frame_length.to_be_bytes()),
+        ie::PACKET_DELTA_COUNT => out.extend_from_slice(&1u64.to_be_bytes()),
+        ie::SAMPLING_INTERVAL => out.extend_from_slice(&r.sampling_rate.to_be_bytes()),
+        ie::SAMPLING_ALGORITHM => out.push(RANDOM_SAMPLING),
+        ie::SELECTOR_ID => out.extend_from_slice(&r.selector_id.to_be_bytes()),
+        ie::FLOW_START_MILLISECONDS | ie::FLOW_END_MILLISECONDS => {
+            out.extend_from_slice(&times.unix_ms.to_be_bytes())
+        }
+        ie::FIRST_SWITCHED | ie::LAST_SWITCHED => {
+            out.extend_from_slice(&times.uptime.to_be_bytes())
+        }
+        _ => unreachable!("information element {id} is not in any template"),
+    }
+}
+
+/// Stateful IPFIX / NetFlow v9 message builder.
+///
+/// Keeps the export sequence number and template refresh schedule of one
+/// observation domain (NetFlow v9 source ID).
+#[derive(Debug, Clone)]
+pub struct FlowExporter {
+    format: ExportFormat,
+    observation_domain_id: u32,
+    template_refresh: u32,
+    max_message_size: usize,
+    sequence_number: u32,
+    messages_since_templates: Option<u32>,
+}
+
+impl Default for FlowExporter {
+    fn default() -> Self {
+        FlowExporter::builder().build()
+    }
+}
+
+impl FlowExporter {
+    /// Create a builder for configuring the exporter.
+    pub fn builder() -> FlowExporterBuilder {
+        FlowExporterBuilder {
+            format: ExportFormat::Ipfix,
+            observation_domain_id: 0,
+            template_refresh: 20,
+            max_message_size: 1400,
+        }
+    }
+
+    /// Convert the flow samples of a datagram received at `now` into export
+    /// messages. Returns no messages if no sample has IP information.
+    pub fn export(&mut self, datagram: &SflowDatagram, now: SystemTime) -> Vec<Vec<u8>> {
+        let records: Vec<(bool, ExportRecord)> = datagram
+            .samples
+            .iter()
+            .filter_map(|sample| sample.normalized_flow())
+            .filter_map(|sample| {
+                let record = ExportRecord::from_sample(&sample);
+                Some((record.is_ipv6()?, record))
+            })
+            .collect();
+        if records.is_empty() {
+            return Vec::new();
+        }
+
+        let unix_ms = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let times = Times {
+            unix_ms,
+            uptime: datagram.uptime,
+        };
+
+        let mut messages = Vec::new();
+        let mut pending = records.as_slice();
+        while !pending.is_empty() {
+            let (message, written) = self.message(pending, times);
+            messages.push(message);
+            pending = &pending[written..];
+        }
+        messages
+    }
+
+    /// Build one message from the front of `records`, returning it and the
+    /// number of records it holds.
+    fn message(&mut self, records: &[(bool, ExportRecord)], times: Times) -> (Vec<u8>, usize) {
+        let header_len = match self.format {
+            ExportFormat::Ipfix => IPFIX_HEADER_LEN,
+            ExportFormat::NetflowV9 => NETFLOW_V9_HEADER_LEN,
+        };
+        let mut out = vec![0u8; header_len];
+        // NetFlow v9 counts template and data records in the header.
+        let mut count: u16 = 0;
+
+        let send_templates = match self.messages_since_templates {
+            Some(sent) => sent + 1 >= self.template_refresh,
+            None => true,
+        };
+        if send_templates {
+            count += self.write_templates(&mut out);
+            self.messages_since_templates = Some(0);
+        } else {
+            self.messages_since_templates = self.messages_since_templates.map(|n| n + 1);
+        }
+
+        // Group consecutive records of the same family into one data set,
+        // allowing 3 bytes of NetFlow v9 set padding.
+        let mut written = 0;
+        while let Some(&(ipv6, _)) = records.get(written) {
+            let (set_id, fields) = if ipv6 {
+                (IPV6_TEMPLATE_ID, template_fields(self.format, true))
+            } else {
+                (IPV4_TEMPLATE_ID, template_fields(self.format, false))
+            };
+            let record_len: usize = fields.iter().map(|&(_, len)| usize::from(len)).sum();
+            if written > 0 && out.len() + 4 + record_len + 3 > self.max_message_size {
+                break;
+            }
+            let set_start = out.len();
+            out.extend_from_slice(&set_id.to_be_bytes());
+            out.extend_from_slice(&[0, 0]);
+            let mut full = false;
+            while let Some((family, record)) = records.get(written) {
+                if *family != ipv6 {
+                    break;
+                }
+                if out.len() > set_start + 4
+                    && out.len() + record_len + 3 > self.max_message_size
+                {
+                    full = true;
+                    break;
+                }
+                for &(id, _) in &fields {
+                    write_field(&mut out, id, record, times);
+                }
+                written += 1;
+                count += 1;
+            }
+            if self.format == ExportFormat::NetflowV9 {
+                let padding = (4 - (out.len() - set_start) % 4) % 4;
+                out.extend_from_slice(&[0u8; 3][..padding]);
+            }
+            let set_len = (out.len() - set_start) as u16;
+            out[set_start + 2..set_start + 4].copy_from_slice(&set_len.to_be_bytes());
+            if full {
+                break;
+            }
+        }
+
+        let export_secs = (times.unix_ms / 1000) as u32;
+        match self.format {
+            ExportFormat::Ipfix => {
+                out[0..2].copy_from_slice(&IPFIX_VERSION.to_be_bytes());
+                let length = out.len() as u16;
+                out[2..4].copy_from_slice(&length.to_be_bytes());
+                out[4..8].copy_from_slice(&export_secs.to_be_bytes());
+                out[8..12].copy_from_slice(&self.sequence_number.to_be_bytes());
+                out[12..16].copy_from_slice(&self.observation_domain_id.to_be_bytes());
+                // IPFIX sequence numbers count data records.
+                self.sequence_number = self.sequence_number.wrapping_add(written as u32);
+            }
+            ExportFormat::NetflowV9 => {
+                out[0..2].copy_from_slice(&NETFLOW_V9_VERSION.to_be_bytes());
+                out[2..4].copy_from_slice(&count.to_be_bytes());
+                out[4..8].copy_from_slice(&times.uptime.to_be_bytes());
+                out[8..12].copy_from_slice(&export_secs.to_be_bytes());
+                out[12..16].copy_from_slice(&self.sequence_number.to_be_bytes());
+                out[16..20].copy_from_slice(&self.observation_domain_id.to_be_bytes());
+                // NetFlow v9 sequence numbers count export packets.
+                self.sequence_number = self.sequence_number.wrapping_add(1);
+            }
+        }
+        (out, written)
+    }
+
+    /// Append a template set with both templates, returning the number of
+    /// template records written.
+    fn write_templates(&self, out: &mut Vec<u8>) -> u16 {
+        let set_id = match self.format {
+            ExportFormat::Ipfix => IPFIX_TEMPLATE_SET_ID,
+            ExportFormat::NetflowV9 => NETFLOW_V9_TEMPLATE_SET_ID,
+        };
+        let set_start = out.len();
+        out.extend_from_slice(&set_id.to_be_bytes());
+        out.extend_from_slice(&[0, 0]);
+        for (template_id, ipv6) in [(IPV4_TEMPLATE_ID, false), (IPV6_TEMPLATE_ID, true)] {
+            let fields = template_fields(self.format, ipv6);
+            out.extend_from_slice(&template_id.to_be_bytes());
+            out.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+            for (id, len) in fields {
+                out.extend_from_slice(&id.to_be_bytes());
+                out.extend_from_slice(&len.to_be_bytes());
+            }
+        }
+        let set_len = (out.len() - set_start) as u16;
+        out[set_start + 2..set_start + 4].copy_from_slice(&set_len.to_be_bytes());
+        2
+    }
+}
+
+/// Builder for configuring a [`FlowExporter`].
+#[derive(Debug, Clone)]
+pub struct FlowExporterBuilder {
+    format: ExportFormat,
+    observation_domain_id: u32,
+    template_refresh: u32,
+    max_message_size: usize,
+}
+
+impl FlowExporterBuilder {
+    /// Wire format (default [`ExportFormat::Ipfix`]).
+    pub fn with_format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// IPFIX observation domain ID or NetFlow v9 source ID (default 0).
+    pub fn with_observation_domain_id(mut self, observation_domain_id: u32) -> Self {
+        self.observation_domain_id = observation_domain_id;
+        self
+    }
+
+    /// Resend templates every `messages` messages (default 20; 1 sends them
+    /// in every message).
+    pub fn with_template_refresh(mut self, messages: u32) -> Self {
+        self.template_refresh = messages.max(1);
+        self
+    }
+
+    /// Largest message to produce, in bytes (default 1400), at most the
+    /// 65535 that the 16-bit length fields can describe. At least one record
+    /// is always written per message.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size.min(usize::from(u16::MAX));
+        self
+    }
+
+    /// Build the configured [`FlowExporter`].
+    pub fn build(self) -> FlowExporter {
+        FlowExporter {
+            format: self.format,
+            observation_domain_id: self.observation_domain_id,
+            template_refresh: self.template_refresh,
+            max_message_size: self.max_message_size,
+            sequence_number: 0,
+            messages_since_templates: None,
+        }
+    }
+}
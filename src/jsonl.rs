@@ -0,0 +1,417 @@
+//! JSON Lines output with a flat, versioned schema.
+//!
+//! The serde derives on [`SflowDatagram`] mirror the wire structure: nested
+//! samples and records, externally tagged variants and numeric enums. This
+//! module instead writes one flat JSON object per record (or per sample, see
+//! [`Granularity`]) that repeats the datagram and sample context, which is
+//! what log pipelines expect.
+//!
+//! # Schema version 1
+//!
+//! Every line carries:
+//!
+//! | Key | Value |
+//! |-----|-------|
+//! | `schema_version` | [`SCHEMA_VERSION`] |
+//! | `time` | Receive time, RFC 3339 UTC with milliseconds |
+//! | `agent` | Agent address as a string |
+//! | `sub_agent_id`, `datagram_sequence`, `uptime_ms` | Datagram header |
+//! | `sample_type` | `flow`, `counter`, `discard`, `unknown` or `custom`; expanded samples report `flow`/`counter` |
+//! | `sample_sequence`, `source_id`, `source_id_type`, `source_id_index` | Sample header; `source_id` is `type:index` |
+//!
+//! Flow samples add `sampling_rate`, `sample_pool` and `drops`; flow and
+//! discard samples add `input_type` and `output_type` (`ifindex`,
+//! `discarded`, `multiple`, `internal` or `unknown`), `input`/`output` with
+//! the ifIndex when the type is `ifindex`, `output_count` for `multiple` and
+//! `drop_reason` for discards. Unknown samples add `sample_enterprise`,
+//! `sample_format` and `sample_data`; samples decoded by a registered
+//! decoder (see [`custom`](crate::custom)) have the `sample_type` `custom`
+//! and add the fields of their value as `custom_<enterprise>_<format>.<field>`.
+//!
+//! Each record is identified by `record_type`, the snake_case name of its
+//! [`FlowRecord`] or [`CounterRecord`] variant (`sampled_ipv4`, `host_cpu`,
+//! `unknown`, ...), with `record_enterprise` and `record_format`. Records
+//! decoded by a registered decoder use `custom_<enterprise>_<format>` and
+//! the fields of their value. Fields follow as `<record_type>.<field>`, with
+//! nested structs joined by `.`.
+//! Per-sample lines list the types in `record_types` instead; a repeated
+//! type gets a `_2`, `_3`, ... suffix in its keys.
+//!
+//! Values are normalised: IP addresses are strings, MAC addresses are
+//! lowercase and colon-separated, byte strings (raw packet headers, unknown
+//! bodies) are lowercase hex and enums are snake_case names, with
+//! `raw_packet_header.header_protocol_name` next to the numeric protocol.
+//! Unrecognised enum values are written as `unrecognized_<value>`. Keys are
+//! only added in later schema versions; renaming or removing a key bumps
+//! [`SCHEMA_VERSION`].
+//!
+//! ```
+//! use flowparser_sflow::jsonl::JsonlWriter;
+//! use flowparser_sflow::SflowParser;
+//! use std::time::SystemTime;
+//!
+//! let parser = SflowParser::default();
+//! let mut writer = JsonlWriter::new(Vec::new());
+//! for datagram in &parser.parse_bytes(&[/* sflow datagram bytes */]).datagrams {
+//!     writer.write_datagram(datagram, SystemTime::now()).unwrap();
+//! }
+//! let lines = writer.into_inner();
+//! ```
+
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::SflowDatagram;
+use crate::counter_records::CounterRecord;
+use crate::flow_records::FlowRecord;
+use crate::format::{civil_date, reparse, unwrap_variant};
+use crate::samples::{DataSource, Interface, SflowSample};
+
+/// Version of the line schema, written as `schema_version`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What each line describes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    /// One line per record; samples without records get one line.
+    #[default]
+    Record,
+    /// One line per sample with the fields of all its records.
+    Sample,
+}
+
+/// Flatten `datagram` into schema objects, one per line.
+pub fn json_lines(
+    datagram: &SflowDatagram,
+    received: SystemTime,
+    granularity: Granularity,
+) -> Vec<Map<String, Value>> {
+    let mut header = Map::new();
+    header.insert("schema_version".into(), SCHEMA_VERSION.into());
+    header.insert("time".into(), rfc3339(received).into());
+    header.insert("agent".into(), datagram.agent_address.to_string().into());
+    header.insert("sub_agent_id".into(), datagram.sub_agent_id.into());
+    header.insert("datagram_sequence".into(), datagram.sequence_number.into());
+    header.insert("uptime_ms".into(), datagram.uptime.into());
+
+    let mut lines = Vec::new();
+    for sample in &datagram.samples {
+        let mut context = header.clone();
+        let records = sample_context(&mut context, sample);
+        match granularity {
+            Granularity::Record if !records.is_empty() => {
+                for (index, (name, enterprise, format, fields)) in
+                    records.into_iter().enumerate()
+                {
+                    let mut line = context.clone();
+                    line.insert("record_index".into(), index.into());
+                    line.insert("record_type".into(), name.clone().into());
+                    line.insert("record_enterprise".into(), enterprise.into());
+                    line.insert("record_format".into(), format.into());
+                    flatten(&mut line, &name, &fields);
+                    lines.push(line);
+                }
+            }
+            Granularity::Record => lines.push(context),
+            Granularity::Sample => {
+                let mut line = context;
+                let mut types: Vec<Value> = Vec::new();
+                let mut seen: Vec<String> = Vec::new();
+                for (name, _, _, fields) in records {
+                    let count = seen.iter().filter(|n| **n == name).count();
+                    let prefix = match count {
+                        0 => name.clone(),
+                        n => format!("{name}_{}", n + 1),
+                    };
+                    types.push(name.clone().into());
+                    seen.push(name);
+                    flatten(&mut line, &prefix, &fields);
+                }
+                line.insert("record_types".into(), types.into());
+                lines.push(line);
+            }
+        }
+    }
+    lines
+}
+
+/// Writes datagrams as JSON Lines in the [module schema](self).
+#[derive(Debug)]
+pub struct JsonlWriter<W: Write> {
+    out: W,
+    granularity: Granularity,
+    lines: u64,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    /// Write one line per record to `out`.
+    pub fn new(out: W) -> Self {
+        JsonlWriter {
+            out,
+            granularity: Granularity::default(),
+            lines: 0,
+        }
+    }
+
+    /// Write one line per record or per sample. Defaults to
+    /// [`Granularity::Record`].
+    pub fn with_granularity(mut self, granularity: Granularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// Write the lines of `datagram`, returning how many were written.
+    pub fn write_datagram(
+        &mut self,
+        datagram: &SflowDatagram,
+        received: SystemTime,
+    ) -> io::Result<usize> {
+        let lines = json_lines(datagram, received, self.granularity);
+        for line in &lines {
+            serde_json::to_writer(&mut self.out, line)?;
+            self.out.write_all(b"\n")?;
+        }
+        self.lines += lines.len() as u64;
+        Ok(lines.len())
+    }
+
+    /// Number of lines written so far.
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Record type name, enterprise, format and field values.
+type RecordFields = (String, u32, u32, Value);
+
+/// Add the sample keys to `line` and return the sample's records.
+fn sample_context(line: &mut Map<String, Value>, sample: &SflowSample) -> Vec<RecordFields> {
+    if let Some(flow) = sample.normalized_flow() {
+        line.insert("sample_type".into(), "flow".into());
+        line.insert("sample_sequence".into(), flow.sequence_number.into());
+        data_source(line, flow.data_source);
+        line.insert("sampling_rate".into(), flow.sampling_rate.into());
+        line.insert("sample_pool".into(), flow.sample_pool.into());
+        line.insert("drops".into(), flow.drops.into());
+        interface(line, "input", flow.input);
+        interface(line, "output", flow.output);
+        return flow.records.iter().map(flow_record).collect();
+    }
+    if let Some(counters) = sample.normalized_counter() {
+        line.insert("sample_type".into(), "counter".into());
+        line.insert("sample_sequence".into(), counters.sequence_number.into());
+        data_source(line, counters.data_source);
+        return counters.records.iter().map(counter_record).collect();
+    }
+    match sample {
+        SflowSample::Discard(d) => {
+            line.insert("sample_type".into(), "discard".into());
+            line.insert("sample_sequence".into(), d.sequence_number.into());
+            data_source(line, d.data_source());
+            line.insert("drops".into(), d.drops.into());
+            interface(line, "input", d.input_interface());
+            interface(line, "output", d.output_interface());
+            line.insert("drop_reason".into(), enum_name(&d.reason).into());
+            d.records.iter().map(flow_record).collect()
+        }
+        SflowSample::Unknown {
+            enterprise,
+            format,
+            data,
+        } => {
+            line.insert("sample_type".into(), "unknown".into());
+            line.insert("sample_enterprise".into(), (*enterprise).into());
+            line.insert("sample_format".into(), (*format).into());
+            line.insert("sample_data".into(), hex(data).into());
+            Vec::new()
+        }
+        SflowSample::Custom(c) => {
+            line.insert("sample_type".into(), "custom".into());
+            line.insert("sample_enterprise".into(), c.enterprise.into());
+            line.insert("sample_format".into(), c.format.into());
+            line.insert("sample_data".into(), hex(&c.data).into());
+            let (name, fields) = c.variant();
+            flatten(line, &name, &fields);
+            Vec::new()
+        }
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn data_source(line: &mut Map<String, Value>, source: DataSource) {
+    line.insert("source_id".into(), source.to_string().into());
+    line.insert("source_id_type".into(), source.source_id_type().into());
+    line.insert("source_id_index".into(), source.index().into());
+}
+
+pub(crate) fn interface(line: &mut Map<String, Value>, name: &str, interface: Interface) {
+    let kind = match interface {
+        Interface::Single(index) => {
+            line.insert(name.into(), index.into());
+            "ifindex"
+        }
+        Interface::Discarded(reason) => {
+            line.insert("drop_reason".into(), enum_name(&reason).into());
+            "discarded"
+        }
+        Interface::Multiple(count) => {
+            line.insert(format!("{name}_count"), count.into());
+            "multiple"
+        }
+        Interface::Internal => "internal",
+        Interface::Unknown => "unknown",
+    };
+    line.insert(format!("{name}_type"), kind.into());
+}
+
+fn flow_record(record: &FlowRecord) -> RecordFields {
+    let (enterprise, format) = record.enterprise_format();
+    let (name, mut fields) = match record {
+        FlowRecord::Custom(c) => c.variant(),
+        _ => variant(record),
+    };
+    if let Value::Object(map) = &mut fields {
+        match record {
+            FlowRecord::RawPacketHeader(r) => {
+                map.insert("header".into(), hex(&r.header).into());
+                let protocol = enum_name(&r.protocol());
+                map.insert("header_protocol_name".into(), protocol.into());
+            }
+            FlowRecord::Unknown { data, .. } => {
+                map.insert("data".into(), hex(data).into());
+            }
+            _ => {}
+        }
+    }
+    (name, enterprise, format, fields)
+}
+
+fn counter_record(record: &CounterRecord) -> RecordFields {
+    let (enterprise, format) = record.enterprise_format();
+    let (name, mut fields) = counter_variant(record);
+    if let (CounterRecord::Unknown { data, .. }, Value::Object(map)) = (record, &mut fields) {
+        map.insert("data".into(), hex(data).into());
+    }
+    (name, enterprise, format, fields)
+}
+
+/// [`variant`] of a counter record, with records decoded by a registered
+/// decoder named `custom_<enterprise>_<format>`.
+pub(crate) fn counter_variant(record: &CounterRecord) -> (String, Value) {
+    match record {
+        CounterRecord::Custom(c) => c.variant(),
+        _ => variant(record),
+    }
+}
+
+/// Snake_case variant name and fields of an externally tagged record.
+///
+/// Field keys are the serde names of the record structs, so renaming a field
+/// changes the schema; `tests/jsonl.rs` snapshots the keys of every record.
+pub(crate) fn variant(record: &impl Serialize) -> (String, Value) {
+    match reparse(record).unwrap_or(Value::Null) {
+        Value::Object(map) if map.len() == 1 => {
+            let (name, fields) = map.into_iter().next().expect("one entry");
+            (snake_case(&name), fields)
+        }
+        other => ("unknown".to_string(), other),
+    }
+}
+
+/// Snake_case name of a unit variant, or `<variant>_<value>` for a newtype.
+pub(crate) fn enum_name(value: &impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => snake_case(&name),
+        Ok(Value::Object(map)) if map.len() == 1 => {
+            let (name, inner) = map.into_iter().next().expect("one entry");
+            format!("{}_{inner}", snake_case(&name))
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+fn flatten(line: &mut Map<String, Value>, prefix: &str, value: &Value) {
+    match unwrap_variant(value) {
+        Value::Object(map) => {
+            for (name, field) in map {
+                flatten(line, &format!("{prefix}.{name}"), field);
+            }
+        }
+        other => {
+            line.insert(prefix.to_string(), normalize(other));
+        }
+    }
+}
+
+/// Unwrap tagged variants and lowercase MAC addresses inside `value`.
+pub(crate) fn normalize(value: &Value) -> Value {
+    match unwrap_variant(value) {
+        Value::String(s) if is_mac(s) => Value::String(s.to_ascii_lowercase()),
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        Value::Object(map) => {
+            Value::Object(map.iter().map(|(k, v)| (k.clone(), normalize(v))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// `mac_address` serialises as `AA:BB:CC:DD:EE:FF`.
+fn is_mac(s: &str) -> bool {
+    s.len() == 17
+        && s.bytes().enumerate().all(|(i, b)| {
+            if i % 3 == 2 {
+                b == b':'
+            } else {
+                b.is_ascii_hexdigit()
+            }
+        })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+fn rfc3339(time: SystemTime) -> String {
+    let since = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since.as_secs();
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60,
+        since.subsec_millis()
+    )
+}
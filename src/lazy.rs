@@ -0,0 +1,418 @@
+//! Lazy, iterator-based decoding of sFlow datagrams.
+//!
+//! [`SflowParser::parse_lazy`](crate::SflowParser::parse_lazy) decodes only
+//! datagram headers up front. Samples and records are yielded by iterators
+//! that skip over bodies using their length fields, so a body is decoded
+//! only when [`decode`](LazyFlowRecord::decode) is called on it.
+//!
+//! ```
+//! use flowparser_sflow::{CounterRecordRef, SflowParser};
+//!
+//! let parser = SflowParser::default();
+//! let buf = [/* sflow datagram bytes */];
+//!
+//! for datagram in parser.parse_lazy(&buf) {
+//!     let Ok(datagram) = datagram else { break };
+//!     for sample in datagram.samples().flatten() {
+//!         let Some(records) = sample.counter_records() else { continue };
+//!         for record in records.flatten() {
+//!             // Only generic interface counters are decoded
+//!             if record.enterprise_format() != (0, 1) {
+//!                 continue;
+//!             }
+//!             if let Ok(CounterRecordRef::GenericInterface(gi)) = record.decode() {
+//!                 println!("ifIndex={} in={}", gi.if_index, gi.if_in_octets);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::counter_records::{CounterRecordRef, parse_counter_record_ref};
+use crate::datagram::{AddressType, SflowDatagramRef, parse_datagram_header};
+use crate::error::{ParseContext, ParseErrorKind, SflowError};
+use crate::flow_records::{FlowRecordRef, Tagged, parse_flow_record_ref, split_record};
+use crate::samples::{
+    SflowSampleRef, nom_err_to_kind, parse_sample, parse_samples, split_sample,
+};
+
+/// Iterator over the datagrams in a buffer, returned by
+/// [`SflowParser::parse_lazy`](crate::SflowParser::parse_lazy).
+///
+/// Stops after the first error, like [`SflowParser::parse_bytes`](crate::SflowParser::parse_bytes).
+#[derive(Debug, Clone)]
+pub struct LazyDatagrams<'a> {
+    input: &'a [u8],
+    max_samples: Option<u32>,
+    done: bool,
+}
+
+impl<'a> LazyDatagrams<'a> {
+    pub(crate) fn new(input: &'a [u8], max_samples: Option<u32>) -> Self {
+        LazyDatagrams {
+            input,
+            max_samples,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for LazyDatagrams<'a> {
+    type Item = Result<LazyDatagram<'a>, SflowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.input.is_empty() {
+            return None;
+        }
+
+        if self.input.len() < 4 {
+            self.done = true;
+            return Some(Err(SflowError::Incomplete {
+                available: self.input.len(),
+                expected: None,
+                context: ParseContext::DatagramHeader,
+            }));
+        }
+
+        match parse_lazy_datagram(self.input, self.max_samples) {
+            Ok((rest, dg)) => {
+                self.input = rest;
+                Some(Ok(dg))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn parse_lazy_datagram(
+    input: &[u8],
+    max_samples: Option<u32>,
+) -> Result<(&[u8], LazyDatagram<'_>), SflowError> {
+    let (samples_start, header) = parse_datagram_header(input, max_samples)?;
+    // v2 and v4 samples have no length fields to skip over
+    if header.version != 5 {
+        return Err(SflowError::LazyUnsupportedVersion {
+            version: header.version,
+        });
+    }
+
+    // Walk the sample headers to find where this datagram ends
+    let mut rest = samples_start;
+    for _ in 0..header.num_samples {
+        let (after, _) = split_sample(rest)?;
+        rest = after;
+    }
+    let samples = &samples_start[..samples_start.len() - rest.len()];
+
+    Ok((
+        rest,
+        LazyDatagram {
+            version: header.version,
+            agent_address: header.agent_address,
+            sub_agent_id: header.sub_agent_id,
+            sequence_number: header.sequence_number,
+            uptime: header.uptime,
+            num_samples: header.num_samples,
+            samples,
+        },
+    ))
+}
+
+/// A datagram whose header has been decoded but whose samples have not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyDatagram<'a> {
+    /// sFlow version (always 5; v2 and v4 datagrams cannot be decoded lazily
+    /// and are rejected with [`SflowError::LazyUnsupportedVersion`]).
+    pub version: u32,
+    /// IP address of the sFlow agent.
+    pub agent_address: AddressType,
+    /// Sub-agent identifier (disambiguates multiple data sources).
+    pub sub_agent_id: u32,
+    /// Sequence number incremented per datagram from this agent.
+    pub sequence_number: u32,
+    /// Agent uptime in milliseconds since boot.
+    pub uptime: u32,
+    /// Number of samples declared in the datagram header.
+    pub num_samples: u32,
+    samples: &'a [u8],
+}
+
+impl<'a> LazyDatagram<'a> {
+    /// Iterate over the samples without decoding their bodies.
+    pub fn samples(&self) -> LazySamples<'a> {
+        LazySamples {
+            input: self.samples,
+            remaining: self.num_samples,
+        }
+    }
+
+    /// Decode every sample, producing the same result as
+    /// [`SflowParser::parse_bytes_ref`](crate::SflowParser::parse_bytes_ref).
+    pub fn decode(&self) -> Result<SflowDatagramRef<'a>, SflowError> {
+        let (_, samples) = parse_samples(self.samples, self.num_samples)?;
+        Ok(SflowDatagramRef {
+            version: self.version,
+            agent_address: self.agent_address.clone(),
+            sub_agent_id: self.sub_agent_id,
+            sequence_number: self.sequence_number,
+            uptime: self.uptime,
+            samples,
+        })
+    }
+}
+
+/// Iterator over the samples of a [`LazyDatagram`].
+#[derive(Debug, Clone)]
+pub struct LazySamples<'a> {
+    input: &'a [u8],
+    remaining: u32,
+}
+
+impl<'a> Iterator for LazySamples<'a> {
+    type Item = Result<LazySample<'a>, SflowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // Framing was already checked when the datagram was split off
+        let (rest, (enterprise, format, data)) = match split_sample(self.input) {
+            Ok(v) => v,
+            Err(e) => {
+                self.remaining = 0;
+                return Some(Err(e));
+            }
+        };
+        self.input = rest;
+        Some(Ok(LazySample {
+            enterprise,
+            format,
+            data,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining as usize))
+    }
+}
+
+/// An undecoded sample: its `data_format` and raw body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazySample<'a> {
+    /// Enterprise code from the sample header.
+    pub enterprise: u32,
+    /// Format code from the sample header.
+    pub format: u32,
+    /// Raw sample body.
+    pub data: &'a [u8],
+}
+
+impl<'a> LazySample<'a> {
+    /// The `(enterprise, format)` pair identifying this sample type.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        (self.enterprise, self.format)
+    }
+
+    /// Decode the sample body, including all of its records.
+    pub fn decode(&self) -> Result<SflowSampleRef<'a>, SflowError> {
+        parse_sample(self.enterprise, self.format, self.data)
+    }
+
+    /// Iterate over the flow records of a flow, expanded flow or discarded
+    /// packet sample without decoding them.
+    ///
+    /// Returns `None` for other sample types.
+    pub fn flow_records(&self) -> Option<LazyFlowRecords<'a>> {
+        let (header_len, context) = match (self.enterprise, self.format) {
+            (0, 1) => (28, ParseContext::FlowSample),
+            (0, 3) => (40, ParseContext::ExpandedFlowSample),
+            (0, 5) => (28, ParseContext::DiscardedPacket),
+            _ => return None,
+        };
+        Some(LazyFlowRecords {
+            cursor: RecordCursor::new(self.data, header_len, context),
+        })
+    }
+
+    /// Iterate over the counter records of a counter or expanded counter
+    /// sample without decoding them.
+    ///
+    /// Returns `None` for other sample types.
+    pub fn counter_records(&self) -> Option<LazyCounterRecords<'a>> {
+        let (header_len, context) = match (self.enterprise, self.format) {
+            (0, 2) => (8, ParseContext::CounterSample),
+            (0, 4) => (12, ParseContext::ExpandedCounterSample),
+            _ => return None,
+        };
+        Some(LazyCounterRecords {
+            cursor: RecordCursor::new(self.data, header_len, context),
+        })
+    }
+}
+
+/// Walks the record list of a sample body, yielding raw records.
+#[derive(Debug, Clone)]
+struct RecordCursor<'a> {
+    input: &'a [u8],
+    remaining: u32,
+    context: ParseContext,
+    error: bool,
+}
+
+impl<'a> RecordCursor<'a> {
+    /// `header_len` is the number of bytes preceding the record count.
+    fn new(data: &'a [u8], header_len: usize, context: ParseContext) -> Self {
+        match data.get(header_len..header_len + 4) {
+            Some(count) => RecordCursor {
+                input: &data[header_len + 4..],
+                remaining: u32::from_be_bytes([count[0], count[1], count[2], count[3]]),
+                context,
+                error: false,
+            },
+            None => RecordCursor {
+                input: &[],
+                remaining: 0,
+                context,
+                error: true,
+            },
+        }
+    }
+
+    fn next_raw(&mut self) -> Option<Result<Tagged<'a>, SflowError>> {
+        if self.error {
+            // The sample body is too short to hold its record count
+            self.error = false;
+            return Some(Err(SflowError::ParseError {
+                offset: 0,
+                context: self.context,
+                kind: ParseErrorKind::NomError(nom::error::ErrorKind::Eof),
+            }));
+        }
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match split_record(self.input) {
+            Ok((rest, raw)) => {
+                self.input = rest;
+                Some(Ok(raw))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(SflowError::ParseError {
+                    offset: 0,
+                    context: self.context,
+                    kind: nom_err_to_kind(&e),
+                }))
+            }
+        }
+    }
+}
+
+/// Iterator over the records of a flow-bearing [`LazySample`].
+#[derive(Debug, Clone)]
+pub struct LazyFlowRecords<'a> {
+    cursor: RecordCursor<'a>,
+}
+
+impl<'a> Iterator for LazyFlowRecords<'a> {
+    type Item = Result<LazyFlowRecord<'a>, SflowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let context = self.cursor.context;
+        self.cursor.next_raw().map(|r| {
+            r.map(|(enterprise, format, data)| LazyFlowRecord {
+                enterprise,
+                format,
+                data,
+                context,
+            })
+        })
+    }
+}
+
+/// An undecoded flow record: its `data_format` and raw body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyFlowRecord<'a> {
+    /// Enterprise code from the record header.
+    pub enterprise: u32,
+    /// Format code from the record header.
+    pub format: u32,
+    /// Raw record body.
+    pub data: &'a [u8],
+    context: ParseContext,
+}
+
+impl<'a> LazyFlowRecord<'a> {
+    /// The `(enterprise, format)` pair identifying this record type.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        (self.enterprise, self.format)
+    }
+
+    /// Decode the record body.
+    pub fn decode(&self) -> Result<FlowRecordRef<'a>, SflowError> {
+        parse_flow_record_ref(self.enterprise, self.format, self.data).map_err(|e| {
+            SflowError::ParseError {
+                offset: 0,
+                context: self.context,
+                kind: nom_err_to_kind(&e),
+            }
+        })
+    }
+}
+
+/// Iterator over the records of a counter-bearing [`LazySample`].
+#[derive(Debug, Clone)]
+pub struct LazyCounterRecords<'a> {
+    cursor: RecordCursor<'a>,
+}
+
+impl<'a> Iterator for LazyCounterRecords<'a> {
+    type Item = Result<LazyCounterRecord<'a>, SflowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let context = self.cursor.context;
+        self.cursor.next_raw().map(|r| {
+            r.map(|(enterprise, format, data)| LazyCounterRecord {
+                enterprise,
+                format,
+                data,
+                context,
+            })
+        })
+    }
+}
+
+/// An undecoded counter record: its `data_format` and raw body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyCounterRecord<'a> {
+    /// Enterprise code from the record header.
+    pub enterprise: u32,
+    /// Format code from the record header.
+    pub format: u32,
+    /// Raw record body.
+    pub data: &'a [u8],
+    context: ParseContext,
+}
+
+impl<'a> LazyCounterRecord<'a> {
+    /// The `(enterprise, format)` pair identifying this record type.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        (self.enterprise, self.format)
+    }
+
+    /// Decode the record body.
+    pub fn decode(&self) -> Result<CounterRecordRef<'a>, SflowError> {
+        parse_counter_record_ref(self.enterprise, self.format, self.data).map_err(|e| {
+            SflowError::ParseError {
+                offset: 0,
+                context: self.context,
+                kind: nom_err_to_kind(&e),
+            }
+        })
+    }
+}
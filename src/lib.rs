@@ -1,21 +1,45 @@
 #![doc = include_str!("../README.md")]
 #![forbid(unsafe_code)]
 
+pub mod agent;
+pub mod aggregate;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "collector")]
+pub mod collector;
 pub mod counter_records;
+pub mod custom;
 pub mod datagram;
+mod encode;
 pub mod error;
+pub mod estimate;
 pub mod flow_records;
+mod format;
+pub mod influx;
+pub mod ipfix;
+pub mod jsonl;
+pub mod lazy;
+#[cfg(feature = "packet")]
+pub mod packet;
+pub mod pcap;
+pub mod prometheus;
+pub mod rates;
 pub mod samples;
+pub mod sequence;
+pub mod sflowtool;
 
 #[cfg(test)]
 mod tests;
 
-pub use counter_records::CounterRecord;
-pub use datagram::{AddressType, SflowDatagram};
+pub use counter_records::{CounterRecord, CounterRecordRef};
+pub use datagram::{AddressType, SflowDatagram, SflowDatagramRef};
 pub use error::{ParseContext, ParseErrorKind, SflowError};
-pub use flow_records::FlowRecord;
-pub use samples::SflowSample;
+pub use flow_records::{FlowRecord, FlowRecordRef};
+pub use lazy::{LazyDatagram, LazyDatagrams};
+pub use samples::{SflowSample, SflowSampleRef};
+pub use sequence::SequenceTracker;
 
+use custom::{CustomValue, DecoderRegistry};
 use serde::{Deserialize, Serialize};
 
 /// Result of parsing one or more sFlow datagrams from a byte buffer.
@@ -33,11 +57,40 @@ pub struct ParseResult {
     pub error: Option<SflowError>,
 }
 
+/// Borrowed result of [`SflowParser::parse_bytes_ref`].
+///
+/// Datagrams borrow raw packet headers, strings and unknown record bodies
+/// from the input buffer. Use [`into_owned`](ParseResultRef::into_owned)
+/// to obtain a [`ParseResult`] that outlives the buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseResultRef<'a> {
+    /// Successfully parsed sFlow datagrams.
+    pub datagrams: Vec<SflowDatagramRef<'a>>,
+    /// Error encountered during parsing, if any.
+    pub error: Option<SflowError>,
+}
+
+impl ParseResultRef<'_> {
+    /// Convert into an owned [`ParseResult`], copying any borrowed data.
+    pub fn into_owned(self) -> ParseResult {
+        ParseResult {
+            datagrams: self
+                .datagrams
+                .into_iter()
+                .map(SflowDatagramRef::into_owned)
+                .collect(),
+            error: self.error,
+        }
+    }
+}
+
 /// Stateless sFlow v5 datagram parser.
 ///
 /// Unlike NetFlow V9/IPFIX parsers, `SflowParser` requires no mutable state
 /// between calls since sFlow v5 is fully self-describing. Each call to
-/// [`parse_bytes`](SflowParser::parse_bytes) is independent.
+/// [`parse_bytes`](SflowParser::parse_bytes) is independent. Legacy v2 and
+/// v4 datagrams are decoded into the same model, with
+/// [`SflowDatagram::version`] preserving the version on the wire.
 ///
 /// # Examples
 ///
@@ -53,12 +106,16 @@ pub struct ParseResult {
 #[derive(Debug, Clone, Default)]
 pub struct SflowParser {
     max_samples: Option<u32>,
+    decoders: DecoderRegistry,
 }
 
 impl SflowParser {
     /// Create a builder for configuring the parser.
     pub fn builder() -> SflowParserBuilder {
-        SflowParserBuilder { max_samples: None }
+        SflowParserBuilder {
+            max_samples: None,
+            decoders: DecoderRegistry::default(),
+        }
     }
 
     /// Parse one or more sFlow v5 datagrams from a byte slice.
@@ -66,6 +123,64 @@ impl SflowParser {
     /// Returns a [`ParseResult`] containing all successfully parsed datagrams
     /// and an optional error. Parsing is stateless — each call is independent.
     pub fn parse_bytes(&self, packet: &[u8]) -> ParseResult {
+        let (mut datagrams, error) = self.parse_all(packet, datagram::parse_datagram);
+        if !self.decoders.is_empty() {
+            for dg in &mut datagrams {
+                self.decoders.apply(&mut dg.samples);
+            }
+        }
+        ParseResult { datagrams, error }
+    }
+
+    /// Parse one or more sFlow v5 datagrams without copying variable-length data.
+    ///
+    /// Behaves like [`parse_bytes`](SflowParser::parse_bytes), but raw packet
+    /// headers, strings and unknown sample or record bodies borrow from `packet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flowparser_sflow::SflowParser;
+    ///
+    /// let parser = SflowParser::default();
+    /// let buf = [/* sflow datagram bytes */];
+    /// let result = parser.parse_bytes_ref(&buf);
+    /// let owned = result.into_owned();
+    /// assert!(owned.datagrams.is_empty());
+    /// ```
+    pub fn parse_bytes_ref<'a>(&self, packet: &'a [u8]) -> ParseResultRef<'a> {
+        let (mut datagrams, error) = self.parse_all(packet, datagram::parse_datagram_ref);
+        if !self.decoders.is_empty() {
+            for dg in &mut datagrams {
+                self.decoders.apply_ref(&mut dg.samples);
+            }
+        }
+        ParseResultRef { datagrams, error }
+    }
+
+    /// Lazily iterate over the sFlow v5 datagrams in a byte slice.
+    ///
+    /// Only datagram headers are decoded up front; samples and records are
+    /// skipped using their length fields until explicitly decoded. See the
+    /// [`lazy`] module for details. v2 and v4 datagrams have no length
+    /// fields and yield [`SflowError::LazyUnsupportedVersion`].
+    ///
+    /// Registered decoders are not applied; unknown bodies can be passed to
+    /// [`decoders`](SflowParser::decoders) instead.
+    pub fn parse_lazy<'a>(&self, packet: &'a [u8]) -> LazyDatagrams<'a> {
+        LazyDatagrams::new(packet, self.max_samples)
+    }
+
+    /// The decoders registered for enterprise-specific samples and records.
+    pub fn decoders(&self) -> &DecoderRegistry {
+        &self.decoders
+    }
+
+    fn parse_all<'a, T>(
+        &self,
+        packet: &'a [u8],
+        parse: impl Fn(&'a [u8], Option<u32>) -> Result<(&'a [u8], T), SflowError>,
+    ) -> (Vec<T>, Option<SflowError>) {
         let mut datagrams = Vec::new();
         let mut remaining = packet;
 
@@ -75,34 +190,26 @@ impl SflowParser {
             }
 
             if remaining.len() < 4 {
-                return ParseResult {
+                return (
                     datagrams,
-                    error: Some(SflowError::Incomplete {
+                    Some(SflowError::Incomplete {
                         available: remaining.len(),
                         expected: None,
                         context: ParseContext::DatagramHeader,
                     }),
-                };
+                );
             }
 
-            match datagram::parse_datagram(remaining, self.max_samples) {
+            match parse(remaining, self.max_samples) {
                 Ok((rest, dg)) => {
                     datagrams.push(dg);
                     remaining = rest;
                 }
-                Err(e) => {
-                    return ParseResult {
-                        datagrams,
-                        error: Some(e),
-                    };
-                }
+                Err(e) => return (datagrams, Some(e)),
             }
         }
 
-        ParseResult {
-            datagrams,
-            error: None,
-        }
+        (datagrams, None)
     }
 }
 
@@ -110,6 +217,7 @@ impl SflowParser {
 #[derive(Debug, Clone)]
 pub struct SflowParserBuilder {
     max_samples: Option<u32>,
+    decoders: DecoderRegistry,
 }
 
 impl SflowParserBuilder {
@@ -121,10 +229,49 @@ impl SflowParserBuilder {
         self
     }
 
+    /// Decode flow records with this `enterprise` and `format` into
+    /// [`FlowRecord::Custom`]. `decode` receives the record body and returns
+    /// `None` to leave the record as [`FlowRecord::Unknown`]. Built-in
+    /// formats are not affected; see the [`custom`] module.
+    pub fn with_flow_decoder<T, F>(mut self, enterprise: u32, format: u32, decode: F) -> Self
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.decoders.register_flow(enterprise, format, decode);
+        self
+    }
+
+    /// Decode counter records with this `enterprise` and `format` into
+    /// [`CounterRecord::Custom`], like
+    /// [`with_flow_decoder`](Self::with_flow_decoder).
+    pub fn with_counter_decoder<T, F>(mut self, enterprise: u32, format: u32, decode: F) -> Self
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.decoders.register_counter(enterprise, format, decode);
+        self
+    }
+
+    /// Decode samples with this `enterprise` and `format` into
+    /// [`SflowSample::Custom`], like
+    /// [`with_flow_decoder`](Self::with_flow_decoder). `decode` receives the
+    /// whole sample body.
+    pub fn with_sample_decoder<T, F>(mut self, enterprise: u32, format: u32, decode: F) -> Self
+    where
+        T: CustomValue,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.decoders.register_sample(enterprise, format, decode);
+        self
+    }
+
     /// Build the configured [`SflowParser`].
     pub fn build(self) -> SflowParser {
         SflowParser {
             max_samples: self.max_samples,
+            decoders: self.decoders,
         }
     }
 }
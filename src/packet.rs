@@ -0,0 +1,452 @@
+//! Decoding of sampled packet headers into L2/L3/L4 fields.
+//!
+//! Enabled with the `packet` cargo feature. [`RawPacketHeader`] records carry
+//! the first bytes of a sampled frame; [`decode_header`] interprets them
+//! according to the record's [`HeaderProtocol`] and extracts the commonly
+//! used fields without allocating beyond the VLAN and MPLS tag lists.
+//!
+//! Decoding never fails. Headers are usually cut short by the agent, so
+//! whatever could be read before the end of the sampled bytes is returned and
+//! [`DecodedHeader::truncated`] is set. Unsupported link types, non-initial IP
+//! fragments and encrypted 802.11 frames simply stop decoding at that layer.
+//!
+//! # Examples
+//!
+//! ```
+//! use flowparser_sflow::flow_records::HeaderProtocol;
+//! use flowparser_sflow::packet::decode_header;
+//!
+//! // IPv4 + UDP 10.0.0.1:53 -> 10.0.0.2:1024, cut after the UDP header.
+//! let bytes = [
+//!     0x45, 0x00, 0x00, 0x24, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00,
+//!     10, 0, 0, 1, 10, 0, 0, 2, 0x00, 0x35, 0x04, 0x00, 0x00, 0x10, 0x00, 0x00,
+//! ];
+//! let decoded = decode_header(HeaderProtocol::Ipv4, &bytes);
+//! assert_eq!(decoded.ip_protocol, Some(17));
+//! assert_eq!(decoded.src_port, Some(53));
+//! assert_eq!(decoded.payload_offset, Some(28));
+//! assert!(!decoded.truncated);
+//! ```
+//!
+//! [`RawPacketHeader`]: crate::flow_records::RawPacketHeader
+
+use mac_address::MacAddress;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::flow_records::HeaderProtocol;
+
+/// Fields extracted from a sampled packet header.
+///
+/// Every field is optional because the sampled bytes may end, or use a
+/// protocol this module does not interpret, before the field is reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedHeader {
+    /// Source MAC address (the 802.11 SA for wireless frames).
+    pub src_mac: Option<MacAddress>,
+    /// Destination MAC address (the 802.11 DA for wireless frames).
+    pub dst_mac: Option<MacAddress>,
+    /// 802.1Q / 802.1ad tags, outermost first.
+    pub vlan_tags: Vec<VlanTag>,
+    /// EtherType of the frame payload, after any VLAN tags or LLC/SNAP header.
+    pub ether_type: Option<u16>,
+    /// MPLS label stack entries, outermost first.
+    pub mpls_labels: Vec<MplsLabel>,
+    /// IP version (4 or 6).
+    pub ip_version: Option<u8>,
+    /// Source IP address.
+    pub src_ip: Option<IpAddr>,
+    /// Destination IP address.
+    pub dst_ip: Option<IpAddr>,
+    /// IPv4 protocol or IPv6 upper-layer next header, after extension headers.
+    pub ip_protocol: Option<u8>,
+    /// IPv4 TTL or IPv6 hop limit.
+    pub ttl: Option<u8>,
+    /// IPv4 type of service or IPv6 traffic class.
+    pub tos: Option<u8>,
+    /// TCP, UDP or SCTP source port.
+    pub src_port: Option<u16>,
+    /// TCP, UDP or SCTP destination port.
+    pub dst_port: Option<u16>,
+    /// TCP flags (the low 9 bits of the offset/flags word).
+    pub tcp_flags: Option<u16>,
+    /// ICMP or ICMPv6 type.
+    pub icmp_type: Option<u8>,
+    /// ICMP or ICMPv6 code.
+    pub icmp_code: Option<u8>,
+    /// Offset of the first byte following the innermost fully decoded header.
+    pub payload_offset: Option<usize>,
+    /// The sampled bytes ended before decoding could finish.
+    pub truncated: bool,
+}
+
+/// An 802.1Q or 802.1ad VLAN tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VlanTag {
+    /// Tag protocol identifier (0x8100, 0x88a8 or 0x9100).
+    pub tpid: u16,
+    /// Priority code point.
+    pub pcp: u8,
+    /// Drop eligible indicator.
+    pub dei: bool,
+    /// VLAN identifier.
+    pub vid: u16,
+}
+
+/// An MPLS label stack entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MplsLabel {
+    /// 20-bit label value.
+    pub label: u32,
+    /// Traffic class.
+    pub tc: u8,
+    /// Bottom-of-stack flag.
+    pub bottom_of_stack: bool,
+    /// Time to live.
+    pub ttl: u8,
+}
+
+/// Decode sampled header `bytes` captured with the given header `protocol`.
+///
+/// Supports Ethernet (with VLAN tags, LLC/SNAP, MPLS and PPPoE), PPP and POS
+/// (including Cisco HDLC), raw IPv4 and IPv6, MPLS, 802.11 MAC frames and
+/// A-MSDU subframes. Other protocols yield an empty [`DecodedHeader`].
+pub fn decode_header(protocol: HeaderProtocol, bytes: &[u8]) -> DecodedHeader {
+    let mut out = DecodedHeader::default();
+    let complete = match protocol {
+        HeaderProtocol::EthernetIso88023 => ethernet(bytes, &mut out),
+        HeaderProtocol::Ppp | HeaderProtocol::Pos => ppp(bytes, 0, &mut out),
+        HeaderProtocol::Ipv4 => ipv4(bytes, 0, &mut out),
+        HeaderProtocol::Ipv6 => ipv6(bytes, 0, &mut out),
+        HeaderProtocol::Mpls => mpls(bytes, 0, &mut out),
+        HeaderProtocol::Ieee80211Mac => ieee80211(bytes, &mut out),
+        HeaderProtocol::Ieee80211AmsduSubframe => amsdu_subframe(bytes, 0, &mut out),
+        _ => Some(()),
+    };
+    out.truncated = complete.is_none();
+    out
+}
+
+// Each layer decoder returns `None` when `b` ends before the layer does and
+// `Some(())` when decoding finished or stopped at an uninterpreted protocol.
+
+fn u8_at(b: &[u8], off: usize) -> Option<u8> {
+    b.get(off).copied()
+}
+
+fn u16_at(b: &[u8], off: usize) -> Option<u16> {
+    let s = b.get(off..off + 2)?;
+    Some(u16::from_be_bytes([s[0], s[1]]))
+}
+
+fn u32_at(b: &[u8], off: usize) -> Option<u32> {
+    let s = b.get(off..off + 4)?;
+    Some(u32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+}
+
+fn mac_at(b: &[u8], off: usize) -> Option<MacAddress> {
+    let s = b.get(off..off + 6)?;
+    let mut bytes = [0u8; 6];
+    bytes.copy_from_slice(s);
+    Some(MacAddress::new(bytes))
+}
+
+fn need(b: &[u8], end: usize) -> Option<()> {
+    (b.len() >= end).then_some(())
+}
+
+fn ethernet(b: &[u8], out: &mut DecodedHeader) -> Option<()> {
+    out.dst_mac = Some(mac_at(b, 0)?);
+    out.src_mac = Some(mac_at(b, 6)?);
+
+    let mut off = 12;
+    let mut ether_type = u16_at(b, off)?;
+    off += 2;
+    while matches!(ether_type, 0x8100 | 0x88a8 | 0x9100) {
+        let tci = u16_at(b, off)?;
+        out.vlan_tags.push(VlanTag {
+            tpid: ether_type,
+            pcp: (tci >> 13) as u8,
+            dei: tci & 0x1000 != 0,
+            vid: tci & 0x0FFF,
+        });
+        ether_type = u16_at(b, off + 2)?;
+        off += 4;
+    }
+    out.payload_offset = Some(off);
+
+    // Values up to 1500 are an 802.3 length field followed by an LLC header.
+    if ether_type <= 1500 {
+        return llc(b, off, out);
+    }
+    out.ether_type = Some(ether_type);
+    ether_payload(b, off, ether_type, out)
+}
+
+fn llc(b: &[u8], off: usize, out: &mut DecodedHeader) -> Option<()> {
+    let dsap = u8_at(b, off)?;
+    let ssap = u8_at(b, off + 1)?;
+    let control = u8_at(b, off + 2)?;
+    if dsap == 0xAA && ssap == 0xAA && control == 0x03 {
+        let ether_type = u16_at(b, off + 6)?;
+        out.ether_type = Some(ether_type);
+        out.payload_offset = Some(off + 8);
+        ether_payload(b, off + 8, ether_type, out)
+    } else {
+        out.payload_offset = Some(off + 3);
+        Some(())
+    }
+}
+
+fn ether_payload(b: &[u8], off: usize, ether_type: u16, out: &mut DecodedHeader) -> Option<()> {
+    match ether_type {
+        0x0800 => ipv4(b, off, out),
+        0x86DD => ipv6(b, off, out),
+        0x8847 | 0x8848 => mpls(b, off, out),
+        0x8864 => {
+            // PPPoE session: version/type, code, session id, length, then PPP.
+            let protocol = u16_at(b, off + 6)?;
+            out.payload_offset = Some(off + 8);
+            ppp_payload(b, off + 8, protocol, out)
+        }
+        _ => Some(()),
+    }
+}
+
+fn ppp(b: &[u8], mut off: usize, out: &mut DecodedHeader) -> Option<()> {
+    let address = u8_at(b, off)?;
+    if address == 0x0F || address == 0x8F {
+        // Cisco HDLC: address, control, EtherType.
+        let ether_type = u16_at(b, off + 2)?;
+        out.ether_type = Some(ether_type);
+        out.payload_offset = Some(off + 4);
+        return ether_payload(b, off + 4, ether_type, out);
+    }
+    if address == 0xFF && u8_at(b, off + 1)? == 0x03 {
+        off += 2;
+    }
+
+    // Protocol field compression leaves a single odd byte.
+    let first = u8_at(b, off)?;
+    let (protocol, len) = if first & 1 == 1 {
+        (u16::from(first), 1)
+    } else {
+        (u16_at(b, off)?, 2)
+    };
+    out.payload_offset = Some(off + len);
+    ppp_payload(b, off + len, protocol, out)
+}
+
+fn ppp_payload(b: &[u8], off: usize, protocol: u16, out: &mut DecodedHeader) -> Option<()> {
+    match protocol {
+        0x0021 => ipv4(b, off, out),
+        0x0057 => ipv6(b, off, out),
+        0x0281 | 0x0283 => mpls(b, off, out),
+        _ => Some(()),
+    }
+}
+
+fn mpls(b: &[u8], mut off: usize, out: &mut DecodedHeader) -> Option<()> {
+    loop {
+        let entry = u32_at(b, off)?;
+        let bottom_of_stack = entry & 0x100 != 0;
+        out.mpls_labels.push(MplsLabel {
+            label: entry >> 12,
+            tc: ((entry >> 9) & 0x7) as u8,
+            bottom_of_stack,
+            ttl: entry as u8,
+        });
+        off += 4;
+        out.payload_offset = Some(off);
+        if bottom_of_stack {
+            break;
+        }
+    }
+
+    // MPLS carries no payload type; guess from the IP version nibble.
+    match u8_at(b, off)? >> 4 {
+        4 => ipv4(b, off, out),
+        6 => ipv6(b, off, out),
+        _ => Some(()),
+    }
+}
+
+fn ipv4(b: &[u8], off: usize, out: &mut DecodedHeader) -> Option<()> {
+    let version_ihl = u8_at(b, off)?;
+    let ihl = usize::from(version_ihl & 0x0F) * 4;
+    if version_ihl >> 4 != 4 || ihl < 20 {
+        return Some(());
+    }
+    need(b, off + 20)?;
+
+    let mut src = [0u8; 4];
+    let mut dst = [0u8; 4];
+    src.copy_from_slice(&b[off + 12..off + 16]);
+    dst.copy_from_slice(&b[off + 16..off + 20]);
+    let fragment_offset = u16_at(b, off + 6)? & 0x1FFF;
+    let protocol = b[off + 9];
+
+    out.ip_version = Some(4);
+    out.tos = Some(b[off + 1]);
+    out.ttl = Some(b[off + 8]);
+    out.ip_protocol = Some(protocol);
+    out.src_ip = Some(IpAddr::V4(Ipv4Addr::from(src)));
+    out.dst_ip = Some(IpAddr::V4(Ipv4Addr::from(dst)));
+
+    need(b, off + ihl)?;
+    out.payload_offset = Some(off + ihl);
+    if fragment_offset != 0 {
+        return Some(());
+    }
+    transport(b, off + ihl, protocol, out)
+}
+
+fn ipv6(b: &[u8], off: usize, out: &mut DecodedHeader) -> Option<()> {
+    if u8_at(b, off)? >> 4 != 6 {
+        return Some(());
+    }
+    need(b, off + 40)?;
+
+    let mut src = [0u8; 16];
+    let mut dst = [0u8; 16];
+    src.copy_from_slice(&b[off + 8..off + 24]);
+    dst.copy_from_slice(&b[off + 24..off + 40]);
+    let mut next_header = b[off + 6];
+
+    out.ip_version = Some(6);
+    out.tos = Some((u16_at(b, off)? >> 4) as u8);
+    out.ttl = Some(b[off + 7]);
+    out.ip_protocol = Some(next_header);
+    out.src_ip = Some(IpAddr::V6(Ipv6Addr::from(src)));
+    out.dst_ip = Some(IpAddr::V6(Ipv6Addr::from(dst)));
+
+    let mut off = off + 40;
+    out.payload_offset = Some(off);
+    let mut first_fragment = true;
+    loop {
+        let len = match next_header {
+            // Hop-by-hop, routing and destination options.
+            0 | 43 | 60 => (usize::from(u8_at(b, off + 1)?) + 1) * 8,
+            // Fragment header.
+            44 => {
+                first_fragment = u16_at(b, off + 2)? >> 3 == 0;
+                8
+            }
+            // Authentication header.
+            51 => (usize::from(u8_at(b, off + 1)?) + 2) * 4,
+            _ => break,
+        };
+        next_header = u8_at(b, off)?;
+        need(b, off + len)?;
+        off += len;
+        out.ip_protocol = Some(next_header);
+        out.payload_offset = Some(off);
+    }
+
+    if !first_fragment {
+        return Some(());
+    }
+    transport(b, off, next_header, out)
+}
+
+fn transport(b: &[u8], off: usize, protocol: u8, out: &mut DecodedHeader) -> Option<()> {
+    match protocol {
+        // ICMP and ICMPv6.
+        1 | 58 => {
+            out.icmp_type = Some(u8_at(b, off)?);
+            out.icmp_code = Some(u8_at(b, off + 1)?);
+            need(b, off + 8)?;
+            out.payload_offset = Some(off + 8);
+        }
+        // TCP.
+        6 => {
+            out.src_port = Some(u16_at(b, off)?);
+            out.dst_port = Some(u16_at(b, off + 2)?);
+            let offset_flags = u16_at(b, off + 12)?;
+            out.tcp_flags = Some(offset_flags & 0x01FF);
+            let data_offset = usize::from(offset_flags >> 12) * 4;
+            if data_offset < 20 {
+                return Some(());
+            }
+            need(b, off + data_offset)?;
+            out.payload_offset = Some(off + data_offset);
+        }
+        // UDP.
+        17 => {
+            out.src_port = Some(u16_at(b, off)?);
+            out.dst_port = Some(u16_at(b, off + 2)?);
+            need(b, off + 8)?;
+            out.payload_offset = Some(off + 8);
+        }
+        // SCTP common header.
+        132 => {
+            out.src_port = Some(u16_at(b, off)?);
+            out.dst_port = Some(u16_at(b, off + 2)?);
+            need(b, off + 12)?;
+            out.payload_offset = Some(off + 12);
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+fn ieee80211(b: &[u8], out: &mut DecodedHeader) -> Option<()> {
+    let fc0 = u8_at(b, 0)?;
+    let fc1 = u8_at(b, 1)?;
+    let frame_type = (fc0 >> 2) & 0x3;
+    let subtype = fc0 >> 4;
+    let to_ds = fc1 & 0x01 != 0;
+    let from_ds = fc1 & 0x02 != 0;
+
+    match frame_type {
+        // Management frames: DA, SA, BSSID.
+        0 => {
+            out.dst_mac = Some(mac_at(b, 4)?);
+            out.src_mac = Some(mac_at(b, 10)?);
+            need(b, 24)?;
+            out.payload_offset = Some(24);
+            return Some(());
+        }
+        2 => {}
+        _ => return Some(()),
+    }
+
+    let (dst, src) = match (to_ds, from_ds) {
+        (false, false) => (4, 10),
+        (true, false) => (16, 10),
+        (false, true) => (4, 16),
+        (true, true) => (16, 24),
+    };
+    out.dst_mac = Some(mac_at(b, dst)?);
+    out.src_mac = Some(mac_at(b, src)?);
+
+    let mut off = if to_ds && from_ds { 30 } else { 24 };
+    let mut amsdu = false;
+    if subtype & 0x8 != 0 {
+        amsdu = u8_at(b, off)? & 0x80 != 0;
+        off += 2;
+        if fc1 & 0x80 != 0 {
+            off += 4;
+        }
+    }
+    need(b, off)?;
+    out.payload_offset = Some(off);
+
+    // Null-function subtypes carry no body; protected bodies are encrypted.
+    if subtype & 0x4 != 0 || fc1 & 0x40 != 0 {
+        return Some(());
+    }
+    if amsdu {
+        amsdu_subframe(b, off, out)
+    } else {
+        llc(b, off, out)
+    }
+}
+
+fn amsdu_subframe(b: &[u8], off: usize, out: &mut DecodedHeader) -> Option<()> {
+    out.dst_mac = Some(mac_at(b, off)?);
+    out.src_mac = Some(mac_at(b, off + 6)?);
+    need(b, off + 14)?;
+    out.payload_offset = Some(off + 14);
+    llc(b, off + 14, out)
+}
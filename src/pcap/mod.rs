@@ -0,0 +1,411 @@
+//! pcap and pcapng output of sampled packet headers.
+//!
+//! [`sampled_packets`] pulls the raw packet header records out of a datagram's
+//! flow samples and discarded packet notifications. [`PcapWriter`] writes them
+//! to a classic pcap file with a single link type; [`PcapNgWriter`] writes a
+//! pcapng file with one interface per agent, data source and link type, and
+//! attaches a comment to every packet with the agent, ifIndex and sampling
+//! rate it was reported with.
+//!
+//! The link type follows the record's `header_protocol` (see [`link_type`]);
+//! headers of protocols without a pcap link type are skipped. Packets are
+//! timestamped with the time the datagram was received, and the original
+//! length is `frame_length` minus the `stripped` bytes (usually the FCS) so
+//! it matches what a capture on the wire would report.
+//!
+//! With the `pcap` cargo feature, `PcapSource` reads sFlow datagrams back
+//! out of pcap and pcapng captures of collector traffic.
+//!
+//! ```
+//! use flowparser_sflow::SflowParser;
+//! use flowparser_sflow::pcap::PcapNgWriter;
+//! use std::time::SystemTime;
+//!
+//! let parser = SflowParser::default();
+//! let mut writer = PcapNgWriter::new(Vec::new()).unwrap();
+//!
+//! let result = parser.parse_bytes(&[/* sflow datagram bytes */]);
+//! for datagram in &result.datagrams {
+//!     writer.write_datagram(datagram, SystemTime::now()).unwrap();
+//! }
+//! let file = writer.into_inner();
+//! assert!(!file.is_empty());
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+use crate::datagram::SflowDatagram;
+use crate::flow_records::{FlowRecord, HeaderProtocol};
+use crate::samples::{DataSource, DropReason, Interface, SflowSample};
+use crate::sequence::AgentKey;
+
+#[cfg(feature = "pcap")]
+mod source;
+#[cfg(feature = "pcap")]
+pub use source::{CapturedDatagram, PcapSource, PcapSourceBuilder, SFLOW_PORT};
+
+/// IEEE 802.3 Ethernet.
+pub const LINKTYPE_ETHERNET: u16 = 1;
+/// IEEE 802.5 Token Ring.
+pub const LINKTYPE_TOKEN_RING: u16 = 6;
+/// PPP (RFC 1661).
+pub const LINKTYPE_PPP: u16 = 9;
+/// FDDI (ANSI X3.139).
+pub const LINKTYPE_FDDI: u16 = 10;
+/// PPP in HDLC-like framing (RFC 1662), used for Packet over SONET.
+pub const LINKTYPE_PPP_HDLC: u16 = 50;
+/// IEEE 802.11 wireless LAN.
+pub const LINKTYPE_IEEE802_11: u16 = 105;
+/// Frame Relay.
+pub const LINKTYPE_FRELAY: u16 = 107;
+/// MPLS label stack.
+pub const LINKTYPE_MPLS: u16 = 219;
+/// Raw IPv4.
+pub const LINKTYPE_IPV4: u16 = 228;
+/// Raw IPv6.
+pub const LINKTYPE_IPV6: u16 = 229;
+
+/// Largest captured length written to either format.
+const SNAPLEN: u32 = 262_144;
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const SECTION_HEADER_BLOCK: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 1;
+const ENHANCED_PACKET_BLOCK: u32 = 6;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const OPT_END: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const SHB_USERAPPL: u16 = 4;
+const IF_NAME: u16 = 2;
+const IF_DESCRIPTION: u16 = 3;
+
+/// The pcap link type for a raw packet header protocol, if there is one.
+pub fn link_type(protocol: HeaderProtocol) -> Option<u16> {
+    match protocol {
+        HeaderProtocol::EthernetIso88023 => Some(LINKTYPE_ETHERNET),
+        HeaderProtocol::Iso88025TokenRing => Some(LINKTYPE_TOKEN_RING),
+        HeaderProtocol::Fddi => Some(LINKTYPE_FDDI),
+        HeaderProtocol::FrameRelay => Some(LINKTYPE_FRELAY),
+        HeaderProtocol::Ppp => Some(LINKTYPE_PPP),
+        HeaderProtocol::Ipv4 => Some(LINKTYPE_IPV4),
+        HeaderProtocol::Ipv6 => Some(LINKTYPE_IPV6),
+        HeaderProtocol::Mpls => Some(LINKTYPE_MPLS),
+        HeaderProtocol::Pos => Some(LINKTYPE_PPP_HDLC),
+        HeaderProtocol::Ieee80211Mac => Some(LINKTYPE_IEEE802_11),
+        _ => None,
+    }
+}
+
+/// A sampled packet header together with the sample it was reported in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampledPacket<'a> {
+    /// Data source of the sample.
+    pub data_source: DataSource,
+    /// Interface the packet was received on.
+    pub input: Interface,
+    /// Interface the packet was sent to, or where it was discarded.
+    pub output: Interface,
+    /// Sampling rate of a flow sample; `None` for discarded packets.
+    pub sampling_rate: Option<u32>,
+    /// Drop reason of a discarded packet notification.
+    pub drop_reason: Option<DropReason>,
+    /// pcap `LINKTYPE_*` value of the captured header bytes.
+    pub link_type: u16,
+    /// Length of the packet on the wire, excluding stripped bytes.
+    pub original_length: u32,
+    /// The captured header bytes.
+    pub data: &'a [u8],
+}
+
+/// All raw packet headers with a pcap link type in `datagram`, in sample order.
+pub fn sampled_packets(datagram: &SflowDatagram) -> Vec<SampledPacket<'_>> {
+    let mut packets = Vec::new();
+    for sample in &datagram.samples {
+        let (records, data_source, input, output, sampling_rate, drop_reason) = match sample {
+            SflowSample::Discard(d) => (
+                &d.records[..],
+                d.data_source(),
+                d.input_interface(),
+                d.output_interface(),
+                None,
+                Some(d.reason),
+            ),
+            _ => match sample.normalized_flow() {
+                Some(flow) => (
+                    flow.records,
+                    flow.data_source,
+                    flow.input,
+                    flow.output,
+                    Some(flow.sampling_rate),
+                    None,
+                ),
+                None => continue,
+            },
+        };
+        for record in records {
+            let FlowRecord::RawPacketHeader(header) = record else {
+                continue;
+            };
+            let Some(link_type) = link_type(header.header_protocol.into()) else {
+                continue;
+            };
+            let len = (header.header_length as usize).min(header.header.len());
+            let data = &header.header[..len];
+            packets.push(SampledPacket {
+                data_source,
+                input,
+                output,
+                sampling_rate,
+                drop_reason,
+                link_type,
+                original_length: header
+                    .frame_length
+                    .saturating_sub(header.stripped)
+                    .max(len as u32),
+                data,
+            });
+        }
+    }
+    packets
+}
+
+/// Writes sampled packet headers of one link type to a classic pcap file.
+///
+/// Packets with any other link type are skipped; use [`PcapNgWriter`] to keep
+/// every packet.
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    out: W,
+    link_type: u16,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Write the pcap file header for `link_type` to `out`.
+    pub fn new(mut out: W, link_type: u16) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&0i32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&SNAPLEN.to_le_bytes());
+        header.extend_from_slice(&u32::from(link_type).to_le_bytes());
+        out.write_all(&header)?;
+        Ok(PcapWriter { out, link_type })
+    }
+
+    /// The link type given to [`PcapWriter::new`].
+    pub fn link_type(&self) -> u16 {
+        self.link_type
+    }
+
+    /// Write one packet record.
+    pub fn write_packet(
+        &mut self,
+        timestamp: SystemTime,
+        data: &[u8],
+        original_length: u32,
+    ) -> io::Result<()> {
+        let data = &data[..data.len().min(SNAPLEN as usize)];
+        let since_epoch = since_epoch(timestamp);
+        let mut record = Vec::with_capacity(16 + data.len());
+        record.extend_from_slice(&(since_epoch.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&since_epoch.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(&original_length.max(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(data);
+        self.out.write_all(&record)
+    }
+
+    /// Write the sampled packets of `datagram` that match this file's link
+    /// type, timestamped with `received`. Returns the number written.
+    pub fn write_datagram(
+        &mut self,
+        datagram: &SflowDatagram,
+        received: SystemTime,
+    ) -> io::Result<usize> {
+        let mut written = 0;
+        for packet in sampled_packets(datagram) {
+            if packet.link_type == self.link_type {
+                self.write_packet(received, packet.data, packet.original_length)?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Consume the writer, returning the underlying output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Interface identity within a pcapng section.
+type InterfaceKey = (AgentKey, DataSource, u16);
+
+/// Writes sampled packet headers to a pcapng file.
+///
+/// An interface description block is written the first time a combination of
+/// agent, data source and link type is seen, named `<agent>/<data source>`.
+/// Every packet carries an `opt_comment` such as
+/// `agent=10.0.0.1 sub_agent=0 source=0:3 input=3 output=9 sampling_rate=512`;
+/// discarded packets report `drop_reason` instead of the sampling rate.
+#[derive(Debug)]
+pub struct PcapNgWriter<W: Write> {
+    out: W,
+    interfaces: HashMap<InterfaceKey, u32>,
+}
+
+impl<W: Write> PcapNgWriter<W> {
+    /// Write the section header block to `out`.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // Section length not specified.
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        let userappl = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+        write_option(&mut body, SHB_USERAPPL, userappl.as_bytes());
+        write_option(&mut body, OPT_END, &[]);
+        out.write_all(&block(SECTION_HEADER_BLOCK, &body))?;
+        Ok(PcapNgWriter {
+            out,
+            interfaces: HashMap::new(),
+        })
+    }
+
+    /// Write the sampled packets of `datagram`, timestamped with `received`.
+    /// Returns the number written.
+    pub fn write_datagram(
+        &mut self,
+        datagram: &SflowDatagram,
+        received: SystemTime,
+    ) -> io::Result<usize> {
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        let micros = since_epoch(received).as_micros() as u64;
+        let packets = sampled_packets(datagram);
+        for packet in &packets {
+            let if_id = self.interface(&agent, packet)?;
+            let data = &packet.data[..packet.data.len().min(SNAPLEN as usize)];
+            let mut body = Vec::with_capacity(20 + data.len() + 128);
+            body.extend_from_slice(&if_id.to_le_bytes());
+            body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+            body.extend_from_slice(&(micros as u32).to_le_bytes());
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(&packet.original_length.to_le_bytes());
+            body.extend_from_slice(data);
+            pad(&mut body);
+            write_option(&mut body, OPT_COMMENT, comment(&agent, packet).as_bytes());
+            write_option(&mut body, OPT_END, &[]);
+            self.out.write_all(&block(ENHANCED_PACKET_BLOCK, &body))?;
+        }
+        Ok(packets.len())
+    }
+
+    /// Number of interface description blocks written so far.
+    pub fn interface_count(&self) -> usize {
+        self.interfaces.len()
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Consume the writer, returning the underlying output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// The interface ID for `packet`, writing its description block if new.
+    fn interface(&mut self, agent: &AgentKey, packet: &SampledPacket<'_>) -> io::Result<u32> {
+        let key = (agent.clone(), packet.data_source, packet.link_type);
+        if let Some(&id) = self.interfaces.get(&key) {
+            return Ok(id);
+        }
+        let id = self.interfaces.len() as u32;
+        let address = agent.agent_address.to_string();
+        let mut body = Vec::new();
+        body.extend_from_slice(&packet.link_type.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&SNAPLEN.to_le_bytes());
+        let name = format!("{}/{}", address, packet.data_source);
+        write_option(&mut body, IF_NAME, name.as_bytes());
+        let description = format!(
+            "sFlow agent {} sub-agent {} data source {}",
+            address, agent.sub_agent_id, packet.data_source
+        );
+        write_option(&mut body, IF_DESCRIPTION, description.as_bytes());
+        write_option(&mut body, OPT_END, &[]);
+        self.out
+            .write_all(&block(INTERFACE_DESCRIPTION_BLOCK, &body))?;
+        self.interfaces.insert(key, id);
+        Ok(id)
+    }
+}
+
+fn since_epoch(timestamp: SystemTime) -> Duration {
+    timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn interface_label(interface: Interface) -> String {
+    match interface {
+        Interface::Single(index) => index.to_string(),
+        Interface::Discarded(reason) => format!("discard:{reason:?}"),
+        Interface::Multiple(count) => format!("multiple:{count}"),
+        Interface::Internal => "internal".to_string(),
+        Interface::Unknown => "unknown".to_string(),
+    }
+}
+
+fn comment(agent: &AgentKey, packet: &SampledPacket<'_>) -> String {
+    let mut comment = format!(
+        "agent={} sub_agent={} source={} input={} output={}",
+        agent.agent_address,
+        agent.sub_agent_id,
+        packet.data_source,
+        interface_label(packet.input),
+        interface_label(packet.output),
+    );
+    if let Some(rate) = packet.sampling_rate {
+        comment.push_str(&format!(" sampling_rate={rate}"));
+    }
+    if let Some(reason) = packet.drop_reason {
+        comment.push_str(&format!(" drop_reason={reason:?}"));
+    }
+    comment
+}
+
+/// Frame `body` as a pcapng block with leading and trailing total lengths.
+fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let total = (12 + body.len()) as u32;
+    let mut out = Vec::with_capacity(total as usize);
+    out.extend_from_slice(&block_type.to_le_bytes());
+    out.extend_from_slice(&total.to_le_bytes());
+    out.extend_from_slice(body);
+    out.extend_from_slice(&total.to_le_bytes());
+    out
+}
+
+fn write_option(out: &mut Vec<u8>, code: u16, value: &[u8]) {
+    out.extend_from_slice(&code.to_le_bytes());
+    out.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    out.extend_from_slice(value);
+    pad(out);
+}
+
+fn pad(out: &mut Vec<u8>) {
+    out.resize(out.len().next_multiple_of(4), 0);
+}
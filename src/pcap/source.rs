@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use etherparse::defrag::IpDefragPool;
+use etherparse::{EtherType, IpNumber, NetSlice, SlicedPacket, TransportSlice, UdpSlice};
+use pcap_parser::pcapng::Block;
+use pcap_parser::traits::PcapReaderIterator;
+use pcap_parser::{PcapBlockOwned, PcapError, create_reader};
+
+use crate::{SflowDatagram, SflowParser};
+
+/// Standard sFlow collector UDP port.
+pub const SFLOW_PORT: u16 = 6343;
+
+const LINKTYPE_NULL: i32 = 0;
+const LINKTYPE_ETHERNET: i32 = 1;
+const DLT_RAW: i32 = 12;
+const DLT_RAW_OPENBSD: i32 = 14;
+const LINKTYPE_RAW: i32 = 101;
+const LINKTYPE_LOOP: i32 = 108;
+const LINKTYPE_LINUX_SLL: i32 = 113;
+const LINKTYPE_IPV4: i32 = 228;
+const LINKTYPE_IPV6: i32 = 229;
+const LINKTYPE_LINUX_SLL2: i32 = 276;
+const SLL2_HEADER_LEN: usize = 20;
+const READ_BUFFER_SIZE: usize = 65536;
+
+/// A decoded datagram with its capture timestamp and UDP source and destination.
+pub type CapturedDatagram = (SystemTime, SocketAddr, SocketAddr, SflowDatagram);
+
+/// Link type and timestamp settings of a capture interface.
+#[derive(Debug, Clone, Copy)]
+struct CaptureInterface {
+    link_type: i32,
+    /// Timestamp units per second.
+    resolution: u64,
+    offset: u64,
+}
+
+/// Reads sFlow datagrams from a pcap or pcapng capture.
+///
+/// Enabled with the `pcap` cargo feature. The file format is detected from
+/// its magic number. Ethernet (with VLAN tags), Linux cooked (SLL and SLL2),
+/// raw IP and BSD loopback link types are supported; packets on other link
+/// types are skipped. UDP packets whose destination port is one of the
+/// configured ports (6343 by default) are parsed, after reassembling IPv4 and
+/// IPv6 fragments. Packets that fail to parse as sFlow are counted in
+/// [`PcapSource::parse_errors`] and skipped.
+///
+/// The iterator yields [`CapturedDatagram`] tuples and stops after the first
+/// error reading the capture.
+pub struct PcapSource {
+    reader: Box<dyn PcapReaderIterator + Send>,
+    parser: SflowParser,
+    ports: Vec<u16>,
+    fragment_timeout: Duration,
+    interfaces: Vec<CaptureInterface>,
+    defrag: IpDefragPool<SystemTime>,
+    pending: VecDeque<CapturedDatagram>,
+    packets: u64,
+    parse_errors: u64,
+    done: bool,
+}
+
+impl PcapSource {
+    /// Create a [`PcapSourceBuilder`].
+    pub fn builder() -> PcapSourceBuilder {
+        PcapSourceBuilder {
+            parser: SflowParser::default(),
+            ports: vec![SFLOW_PORT],
+            fragment_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Read `reader` with the default settings.
+    pub fn new<R: Read + Send + 'static>(reader: R) -> io::Result<Self> {
+        Self::builder().build(reader)
+    }
+
+    /// Open the capture at `path` with the default settings.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::builder().open(path)
+    }
+
+    /// Number of captured packets read so far.
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Number of matching UDP payloads that failed to parse as sFlow.
+    pub fn parse_errors(&self) -> u64 {
+        self.parse_errors
+    }
+
+    /// Read blocks until at least one datagram is pending or the capture ends.
+    fn fill(&mut self) -> io::Result<()> {
+        while self.pending.is_empty() && !self.done {
+            match self.reader.next() {
+                Ok((offset, block)) => {
+                    let packet = match block {
+                        PcapBlockOwned::LegacyHeader(header) => {
+                            self.interfaces = vec![CaptureInterface {
+                                link_type: header.network.0,
+                                resolution: if header.is_nanosecond_precision() {
+                                    1_000_000_000
+                                } else {
+                                    1_000_000
+                                },
+                                offset: 0,
+                            }];
+                            None
+                        }
+                        PcapBlockOwned::Legacy(b) => self.interfaces.first().map(|i| {
+                            let frac = u64::from(b.ts_usec);
+                            (
+                                *i,
+                                timestamp(u64::from(b.ts_sec), frac, i.resolution),
+                                b.data,
+                            )
+                        }),
+                        PcapBlockOwned::NG(Block::SectionHeader(_)) => {
+                            self.interfaces.clear();
+                            None
+                        }
+                        PcapBlockOwned::NG(Block::InterfaceDescription(idb)) => {
+                            self.interfaces.push(CaptureInterface {
+                                link_type: idb.linktype.0,
+                                resolution: idb.ts_resolution().unwrap_or(1_000_000),
+                                offset: idb.if_tsoffset as u64,
+                            });
+                            None
+                        }
+                        PcapBlockOwned::NG(Block::EnhancedPacket(epb)) => {
+                            self.interfaces.get(epb.if_id as usize).map(|i| {
+                                let (secs, frac) = epb.decode_ts(i.offset, i.resolution);
+                                let len = (epb.caplen as usize).min(epb.data.len());
+                                let ts =
+                                    timestamp(u64::from(secs), u64::from(frac), i.resolution);
+                                (*i, ts, &epb.data[..len])
+                            })
+                        }
+                        PcapBlockOwned::NG(_) => None,
+                    };
+                    if let Some((interface, ts, data)) = packet {
+                        self.packets += 1;
+                        let datagrams = handle_packet(
+                            &self.parser,
+                            &self.ports,
+                            &mut self.defrag,
+                            &mut self.parse_errors,
+                            interface.link_type,
+                            ts,
+                            data,
+                        );
+                        self.pending.extend(datagrams);
+                        let expiry = ts.checked_sub(self.fragment_timeout);
+                        if let Some(expiry) = expiry {
+                            self.defrag.retain(|seen| *seen >= expiry);
+                        }
+                    }
+                    self.reader.consume(offset);
+                }
+                Err(PcapError::Eof) => self.done = true,
+                Err(PcapError::Incomplete(_)) => {
+                    if self.reader.reader_exhausted() {
+                        // Truncated final block.
+                        self.done = true;
+                    } else {
+                        self.reader.refill().map_err(pcap_error)?;
+                    }
+                }
+                Err(e) => return Err(pcap_error(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for PcapSource {
+    type Item = io::Result<CapturedDatagram>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            self.done = true;
+            self.pending.clear();
+            return Some(Err(e));
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl std::fmt::Debug for PcapSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PcapSource")
+            .field("ports", &self.ports)
+            .field("packets", &self.packets)
+            .field("parse_errors", &self.parse_errors)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for configuring a [`PcapSource`].
+#[derive(Debug, Clone)]
+pub struct PcapSourceBuilder {
+    parser: SflowParser,
+    ports: Vec<u16>,
+    fragment_timeout: Duration,
+}
+
+impl PcapSourceBuilder {
+    /// Parser used for UDP payloads. Defaults to [`SflowParser::default`].
+    pub fn with_parser(mut self, parser: SflowParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    /// UDP destination ports carrying sFlow. An empty list accepts every UDP
+    /// packet. Defaults to `[6343]`.
+    pub fn with_ports(mut self, ports: &[u16]) -> Self {
+        self.ports = ports.to_vec();
+        self
+    }
+
+    /// Capture time after which incomplete fragmented packets are dropped.
+    /// Defaults to 30 seconds.
+    pub fn with_fragment_timeout(mut self, timeout: Duration) -> Self {
+        self.fragment_timeout = timeout;
+        self
+    }
+
+    /// Build a [`PcapSource`] reading from `reader`.
+    pub fn build<R: Read + Send + 'static>(self, reader: R) -> io::Result<PcapSource> {
+        let reader = create_reader(READ_BUFFER_SIZE, reader).map_err(pcap_error)?;
+        Ok(PcapSource {
+            reader,
+            parser: self.parser,
+            ports: self.ports,
+            fragment_timeout: self.fragment_timeout,
+            interfaces: Vec::new(),
+            defrag: IpDefragPool::new(),
+            pending: VecDeque::new(),
+            packets: 0,
+            parse_errors: 0,
+            done: false,
+        })
+    }
+
+    /// Build a [`PcapSource`] reading the capture at `path`.
+    pub fn open(self, path: impl AsRef<Path>) -> io::Result<PcapSource> {
+        self.build(BufReader::new(File::open(path)?))
+    }
+}
+
+fn pcap_error<I: std::fmt::Debug>(e: PcapError<I>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{e:?}"))
+}
+
+fn timestamp(secs: u64, frac: u64, resolution: u64) -> SystemTime {
+    let nanos = u128::from(frac) * 1_000_000_000 / u128::from(resolution.max(1));
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_nanos(nanos as u64)
+}
+
+/// Strip the link layer of `data` according to `link_type`.
+fn slice(link_type: i32, data: &[u8]) -> Option<SlicedPacket<'_>> {
+    match link_type {
+        LINKTYPE_ETHERNET => SlicedPacket::from_ethernet(data).ok(),
+        LINKTYPE_LINUX_SLL => SlicedPacket::from_linux_sll(data).ok(),
+        LINKTYPE_LINUX_SLL2 if data.len() >= SLL2_HEADER_LEN => {
+            let ether_type = EtherType(u16::from_be_bytes([data[0], data[1]]));
+            SlicedPacket::from_ether_type(ether_type, &data[SLL2_HEADER_LEN..]).ok()
+        }
+        DLT_RAW | DLT_RAW_OPENBSD | LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => {
+            SlicedPacket::from_ip(data).ok()
+        }
+        LINKTYPE_NULL | LINKTYPE_LOOP if data.len() >= 4 => {
+            SlicedPacket::from_ip(&data[4..]).ok()
+        }
+        _ => None,
+    }
+}
+
+fn handle_packet(
+    parser: &SflowParser,
+    ports: &[u16],
+    defrag: &mut IpDefragPool<SystemTime>,
+    parse_errors: &mut u64,
+    link_type: i32,
+    ts: SystemTime,
+    data: &[u8],
+) -> Vec<CapturedDatagram> {
+    let Some(sliced) = slice(link_type, data) else {
+        return Vec::new();
+    };
+    let (src_ip, dst_ip, fragmented) = match &sliced.net {
+        Some(NetSlice::Ipv4(ip)) => (
+            IpAddr::V4(ip.header().source_addr()),
+            IpAddr::V4(ip.header().destination_addr()),
+            ip.payload().fragmented,
+        ),
+        Some(NetSlice::Ipv6(ip)) => (
+            IpAddr::V6(ip.header().source_addr()),
+            IpAddr::V6(ip.header().destination_addr()),
+            ip.payload().fragmented,
+        ),
+        _ => return Vec::new(),
+    };
+
+    let parse = |udp: &UdpSlice<'_>, parse_errors: &mut u64| {
+        if !ports.is_empty() && !ports.contains(&udp.destination_port()) {
+            return Vec::new();
+        }
+        let src = SocketAddr::new(src_ip, udp.source_port());
+        let dst = SocketAddr::new(dst_ip, udp.destination_port());
+        let result = parser.parse_bytes(udp.payload());
+        if result.error.is_some() {
+            *parse_errors += 1;
+        }
+        result
+            .datagrams
+            .into_iter()
+            .map(|datagram| (ts, src, dst, datagram))
+            .collect()
+    };
+
+    if fragmented {
+        let Ok(Some(payload)) = defrag.process_sliced_packet(&sliced, ts, ()) else {
+            return Vec::new();
+        };
+        let datagrams = match UdpSlice::from_slice(&payload.payload) {
+            Ok(udp) if payload.ip_number == IpNumber::UDP => parse(&udp, parse_errors),
+            _ => Vec::new(),
+        };
+        defrag.return_buf(payload);
+        datagrams
+    } else if let Some(TransportSlice::Udp(udp)) = &sliced.transport {
+        parse(udp, parse_errors)
+    } else {
+        Vec::new()
+    }
+}
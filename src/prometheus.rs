@@ -0,0 +1,513 @@
+//! Prometheus and OpenMetrics exposition of counter samples.
+//!
+//! [`MetricsExporter`] keeps the latest value of every numeric field of every
+//! counter record it has seen and renders them in the Prometheus text format
+//! or OpenMetrics. Each field becomes the metric
+//! `sflow_<record_type>_<field>`, where `record_type` is the snake_case
+//! [`CounterRecord`] variant name (`generic_interface`, `host_cpu`,
+//! `jvm_statistics`, ..., or `custom_<enterprise>_<format>` for records
+//! decoded by a registered decoder). Cumulative fields (see
+//! [`CounterRecord::cumulative_counters`], plus CPU times, paging, HTTP,
+//! memcache, application and other event counts) are typed `counter` and get
+//! a `_total` suffix; all other numbers are `gauge`s. Strings, addresses and
+//! lists are not exported. Values are exported as reported, in the units of
+//! the sFlow structures; a 32-bit counter wrap appears to Prometheus as a
+//! counter reset.
+//!
+//! Every series is labelled with `agent` and `source` (the data source as
+//! `type:index`). Interface data sources also get `ifindex`, and `ifname`
+//! once a port name record has been seen for that source. Queue length
+//! records are labelled with `queue`. `sflow_agent_last_seen_timestamp_seconds`
+//! reports when each agent last sent a datagram.
+//!
+//! A series that is not updated for the staleness period (5 minutes by
+//! default) is dropped by [`MetricsExporter::expire`], so agents that stop
+//! reporting, and interfaces they stop reporting, disappear from the output.
+//!
+//! ```
+//! use flowparser_sflow::prometheus::{ExpositionFormat, MetricsExporter};
+//! use flowparser_sflow::SflowParser;
+//! use std::time::{Duration, SystemTime};
+//!
+//! let parser = SflowParser::default();
+//! let mut exporter = MetricsExporter::builder()
+//!     .with_stale_after(Duration::from_secs(120))
+//!     .build();
+//! let now = SystemTime::now();
+//! for datagram in &parser.parse_bytes(&[/* sflow datagram bytes */]).datagrams {
+//!     exporter.observe(datagram, now);
+//! }
+//! exporter.expire(now);
+//! let body = exporter.render(ExpositionFormat::Prometheus);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+
+use crate::counter_records::CounterRecord;
+use crate::format::unwrap_variant;
+use crate::jsonl::counter_variant;
+use crate::samples::DataSource;
+use crate::{AddressType, SflowDatagram};
+
+/// `Content-Type` of [`ExpositionFormat::Prometheus`] output.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// `Content-Type` of [`ExpositionFormat::OpenMetrics`] output.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Cumulative fields of records not covered by
+/// [`CounterRecord::cumulative_counters`].
+const COUNTER_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "host_cpu",
+        &[
+            "cpu_user",
+            "cpu_nice",
+            "cpu_system",
+            "cpu_idle",
+            "cpu_wio",
+            "cpu_intr",
+            "cpu_sintr",
+            "interrupts",
+            "contexts",
+        ],
+    ),
+    (
+        "host_memory",
+        &["page_in", "page_out", "swap_in", "swap_out"],
+    ),
+    ("virt_cpu", &["cpu_time"]),
+    (
+        "jvm_statistics",
+        &[
+            "gc_count",
+            "gc_time",
+            "classes_total",
+            "classes_unloaded",
+            "compilation_time",
+            "threads_started",
+        ],
+    ),
+    (
+        "http_counters",
+        &[
+            "method_option_count",
+            "method_get_count",
+            "method_head_count",
+            "method_post_count",
+            "method_put_count",
+            "method_delete_count",
+            "method_trace_count",
+            "method_connect_count",
+            "method_other_count",
+            "status_1xx_count",
+            "status_2xx_count",
+            "status_3xx_count",
+            "status_4xx_count",
+            "status_5xx_count",
+            "status_other_count",
+        ],
+    ),
+    (
+        "app_operations",
+        &[
+            "success",
+            "other",
+            "timeout",
+            "internal_error",
+            "bad_request",
+            "forbidden",
+            "too_large",
+            "not_implemented",
+            "not_found",
+            "unavailable",
+            "unauthorized",
+            "status_ok",
+        ],
+    ),
+    ("app_resources", &["user_time", "system_time"]),
+    ("app_workers", &["req_delayed", "req_dropped"]),
+    (
+        "memcache_counters",
+        &[
+            "cmd_set",
+            "cmd_touch",
+            "cmd_flush",
+            "get_hits",
+            "get_misses",
+            "delete_hits",
+            "delete_misses",
+            "incr_hits",
+            "incr_misses",
+            "decr_hits",
+            "decr_misses",
+            "cas_hits",
+            "cas_misses",
+            "cas_badval",
+            "auth_cmds",
+            "auth_errors",
+            "conn_yields",
+            "listen_disabled_num",
+            "rejected_connections",
+            "total_connections",
+            "evictions",
+            "reclaimed",
+            "total_items",
+            "bytes_read",
+            "bytes_written",
+        ],
+    ),
+    (
+        "ovs_dp_stats",
+        &["n_hit", "n_missed", "n_lost", "n_mask_hit"],
+    ),
+    (
+        "lag_port_stats",
+        &[
+            "lacpdu_rx",
+            "marker_pdu_rx",
+            "marker_response_pdu_rx",
+            "unknown_rx",
+            "illegal_rx",
+            "lacpdu_tx",
+            "marker_pdu_tx",
+            "marker_response_pdu_tx",
+        ],
+    ),
+    (
+        "slow_path_counts",
+        &[
+            "unknown",
+            "other",
+            "cam_miss",
+            "cam_full",
+            "no_hw_support",
+            "cntrl",
+        ],
+    ),
+    (
+        "ib_counters",
+        &[
+            "port_xmit_data",
+            "port_rcv_data",
+            "port_xmit_pkts",
+            "port_rcv_pkts",
+            "symbol_error_counter",
+            "link_error_recovery_counter",
+            "link_downed_counter",
+            "port_rcv_errors",
+            "port_rcv_remote_physical_errors",
+            "port_rcv_switch_relay_errors",
+            "port_xmit_discards",
+            "port_xmit_constraint_errors",
+            "port_rcv_constraint_errors",
+            "local_link_integrity_errors",
+            "excessive_buffer_overrun_errors",
+            "vl15_dropped",
+        ],
+    ),
+    (
+        "queue_length",
+        &[
+            "queue_length_0",
+            "queue_length_1",
+            "queue_length_2",
+            "queue_length_4",
+            "queue_length_8",
+            "queue_length_32",
+            "queue_length_128",
+            "queue_length_1024",
+            "queue_length_more",
+            "dropped",
+        ],
+    ),
+    ("energy", &["energy", "errors"]),
+    ("temperature", &["errors"]),
+    (
+        "radio_utilization",
+        &["elapsed_time", "on_channel_time", "on_channel_busy_time"],
+    ),
+];
+
+/// Fields exported as labels instead of values, with their label names.
+const LABEL_FIELDS: &[(&str, &str, &str)] = &[
+    ("generic_interface", "if_index", "ifindex"),
+    ("queue_length", "queue_index", "queue"),
+];
+
+/// Output syntax of [`MetricsExporter::render`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpositionFormat {
+    /// Prometheus text format 0.0.4.
+    #[default]
+    Prometheus,
+    /// OpenMetrics 1.0 text format, terminated by `# EOF`.
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    /// HTTP `Content-Type` of this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExpositionFormat::Prometheus => PROMETHEUS_CONTENT_TYPE,
+            ExpositionFormat::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Prometheus metric type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// Identifies one series: metric family name and rendered label set.
+type SeriesKey = (String, String);
+
+#[derive(Debug, Clone)]
+struct Series {
+    kind: MetricKind,
+    value: String,
+    agent: AddressType,
+    source: DataSource,
+    last_seen: SystemTime,
+}
+
+/// Latest counter values per agent and data source, rendered as metrics.
+#[derive(Debug, Clone)]
+pub struct MetricsExporter {
+    stale_after: Duration,
+    series: BTreeMap<SeriesKey, Series>,
+    port_names: HashMap<(AddressType, DataSource), String>,
+    agents: HashMap<AddressType, SystemTime>,
+}
+
+impl Default for MetricsExporter {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl MetricsExporter {
+    /// Create a [`MetricsExporterBuilder`].
+    pub fn builder() -> MetricsExporterBuilder {
+        MetricsExporterBuilder {
+            stale_after: Duration::from_secs(300),
+        }
+    }
+
+    /// Update the series of every counter record in `datagram`, received at
+    /// `now`.
+    pub fn observe(&mut self, datagram: &SflowDatagram, now: SystemTime) {
+        let agent = &datagram.agent_address;
+        self.agents.insert(agent.clone(), now);
+        for sample in &datagram.samples {
+            let Some(counters) = sample.normalized_counter() else {
+                continue;
+            };
+            let source = counters.data_source;
+            for record in counters.records {
+                if let CounterRecord::PortName(p) = record {
+                    let key = (agent.clone(), source);
+                    if self.port_names.get(&key) != Some(&p.name) {
+                        // Series labelled with the previous name are replaced.
+                        self.series
+                            .retain(|_, s| s.agent != *agent || s.source != source);
+                        self.port_names.insert(key, p.name.clone());
+                    }
+                }
+            }
+            let mut labels = vec![("agent", agent.to_string()), ("source", source.to_string())];
+            if let DataSource::IfIndex(index) = source {
+                labels.push(("ifindex", index.to_string()));
+                if let Some(name) = self.port_names.get(&(agent.clone(), source)) {
+                    labels.push(("ifname", name.clone()));
+                }
+            }
+            for record in counters.records {
+                self.observe_record(agent, source, &labels, record, now);
+            }
+        }
+    }
+
+    /// Drop series not updated within the staleness period before `now`.
+    pub fn expire(&mut self, now: SystemTime) {
+        let Some(cutoff) = now.checked_sub(self.stale_after) else {
+            return;
+        };
+        self.series.retain(|_, s| s.last_seen >= cutoff);
+        self.agents.retain(|_, seen| *seen >= cutoff);
+        self.port_names
+            .retain(|(agent, _), _| self.agents.contains_key(agent));
+    }
+
+    /// Number of series currently exported, excluding per-agent metrics.
+    pub fn series_count(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Render every series in `format`.
+    pub fn render(&self, format: ExpositionFormat) -> String {
+        let mut out = String::new();
+        let mut family: Option<&str> = None;
+        for ((name, labels), series) in &self.series {
+            if family != Some(name.as_str()) {
+                type_line(&mut out, format, name, series.kind);
+                family = Some(name);
+            }
+            let suffix = match series.kind {
+                MetricKind::Counter => "_total",
+                MetricKind::Gauge => "",
+            };
+            let _ = writeln!(out, "{name}{suffix}{{{labels}}} {}", series.value);
+        }
+
+        let mut agents: Vec<_> = self.agents.iter().collect();
+        agents.sort_by_key(|(agent, _)| agent.to_string());
+        if !agents.is_empty() {
+            let name = "sflow_agent_last_seen_timestamp_seconds";
+            type_line(&mut out, format, name, MetricKind::Gauge);
+            for (agent, seen) in agents {
+                let secs = seen
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64();
+                let labels = render_labels(&[("agent", agent.to_string())]);
+                let _ = writeln!(out, "{name}{{{labels}}} {secs:.3}");
+            }
+        }
+        if format == ExpositionFormat::OpenMetrics {
+            out.push_str("# EOF\n");
+        }
+        out
+    }
+
+    /// Remove every series of `agent`.
+    pub fn remove_agent(&mut self, agent: &AddressType) {
+        self.series.retain(|_, s| s.agent != *agent);
+        self.agents.remove(agent);
+        self.port_names.retain(|(a, _), _| a != agent);
+    }
+
+    fn observe_record(
+        &mut self,
+        agent: &AddressType,
+        source: DataSource,
+        labels: &[(&'static str, String)],
+        record: &CounterRecord,
+        now: SystemTime,
+    ) {
+        let (record_type, fields) = counter_variant(record);
+        let Value::Object(fields) = fields else {
+            return;
+        };
+        let cumulative = record.cumulative_counters();
+        let extra = COUNTER_FIELDS
+            .iter()
+            .find(|(name, _)| *name == record_type)
+            .map_or(&[][..], |(_, fields)| *fields);
+
+        let mut labels = labels.to_vec();
+        for (name, field, label) in LABEL_FIELDS {
+            if *name == record_type
+                && let Some(value) = fields.get(*field)
+                && !labels.iter().any(|(l, _)| l == label)
+            {
+                labels.push((*label, value.to_string()));
+            }
+        }
+        let labels = render_labels(&labels);
+
+        for (field, value) in &fields {
+            if LABEL_FIELDS
+                .iter()
+                .any(|(name, f, _)| *name == record_type && f == field)
+            {
+                continue;
+            }
+            let Value::Number(number) = unwrap_variant(value) else {
+                continue;
+            };
+            let kind = if cumulative.iter().any(|(f, _)| f == field)
+                || extra.contains(&field.as_str())
+            {
+                MetricKind::Counter
+            } else {
+                MetricKind::Gauge
+            };
+            self.series.insert(
+                (format!("sflow_{record_type}_{field}"), labels.clone()),
+                Series {
+                    kind,
+                    value: number.to_string(),
+                    agent: agent.clone(),
+                    source,
+                    last_seen: now,
+                },
+            );
+        }
+    }
+}
+
+/// Builder for configuring a [`MetricsExporter`].
+#[derive(Debug, Clone)]
+pub struct MetricsExporterBuilder {
+    stale_after: Duration,
+}
+
+impl MetricsExporterBuilder {
+    /// Drop series that have not been updated for `stale_after`. Defaults to
+    /// 5 minutes.
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Build the configured [`MetricsExporter`].
+    pub fn build(self) -> MetricsExporter {
+        MetricsExporter {
+            stale_after: self.stale_after,
+            series: BTreeMap::new(),
+            port_names: HashMap::new(),
+            agents: HashMap::new(),
+        }
+    }
+}
+
+fn type_line(out: &mut String, format: ExpositionFormat, name: &str, kind: MetricKind) {
+    // Prometheus 0.0.4 names the family after the sample, OpenMetrics without
+    // the `_total` suffix.
+    let (family, kind) = match (format, kind) {
+        (ExpositionFormat::Prometheus, MetricKind::Counter) => {
+            (format!("{name}_total"), "counter")
+        }
+        (ExpositionFormat::OpenMetrics, MetricKind::Counter) => (name.to_string(), "counter"),
+        (_, MetricKind::Gauge) => (name.to_string(), "gauge"),
+    };
+    let _ = writeln!(out, "# TYPE {family} {kind}");
+}
+
+fn render_labels(labels: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    for (i, (name, value)) in labels.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{name}=\"");
+        for c in value.chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                '\n' => out.push_str("\\n"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+    out
+}
@@ -0,0 +1,944 @@
+//! Per-second deltas and rates from cumulative counter records.
+//!
+//! Most counter records hold cumulative totals, some 32 bits wide and some 64.
+//! [`CounterEngine`] remembers the previous value of every cumulative field per
+//! agent, [`DataSource`] and record type, and turns each new counter sample
+//! into deltas over the agent's uptime clock. 32-bit fields that decrease by
+//! less than half the counter range are treated as having wrapped; rates
+//! above the configured maximum are flagged as implausible.
+//!
+//! Deltas are never computed across an agent restart: when the datagram
+//! uptime goes backwards without wrapping, the interval exceeds the
+//! configured maximum, or a counter decreases by more than a 32-bit wrap
+//! explains, the new values become the baseline instead. Feed
+//! [`AgentEvent::AgentRestarted`](crate::agent::AgentEvent::AgentRestarted)
+//! events to [`CounterEngine::reset_agent`] to be certain.
+//!
+//! For generic interface counters, [`InterfaceMetrics`] turns the deltas into
+//! utilization, packet rates and error/discard ratios.
+//!
+//! ```
+//! use flowparser_sflow::rates::{CounterEngine, CounterUpdate};
+//! use flowparser_sflow::SflowParser;
+//!
+//! let parser = SflowParser::default();
+//! let mut engine = CounterEngine::default();
+//!
+//! let result = parser.parse_bytes(&[/* sflow datagram bytes */]);
+//! for datagram in &result.datagrams {
+//!     for (key, update) in engine.observe(datagram) {
+//!         if let CounterUpdate::Rates(rates) = update {
+//!             if let Some(octets) = rates.get("if_in_octets") {
+//!                 println!("{} in: {:.0} bit/s", key.data_source, octets.rate * 8.0);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::counter_records::{CounterRecord, GenericInterface, IfDirection};
+use crate::datagram::{AddressType, SflowDatagram};
+use crate::samples::DataSource;
+use crate::sequence::AgentKey;
+
+/// Value of a cumulative counter field, keeping its on-the-wire width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterValue {
+    U32(u32),
+    U64(u64),
+}
+
+impl CounterValue {
+    /// The value widened to 64 bits.
+    pub fn get(self) -> u64 {
+        match self {
+            CounterValue::U32(v) => u64::from(v),
+            CounterValue::U64(v) => v,
+        }
+    }
+}
+
+impl CounterRecord {
+    /// The cumulative counter fields of this record, by field name.
+    ///
+    /// Gauges and configuration fields such as `if_speed`, `if_status` or
+    /// `tcp_curr_estab` are excluded. Records without cumulative counters
+    /// return an empty list.
+    pub fn cumulative_counters(&self) -> Vec<(&'static str, CounterValue)> {
+        match self {
+            CounterRecord::GenericInterface(r) => vec![
+                ("if_in_octets", CounterValue::U64(r.if_in_octets)),
+                ("if_in_ucast_pkts", CounterValue::U32(r.if_in_ucast_pkts)),
+                (
+                    "if_in_multicast_pkts",
+                    CounterValue::U32(r.if_in_multicast_pkts),
+                ),
+                (
+                    "if_in_broadcast_pkts",
+                    CounterValue::U32(r.if_in_broadcast_pkts),
+                ),
+                ("if_in_discards", CounterValue::U32(r.if_in_discards)),
+                ("if_in_errors", CounterValue::U32(r.if_in_errors)),
+                (
+                    "if_in_unknown_protos",
+                    CounterValue::U32(r.if_in_unknown_protos),
+                ),
+                ("if_out_octets", CounterValue::U64(r.if_out_octets)),
+                ("if_out_ucast_pkts", CounterValue::U32(r.if_out_ucast_pkts)),
+                (
+                    "if_out_multicast_pkts",
+                    CounterValue::U32(r.if_out_multicast_pkts),
+                ),
+                (
+                    "if_out_broadcast_pkts",
+                    CounterValue::U32(r.if_out_broadcast_pkts),
+                ),
+                ("if_out_discards", CounterValue::U32(r.if_out_discards)),
+                ("if_out_errors", CounterValue::U32(r.if_out_errors)),
+            ],
+            CounterRecord::EthernetInterface(r) => vec![
+                (
+                    "dot3_stats_alignment_errors",
+                    CounterValue::U32(r.dot3_stats_alignment_errors),
+                ),
+                (
+                    "dot3_stats_fcs_errors",
+                    CounterValue::U32(r.dot3_stats_fcs_errors),
+                ),
+                (
+                    "dot3_stats_single_collision_frames",
+                    CounterValue::U32(r.dot3_stats_single_collision_frames),
+                ),
+                (
+                    "dot3_stats_multiple_collision_frames",
+                    CounterValue::U32(r.dot3_stats_multiple_collision_frames),
+                ),
+                (
+                    "dot3_stats_sqe_test_errors",
+                    CounterValue::U32(r.dot3_stats_sqe_test_errors),
+                ),
+                (
+                    "dot3_stats_deferred_transmissions",
+                    CounterValue::U32(r.dot3_stats_deferred_transmissions),
+                ),
+                (
+                    "dot3_stats_late_collisions",
+                    CounterValue::U32(r.dot3_stats_late_collisions),
+                ),
+                (
+                    "dot3_stats_excessive_collisions",
+                    CounterValue::U32(r.dot3_stats_excessive_collisions),
+                ),
+                (
+                    "dot3_stats_internal_mac_transmit_errors",
+                    CounterValue::U32(r.dot3_stats_internal_mac_transmit_errors),
+                ),
+                (
+                    "dot3_stats_carrier_sense_errors",
+                    CounterValue::U32(r.dot3_stats_carrier_sense_errors),
+                ),
+                (
+                    "dot3_stats_frame_too_longs",
+                    CounterValue::U32(r.dot3_stats_frame_too_longs),
+                ),
+                (
+                    "dot3_stats_internal_mac_receive_errors",
+                    CounterValue::U32(r.dot3_stats_internal_mac_receive_errors),
+                ),
+                (
+                    "dot3_stats_symbol_errors",
+                    CounterValue::U32(r.dot3_stats_symbol_errors),
+                ),
+            ],
+            CounterRecord::TokenRing(r) => vec![
+                (
+                    "dot5_stats_line_errors",
+                    CounterValue::U32(r.dot5_stats_line_errors),
+                ),
+                (
+                    "dot5_stats_burst_errors",
+                    CounterValue::U32(r.dot5_stats_burst_errors),
+                ),
+                (
+                    "dot5_stats_ac_errors",
+                    CounterValue::U32(r.dot5_stats_ac_errors),
+                ),
+                (
+                    "dot5_stats_abort_trans_errors",
+                    CounterValue::U32(r.dot5_stats_abort_trans_errors),
+                ),
+                (
+                    "dot5_stats_internal_errors",
+                    CounterValue::U32(r.dot5_stats_internal_errors),
+                ),
+                (
+                    "dot5_stats_lost_frame_errors",
+                    CounterValue::U32(r.dot5_stats_lost_frame_errors),
+                ),
+                (
+                    "dot5_stats_receive_congestions",
+                    CounterValue::U32(r.dot5_stats_receive_congestions),
+                ),
+                (
+                    "dot5_stats_frame_copied_errors",
+                    CounterValue::U32(r.dot5_stats_frame_copied_errors),
+                ),
+                (
+                    "dot5_stats_token_errors",
+                    CounterValue::U32(r.dot5_stats_token_errors),
+                ),
+                (
+                    "dot5_stats_soft_errors",
+                    CounterValue::U32(r.dot5_stats_soft_errors),
+                ),
+                (
+                    "dot5_stats_hard_errors",
+                    CounterValue::U32(r.dot5_stats_hard_errors),
+                ),
+                (
+                    "dot5_stats_signal_loss",
+                    CounterValue::U32(r.dot5_stats_signal_loss),
+                ),
+                (
+                    "dot5_stats_transmit_beacons",
+                    CounterValue::U32(r.dot5_stats_transmit_beacons),
+                ),
+                (
+                    "dot5_stats_recoverys",
+                    CounterValue::U32(r.dot5_stats_recoverys),
+                ),
+                (
+                    "dot5_stats_lobe_wires",
+                    CounterValue::U32(r.dot5_stats_lobe_wires),
+                ),
+                (
+                    "dot5_stats_removes",
+                    CounterValue::U32(r.dot5_stats_removes),
+                ),
+                (
+                    "dot5_stats_singles",
+                    CounterValue::U32(r.dot5_stats_singles),
+                ),
+                (
+                    "dot5_stats_freq_errors",
+                    CounterValue::U32(r.dot5_stats_freq_errors),
+                ),
+            ],
+            CounterRecord::VgCounters(r) => vec![
+                (
+                    "in_high_priority_frames",
+                    CounterValue::U32(r.in_high_priority_frames),
+                ),
+                (
+                    "in_high_priority_octets",
+                    CounterValue::U64(r.in_high_priority_octets),
+                ),
+                (
+                    "in_norm_priority_frames",
+                    CounterValue::U32(r.in_norm_priority_frames),
+                ),
+                (
+                    "in_norm_priority_octets",
+                    CounterValue::U64(r.in_norm_priority_octets),
+                ),
+                ("in_ipm_errors", CounterValue::U32(r.in_ipm_errors)),
+                (
+                    "in_oversize_frame_errors",
+                    CounterValue::U32(r.in_oversize_frame_errors),
+                ),
+                ("in_data_errors", CounterValue::U32(r.in_data_errors)),
+                (
+                    "in_null_addressed_frames",
+                    CounterValue::U32(r.in_null_addressed_frames),
+                ),
+                (
+                    "out_high_priority_frames",
+                    CounterValue::U32(r.out_high_priority_frames),
+                ),
+                (
+                    "out_high_priority_octets",
+                    CounterValue::U64(r.out_high_priority_octets),
+                ),
+                (
+                    "out_norm_priority_frames",
+                    CounterValue::U32(r.out_norm_priority_frames),
+                ),
+                (
+                    "out_norm_priority_octets",
+                    CounterValue::U64(r.out_norm_priority_octets),
+                ),
+                (
+                    "in_hc_high_priority_octets",
+                    CounterValue::U64(r.in_hc_high_priority_octets),
+                ),
+                (
+                    "in_hc_norm_priority_octets",
+                    CounterValue::U64(r.in_hc_norm_priority_octets),
+                ),
+                (
+                    "out_hc_high_priority_octets",
+                    CounterValue::U64(r.out_hc_high_priority_octets),
+                ),
+                (
+                    "out_hc_norm_priority_octets",
+                    CounterValue::U64(r.out_hc_norm_priority_octets),
+                ),
+            ],
+            CounterRecord::Vlan(r) => vec![
+                ("octets", CounterValue::U64(r.octets)),
+                ("ucast_pkts", CounterValue::U32(r.ucast_pkts)),
+                ("multicast_pkts", CounterValue::U32(r.multicast_pkts)),
+                ("broadcast_pkts", CounterValue::U32(r.broadcast_pkts)),
+                ("discards", CounterValue::U32(r.discards)),
+            ],
+            CounterRecord::Ieee80211Counters(r) => vec![
+                (
+                    "transmitted_fragments",
+                    CounterValue::U32(r.transmitted_fragments),
+                ),
+                (
+                    "multicast_transmitted_frames",
+                    CounterValue::U32(r.multicast_transmitted_frames),
+                ),
+                ("failures", CounterValue::U32(r.failures)),
+                ("retries", CounterValue::U32(r.retries)),
+                ("multiple_retries", CounterValue::U32(r.multiple_retries)),
+                ("frame_duplicates", CounterValue::U32(r.frame_duplicates)),
+                ("rts_successes", CounterValue::U32(r.rts_successes)),
+                ("rts_failures", CounterValue::U32(r.rts_failures)),
+                ("ack_failures", CounterValue::U32(r.ack_failures)),
+                (
+                    "received_fragments",
+                    CounterValue::U32(r.received_fragments),
+                ),
+                (
+                    "multicast_received_frames",
+                    CounterValue::U32(r.multicast_received_frames),
+                ),
+                ("fcs_errors", CounterValue::U32(r.fcs_errors)),
+                (
+                    "transmitted_frames",
+                    CounterValue::U32(r.transmitted_frames),
+                ),
+                (
+                    "wep_undecryptables",
+                    CounterValue::U32(r.wep_undecryptables),
+                ),
+                (
+                    "qos_discarded_fragments",
+                    CounterValue::U32(r.qos_discarded_fragments),
+                ),
+                (
+                    "qos_cf_polls_received",
+                    CounterValue::U32(r.qos_cf_polls_received),
+                ),
+                (
+                    "qos_cf_polls_unused",
+                    CounterValue::U32(r.qos_cf_polls_unused),
+                ),
+                (
+                    "qos_cf_polls_unusable",
+                    CounterValue::U32(r.qos_cf_polls_unusable),
+                ),
+                ("qos_cf_polls_lost", CounterValue::U32(r.qos_cf_polls_lost)),
+            ],
+            CounterRecord::HostDiskIo(r) => vec![
+                ("reads", CounterValue::U32(r.reads)),
+                ("bytes_read", CounterValue::U64(r.bytes_read)),
+                ("read_time", CounterValue::U32(r.read_time)),
+                ("writes", CounterValue::U32(r.writes)),
+                ("bytes_written", CounterValue::U64(r.bytes_written)),
+                ("write_time", CounterValue::U32(r.write_time)),
+            ],
+            CounterRecord::HostNetIo(r) => vec![
+                ("bytes_in", CounterValue::U64(r.bytes_in)),
+                ("packets_in", CounterValue::U32(r.packets_in)),
+                ("errs_in", CounterValue::U32(r.errs_in)),
+                ("drops_in", CounterValue::U32(r.drops_in)),
+                ("bytes_out", CounterValue::U64(r.bytes_out)),
+                ("packets_out", CounterValue::U32(r.packets_out)),
+                ("errs_out", CounterValue::U32(r.errs_out)),
+                ("drops_out", CounterValue::U32(r.drops_out)),
+            ],
+            CounterRecord::VirtDiskIo(r) => vec![
+                ("rd_req", CounterValue::U32(r.rd_req)),
+                ("rd_bytes", CounterValue::U64(r.rd_bytes)),
+                ("wr_req", CounterValue::U32(r.wr_req)),
+                ("wr_bytes", CounterValue::U64(r.wr_bytes)),
+                ("errs", CounterValue::U32(r.errs)),
+            ],
+            CounterRecord::VirtNetIo(r) => vec![
+                ("rx_bytes", CounterValue::U64(r.rx_bytes)),
+                ("rx_packets", CounterValue::U32(r.rx_packets)),
+                ("rx_errs", CounterValue::U32(r.rx_errs)),
+                ("rx_drop", CounterValue::U32(r.rx_drop)),
+                ("tx_bytes", CounterValue::U64(r.tx_bytes)),
+                ("tx_packets", CounterValue::U32(r.tx_packets)),
+                ("tx_errs", CounterValue::U32(r.tx_errs)),
+                ("tx_drop", CounterValue::U32(r.tx_drop)),
+            ],
+            CounterRecord::Mib2IpGroup(r) => vec![
+                ("ip_in_receives", CounterValue::U32(r.ip_in_receives)),
+                ("ip_in_hdr_errors", CounterValue::U32(r.ip_in_hdr_errors)),
+                ("ip_in_addr_errors", CounterValue::U32(r.ip_in_addr_errors)),
+                ("ip_forw_datagrams", CounterValue::U32(r.ip_forw_datagrams)),
+                (
+                    "ip_in_unknown_protos",
+                    CounterValue::U32(r.ip_in_unknown_protos),
+                ),
+                ("ip_in_discards", CounterValue::U32(r.ip_in_discards)),
+                ("ip_in_delivers", CounterValue::U32(r.ip_in_delivers)),
+                ("ip_out_requests", CounterValue::U32(r.ip_out_requests)),
+                ("ip_out_discards", CounterValue::U32(r.ip_out_discards)),
+                ("ip_out_no_routes", CounterValue::U32(r.ip_out_no_routes)),
+                ("ip_reasm_timeout", CounterValue::U32(r.ip_reasm_timeout)),
+                ("ip_reasm_reqds", CounterValue::U32(r.ip_reasm_reqds)),
+                ("ip_reasm_oks", CounterValue::U32(r.ip_reasm_oks)),
+                ("ip_reasm_fails", CounterValue::U32(r.ip_reasm_fails)),
+                ("ip_frag_oks", CounterValue::U32(r.ip_frag_oks)),
+                ("ip_frag_fails", CounterValue::U32(r.ip_frag_fails)),
+                ("ip_frag_creates", CounterValue::U32(r.ip_frag_creates)),
+            ],
+            CounterRecord::Mib2IcmpGroup(r) => vec![
+                ("icmp_in_msgs", CounterValue::U32(r.icmp_in_msgs)),
+                ("icmp_in_errors", CounterValue::U32(r.icmp_in_errors)),
+                (
+                    "icmp_in_dest_unreachs",
+                    CounterValue::U32(r.icmp_in_dest_unreachs),
+                ),
+                (
+                    "icmp_in_time_excds",
+                    CounterValue::U32(r.icmp_in_time_excds),
+                ),
+                (
+                    "icmp_in_parm_probs",
+                    CounterValue::U32(r.icmp_in_parm_probs),
+                ),
+                (
+                    "icmp_in_src_quenchs",
+                    CounterValue::U32(r.icmp_in_src_quenchs),
+                ),
+                ("icmp_in_redirects", CounterValue::U32(r.icmp_in_redirects)),
+                ("icmp_in_echos", CounterValue::U32(r.icmp_in_echos)),
+                ("icmp_in_echo_reps", CounterValue::U32(r.icmp_in_echo_reps)),
+                (
+                    "icmp_in_timestamps",
+                    CounterValue::U32(r.icmp_in_timestamps),
+                ),
+                (
+                    "icmp_in_timestamp_reps",
+                    CounterValue::U32(r.icmp_in_timestamp_reps),
+                ),
+                (
+                    "icmp_in_addr_masks",
+                    CounterValue::U32(r.icmp_in_addr_masks),
+                ),
+                (
+                    "icmp_in_addr_mask_reps",
+                    CounterValue::U32(r.icmp_in_addr_mask_reps),
+                ),
+                ("icmp_out_msgs", CounterValue::U32(r.icmp_out_msgs)),
+                ("icmp_out_errors", CounterValue::U32(r.icmp_out_errors)),
+                (
+                    "icmp_out_dest_unreachs",
+                    CounterValue::U32(r.icmp_out_dest_unreachs),
+                ),
+                (
+                    "icmp_out_time_excds",
+                    CounterValue::U32(r.icmp_out_time_excds),
+                ),
+                (
+                    "icmp_out_parm_probs",
+                    CounterValue::U32(r.icmp_out_parm_probs),
+                ),
+                (
+                    "icmp_out_src_quenchs",
+                    CounterValue::U32(r.icmp_out_src_quenchs),
+                ),
+                (
+                    "icmp_out_redirects",
+                    CounterValue::U32(r.icmp_out_redirects),
+                ),
+                ("icmp_out_echos", CounterValue::U32(r.icmp_out_echos)),
+                (
+                    "icmp_out_echo_reps",
+                    CounterValue::U32(r.icmp_out_echo_reps),
+                ),
+                (
+                    "icmp_out_timestamps",
+                    CounterValue::U32(r.icmp_out_timestamps),
+                ),
+                (
+                    "icmp_out_timestamp_reps",
+                    CounterValue::U32(r.icmp_out_timestamp_reps),
+                ),
+                (
+                    "icmp_out_addr_masks",
+                    CounterValue::U32(r.icmp_out_addr_masks),
+                ),
+                (
+                    "icmp_out_addr_mask_reps",
+                    CounterValue::U32(r.icmp_out_addr_mask_reps),
+                ),
+            ],
+            CounterRecord::Mib2TcpGroup(r) => vec![
+                ("tcp_active_opens", CounterValue::U32(r.tcp_active_opens)),
+                ("tcp_passive_opens", CounterValue::U32(r.tcp_passive_opens)),
+                ("tcp_attempt_fails", CounterValue::U32(r.tcp_attempt_fails)),
+                ("tcp_estab_resets", CounterValue::U32(r.tcp_estab_resets)),
+                ("tcp_in_segs", CounterValue::U32(r.tcp_in_segs)),
+                ("tcp_out_segs", CounterValue::U32(r.tcp_out_segs)),
+                ("tcp_retrans_segs", CounterValue::U32(r.tcp_retrans_segs)),
+                ("tcp_in_errs", CounterValue::U32(r.tcp_in_errs)),
+                ("tcp_out_rsts", CounterValue::U32(r.tcp_out_rsts)),
+                ("tcp_in_csum_errs", CounterValue::U32(r.tcp_in_csum_errs)),
+            ],
+            CounterRecord::Mib2UdpGroup(r) => vec![
+                ("udp_in_datagrams", CounterValue::U32(r.udp_in_datagrams)),
+                ("udp_no_ports", CounterValue::U32(r.udp_no_ports)),
+                ("udp_in_errors", CounterValue::U32(r.udp_in_errors)),
+                ("udp_out_datagrams", CounterValue::U32(r.udp_out_datagrams)),
+                ("udp_rcvbuf_errors", CounterValue::U32(r.udp_rcvbuf_errors)),
+                ("udp_sndbuf_errors", CounterValue::U32(r.udp_sndbuf_errors)),
+                (
+                    "udp_in_csum_errors",
+                    CounterValue::U32(r.udp_in_csum_errors),
+                ),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Identifies one counter record stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CounterKey {
+    pub agent: AgentKey,
+    pub data_source: DataSource,
+    /// `(enterprise, format)` of the counter record.
+    pub record: (u32, u32),
+}
+
+/// Change of one cumulative field between two counter samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FieldDelta {
+    pub name: &'static str,
+    /// Increase since the previous sample, accounting for a 32-bit wrap.
+    pub delta: u64,
+    /// `delta` per second.
+    pub rate: f64,
+    /// A 32-bit counter wrapped past zero.
+    pub wrapped: bool,
+    /// The change is unlikely to be real traffic: a 64-bit counter decreased
+    /// (`delta` is 0), or a 32-bit delta exceeds half the counter range or
+    /// the rate exceeds the configured maximum. Usually a counter reset.
+    pub implausible: bool,
+}
+
+/// Deltas of every cumulative field of a counter record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CounterRates {
+    /// Time between the two samples, from the agent's uptime.
+    pub interval: Duration,
+    pub fields: Vec<FieldDelta>,
+}
+
+impl CounterRates {
+    fn between(
+        previous: &[(&'static str, CounterValue)],
+        current: &[(&'static str, CounterValue)],
+        interval: Duration,
+        max_rate: Option<f64>,
+    ) -> Self {
+        let seconds = interval.as_secs_f64();
+        let fields = current
+            .iter()
+            .zip(previous)
+            .map(|(&(name, current), &(_, prior))| {
+                field_delta(name, prior, current, seconds, max_rate)
+            })
+            .collect();
+        CounterRates { interval, fields }
+    }
+
+    /// Delta of a field, or 0 if the record has no such field.
+    pub fn delta(&self, name: &str) -> u64 {
+        self.get(name).map_or(0, |f| f.delta)
+    }
+
+    /// Look up a field by name.
+    pub fn get(&self, name: &str) -> Option<&FieldDelta> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Whether any field was flagged implausible.
+    pub fn has_implausible(&self) -> bool {
+        self.fields.iter().any(|f| f.implausible)
+    }
+}
+
+/// Utilization, packet rates and error ratios of one interface, derived from
+/// two consecutive [`GenericInterface`] counter records.
+///
+/// Utilization is a percentage of `if_speed`. On half-duplex links both
+/// directions share the medium, so in and out utilization are both the
+/// combined traffic. Utilization is `None` when the speed is unknown (0) or
+/// the interface is operationally down. Ratios are `None` when no packets
+/// were seen in that direction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceMetrics {
+    pub if_index: u32,
+    pub if_speed: u64,
+    pub direction: IfDirection,
+    pub admin_up: bool,
+    pub oper_up: bool,
+    pub interval: Duration,
+    pub in_bps: f64,
+    pub out_bps: f64,
+    pub in_pps: f64,
+    pub out_pps: f64,
+    pub in_utilization: Option<f64>,
+    pub out_utilization: Option<f64>,
+    /// `if_in_errors` over all packets received, including errored,
+    /// discarded and unknown-protocol packets.
+    pub in_error_ratio: Option<f64>,
+    /// `if_in_discards` over all packets received.
+    pub in_discard_ratio: Option<f64>,
+    /// `if_out_errors` over packets requested for transmission.
+    pub out_error_ratio: Option<f64>,
+    /// `if_out_discards` over packets requested for transmission.
+    pub out_discard_ratio: Option<f64>,
+    /// Any underlying counter delta was flagged implausible.
+    pub implausible: bool,
+}
+
+impl InterfaceMetrics {
+    /// Derive metrics from the current record and the deltas of its
+    /// cumulative counters, as returned by [`CounterEngine`].
+    pub fn from_rates(current: &GenericInterface, rates: &CounterRates) -> Self {
+        let seconds = rates.interval.as_secs_f64();
+        let per_second = |delta: u64| {
+            if seconds > 0.0 {
+                delta as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+
+        let in_packets = rates.delta("if_in_ucast_pkts")
+            + rates.delta("if_in_multicast_pkts")
+            + rates.delta("if_in_broadcast_pkts");
+        let out_packets = rates.delta("if_out_ucast_pkts")
+            + rates.delta("if_out_multicast_pkts")
+            + rates.delta("if_out_broadcast_pkts");
+        let in_errors = rates.delta("if_in_errors");
+        let in_discards = rates.delta("if_in_discards");
+        let in_received =
+            in_packets + in_errors + in_discards + rates.delta("if_in_unknown_protos");
+
+        let in_bps = per_second(rates.delta("if_in_octets")) * 8.0;
+        let out_bps = per_second(rates.delta("if_out_octets")) * 8.0;
+        let direction = current.direction();
+        let oper_up = current.oper_up();
+        let utilization = |bps: f64| {
+            (oper_up && current.if_speed > 0).then(|| bps / current.if_speed as f64 * 100.0)
+        };
+        let (in_utilization, out_utilization) = match direction {
+            IfDirection::HalfDuplex => {
+                let shared = utilization(in_bps + out_bps);
+                (shared, shared)
+            }
+            _ => (utilization(in_bps), utilization(out_bps)),
+        };
+
+        InterfaceMetrics {
+            if_index: current.if_index,
+            if_speed: current.if_speed,
+            direction,
+            admin_up: current.admin_up(),
+            oper_up,
+            interval: rates.interval,
+            in_bps,
+            out_bps,
+            in_pps: per_second(in_packets),
+            out_pps: per_second(out_packets),
+            in_utilization,
+            out_utilization,
+            in_error_ratio: ratio(in_errors, in_received),
+            in_discard_ratio: ratio(in_discards, in_received),
+            out_error_ratio: ratio(rates.delta("if_out_errors"), out_packets),
+            out_discard_ratio: ratio(rates.delta("if_out_discards"), out_packets),
+            implausible: rates.has_implausible(),
+        }
+    }
+
+    /// Derive metrics from two records of the same interface taken
+    /// `interval` apart. Counter wraps are handled as in [`CounterEngine`],
+    /// but restarts between the samples are not detected.
+    pub fn between(
+        previous: &GenericInterface,
+        current: &GenericInterface,
+        interval: Duration,
+    ) -> Self {
+        let previous = CounterRecord::GenericInterface(previous.clone()).cumulative_counters();
+        let values = CounterRecord::GenericInterface(current.clone()).cumulative_counters();
+        let rates = CounterRates::between(&previous, &values, interval, None);
+        InterfaceMetrics::from_rates(current, &rates)
+    }
+}
+
+fn ratio(part: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64)
+}
+
+/// Why a counter sample produced no deltas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// The agent's uptime went backwards; the sample is the new baseline.
+    AgentRestarted,
+    /// Longer than the maximum interval since the previous sample; the sample
+    /// is the new baseline.
+    Stale,
+    /// A cumulative counter went backwards by more than a 32-bit wrap
+    /// explains, as after an agent or interface restart that the uptime did
+    /// not reveal; the sample is the new baseline.
+    CounterReset,
+    /// Same uptime as the previous sample, e.g. a duplicated datagram.
+    Duplicate,
+}
+
+/// Outcome of observing one counter record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum CounterUpdate {
+    /// First sample of this stream, stored as the baseline.
+    Baseline,
+    /// Deltas since the previous sample.
+    Rates(CounterRates),
+    /// No deltas were produced.
+    Skipped(SkipReason),
+}
+
+#[derive(Debug, Clone)]
+struct Baseline {
+    uptime: u32,
+    values: Vec<(&'static str, CounterValue)>,
+}
+
+/// Stateful engine computing counter deltas and rates.
+#[derive(Debug, Clone)]
+pub struct CounterEngine {
+    max_interval: Duration,
+    max_rate: Option<f64>,
+    baselines: HashMap<CounterKey, Baseline>,
+}
+
+impl Default for CounterEngine {
+    fn default() -> Self {
+        CounterEngine::builder().build()
+    }
+}
+
+impl CounterEngine {
+    /// Create a builder for configuring the engine.
+    pub fn builder() -> CounterEngineBuilder {
+        CounterEngineBuilder {
+            max_interval: Duration::from_secs(3600),
+            max_rate: None,
+        }
+    }
+
+    /// Observe every counter record with cumulative fields in a datagram.
+    pub fn observe(&mut self, datagram: &SflowDatagram) -> Vec<(CounterKey, CounterUpdate)> {
+        let mut updates = Vec::new();
+        for (key, record) in counter_records(datagram) {
+            if let Some(update) = self.observe_record(key.clone(), datagram.uptime, record) {
+                updates.push((key, update));
+            }
+        }
+        updates
+    }
+
+    /// Observe one counter record taken at agent `uptime` (milliseconds).
+    ///
+    /// Returns `None` if the record has no cumulative fields.
+    pub fn observe_record(
+        &mut self,
+        key: CounterKey,
+        uptime: u32,
+        record: &CounterRecord,
+    ) -> Option<CounterUpdate> {
+        let values = record.cumulative_counters();
+        if values.is_empty() {
+            return None;
+        }
+
+        let Some(previous) = self.baselines.get_mut(&key) else {
+            self.baselines.insert(key, Baseline { uptime, values });
+            return Some(CounterUpdate::Baseline);
+        };
+
+        // Forward distance on the uptime clock, through 2^32 if it wrapped.
+        let elapsed = uptime.wrapping_sub(previous.uptime);
+        if elapsed == 0 {
+            return Some(CounterUpdate::Skipped(SkipReason::Duplicate));
+        }
+        let interval = Duration::from_millis(u64::from(elapsed));
+        if interval > self.max_interval {
+            let reason = if uptime < previous.uptime {
+                SkipReason::AgentRestarted
+            } else {
+                SkipReason::Stale
+            };
+            *previous = Baseline { uptime, values };
+            return Some(CounterUpdate::Skipped(reason));
+        }
+        if counters_reset(&previous.values, &values) {
+            *previous = Baseline { uptime, values };
+            return Some(CounterUpdate::Skipped(SkipReason::CounterReset));
+        }
+
+        let rates = CounterRates::between(&previous.values, &values, interval, self.max_rate);
+        *previous = Baseline { uptime, values };
+        Some(CounterUpdate::Rates(rates))
+    }
+
+    /// Observe a datagram and derive [`InterfaceMetrics`] for every generic
+    /// interface counter record that has a usable previous sample.
+    pub fn observe_interfaces(
+        &mut self,
+        datagram: &SflowDatagram,
+    ) -> Vec<(CounterKey, InterfaceMetrics)> {
+        let mut metrics = Vec::new();
+        for (key, record) in counter_records(datagram) {
+            let CounterRecord::GenericInterface(current) = record else {
+                continue;
+            };
+            if let Some(CounterUpdate::Rates(rates)) =
+                self.observe_record(key.clone(), datagram.uptime, record)
+            {
+                metrics.push((key, InterfaceMetrics::from_rates(current, &rates)));
+            }
+        }
+        metrics
+    }
+
+    /// Drop all baselines of an agent, e.g. after it restarted.
+    pub fn reset_agent(&mut self, agent_address: &AddressType) {
+        self.baselines
+            .retain(|key, _| key.agent.agent_address != *agent_address);
+    }
+
+    /// Number of counter streams with a baseline.
+    pub fn len(&self) -> usize {
+        self.baselines.len()
+    }
+
+    /// Whether no baselines are stored.
+    pub fn is_empty(&self) -> bool {
+        self.baselines.is_empty()
+    }
+}
+
+/// Builder for configuring a [`CounterEngine`].
+#[derive(Debug, Clone)]
+pub struct CounterEngineBuilder {
+    max_interval: Duration,
+    max_rate: Option<f64>,
+}
+
+impl CounterEngineBuilder {
+    /// Longest interval between samples that still yields deltas (default 1 hour).
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Per-second rate above which any field is flagged implausible.
+    pub fn with_max_rate(mut self, max_rate: f64) -> Self {
+        self.max_rate = Some(max_rate);
+        self
+    }
+
+    /// Build the configured [`CounterEngine`].
+    pub fn build(self) -> CounterEngine {
+        CounterEngine {
+            max_interval: self.max_interval,
+            max_rate: self.max_rate,
+            baselines: HashMap::new(),
+        }
+    }
+}
+
+fn counter_records(
+    datagram: &SflowDatagram,
+) -> impl Iterator<Item = (CounterKey, &CounterRecord)> {
+    let agent = AgentKey {
+        agent_address: datagram.agent_address.clone(),
+        sub_agent_id: datagram.sub_agent_id,
+    };
+    datagram
+        .samples
+        .iter()
+        .filter_map(|sample| sample.normalized_counter())
+        .flat_map(move |counters| {
+            let agent = agent.clone();
+            counters.records.iter().map(move |record| {
+                let key = CounterKey {
+                    agent: agent.clone(),
+                    data_source: counters.data_source,
+                    record: record.enterprise_format(),
+                };
+                (key, record)
+            })
+        })
+}
+
+/// Whether any field decreased other than by a plausible 32-bit wrap.
+fn counters_reset(
+    previous: &[(&'static str, CounterValue)],
+    current: &[(&'static str, CounterValue)],
+) -> bool {
+    current
+        .iter()
+        .zip(previous)
+        .any(|(&(_, current), &(_, prior))| match (prior, current) {
+            (CounterValue::U32(prior), CounterValue::U32(current)) => {
+                current < prior && current.wrapping_sub(prior) > u32::MAX / 2
+            }
+            (prior, current) => current.get() < prior.get(),
+        })
+}
+
+fn field_delta(
+    name: &'static str,
+    prior: CounterValue,
+    current: CounterValue,
+    seconds: f64,
+    max_rate: Option<f64>,
+) -> FieldDelta {
+    let (delta, wrapped, mut implausible) = match (prior, current) {
+        (CounterValue::U32(prior), CounterValue::U32(current)) => {
+            let delta = current.wrapping_sub(prior);
+            (u64::from(delta), current < prior, delta > u32::MAX / 2)
+        }
+        (prior, current) => match current.get().checked_sub(prior.get()) {
+            Some(delta) => (delta, false, false),
+            None => (0, false, true),
+        },
+    };
+    let rate = delta as f64 / seconds;
+    if max_rate.is_some_and(|max| rate > max) {
+        implausible = true;
+    }
+    FieldDelta {
+        name,
+        delta,
+        rate,
+        wrapped,
+        implausible,
+    }
+}
@@ -2,7 +2,11 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-use crate::counter_records::{CounterRecord, parse_counter_records};
+use crate::counter_records::{
+    CounterRecord, CounterRecordRef, parse_counter_records, write_counter_records,
+};
+use crate::encode::write_u32;
+use crate::samples::{DataSource, ParsedSample};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CounterSample {
@@ -12,6 +16,38 @@ pub struct CounterSample {
     pub records: Vec<CounterRecord>,
 }
 
+impl CounterSample {
+    /// The [`DataSource`] this sample was taken from.
+    pub fn data_source(&self) -> DataSource {
+        DataSource::new(self.source_id_type, self.source_id_index)
+    }
+}
+
+/// Borrowed form of [`CounterSample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CounterSampleRef<'a> {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    pub records: Vec<CounterRecordRef<'a>>,
+}
+
+impl CounterSampleRef<'_> {
+    /// Convert into the owned [`CounterSample`], copying any borrowed data.
+    pub fn into_owned(self) -> CounterSample {
+        CounterSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records: self
+                .records
+                .into_iter()
+                .map(CounterRecordRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ExpandedCounterSample {
     pub sequence_number: u32,
@@ -20,7 +56,93 @@ pub struct ExpandedCounterSample {
     pub records: Vec<CounterRecord>,
 }
 
-pub(crate) fn parse_counter_sample(input: &[u8]) -> IResult<&[u8], CounterSample> {
+impl ExpandedCounterSample {
+    /// The [`DataSource`] this sample was taken from.
+    pub fn data_source(&self) -> DataSource {
+        DataSource::new(self.source_id_type, self.source_id_index)
+    }
+}
+
+/// Borrowed form of [`ExpandedCounterSample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpandedCounterSampleRef<'a> {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    pub records: Vec<CounterRecordRef<'a>>,
+}
+
+impl ExpandedCounterSampleRef<'_> {
+    /// Convert into the owned [`ExpandedCounterSample`], copying any borrowed data.
+    pub fn into_owned(self) -> ExpandedCounterSample {
+        ExpandedCounterSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records: self
+                .records
+                .into_iter()
+                .map(CounterRecordRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Fields of a [`CounterSample`] or [`ExpandedCounterSample`] preceding
+/// its records.
+pub(crate) struct CounterSampleHeader {
+    pub(crate) sequence_number: u32,
+    pub(crate) source_id_type: u32,
+    pub(crate) source_id_index: u32,
+}
+
+impl CounterSampleHeader {
+    pub(crate) fn into_sample(self, records: Vec<CounterRecord>) -> CounterSample {
+        CounterSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records,
+        }
+    }
+
+    pub(crate) fn into_sample_ref(
+        self,
+        records: Vec<CounterRecordRef<'_>>,
+    ) -> CounterSampleRef<'_> {
+        CounterSampleRef {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records,
+        }
+    }
+
+    pub(crate) fn into_expanded(self, records: Vec<CounterRecord>) -> ExpandedCounterSample {
+        ExpandedCounterSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records,
+        }
+    }
+
+    pub(crate) fn into_expanded_ref(
+        self,
+        records: Vec<CounterRecordRef<'_>>,
+    ) -> ExpandedCounterSampleRef<'_> {
+        ExpandedCounterSampleRef {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            records,
+        }
+    }
+}
+
+pub(crate) fn parse_counter_sample<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], S> {
     let (input, sequence_number) = be_u32(input)?;
     let (input, source_id) = be_u32(input)?;
     let source_id_type = source_id >> 24;
@@ -29,20 +151,17 @@ pub(crate) fn parse_counter_sample(input: &[u8]) -> IResult<&[u8], CounterSample
 
     let (input, records) = parse_counter_records(input, num_records)?;
 
-    Ok((
-        input,
-        CounterSample {
-            sequence_number,
-            source_id_type,
-            source_id_index,
-            records,
-        },
-    ))
+    let header = CounterSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+    };
+    Ok((input, S::counter(header, records)))
 }
 
-pub(crate) fn parse_expanded_counter_sample(
-    input: &[u8],
-) -> IResult<&[u8], ExpandedCounterSample> {
+pub(crate) fn parse_expanded_counter_sample<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], S> {
     let (input, sequence_number) = be_u32(input)?;
     let (input, source_id_type) = be_u32(input)?;
     let (input, source_id_index) = be_u32(input)?;
@@ -50,13 +169,26 @@ pub(crate) fn parse_expanded_counter_sample(
 
     let (input, records) = parse_counter_records(input, num_records)?;
 
-    Ok((
-        input,
-        ExpandedCounterSample {
-            sequence_number,
-            source_id_type,
-            source_id_index,
-            records,
-        },
-    ))
+    let header = CounterSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+    };
+    Ok((input, S::expanded_counter(header, records)))
+}
+
+pub(crate) fn write_counter_sample(out: &mut Vec<u8>, s: &CounterSample) {
+    write_u32(out, s.sequence_number);
+    write_u32(
+        out,
+        (s.source_id_type << 24) | (s.source_id_index & 0x00FF_FFFF),
+    );
+    write_counter_records(out, &s.records);
+}
+
+pub(crate) fn write_expanded_counter_sample(out: &mut Vec<u8>, s: &ExpandedCounterSample) {
+    write_u32(out, s.sequence_number);
+    write_u32(out, s.source_id_type);
+    write_u32(out, s.source_id_index);
+    write_counter_records(out, &s.records);
 }
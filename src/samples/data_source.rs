@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The data source a sample was taken from, decoded from a sample's
+/// `source_id_type` and `source_id_index`.
+///
+/// Formats as `type:index`, matching sflowtool (e.g. `0:17` for ifIndex 17).
+/// Ordering compares the source type first, then the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DataSource {
+    /// ifIndex of a physical or logical interface (type 0).
+    IfIndex(u32),
+    /// smonVlanDataSource, the VLAN ID of a VLAN data source (type 1).
+    SmonVlan(u32),
+    /// entPhysicalEntry index of a physical entity (type 2).
+    EntPhysical(u32),
+    /// Any other source type.
+    Other { source_id_type: u32, index: u32 },
+}
+
+impl DataSource {
+    /// Build a data source from its type and index.
+    pub fn new(source_id_type: u32, index: u32) -> Self {
+        match source_id_type {
+            0 => DataSource::IfIndex(index),
+            1 => DataSource::SmonVlan(index),
+            2 => DataSource::EntPhysical(index),
+            source_id_type => DataSource::Other {
+                source_id_type,
+                index,
+            },
+        }
+    }
+
+    /// The `source_id_type` value.
+    pub fn source_id_type(&self) -> u32 {
+        match self {
+            DataSource::IfIndex(_) => 0,
+            DataSource::SmonVlan(_) => 1,
+            DataSource::EntPhysical(_) => 2,
+            DataSource::Other { source_id_type, .. } => *source_id_type,
+        }
+    }
+
+    /// The `source_id_index` value.
+    pub fn index(&self) -> u32 {
+        match self {
+            DataSource::IfIndex(index)
+            | DataSource::SmonVlan(index)
+            | DataSource::EntPhysical(index)
+            | DataSource::Other { index, .. } => *index,
+        }
+    }
+}
+
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source_id_type(), self.index())
+    }
+}
@@ -0,0 +1,389 @@
+use nom::IResult;
+use nom::number::complete::be_u32;
+use serde::{Deserialize, Serialize};
+
+use crate::encode::write_u32;
+use crate::flow_records::{FlowRecord, FlowRecordRef, parse_flow_records, write_flow_records};
+use crate::samples::{DataSource, Interface, ParsedSample};
+
+/// Reason a packet was dropped, from the sFlow drop notification specification.
+///
+/// Values 0–15 mirror the ICMP destination unreachable codes; values from
+/// 256 upward are sFlow-defined reasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DropReason {
+    NetUnreachable,
+    HostUnreachable,
+    ProtocolUnreachable,
+    PortUnreachable,
+    FragNeeded,
+    SrcRouteFailed,
+    DstNetUnknown,
+    DstHostUnknown,
+    SrcHostIsolated,
+    DstNetProhibited,
+    DstHostProhibited,
+    DstNetTosUnreachable,
+    DstHostTosUnreachable,
+    CommAdminProhibited,
+    HostPrecedenceViolation,
+    PrecedenceCutoff,
+    Unknown,
+    TtlExceeded,
+    Acl,
+    NoBufferSpace,
+    Red,
+    TrafficShaping,
+    PktTooBig,
+    SrcMacIsMulticast,
+    VlanTagMismatch,
+    IngressVlanFilter,
+    IngressSpanningTreeFilter,
+    PortListIsEmpty,
+    PortLoopbackFilter,
+    BlackholeRoute,
+    NonIp,
+    UcDipOverMcDmac,
+    DipIsLoopbackAddress,
+    SipIsMc,
+    SipIsLoopbackAddress,
+    IpHeaderCorrupted,
+    Ipv4SipIsLimitedBc,
+    Ipv6McDipReservedScope,
+    Ipv6McDipInterfaceLocalScope,
+    UnresolvedNeigh,
+    McReversePathForwarding,
+    NonRoutablePacket,
+    DecapError,
+    OverlaySmacIsMc,
+    UnknownL2,
+    UnknownL3,
+    UnknownL3Exception,
+    UnknownBuffer,
+    UnknownTunnel,
+    UnknownL4,
+    SipIsUnspecified,
+    MlagPortIsolation,
+    BlackholeArpNeigh,
+    SrcMacIsDmac,
+    DmacIsReserved,
+    SipIsClassE,
+    McDmacMismatch,
+    SipIsDip,
+    DipIsLocalNetwork,
+    DipIsLinkLocal,
+    OverlaySmacIsDmac,
+    MtuExceeded,
+    Unrecognized(u32),
+}
+
+impl From<u32> for DropReason {
+    fn from(v: u32) -> Self {
+        match v {
+            0 => DropReason::NetUnreachable,
+            1 => DropReason::HostUnreachable,
+            2 => DropReason::ProtocolUnreachable,
+            3 => DropReason::PortUnreachable,
+            4 => DropReason::FragNeeded,
+            5 => DropReason::SrcRouteFailed,
+            6 => DropReason::DstNetUnknown,
+            7 => DropReason::DstHostUnknown,
+            8 => DropReason::SrcHostIsolated,
+            9 => DropReason::DstNetProhibited,
+            10 => DropReason::DstHostProhibited,
+            11 => DropReason::DstNetTosUnreachable,
+            12 => DropReason::DstHostTosUnreachable,
+            13 => DropReason::CommAdminProhibited,
+            14 => DropReason::HostPrecedenceViolation,
+            15 => DropReason::PrecedenceCutoff,
+            256 => DropReason::Unknown,
+            257 => DropReason::TtlExceeded,
+            258 => DropReason::Acl,
+            259 => DropReason::NoBufferSpace,
+            260 => DropReason::Red,
+            261 => DropReason::TrafficShaping,
+            262 => DropReason::PktTooBig,
+            263 => DropReason::SrcMacIsMulticast,
+            264 => DropReason::VlanTagMismatch,
+            265 => DropReason::IngressVlanFilter,
+            266 => DropReason::IngressSpanningTreeFilter,
+            267 => DropReason::PortListIsEmpty,
+            268 => DropReason::PortLoopbackFilter,
+            269 => DropReason::BlackholeRoute,
+            270 => DropReason::NonIp,
+            271 => DropReason::UcDipOverMcDmac,
+            272 => DropReason::DipIsLoopbackAddress,
+            273 => DropReason::SipIsMc,
+            274 => DropReason::SipIsLoopbackAddress,
+            275 => DropReason::IpHeaderCorrupted,
+            276 => DropReason::Ipv4SipIsLimitedBc,
+            277 => DropReason::Ipv6McDipReservedScope,
+            278 => DropReason::Ipv6McDipInterfaceLocalScope,
+            279 => DropReason::UnresolvedNeigh,
+            280 => DropReason::McReversePathForwarding,
+            281 => DropReason::NonRoutablePacket,
+            282 => DropReason::DecapError,
+            283 => DropReason::OverlaySmacIsMc,
+            284 => DropReason::UnknownL2,
+            285 => DropReason::UnknownL3,
+            286 => DropReason::UnknownL3Exception,
+            287 => DropReason::UnknownBuffer,
+            288 => DropReason::UnknownTunnel,
+            289 => DropReason::UnknownL4,
+            290 => DropReason::SipIsUnspecified,
+            291 => DropReason::MlagPortIsolation,
+            292 => DropReason::BlackholeArpNeigh,
+            293 => DropReason::SrcMacIsDmac,
+            294 => DropReason::DmacIsReserved,
+            295 => DropReason::SipIsClassE,
+            296 => DropReason::McDmacMismatch,
+            297 => DropReason::SipIsDip,
+            298 => DropReason::DipIsLocalNetwork,
+            299 => DropReason::DipIsLinkLocal,
+            300 => DropReason::OverlaySmacIsDmac,
+            301 => DropReason::MtuExceeded,
+            v => DropReason::Unrecognized(v),
+        }
+    }
+}
+
+impl From<DropReason> for u32 {
+    fn from(v: DropReason) -> Self {
+        match v {
+            DropReason::NetUnreachable => 0,
+            DropReason::HostUnreachable => 1,
+            DropReason::ProtocolUnreachable => 2,
+            DropReason::PortUnreachable => 3,
+            DropReason::FragNeeded => 4,
+            DropReason::SrcRouteFailed => 5,
+            DropReason::DstNetUnknown => 6,
+            DropReason::DstHostUnknown => 7,
+            DropReason::SrcHostIsolated => 8,
+            DropReason::DstNetProhibited => 9,
+            DropReason::DstHostProhibited => 10,
+            DropReason::DstNetTosUnreachable => 11,
+            DropReason::DstHostTosUnreachable => 12,
+            DropReason::CommAdminProhibited => 13,
+            DropReason::HostPrecedenceViolation => 14,
+            DropReason::PrecedenceCutoff => 15,
+            DropReason::Unknown => 256,
+            DropReason::TtlExceeded => 257,
+            DropReason::Acl => 258,
+            DropReason::NoBufferSpace => 259,
+            DropReason::Red => 260,
+            DropReason::TrafficShaping => 261,
+            DropReason::PktTooBig => 262,
+            DropReason::SrcMacIsMulticast => 263,
+            DropReason::VlanTagMismatch => 264,
+            DropReason::IngressVlanFilter => 265,
+            DropReason::IngressSpanningTreeFilter => 266,
+            DropReason::PortListIsEmpty => 267,
+            DropReason::PortLoopbackFilter => 268,
+            DropReason::BlackholeRoute => 269,
+            DropReason::NonIp => 270,
+            DropReason::UcDipOverMcDmac => 271,
+            DropReason::DipIsLoopbackAddress => 272,
+            DropReason::SipIsMc => 273,
+            DropReason::SipIsLoopbackAddress => 274,
+            DropReason::IpHeaderCorrupted => 275,
+            DropReason::Ipv4SipIsLimitedBc => 276,
+            DropReason::Ipv6McDipReservedScope => 277,
+            DropReason::Ipv6McDipInterfaceLocalScope => 278,
+            DropReason::UnresolvedNeigh => 279,
+            DropReason::McReversePathForwarding => 280,
+            DropReason::NonRoutablePacket => 281,
+            DropReason::DecapError => 282,
+            DropReason::OverlaySmacIsMc => 283,
+            DropReason::UnknownL2 => 284,
+            DropReason::UnknownL3 => 285,
+            DropReason::UnknownL3Exception => 286,
+            DropReason::UnknownBuffer => 287,
+            DropReason::UnknownTunnel => 288,
+            DropReason::UnknownL4 => 289,
+            DropReason::SipIsUnspecified => 290,
+            DropReason::MlagPortIsolation => 291,
+            DropReason::BlackholeArpNeigh => 292,
+            DropReason::SrcMacIsDmac => 293,
+            DropReason::DmacIsReserved => 294,
+            DropReason::SipIsClassE => 295,
+            DropReason::McDmacMismatch => 296,
+            DropReason::SipIsDip => 297,
+            DropReason::DipIsLocalNetwork => 298,
+            DropReason::DipIsLinkLocal => 299,
+            DropReason::OverlaySmacIsDmac => 300,
+            DropReason::MtuExceeded => 301,
+            DropReason::Unrecognized(v) => v,
+        }
+    }
+}
+
+/// Discarded packet sample (enterprise=0, format=5).
+///
+/// Emitted by agents implementing the sFlow drop notification extension.
+/// The source ID is always carried in expanded form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscardedPacket {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    /// Number of discards not reported due to rate limiting.
+    pub drops: u32,
+    /// Input interface ifIndex, or 0 if unknown.
+    pub input: u32,
+    /// Output interface ifIndex, or 0 if unknown.
+    pub output: u32,
+    /// Reason the packet was discarded.
+    pub reason: DropReason,
+    pub records: Vec<FlowRecord>,
+}
+
+impl DiscardedPacket {
+    /// The [`DataSource`] this sample was taken from.
+    pub fn data_source(&self) -> DataSource {
+        DataSource::new(self.source_id_type, self.source_id_index)
+    }
+
+    /// The [`Interface`] the discarded packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        from_ifindex(self.input)
+    }
+
+    /// The [`Interface`] the discarded packet would have been sent to.
+    pub fn output_interface(&self) -> Interface {
+        from_ifindex(self.output)
+    }
+}
+
+/// Borrowed form of [`DiscardedPacket`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscardedPacketRef<'a> {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    /// Number of discards not reported due to rate limiting.
+    pub drops: u32,
+    /// Input interface ifIndex, or 0 if unknown.
+    pub input: u32,
+    /// Output interface ifIndex, or 0 if unknown.
+    pub output: u32,
+    /// Reason the packet was discarded.
+    pub reason: DropReason,
+    pub records: Vec<FlowRecordRef<'a>>,
+}
+
+impl DiscardedPacketRef<'_> {
+    /// The [`Interface`] the discarded packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        from_ifindex(self.input)
+    }
+
+    /// The [`Interface`] the discarded packet would have been sent to.
+    pub fn output_interface(&self) -> Interface {
+        from_ifindex(self.output)
+    }
+
+    /// Convert into the owned [`DiscardedPacket`], copying any borrowed data.
+    pub fn into_owned(self) -> DiscardedPacket {
+        DiscardedPacket {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            reason: self.reason,
+            records: self
+                .records
+                .into_iter()
+                .map(FlowRecordRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Discard interfaces are plain ifIndexes without a format.
+fn from_ifindex(value: u32) -> Interface {
+    match value {
+        0 => Interface::Unknown,
+        index => Interface::Single(index),
+    }
+}
+
+/// Fields of a [`DiscardedPacket`] preceding its records.
+pub(crate) struct DiscardedPacketHeader {
+    pub(crate) sequence_number: u32,
+    pub(crate) source_id_type: u32,
+    pub(crate) source_id_index: u32,
+    pub(crate) drops: u32,
+    pub(crate) input: u32,
+    pub(crate) output: u32,
+    pub(crate) reason: DropReason,
+}
+
+impl DiscardedPacketHeader {
+    pub(crate) fn into_sample(self, records: Vec<FlowRecord>) -> DiscardedPacket {
+        DiscardedPacket {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            reason: self.reason,
+            records,
+        }
+    }
+
+    pub(crate) fn into_sample_ref(
+        self,
+        records: Vec<FlowRecordRef<'_>>,
+    ) -> DiscardedPacketRef<'_> {
+        DiscardedPacketRef {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            reason: self.reason,
+            records,
+        }
+    }
+}
+
+pub(crate) fn parse_discarded_packet<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], S> {
+    let (input, sequence_number) = be_u32(input)?;
+    let (input, source_id_type) = be_u32(input)?;
+    let (input, source_id_index) = be_u32(input)?;
+    let (input, drops) = be_u32(input)?;
+    let (input, input_if) = be_u32(input)?;
+    let (input, output_if) = be_u32(input)?;
+    let (input, reason) = be_u32(input)?;
+    let (input, num_records) = be_u32(input)?;
+
+    let (input, records) = parse_flow_records(input, num_records)?;
+
+    let header = DiscardedPacketHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+        drops,
+        input: input_if,
+        output: output_if,
+        reason: DropReason::from(reason),
+    };
+    Ok((input, S::discard(header, records)))
+}
+
+pub(crate) fn write_discarded_packet(out: &mut Vec<u8>, s: &DiscardedPacket) {
+    write_u32(out, s.sequence_number);
+    write_u32(out, s.source_id_type);
+    write_u32(out, s.source_id_index);
+    write_u32(out, s.drops);
+    write_u32(out, s.input);
+    write_u32(out, s.output);
+    write_u32(out, u32::from(s.reason));
+    write_flow_records(out, &s.records);
+}
@@ -2,9 +2,69 @@ use nom::IResult;
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
-use crate::flow_records::{FlowRecord, parse_flow_records};
+use crate::encode::write_u32;
+use crate::flow_records::{FlowRecord, FlowRecordRef, parse_flow_records, write_flow_records};
+use crate::samples::{DataSource, DropReason, ParsedSample};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Decoded input or output interface of a flow sample.
+///
+/// Compact flow samples pack a 2-bit format into the top bits of the
+/// `input`/`output` fields; expanded flow samples carry the format and value
+/// separately. See [`FlowSample::input_interface`] and
+/// [`ExpandedFlowSample::input_interface`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interface {
+    /// The packet was received or sent on a single interface with this ifIndex.
+    Single(u32),
+    /// The packet was discarded for this reason (output only).
+    Discarded(DropReason),
+    /// The packet was sent to this many interfaces; 0 if the count is unknown.
+    Multiple(u32),
+    /// The packet originated from or was delivered to the agent itself.
+    Internal,
+    /// The interface is not known (ifIndex 0 or an undefined format).
+    Unknown,
+}
+
+impl Interface {
+    const INTERNAL: u32 = 0x3FFF_FFFF;
+
+    /// Decode a compact interface value from a [`FlowSample`].
+    pub fn from_compact(value: u32) -> Self {
+        Self::from_expanded(value >> 30, value & Self::INTERNAL)
+    }
+
+    /// Decode an expanded interface `format` and `value` from an [`ExpandedFlowSample`].
+    pub fn from_expanded(format: u32, value: u32) -> Self {
+        match (format, value) {
+            (0, 0) => Interface::Unknown,
+            (0, Self::INTERNAL) => Interface::Internal,
+            (0, v) => Interface::Single(v),
+            (1, v) => Interface::Discarded(DropReason::from(v)),
+            (2, v) => Interface::Multiple(v),
+            _ => Interface::Unknown,
+        }
+    }
+
+    /// Encode as a compact interface value. Values wider than 30 bits are truncated.
+    pub fn to_compact(self) -> u32 {
+        let (format, value) = self.to_expanded();
+        (format << 30) | (value & Self::INTERNAL)
+    }
+
+    /// Encode as an expanded `(format, value)` pair.
+    pub fn to_expanded(self) -> (u32, u32) {
+        match self {
+            Interface::Single(v) => (0, v),
+            Interface::Discarded(reason) => (1, u32::from(reason)),
+            Interface::Multiple(n) => (2, n),
+            Interface::Internal => (0, Self::INTERNAL),
+            Interface::Unknown => (0, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FlowSample {
     pub sequence_number: u32,
     pub source_id_type: u32,
@@ -17,7 +77,69 @@ pub struct FlowSample {
     pub records: Vec<FlowRecord>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Borrowed form of [`FlowSample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlowSampleRef<'a> {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    pub sampling_rate: u32,
+    pub sample_pool: u32,
+    pub drops: u32,
+    pub input: u32,
+    pub output: u32,
+    pub records: Vec<FlowRecordRef<'a>>,
+}
+
+impl FlowSample {
+    /// The [`DataSource`] this sample was taken from.
+    pub fn data_source(&self) -> DataSource {
+        DataSource::new(self.source_id_type, self.source_id_index)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        Interface::from_compact(self.input)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was sent to or discarded at.
+    pub fn output_interface(&self) -> Interface {
+        Interface::from_compact(self.output)
+    }
+}
+
+impl FlowSampleRef<'_> {
+    /// The decoded [`Interface`] the sampled packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        Interface::from_compact(self.input)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was sent to or discarded at.
+    pub fn output_interface(&self) -> Interface {
+        Interface::from_compact(self.output)
+    }
+
+    /// Convert into the owned [`FlowSample`], copying any borrowed data.
+    pub fn into_owned(self) -> FlowSample {
+        FlowSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            records: self
+                .records
+                .into_iter()
+                .map(FlowRecordRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ExpandedFlowSample {
     pub sequence_number: u32,
     pub source_id_type: u32,
@@ -32,7 +154,168 @@ pub struct ExpandedFlowSample {
     pub records: Vec<FlowRecord>,
 }
 
-pub(crate) fn parse_flow_sample(input: &[u8]) -> IResult<&[u8], FlowSample> {
+/// Borrowed form of [`ExpandedFlowSample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpandedFlowSampleRef<'a> {
+    pub sequence_number: u32,
+    pub source_id_type: u32,
+    pub source_id_index: u32,
+    pub sampling_rate: u32,
+    pub sample_pool: u32,
+    pub drops: u32,
+    pub input_format: u32,
+    pub input_value: u32,
+    pub output_format: u32,
+    pub output_value: u32,
+    pub records: Vec<FlowRecordRef<'a>>,
+}
+
+impl ExpandedFlowSample {
+    /// The [`DataSource`] this sample was taken from.
+    pub fn data_source(&self) -> DataSource {
+        DataSource::new(self.source_id_type, self.source_id_index)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        Interface::from_expanded(self.input_format, self.input_value)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was sent to or discarded at.
+    pub fn output_interface(&self) -> Interface {
+        Interface::from_expanded(self.output_format, self.output_value)
+    }
+}
+
+impl ExpandedFlowSampleRef<'_> {
+    /// The decoded [`Interface`] the sampled packet was received on.
+    pub fn input_interface(&self) -> Interface {
+        Interface::from_expanded(self.input_format, self.input_value)
+    }
+
+    /// The decoded [`Interface`] the sampled packet was sent to or discarded at.
+    pub fn output_interface(&self) -> Interface {
+        Interface::from_expanded(self.output_format, self.output_value)
+    }
+
+    /// Convert into the owned [`ExpandedFlowSample`], copying any borrowed data.
+    pub fn into_owned(self) -> ExpandedFlowSample {
+        ExpandedFlowSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input_format: self.input_format,
+            input_value: self.input_value,
+            output_format: self.output_format,
+            output_value: self.output_value,
+            records: self
+                .records
+                .into_iter()
+                .map(FlowRecordRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Fields of a [`FlowSample`] preceding its records.
+pub(crate) struct FlowSampleHeader {
+    pub(crate) sequence_number: u32,
+    pub(crate) source_id_type: u32,
+    pub(crate) source_id_index: u32,
+    pub(crate) sampling_rate: u32,
+    pub(crate) sample_pool: u32,
+    pub(crate) drops: u32,
+    pub(crate) input: u32,
+    pub(crate) output: u32,
+}
+
+impl FlowSampleHeader {
+    pub(crate) fn into_sample(self, records: Vec<FlowRecord>) -> FlowSample {
+        FlowSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            records,
+        }
+    }
+
+    pub(crate) fn into_sample_ref(self, records: Vec<FlowRecordRef<'_>>) -> FlowSampleRef<'_> {
+        FlowSampleRef {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input: self.input,
+            output: self.output,
+            records,
+        }
+    }
+}
+
+/// Fields of an [`ExpandedFlowSample`] preceding its records.
+pub(crate) struct ExpandedFlowSampleHeader {
+    pub(crate) sequence_number: u32,
+    pub(crate) source_id_type: u32,
+    pub(crate) source_id_index: u32,
+    pub(crate) sampling_rate: u32,
+    pub(crate) sample_pool: u32,
+    pub(crate) drops: u32,
+    pub(crate) input_format: u32,
+    pub(crate) input_value: u32,
+    pub(crate) output_format: u32,
+    pub(crate) output_value: u32,
+}
+
+impl ExpandedFlowSampleHeader {
+    pub(crate) fn into_sample(self, records: Vec<FlowRecord>) -> ExpandedFlowSample {
+        ExpandedFlowSample {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input_format: self.input_format,
+            input_value: self.input_value,
+            output_format: self.output_format,
+            output_value: self.output_value,
+            records,
+        }
+    }
+
+    pub(crate) fn into_sample_ref(
+        self,
+        records: Vec<FlowRecordRef<'_>>,
+    ) -> ExpandedFlowSampleRef<'_> {
+        ExpandedFlowSampleRef {
+            sequence_number: self.sequence_number,
+            source_id_type: self.source_id_type,
+            source_id_index: self.source_id_index,
+            sampling_rate: self.sampling_rate,
+            sample_pool: self.sample_pool,
+            drops: self.drops,
+            input_format: self.input_format,
+            input_value: self.input_value,
+            output_format: self.output_format,
+            output_value: self.output_value,
+            records,
+        }
+    }
+}
+
+pub(crate) fn parse_flow_sample<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], S> {
     let (input, sequence_number) = be_u32(input)?;
     let (input, source_id) = be_u32(input)?;
     let source_id_type = source_id >> 24;
@@ -46,23 +329,22 @@ pub(crate) fn parse_flow_sample(input: &[u8]) -> IResult<&[u8], FlowSample> {
 
     let (input, records) = parse_flow_records(input, num_records)?;
 
-    Ok((
-        input,
-        FlowSample {
-            sequence_number,
-            source_id_type,
-            source_id_index,
-            sampling_rate,
-            sample_pool,
-            drops,
-            input: input_if,
-            output: output_if,
-            records,
-        },
-    ))
+    let header = FlowSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+        sampling_rate,
+        sample_pool,
+        drops,
+        input: input_if,
+        output: output_if,
+    };
+    Ok((input, S::flow(header, records)))
 }
 
-pub(crate) fn parse_expanded_flow_sample(input: &[u8]) -> IResult<&[u8], ExpandedFlowSample> {
+pub(crate) fn parse_expanded_flow_sample<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], S> {
     let (input, sequence_number) = be_u32(input)?;
     let (input, source_id_type) = be_u32(input)?;
     let (input, source_id_index) = be_u32(input)?;
@@ -77,20 +359,45 @@ pub(crate) fn parse_expanded_flow_sample(input: &[u8]) -> IResult<&[u8], Expande
 
     let (input, records) = parse_flow_records(input, num_records)?;
 
-    Ok((
-        input,
-        ExpandedFlowSample {
-            sequence_number,
-            source_id_type,
-            source_id_index,
-            sampling_rate,
-            sample_pool,
-            drops,
-            input_format,
-            input_value,
-            output_format,
-            output_value,
-            records,
-        },
-    ))
+    let header = ExpandedFlowSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+        sampling_rate,
+        sample_pool,
+        drops,
+        input_format,
+        input_value,
+        output_format,
+        output_value,
+    };
+    Ok((input, S::expanded_flow(header, records)))
+}
+
+pub(crate) fn write_flow_sample(out: &mut Vec<u8>, s: &FlowSample) {
+    write_u32(out, s.sequence_number);
+    write_u32(
+        out,
+        (s.source_id_type << 24) | (s.source_id_index & 0x00FF_FFFF),
+    );
+    write_u32(out, s.sampling_rate);
+    write_u32(out, s.sample_pool);
+    write_u32(out, s.drops);
+    write_u32(out, s.input);
+    write_u32(out, s.output);
+    write_flow_records(out, &s.records);
+}
+
+pub(crate) fn write_expanded_flow_sample(out: &mut Vec<u8>, s: &ExpandedFlowSample) {
+    write_u32(out, s.sequence_number);
+    write_u32(out, s.source_id_type);
+    write_u32(out, s.source_id_index);
+    write_u32(out, s.sampling_rate);
+    write_u32(out, s.sample_pool);
+    write_u32(out, s.drops);
+    write_u32(out, s.input_format);
+    write_u32(out, s.input_value);
+    write_u32(out, s.output_format);
+    write_u32(out, s.output_value);
+    write_flow_records(out, &s.records);
 }
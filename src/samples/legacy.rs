@@ -0,0 +1,348 @@
+//! sFlow v2 and v4 samples (RFC 3176).
+//!
+//! Legacy samples carry no length fields and have a fixed layout: a flow
+//! sample holds one packet description followed by a list of extended data,
+//! and a counter sample holds one counters block selected by its version.
+//! Both are decoded into the v5 [`FlowSample`](super::FlowSample) and
+//! [`CounterSample`](super::CounterSample) shapes, with the packet description and extended data as flow records and
+//! the counters blocks as counter records. Fields v5 added are filled in:
+//! `stripped` is 0 for packet headers, charsets are 0 for user data, the URL
+//! `host` is empty and the gateway `next_hop` is `0.0.0.0`. The counter
+//! sampling interval and the gateway local preference have no v5 equivalent
+//! and are dropped.
+
+use nom::IResult;
+use nom::bytes::complete::take;
+use nom::number::complete::be_u32;
+use std::borrow::Cow;
+use std::net::Ipv4Addr;
+
+use super::counter_sample::CounterSampleHeader;
+use super::flow_sample::FlowSampleHeader;
+use super::{ParsedSample, nom_err_to_kind};
+use crate::counter_records::CounterRecordRef;
+use crate::counter_records::ethernet_interface::parse_ethernet_interface;
+use crate::counter_records::generic_interface::parse_generic_interface;
+use crate::counter_records::token_ring::parse_token_ring;
+use crate::counter_records::vg_counters::parse_vg_counters;
+use crate::counter_records::vlan::parse_vlan;
+use crate::datagram::AddressType;
+use crate::error::{ParseContext, ParseErrorKind, SflowError};
+use crate::flow_records::extended_gateway::{AsPathSegment, parse_as_path_segment};
+use crate::flow_records::extended_router::parse_extended_router;
+use crate::flow_records::extended_switch::parse_extended_switch;
+use crate::flow_records::sampled_ipv4::parse_sampled_ipv4;
+use crate::flow_records::sampled_ipv6::parse_sampled_ipv6;
+use crate::flow_records::{
+    ExtendedGateway, ExtendedUrlRef, ExtendedUserRef, FlowRecordRef, RawPacketHeaderRef,
+    parse_sflow_str,
+};
+
+/// BGP `AS_SEQUENCE`, the segment type given to the flat v2 AS path.
+const AS_SEQUENCE: u32 = 2;
+
+pub(crate) fn parse_legacy_samples<'a, S: ParsedSample<'a>>(
+    mut input: &'a [u8],
+    num_samples: u32,
+    version: u32,
+) -> Result<(&'a [u8], Vec<S>), SflowError> {
+    // Cap capacity to prevent DoS: each sample needs at least 8 bytes
+    let cap = (num_samples as usize).min(input.len() / 8);
+    let mut samples = Vec::with_capacity(cap);
+
+    for _ in 0..num_samples {
+        let (rest, sample_type) =
+            be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
+                SflowError::Incomplete {
+                    available: input.len(),
+                    expected: None,
+                    context: ParseContext::SampleDataFormat,
+                }
+            })?;
+
+        let (rest, sample) = match sample_type {
+            1 => parse_flow_sample(rest, version).map_err(|e| SflowError::ParseError {
+                offset: 0,
+                context: ParseContext::FlowSample,
+                kind: nom_err_to_kind(&e),
+            })?,
+            2 => parse_counter_sample(rest).map_err(|e| SflowError::ParseError {
+                offset: 0,
+                context: ParseContext::CounterSample,
+                kind: nom_err_to_kind(&e),
+            })?,
+            // Without a length field an unknown sample cannot be skipped
+            _ => {
+                return Err(SflowError::ParseError {
+                    offset: 0,
+                    context: ParseContext::SampleDataFormat,
+                    kind: ParseErrorKind::NomError(nom::error::ErrorKind::Switch),
+                });
+            }
+        };
+        samples.push(sample);
+        input = rest;
+    }
+
+    Ok((input, samples))
+}
+
+fn parse_flow_sample<'a, S: ParsedSample<'a>>(
+    input: &'a [u8],
+    version: u32,
+) -> IResult<&'a [u8], S> {
+    let (input, sequence_number) = be_u32(input)?;
+    let (input, source_id) = be_u32(input)?;
+    let source_id_type = source_id >> 24;
+    let source_id_index = source_id & 0x00FF_FFFF;
+    let (input, sampling_rate) = be_u32(input)?;
+    let (input, sample_pool) = be_u32(input)?;
+    let (input, drops) = be_u32(input)?;
+    let (input, input_if) = be_u32(input)?;
+    let (input, output_if) = be_u32(input)?;
+
+    let (input, packet_type) = be_u32(input)?;
+    let (input, packet) = match packet_type {
+        1 => {
+            let (input, header) = parse_sampled_header_ref(input)?;
+            (input, FlowRecordRef::RawPacketHeader(header))
+        }
+        2 => {
+            let (input, ipv4) = parse_sampled_ipv4(input)?;
+            (input, FlowRecordRef::SampledIpv4(ipv4))
+        }
+        3 => {
+            let (input, ipv6) = parse_sampled_ipv6(input)?;
+            (input, FlowRecordRef::SampledIpv6(ipv6))
+        }
+        _ => return Err(switch_error(input)),
+    };
+
+    let (mut input, num_extended) = be_u32(input)?;
+    // Cap capacity: each extended data item needs at least 4 bytes (tag)
+    let cap = (num_extended as usize).min(input.len() / 4);
+    let mut records = Vec::with_capacity(cap + 1);
+    records.push(packet.into());
+    for _ in 0..num_extended {
+        let (rest, record) = parse_extended_ref(input, version)?;
+        records.push(record.into());
+        input = rest;
+    }
+
+    let header = FlowSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+        sampling_rate,
+        sample_pool,
+        drops,
+        input: input_if,
+        output: output_if,
+    };
+    Ok((input, S::flow(header, records)))
+}
+
+/// A sampled header as in v5, without the `stripped` field.
+fn parse_sampled_header_ref(input: &[u8]) -> IResult<&[u8], RawPacketHeaderRef<'_>> {
+    let (input, header_protocol) = be_u32(input)?;
+    let (input, frame_length) = be_u32(input)?;
+    let (input, header_length) = be_u32(input)?;
+    let (input, header) = take(header_length as usize)(input)?;
+    // Skip XDR padding to 4-byte boundary
+    let padding = (4 - (header_length as usize % 4)) % 4;
+    let (input, _) = take(padding)(input)?;
+
+    Ok((
+        input,
+        RawPacketHeaderRef {
+            header_protocol,
+            frame_length,
+            stripped: 0,
+            header_length,
+            header,
+        },
+    ))
+}
+
+fn parse_extended_ref(input: &[u8], version: u32) -> IResult<&[u8], FlowRecordRef<'_>> {
+    let (input, tag) = be_u32(input)?;
+    match tag {
+        1 => {
+            let (input, switch) = parse_extended_switch(input)?;
+            Ok((input, FlowRecordRef::ExtendedSwitch(switch)))
+        }
+        2 => {
+            let (input, router) = parse_extended_router(input)?;
+            Ok((input, FlowRecordRef::ExtendedRouter(router)))
+        }
+        3 => {
+            let (input, gateway) = if version == 2 {
+                parse_gateway_v2(input)?
+            } else {
+                parse_gateway_v4(input)?
+            };
+            Ok((input, FlowRecordRef::ExtendedGateway(gateway)))
+        }
+        4 => {
+            let (input, src_user) = parse_sflow_str(input)?;
+            let (input, dst_user) = parse_sflow_str(input)?;
+            Ok((
+                input,
+                FlowRecordRef::ExtendedUser(ExtendedUserRef {
+                    src_charset: 0,
+                    src_user,
+                    dst_charset: 0,
+                    dst_user,
+                }),
+            ))
+        }
+        5 => {
+            let (input, direction) = be_u32(input)?;
+            let (input, url) = parse_sflow_str(input)?;
+            Ok((
+                input,
+                FlowRecordRef::ExtendedUrl(ExtendedUrlRef {
+                    direction,
+                    url,
+                    host: Cow::Borrowed(""),
+                }),
+            ))
+        }
+        _ => Err(switch_error(input)),
+    }
+}
+
+/// The v2 gateway: a flat AS path, no communities and no local preference.
+fn parse_gateway_v2(input: &[u8]) -> IResult<&[u8], ExtendedGateway> {
+    let (input, as_number) = be_u32(input)?;
+    let (input, src_as) = be_u32(input)?;
+    let (input, src_peer_as) = be_u32(input)?;
+    let (input, path_length) = be_u32(input)?;
+
+    // Cap capacity: each AS is 4 bytes
+    let cap = (path_length as usize).min(input.len() / 4);
+    let mut values = Vec::with_capacity(cap);
+    let mut input = input;
+    for _ in 0..path_length {
+        let (rest, val) = be_u32(input)?;
+        values.push(val);
+        input = rest;
+    }
+    let as_path_segments = if values.is_empty() {
+        Vec::new()
+    } else {
+        vec![AsPathSegment {
+            segment_type: AS_SEQUENCE,
+            values,
+        }]
+    };
+
+    Ok((
+        input,
+        ExtendedGateway {
+            next_hop: AddressType::IPv4(Ipv4Addr::UNSPECIFIED),
+            as_number,
+            src_as,
+            src_peer_as,
+            as_path_segments,
+            communities: Vec::new(),
+        },
+    ))
+}
+
+/// The v4 gateway: the v5 layout without `next_hop`, ending in a local
+/// preference that is read and dropped.
+fn parse_gateway_v4(input: &[u8]) -> IResult<&[u8], ExtendedGateway> {
+    let (input, as_number) = be_u32(input)?;
+    let (input, src_as) = be_u32(input)?;
+    let (input, src_peer_as) = be_u32(input)?;
+    let (input, as_path_count) = be_u32(input)?;
+
+    // Cap capacity: each segment needs at least 8 bytes (type + count)
+    let cap = (as_path_count as usize).min(input.len() / 8);
+    let mut as_path_segments = Vec::with_capacity(cap);
+    let mut input = input;
+    for _ in 0..as_path_count {
+        let (rest, segment) = parse_as_path_segment(input)?;
+        as_path_segments.push(segment);
+        input = rest;
+    }
+
+    let (input, communities_count) = be_u32(input)?;
+    // Cap capacity: each community is 4 bytes
+    let cap = (communities_count as usize).min(input.len() / 4);
+    let mut communities = Vec::with_capacity(cap);
+    let mut input = input;
+    for _ in 0..communities_count {
+        let (rest, val) = be_u32(input)?;
+        communities.push(val);
+        input = rest;
+    }
+    let (input, _localpref) = be_u32(input)?;
+
+    Ok((
+        input,
+        ExtendedGateway {
+            next_hop: AddressType::IPv4(Ipv4Addr::UNSPECIFIED),
+            as_number,
+            src_as,
+            src_peer_as,
+            as_path_segments,
+            communities,
+        },
+    ))
+}
+
+fn parse_counter_sample<'a, S: ParsedSample<'a>>(input: &'a [u8]) -> IResult<&'a [u8], S> {
+    let (input, sequence_number) = be_u32(input)?;
+    let (input, source_id) = be_u32(input)?;
+    let source_id_type = source_id >> 24;
+    let source_id_index = source_id & 0x00FF_FFFF;
+    let (input, _sampling_interval) = be_u32(input)?;
+    let (input, counters_version) = be_u32(input)?;
+
+    let mut records = Vec::with_capacity(2);
+    let input = match counters_version {
+        // Generic, Ethernet, Token Ring, FDDI, 100BaseVG and WAN all start
+        // with the generic interface counters
+        1..=6 => {
+            let (input, generic) = parse_generic_interface(input)?;
+            records.push(CounterRecordRef::GenericInterface(generic).into());
+            match counters_version {
+                2 => {
+                    let (input, ethernet) = parse_ethernet_interface(input)?;
+                    records.push(CounterRecordRef::EthernetInterface(ethernet).into());
+                    input
+                }
+                3 => {
+                    let (input, token_ring) = parse_token_ring(input)?;
+                    records.push(CounterRecordRef::TokenRing(token_ring).into());
+                    input
+                }
+                5 => {
+                    let (input, vg) = parse_vg_counters(input)?;
+                    records.push(CounterRecordRef::VgCounters(vg).into());
+                    input
+                }
+                _ => input,
+            }
+        }
+        7 => {
+            let (input, vlan) = parse_vlan(input)?;
+            records.push(CounterRecordRef::Vlan(vlan).into());
+            input
+        }
+        _ => return Err(switch_error(input)),
+    };
+
+    let header = CounterSampleHeader {
+        sequence_number,
+        source_id_type,
+        source_id_index,
+    };
+    Ok((input, S::counter(header, records)))
+}
+
+fn switch_error(input: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))
+}
@@ -1,12 +1,31 @@
 pub mod counter_sample;
+pub mod data_source;
+pub mod discarded_packet;
 pub mod flow_sample;
+mod legacy;
+pub mod normalized;
 
 use nom::number::complete::be_u32;
 use serde::{Deserialize, Serialize};
 
+use crate::counter_records::{CounterRecord, CounterRecordRef};
+use crate::custom::Custom;
+use crate::encode::{write_tagged, write_u32};
 use crate::error::{ParseContext, ParseErrorKind, SflowError};
-pub use counter_sample::{CounterSample, ExpandedCounterSample};
-pub use flow_sample::{ExpandedFlowSample, FlowSample};
+use crate::flow_records::{FlowRecord, FlowRecordRef, Tagged};
+use counter_sample::CounterSampleHeader;
+pub use counter_sample::{
+    CounterSample, CounterSampleRef, ExpandedCounterSample, ExpandedCounterSampleRef,
+};
+pub use data_source::DataSource;
+use discarded_packet::DiscardedPacketHeader;
+pub use discarded_packet::{DiscardedPacket, DiscardedPacketRef, DropReason};
+pub use flow_sample::{
+    ExpandedFlowSample, ExpandedFlowSampleRef, FlowSample, FlowSampleRef, Interface,
+};
+use flow_sample::{ExpandedFlowSampleHeader, FlowSampleHeader};
+pub(crate) use legacy::parse_legacy_samples;
+pub use normalized::{NormalizedCounterSample, NormalizedFlowSample};
 
 /// An sFlow sample carried within a datagram.
 ///
@@ -23,6 +42,8 @@ pub enum SflowSample {
     ExpandedFlow(ExpandedFlowSample),
     /// Expanded counter sample with unpacked source ID (enterprise=0, format=4).
     ExpandedCounter(ExpandedCounterSample),
+    /// Discarded packet notification (enterprise=0, format=5).
+    Discard(DiscardedPacket),
     /// Unrecognized sample type, preserved as raw bytes.
     Unknown {
         /// Enterprise code from the sample header.
@@ -32,51 +53,323 @@ pub enum SflowSample {
         /// Raw sample data.
         data: Vec<u8>,
     },
+    /// Enterprise-specific sample decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
 }
 
-pub(crate) fn parse_samples(
-    mut input: &[u8],
+/// Borrowed form of [`SflowSample`], returned by
+/// [`SflowParser::parse_bytes_ref`](crate::SflowParser::parse_bytes_ref).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SflowSampleRef<'a> {
+    /// Standard flow sample (enterprise=0, format=1).
+    Flow(FlowSampleRef<'a>),
+    /// Standard counter sample (enterprise=0, format=2).
+    Counter(CounterSampleRef<'a>),
+    /// Expanded flow sample with unpacked source ID (enterprise=0, format=3).
+    ExpandedFlow(ExpandedFlowSampleRef<'a>),
+    /// Expanded counter sample with unpacked source ID (enterprise=0, format=4).
+    ExpandedCounter(ExpandedCounterSampleRef<'a>),
+    /// Discarded packet notification (enterprise=0, format=5).
+    Discard(DiscardedPacketRef<'a>),
+    /// Unrecognized sample type, preserved as raw bytes.
+    Unknown {
+        /// Enterprise code from the sample header.
+        enterprise: u32,
+        /// Format code from the sample header.
+        format: u32,
+        /// Raw sample data.
+        data: &'a [u8],
+    },
+    /// Enterprise-specific sample decoded by a decoder registered on
+    /// [`SflowParserBuilder`](crate::SflowParserBuilder).
+    Custom(Custom),
+}
+
+impl<'a> SflowSampleRef<'a> {
+    /// Borrowed counterpart of [`SflowSample::normalized_flow`].
+    pub fn normalized_flow(&self) -> Option<NormalizedFlowSample<'_, FlowRecordRef<'a>>> {
+        match self {
+            SflowSampleRef::Flow(s) => Some(s.into()),
+            SflowSampleRef::ExpandedFlow(s) => Some(s.into()),
+            _ => None,
+        }
+    }
+
+    /// Borrowed counterpart of [`SflowSample::normalized_counter`].
+    pub fn normalized_counter(
+        &self,
+    ) -> Option<NormalizedCounterSample<'_, CounterRecordRef<'a>>> {
+        match self {
+            SflowSampleRef::Counter(s) => Some(s.into()),
+            SflowSampleRef::ExpandedCounter(s) => Some(s.into()),
+            _ => None,
+        }
+    }
+
+    /// The [`DataSource`] this sample was taken from, or `None` for unknown samples.
+    pub fn data_source(&self) -> Option<DataSource> {
+        let (source_id_type, source_id_index) = match self {
+            SflowSampleRef::Flow(s) => (s.source_id_type, s.source_id_index),
+            SflowSampleRef::Counter(s) => (s.source_id_type, s.source_id_index),
+            SflowSampleRef::ExpandedFlow(s) => (s.source_id_type, s.source_id_index),
+            SflowSampleRef::ExpandedCounter(s) => (s.source_id_type, s.source_id_index),
+            SflowSampleRef::Discard(s) => (s.source_id_type, s.source_id_index),
+            SflowSampleRef::Unknown { .. } | SflowSampleRef::Custom(_) => return None,
+        };
+        Some(DataSource::new(source_id_type, source_id_index))
+    }
+
+    /// Convert into the owned [`SflowSample`], copying any borrowed data.
+    pub fn into_owned(self) -> SflowSample {
+        match self {
+            SflowSampleRef::Flow(s) => SflowSample::Flow(s.into_owned()),
+            SflowSampleRef::Counter(s) => SflowSample::Counter(s.into_owned()),
+            SflowSampleRef::ExpandedFlow(s) => SflowSample::ExpandedFlow(s.into_owned()),
+            SflowSampleRef::ExpandedCounter(s) => SflowSample::ExpandedCounter(s.into_owned()),
+            SflowSampleRef::Discard(s) => SflowSample::Discard(s.into_owned()),
+            SflowSampleRef::Unknown {
+                enterprise,
+                format,
+                data,
+            } => SflowSample::Unknown {
+                enterprise,
+                format,
+                data: data.to_vec(),
+            },
+            SflowSampleRef::Custom(c) => SflowSample::Custom(c),
+        }
+    }
+}
+
+impl SflowSample {
+    /// View a [`Flow`](SflowSample::Flow) or [`ExpandedFlow`](SflowSample::ExpandedFlow)
+    /// sample in a common shape. Returns `None` for other sample types.
+    pub fn normalized_flow(&self) -> Option<NormalizedFlowSample<'_>> {
+        match self {
+            SflowSample::Flow(s) => Some(s.into()),
+            SflowSample::ExpandedFlow(s) => Some(s.into()),
+            _ => None,
+        }
+    }
+
+    /// View a [`Counter`](SflowSample::Counter) or
+    /// [`ExpandedCounter`](SflowSample::ExpandedCounter) sample in a common shape.
+    /// Returns `None` for other sample types.
+    pub fn normalized_counter(&self) -> Option<NormalizedCounterSample<'_>> {
+        match self {
+            SflowSample::Counter(s) => Some(s.into()),
+            SflowSample::ExpandedCounter(s) => Some(s.into()),
+            _ => None,
+        }
+    }
+
+    /// The [`DataSource`] this sample was taken from, or `None` for unknown samples.
+    pub fn data_source(&self) -> Option<DataSource> {
+        match self {
+            SflowSample::Flow(s) => Some(s.data_source()),
+            SflowSample::Counter(s) => Some(s.data_source()),
+            SflowSample::ExpandedFlow(s) => Some(s.data_source()),
+            SflowSample::ExpandedCounter(s) => Some(s.data_source()),
+            SflowSample::Discard(s) => Some(s.data_source()),
+            SflowSample::Unknown { .. } | SflowSample::Custom(_) => None,
+        }
+    }
+
+    /// The `(enterprise, format)` pair identifying this sample type on the wire.
+    pub fn enterprise_format(&self) -> (u32, u32) {
+        match self {
+            SflowSample::Flow(_) => (0, 1),
+            SflowSample::Counter(_) => (0, 2),
+            SflowSample::ExpandedFlow(_) => (0, 3),
+            SflowSample::ExpandedCounter(_) => (0, 4),
+            SflowSample::Discard(_) => (0, 5),
+            SflowSample::Unknown {
+                enterprise, format, ..
+            } => (*enterprise, *format),
+            SflowSample::Custom(c) => (c.enterprise, c.format),
+        }
+    }
+
+    /// Append the XDR encoding of this sample, including its
+    /// `data_format` and `length` header, to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let (enterprise, format) = self.enterprise_format();
+        write_tagged(out, enterprise, format, |out| match self {
+            SflowSample::Flow(s) => flow_sample::write_flow_sample(out, s),
+            SflowSample::Counter(s) => counter_sample::write_counter_sample(out, s),
+            SflowSample::ExpandedFlow(s) => flow_sample::write_expanded_flow_sample(out, s),
+            SflowSample::ExpandedCounter(s) => {
+                counter_sample::write_expanded_counter_sample(out, s)
+            }
+            SflowSample::Discard(s) => discarded_packet::write_discarded_packet(out, s),
+            SflowSample::Unknown { data, .. } => out.extend_from_slice(data),
+            SflowSample::Custom(c) => out.extend_from_slice(&c.data),
+        });
+    }
+
+    /// Encode this sample, including its header, into a new buffer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+/// A sample as built by the parsers: [`SflowSample`], or [`SflowSampleRef`]
+/// borrowing from the input.
+///
+/// Records are converted to the target type as they are decoded, so owned
+/// parsing does not build a borrowed sample first.
+pub(crate) trait ParsedSample<'a>: Sized {
+    type FlowRecord: From<FlowRecordRef<'a>>;
+    type CounterRecord: From<CounterRecordRef<'a>>;
+
+    fn flow(header: FlowSampleHeader, records: Vec<Self::FlowRecord>) -> Self;
+    fn expanded_flow(header: ExpandedFlowSampleHeader, records: Vec<Self::FlowRecord>) -> Self;
+    fn counter(header: CounterSampleHeader, records: Vec<Self::CounterRecord>) -> Self;
+    fn expanded_counter(header: CounterSampleHeader, records: Vec<Self::CounterRecord>)
+    -> Self;
+    fn discard(header: DiscardedPacketHeader, records: Vec<Self::FlowRecord>) -> Self;
+    fn unknown(enterprise: u32, format: u32, data: &'a [u8]) -> Self;
+}
+
+impl<'a> ParsedSample<'a> for SflowSample {
+    type FlowRecord = FlowRecord;
+    type CounterRecord = CounterRecord;
+
+    fn flow(header: FlowSampleHeader, records: Vec<FlowRecord>) -> Self {
+        SflowSample::Flow(header.into_sample(records))
+    }
+
+    fn expanded_flow(header: ExpandedFlowSampleHeader, records: Vec<FlowRecord>) -> Self {
+        SflowSample::ExpandedFlow(header.into_sample(records))
+    }
+
+    fn counter(header: CounterSampleHeader, records: Vec<CounterRecord>) -> Self {
+        SflowSample::Counter(header.into_sample(records))
+    }
+
+    fn expanded_counter(header: CounterSampleHeader, records: Vec<CounterRecord>) -> Self {
+        SflowSample::ExpandedCounter(header.into_expanded(records))
+    }
+
+    fn discard(header: DiscardedPacketHeader, records: Vec<FlowRecord>) -> Self {
+        SflowSample::Discard(header.into_sample(records))
+    }
+
+    fn unknown(enterprise: u32, format: u32, data: &'a [u8]) -> Self {
+        SflowSample::Unknown {
+            enterprise,
+            format,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl<'a> ParsedSample<'a> for SflowSampleRef<'a> {
+    type FlowRecord = FlowRecordRef<'a>;
+    type CounterRecord = CounterRecordRef<'a>;
+
+    fn flow(header: FlowSampleHeader, records: Vec<FlowRecordRef<'a>>) -> Self {
+        SflowSampleRef::Flow(header.into_sample_ref(records))
+    }
+
+    fn expanded_flow(
+        header: ExpandedFlowSampleHeader,
+        records: Vec<FlowRecordRef<'a>>,
+    ) -> Self {
+        SflowSampleRef::ExpandedFlow(header.into_sample_ref(records))
+    }
+
+    fn counter(header: CounterSampleHeader, records: Vec<CounterRecordRef<'a>>) -> Self {
+        SflowSampleRef::Counter(header.into_sample_ref(records))
+    }
+
+    fn expanded_counter(
+        header: CounterSampleHeader,
+        records: Vec<CounterRecordRef<'a>>,
+    ) -> Self {
+        SflowSampleRef::ExpandedCounter(header.into_expanded_ref(records))
+    }
+
+    fn discard(header: DiscardedPacketHeader, records: Vec<FlowRecordRef<'a>>) -> Self {
+        SflowSampleRef::Discard(header.into_sample_ref(records))
+    }
+
+    fn unknown(enterprise: u32, format: u32, data: &'a [u8]) -> Self {
+        SflowSampleRef::Unknown {
+            enterprise,
+            format,
+            data,
+        }
+    }
+}
+
+pub(crate) fn parse_samples<'a, S: ParsedSample<'a>>(
+    mut input: &'a [u8],
     num_samples: u32,
-) -> Result<(&[u8], Vec<SflowSample>), SflowError> {
+) -> Result<(&'a [u8], Vec<S>), SflowError> {
     // Cap capacity to prevent DoS: each sample needs at least 8 bytes (format + length)
     let cap = (num_samples as usize).min(input.len() / 8);
     let mut samples = Vec::with_capacity(cap);
 
     for _ in 0..num_samples {
-        let (rest, data_format) =
-            be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
-                SflowError::Incomplete {
-                    available: input.len(),
-                    expected: None,
-                    context: ParseContext::SampleDataFormat,
-                }
-            })?;
+        let (after_sample, (enterprise, format, sample_data)) = split_sample(input)?;
+        samples.push(parse_sample(enterprise, format, sample_data)?);
+        input = after_sample;
+    }
 
-        let enterprise = data_format >> 12;
-        let format = data_format & 0xFFF;
+    Ok((input, samples))
+}
 
-        let (rest, sample_length) =
-            be_u32(rest).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
-                SflowError::Incomplete {
-                    available: rest.len(),
-                    expected: None,
-                    context: ParseContext::SampleLength,
-                }
-            })?;
+/// Split the next sample off `input`, returning its `(enterprise, format, data)`
+/// without decoding the body.
+pub(crate) fn split_sample(input: &[u8]) -> Result<(&[u8], Tagged<'_>), SflowError> {
+    let (rest, data_format) =
+        be_u32(input).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
+            SflowError::Incomplete {
+                available: input.len(),
+                expected: None,
+                context: ParseContext::SampleDataFormat,
+            }
+        })?;
+
+    let enterprise = data_format >> 12;
+    let format = data_format & 0xFFF;
 
-        let sample_length = sample_length as usize;
-        if rest.len() < sample_length {
-            return Err(SflowError::Incomplete {
+    let (rest, sample_length) =
+        be_u32(rest).map_err(|_: nom::Err<nom::error::Error<&[u8]>>| {
+            SflowError::Incomplete {
                 available: rest.len(),
-                expected: Some(sample_length),
-                context: ParseContext::SampleData,
-            });
-        }
+                expected: None,
+                context: ParseContext::SampleLength,
+            }
+        })?;
 
-        let sample_data = &rest[..sample_length];
-        let after_sample = &rest[sample_length..];
+    let sample_length = sample_length as usize;
+    if rest.len() < sample_length {
+        return Err(SflowError::Incomplete {
+            available: rest.len(),
+            expected: Some(sample_length),
+            context: ParseContext::SampleData,
+        });
+    }
 
-        let sample = if enterprise == 0 {
+    Ok((
+        &rest[sample_length..],
+        (enterprise, format, &rest[..sample_length]),
+    ))
+}
+
+/// Decode a single sample body identified by `enterprise` and `format`.
+pub(crate) fn parse_sample<'a, S: ParsedSample<'a>>(
+    enterprise: u32,
+    format: u32,
+    sample_data: &'a [u8],
+) -> Result<S, SflowError> {
+    let sample =
+        if enterprise == 0 {
             match format {
                 1 => {
                     let (_, fs) = flow_sample::parse_flow_sample(sample_data).map_err(|e| {
@@ -86,7 +379,7 @@ pub(crate) fn parse_samples(
                             kind: nom_err_to_kind(&e),
                         }
                     })?;
-                    SflowSample::Flow(fs)
+                    fs
                 }
                 2 => {
                     let (_, cs) =
@@ -97,7 +390,7 @@ pub(crate) fn parse_samples(
                                 kind: nom_err_to_kind(&e),
                             }
                         })?;
-                    SflowSample::Counter(cs)
+                    cs
                 }
                 3 => {
                     let (_, efs) = flow_sample::parse_expanded_flow_sample(sample_data)
@@ -106,7 +399,7 @@ pub(crate) fn parse_samples(
                             context: ParseContext::ExpandedFlowSample,
                             kind: nom_err_to_kind(&e),
                         })?;
-                    SflowSample::ExpandedFlow(efs)
+                    efs
                 }
                 4 => {
                     let (_, ecs) = counter_sample::parse_expanded_counter_sample(sample_data)
@@ -115,32 +408,36 @@ pub(crate) fn parse_samples(
                         context: ParseContext::ExpandedCounterSample,
                         kind: nom_err_to_kind(&e),
                     })?;
-                    SflowSample::ExpandedCounter(ecs)
+                    ecs
+                }
+                5 => {
+                    let (_, dp) = discarded_packet::parse_discarded_packet(sample_data)
+                        .map_err(|e| SflowError::ParseError {
+                            offset: 0,
+                            context: ParseContext::DiscardedPacket,
+                            kind: nom_err_to_kind(&e),
+                        })?;
+                    dp
                 }
-                _ => SflowSample::Unknown {
-                    enterprise,
-                    format,
-                    data: sample_data.to_vec(),
-                },
+                _ => S::unknown(enterprise, format, sample_data),
             }
         } else {
-            SflowSample::Unknown {
-                enterprise,
-                format,
-                data: sample_data.to_vec(),
-            }
+            S::unknown(enterprise, format, sample_data)
         };
 
-        samples.push(sample);
-        input = after_sample;
-    }
-
-    Ok((input, samples))
+    Ok(sample)
 }
 
-fn nom_err_to_kind(e: &nom::Err<nom::error::Error<&[u8]>>) -> ParseErrorKind {
+pub(crate) fn nom_err_to_kind(e: &nom::Err<nom::error::Error<&[u8]>>) -> ParseErrorKind {
     match e {
         nom::Err::Error(e) | nom::Err::Failure(e) => ParseErrorKind::NomError(e.code),
         nom::Err::Incomplete(_) => ParseErrorKind::NomError(nom::error::ErrorKind::Complete),
     }
 }
+
+pub(crate) fn write_samples(out: &mut Vec<u8>, samples: &[SflowSample]) {
+    write_u32(out, samples.len() as u32);
+    for sample in samples {
+        sample.encode(out);
+    }
+}
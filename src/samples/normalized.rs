@@ -0,0 +1,197 @@
+use serde::Serialize;
+
+use crate::counter_records::{CounterRecord, CounterRecordRef};
+use crate::flow_records::{FlowRecord, FlowRecordRef};
+use crate::samples::{
+    CounterSample, CounterSampleRef, DataSource, ExpandedCounterSample,
+    ExpandedCounterSampleRef, ExpandedFlowSample, ExpandedFlowSampleRef, FlowSample,
+    FlowSampleRef, Interface,
+};
+
+/// A flow sample with the compact/expanded encoding differences removed.
+///
+/// Returned by [`SflowSample::normalized_flow`](crate::SflowSample::normalized_flow)
+/// for both [`FlowSample`] and [`ExpandedFlowSample`], and by
+/// [`SflowSampleRef::normalized_flow`](crate::SflowSampleRef::normalized_flow)
+/// with borrowed records.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NormalizedFlowSample<'a, R = FlowRecord> {
+    pub sequence_number: u32,
+    pub data_source: DataSource,
+    pub sampling_rate: u32,
+    pub sample_pool: u32,
+    pub drops: u32,
+    pub input: Interface,
+    pub output: Interface,
+    pub records: &'a [R],
+}
+
+/// A counter sample with the compact/expanded encoding differences removed.
+///
+/// Returned by [`SflowSample::normalized_counter`](crate::SflowSample::normalized_counter)
+/// for both [`CounterSample`] and [`ExpandedCounterSample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NormalizedCounterSample<'a, R = CounterRecord> {
+    pub sequence_number: u32,
+    pub data_source: DataSource,
+    pub records: &'a [R],
+}
+
+impl NormalizedFlowSample<'_> {
+    /// Length of the sampled packet, preferring a raw packet header's
+    /// `frame_length` over sampled Ethernet/IPv4/IPv6 lengths.
+    pub fn frame_length(&self) -> Option<u32> {
+        self.records
+            .iter()
+            .find(|r| matches!(r, FlowRecord::RawPacketHeader(_)))
+            .or_else(|| self.records.iter().find(|r| r.frame_length().is_some()))
+            .and_then(FlowRecord::frame_length)
+    }
+}
+
+impl NormalizedFlowSample<'_, FlowRecordRef<'_>> {
+    /// Borrowed counterpart of [`NormalizedFlowSample::frame_length`].
+    pub fn frame_length(&self) -> Option<u32> {
+        self.records
+            .iter()
+            .find(|r| matches!(r, FlowRecordRef::RawPacketHeader(_)))
+            .or_else(|| self.records.iter().find(|r| r.frame_length().is_some()))
+            .and_then(FlowRecordRef::frame_length)
+    }
+}
+
+impl<'a> From<&'a FlowSample> for NormalizedFlowSample<'a> {
+    fn from(s: &'a FlowSample) -> Self {
+        NormalizedFlowSample {
+            sequence_number: s.sequence_number,
+            data_source: s.data_source(),
+            sampling_rate: s.sampling_rate,
+            sample_pool: s.sample_pool,
+            drops: s.drops,
+            input: s.input_interface(),
+            output: s.output_interface(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a> From<&'a ExpandedFlowSample> for NormalizedFlowSample<'a> {
+    fn from(s: &'a ExpandedFlowSample) -> Self {
+        NormalizedFlowSample {
+            sequence_number: s.sequence_number,
+            data_source: s.data_source(),
+            sampling_rate: s.sampling_rate,
+            sample_pool: s.sample_pool,
+            drops: s.drops,
+            input: s.input_interface(),
+            output: s.output_interface(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a, 'b> From<&'a FlowSampleRef<'b>> for NormalizedFlowSample<'a, FlowRecordRef<'b>> {
+    fn from(s: &'a FlowSampleRef<'b>) -> Self {
+        NormalizedFlowSample {
+            sequence_number: s.sequence_number,
+            data_source: DataSource::new(s.source_id_type, s.source_id_index),
+            sampling_rate: s.sampling_rate,
+            sample_pool: s.sample_pool,
+            drops: s.drops,
+            input: s.input_interface(),
+            output: s.output_interface(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a, 'b> From<&'a ExpandedFlowSampleRef<'b>>
+    for NormalizedFlowSample<'a, FlowRecordRef<'b>>
+{
+    fn from(s: &'a ExpandedFlowSampleRef<'b>) -> Self {
+        NormalizedFlowSample {
+            sequence_number: s.sequence_number,
+            data_source: DataSource::new(s.source_id_type, s.source_id_index),
+            sampling_rate: s.sampling_rate,
+            sample_pool: s.sample_pool,
+            drops: s.drops,
+            input: s.input_interface(),
+            output: s.output_interface(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a> From<&'a CounterSample> for NormalizedCounterSample<'a> {
+    fn from(s: &'a CounterSample) -> Self {
+        NormalizedCounterSample {
+            sequence_number: s.sequence_number,
+            data_source: s.data_source(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a> From<&'a ExpandedCounterSample> for NormalizedCounterSample<'a> {
+    fn from(s: &'a ExpandedCounterSample) -> Self {
+        NormalizedCounterSample {
+            sequence_number: s.sequence_number,
+            data_source: s.data_source(),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a, 'b> From<&'a CounterSampleRef<'b>>
+    for NormalizedCounterSample<'a, CounterRecordRef<'b>>
+{
+    fn from(s: &'a CounterSampleRef<'b>) -> Self {
+        NormalizedCounterSample {
+            sequence_number: s.sequence_number,
+            data_source: DataSource::new(s.source_id_type, s.source_id_index),
+            records: &s.records,
+        }
+    }
+}
+
+impl<'a, 'b> From<&'a ExpandedCounterSampleRef<'b>>
+    for NormalizedCounterSample<'a, CounterRecordRef<'b>>
+{
+    fn from(s: &'a ExpandedCounterSampleRef<'b>) -> Self {
+        NormalizedCounterSample {
+            sequence_number: s.sequence_number,
+            data_source: DataSource::new(s.source_id_type, s.source_id_index),
+            records: &s.records,
+        }
+    }
+}
+
+impl From<FlowSample> for ExpandedFlowSample {
+    /// Unpack the compact interface fields; the conversion is lossless.
+    fn from(s: FlowSample) -> Self {
+        ExpandedFlowSample {
+            sequence_number: s.sequence_number,
+            source_id_type: s.source_id_type,
+            source_id_index: s.source_id_index,
+            sampling_rate: s.sampling_rate,
+            sample_pool: s.sample_pool,
+            drops: s.drops,
+            input_format: s.input >> 30,
+            input_value: s.input & 0x3FFF_FFFF,
+            output_format: s.output >> 30,
+            output_value: s.output & 0x3FFF_FFFF,
+            records: s.records,
+        }
+    }
+}
+
+impl From<CounterSample> for ExpandedCounterSample {
+    fn from(s: CounterSample) -> Self {
+        ExpandedCounterSample {
+            sequence_number: s.sequence_number,
+            source_id_type: s.source_id_type,
+            source_id_index: s.source_id_index,
+            records: s.records,
+        }
+    }
+}
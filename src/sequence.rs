@@ -0,0 +1,435 @@
+//! Datagram and sample sequence tracking for loss detection.
+//!
+//! Agents number datagrams per `(agent_address, sub_agent_id)` and samples
+//! per data source and sample type. [`SequenceTracker`] remembers the last
+//! sequence number of each stream and classifies every new one as in order,
+//! a gap, a duplicate, a late (reordered) arrival or a reset, keeping
+//! [`SequenceStats`] per stream.
+//!
+//! ```
+//! use flowparser_sflow::{AddressType, SflowDatagram, SequenceTracker};
+//! use flowparser_sflow::sequence::SequenceStatus;
+//! use std::net::Ipv4Addr;
+//!
+//! let mut tracker = SequenceTracker::default();
+//! let mut dg = SflowDatagram {
+//!     version: 5,
+//!     agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+//!     sub_agent_id: 0,
+//!     sequence_number: 1,
+//!     uptime: 1000,
+//!     samples: vec![],
+//! };
+//! assert_eq!(tracker.observe(&dg).datagram, SequenceStatus::First);
+//!
+//! dg.sequence_number = 4;
+//! assert_eq!(tracker.observe(&dg).datagram, SequenceStatus::Gap { missing: 2 });
+//! assert_eq!(tracker.datagram_totals().lost, 2);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::datagram::{AddressType, SflowDatagram, SflowDatagramRef};
+use crate::samples::{DataSource, SflowSample, SflowSampleRef};
+
+/// Identifies the datagram stream of one sFlow agent or sub-agent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AgentKey {
+    pub agent_address: AddressType,
+    pub sub_agent_id: u32,
+}
+
+/// The sample types that carry independent sequence numbers.
+///
+/// Compact and expanded encodings of the same type share a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SampleKind {
+    Flow,
+    Counter,
+    Discard,
+}
+
+/// Identifies the sample stream of one data source on one agent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SampleKey {
+    pub agent: AgentKey,
+    pub data_source: DataSource,
+    pub kind: SampleKind,
+}
+
+/// Classification of a sequence number relative to the last one seen on its stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SequenceStatus {
+    /// First sequence number seen on this stream.
+    First,
+    /// Exactly one more than the previous sequence number.
+    InOrder,
+    /// Ahead of the expected sequence number; `missing` numbers were skipped.
+    Gap { missing: u32 },
+    /// Already received: equal to the previous sequence number, or behind
+    /// it within the reorder window without filling a gap.
+    Duplicate,
+    /// Behind the previous sequence number, within the reorder window, and
+    /// filling a gap left earlier.
+    Reordered,
+    /// Too far from the previous sequence number to be a gap or a late
+    /// arrival, typically because the agent restarted. Tracking restarts here.
+    Reset,
+}
+
+/// Per-stream sequence counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceStats {
+    /// Sequence numbers observed, including duplicates.
+    pub received: u64,
+    /// Sequence numbers skipped by gaps and not later recovered by reordering.
+    pub lost: u64,
+    pub duplicates: u64,
+    pub reordered: u64,
+    pub resets: u64,
+    /// Last in-order sequence number.
+    pub last: u32,
+}
+
+impl SequenceStats {
+    /// Fraction of expected sequence numbers that were lost.
+    pub fn loss_ratio(&self) -> f64 {
+        let unique = self.received - self.duplicates;
+        let expected = unique + self.lost;
+        if expected == 0 {
+            0.0
+        } else {
+            self.lost as f64 / expected as f64
+        }
+    }
+
+    fn add(&mut self, other: &SequenceStats) {
+        self.received += other.received;
+        self.lost += other.lost;
+        self.duplicates += other.duplicates;
+        self.reordered += other.reordered;
+        self.resets += other.resets;
+    }
+}
+
+/// The status of one sample's sequence number within an observed datagram.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleSequence {
+    pub data_source: DataSource,
+    pub kind: SampleKind,
+    pub sequence_number: u32,
+    pub status: SequenceStatus,
+}
+
+/// Result of [`SequenceTracker::observe`] for one datagram.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceReport {
+    /// Status of the datagram sequence number.
+    pub datagram: SequenceStatus,
+    /// Status of each flow, counter and discard sample, in datagram order.
+    /// Unknown samples are skipped.
+    pub samples: Vec<SampleSequence>,
+}
+
+/// Stateful tracker of datagram and sample sequence numbers.
+///
+/// Sequence numbers wrap at 2^32. A number up to `max_gap` ahead of the
+/// expected one is a gap; one up to `reorder_window` behind the last is a late
+/// arrival that recovers one lost number if it was still missing, and a
+/// duplicate otherwise; anything else is a reset.
+///
+/// A backward number that was never seen on the stream is a reset too. When
+/// an agent restarts at a number it already sent, the first number is
+/// reported as a duplicate; the one right after it is reported as the reset
+/// and the duplicate is taken back out of [`SequenceStats`].
+#[derive(Debug, Clone)]
+pub struct SequenceTracker {
+    max_gap: u32,
+    reorder_window: u32,
+    datagrams: HashMap<AgentKey, Stream>,
+    samples: HashMap<SampleKey, Stream>,
+}
+
+/// Counters of one stream and the numbers it is still missing within the
+/// reorder window, oldest first.
+#[derive(Debug, Clone, Default)]
+struct Stream {
+    stats: SequenceStats,
+    missing: VecDeque<u32>,
+    /// How far behind `last` the stream has been followed, capped at the
+    /// reorder window. Older numbers were never seen and cannot repeat.
+    span: u32,
+    /// The last backward number counted as a duplicate, which starts a new
+    /// sequence if the next number continues from it.
+    restart: Option<u32>,
+}
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        SequenceTracker::builder().build()
+    }
+}
+
+impl SequenceTracker {
+    /// Create a builder for configuring the tracker.
+    pub fn builder() -> SequenceTrackerBuilder {
+        SequenceTrackerBuilder {
+            max_gap: 10_000,
+            reorder_window: 64,
+        }
+    }
+
+    /// Record a datagram and all of its samples.
+    pub fn observe(&mut self, datagram: &SflowDatagram) -> SequenceReport {
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        let samples = datagram
+            .samples
+            .iter()
+            .filter_map(|sample| {
+                let (kind, sequence_number) = sample_sequence(sample)?;
+                Some((sample.data_source()?, kind, sequence_number))
+            })
+            .collect::<Vec<_>>();
+        self.observe_parts(agent, datagram.sequence_number, samples)
+    }
+
+    /// Record a borrowed datagram and all of its samples.
+    pub fn observe_ref(&mut self, datagram: &SflowDatagramRef<'_>) -> SequenceReport {
+        let agent = AgentKey {
+            agent_address: datagram.agent_address.clone(),
+            sub_agent_id: datagram.sub_agent_id,
+        };
+        let samples = datagram
+            .samples
+            .iter()
+            .filter_map(|sample| {
+                let (kind, sequence_number) = sample_ref_sequence(sample)?;
+                Some((sample.data_source()?, kind, sequence_number))
+            })
+            .collect::<Vec<_>>();
+        self.observe_parts(agent, datagram.sequence_number, samples)
+    }
+
+    /// Record a datagram sequence number on its own.
+    pub fn observe_datagram(
+        &mut self,
+        agent: AgentKey,
+        sequence_number: u32,
+    ) -> SequenceStatus {
+        let stream = self.datagrams.entry(agent).or_default();
+        classify(stream, sequence_number, self.max_gap, self.reorder_window)
+    }
+
+    /// Record a sample sequence number on its own.
+    pub fn observe_sample(&mut self, key: SampleKey, sequence_number: u32) -> SequenceStatus {
+        let stream = self.samples.entry(key).or_default();
+        classify(stream, sequence_number, self.max_gap, self.reorder_window)
+    }
+
+    /// Counters for one agent's datagram stream.
+    pub fn datagram_stats(&self, agent: &AgentKey) -> Option<&SequenceStats> {
+        self.datagrams.get(agent).map(|s| &s.stats)
+    }
+
+    /// Counters for one data source's sample stream.
+    pub fn sample_stats(&self, key: &SampleKey) -> Option<&SequenceStats> {
+        self.samples.get(key).map(|s| &s.stats)
+    }
+
+    /// Iterate over all datagram streams.
+    pub fn datagram_streams(&self) -> impl Iterator<Item = (&AgentKey, &SequenceStats)> {
+        self.datagrams.iter().map(|(k, s)| (k, &s.stats))
+    }
+
+    /// Iterate over all sample streams.
+    pub fn sample_streams(&self) -> impl Iterator<Item = (&SampleKey, &SequenceStats)> {
+        self.samples.iter().map(|(k, s)| (k, &s.stats))
+    }
+
+    /// Counters summed over all datagram streams. `last` is left at 0.
+    pub fn datagram_totals(&self) -> SequenceStats {
+        let mut total = SequenceStats::default();
+        self.datagrams.values().for_each(|s| total.add(&s.stats));
+        total
+    }
+
+    /// Counters summed over all sample streams. `last` is left at 0.
+    pub fn sample_totals(&self) -> SequenceStats {
+        let mut total = SequenceStats::default();
+        self.samples.values().for_each(|s| total.add(&s.stats));
+        total
+    }
+
+    /// Forget all state for an agent's datagram and sample streams.
+    pub fn remove_agent(&mut self, agent: &AgentKey) {
+        self.datagrams.remove(agent);
+        self.samples.retain(|key, _| key.agent != *agent);
+    }
+
+    fn observe_parts(
+        &mut self,
+        agent: AgentKey,
+        sequence_number: u32,
+        samples: Vec<(DataSource, SampleKind, u32)>,
+    ) -> SequenceReport {
+        let samples = samples
+            .into_iter()
+            .map(|(data_source, kind, sequence_number)| {
+                let key = SampleKey {
+                    agent: agent.clone(),
+                    data_source,
+                    kind,
+                };
+                SampleSequence {
+                    data_source,
+                    kind,
+                    sequence_number,
+                    status: self.observe_sample(key, sequence_number),
+                }
+            })
+            .collect();
+        SequenceReport {
+            datagram: self.observe_datagram(agent, sequence_number),
+            samples,
+        }
+    }
+}
+
+/// Builder for configuring a [`SequenceTracker`].
+#[derive(Debug, Clone)]
+pub struct SequenceTrackerBuilder {
+    max_gap: u32,
+    reorder_window: u32,
+}
+
+impl SequenceTrackerBuilder {
+    /// Largest forward jump treated as a gap rather than a reset (default 10000).
+    pub fn with_max_gap(mut self, max_gap: u32) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Largest backward step treated as a late arrival rather than a reset (default 64).
+    pub fn with_reorder_window(mut self, reorder_window: u32) -> Self {
+        self.reorder_window = reorder_window;
+        self
+    }
+
+    /// Build the configured [`SequenceTracker`].
+    pub fn build(self) -> SequenceTracker {
+        SequenceTracker {
+            max_gap: self.max_gap,
+            reorder_window: self.reorder_window,
+            datagrams: HashMap::new(),
+            samples: HashMap::new(),
+        }
+    }
+}
+
+fn classify(
+    stream: &mut Stream,
+    sequence_number: u32,
+    max_gap: u32,
+    reorder_window: u32,
+) -> SequenceStatus {
+    let stats = &mut stream.stats;
+    let first = stats.received == 0;
+    stats.received += 1;
+    if first {
+        stats.last = sequence_number;
+        return SequenceStatus::First;
+    }
+
+    let ahead = sequence_number.wrapping_sub(stats.last);
+    let behind = stats.last.wrapping_sub(sequence_number);
+    if ahead == 0 {
+        stats.duplicates += 1;
+        SequenceStatus::Duplicate
+    } else if ahead == 1 {
+        stats.last = sequence_number;
+        stream.span = stream.span.saturating_add(1).min(reorder_window);
+        stream.restart = None;
+        expire_missing(stream, reorder_window);
+        SequenceStatus::InOrder
+    } else if ahead <= max_gap.saturating_add(1) {
+        // Only the skipped numbers that can still arrive late are remembered
+        let first_kept = ahead.saturating_sub(reorder_window).max(1);
+        stream
+            .missing
+            .extend((first_kept..ahead).map(|k| stats.last.wrapping_add(k)));
+        stats.last = sequence_number;
+        stats.lost += u64::from(ahead - 1);
+        stream.span = stream.span.saturating_add(ahead).min(reorder_window);
+        stream.restart = None;
+        expire_missing(stream, reorder_window);
+        SequenceStatus::Gap { missing: ahead - 1 }
+    } else if behind <= stream.span {
+        if let Some(pos) = stream.missing.iter().position(|&n| n == sequence_number) {
+            stream.missing.remove(pos);
+            stats.reordered += 1;
+            stats.lost = stats.lost.saturating_sub(1);
+            stream.restart = None;
+            return SequenceStatus::Reordered;
+        }
+        match stream.restart {
+            // The agent restarted at `start`, which was not a duplicate after all
+            Some(start) if sequence_number == start.wrapping_add(1) => {
+                stats.duplicates = stats.duplicates.saturating_sub(1);
+                reset(stream, sequence_number, 1)
+            }
+            _ => {
+                stats.duplicates += 1;
+                stream.restart = Some(sequence_number);
+                SequenceStatus::Duplicate
+            }
+        }
+    } else {
+        reset(stream, sequence_number, 0)
+    }
+}
+
+/// Restart tracking at `sequence_number`, with `span` numbers already seen behind it.
+fn reset(stream: &mut Stream, sequence_number: u32, span: u32) -> SequenceStatus {
+    stream.stats.last = sequence_number;
+    stream.stats.resets += 1;
+    stream.missing.clear();
+    stream.span = span;
+    stream.restart = None;
+    SequenceStatus::Reset
+}
+
+/// Drop missing numbers that fell out of the reorder window behind `last`.
+fn expire_missing(stream: &mut Stream, reorder_window: u32) {
+    let last = stream.stats.last;
+    while let Some(&n) = stream.missing.front()
+        && last.wrapping_sub(n) > reorder_window
+    {
+        stream.missing.pop_front();
+    }
+}
+
+fn sample_sequence(sample: &SflowSample) -> Option<(SampleKind, u32)> {
+    match sample {
+        SflowSample::Flow(s) => Some((SampleKind::Flow, s.sequence_number)),
+        SflowSample::ExpandedFlow(s) => Some((SampleKind::Flow, s.sequence_number)),
+        SflowSample::Counter(s) => Some((SampleKind::Counter, s.sequence_number)),
+        SflowSample::ExpandedCounter(s) => Some((SampleKind::Counter, s.sequence_number)),
+        SflowSample::Discard(s) => Some((SampleKind::Discard, s.sequence_number)),
+        SflowSample::Unknown { .. } | SflowSample::Custom(_) => None,
+    }
+}
+
+fn sample_ref_sequence(sample: &SflowSampleRef<'_>) -> Option<(SampleKind, u32)> {
+    match sample {
+        SflowSampleRef::Flow(s) => Some((SampleKind::Flow, s.sequence_number)),
+        SflowSampleRef::ExpandedFlow(s) => Some((SampleKind::Flow, s.sequence_number)),
+        SflowSampleRef::Counter(s) => Some((SampleKind::Counter, s.sequence_number)),
+        SflowSampleRef::ExpandedCounter(s) => Some((SampleKind::Counter, s.sequence_number)),
+        SflowSampleRef::Discard(s) => Some((SampleKind::Discard, s.sequence_number)),
+        SflowSampleRef::Unknown { .. } | SflowSampleRef::Custom(_) => None,
+    }
+}
@@ -0,0 +1,733 @@
+//! sflowtool-compatible text output.
+//!
+//! [`datagram_text`] renders a datagram in the verbose key/value format
+//! printed by `sflowtool` with no options: one `key value` pair per line,
+//! framed by `startDatagram`/`endDatagram` and `startSample`/`endSample`
+//! markers, with a `flowBlock_tag` or `counterBlock_tag` line before every
+//! record. [`datagram_lines`] renders the `sflowtool -l` line format: one
+//! `FLOW,...` line per flow sample and one `CNTR,...` line per generic
+//! interface counter record.
+//!
+//! Raw packet headers, sampled Ethernet/IPv4/IPv6, extended switch, router,
+//! gateway, user and URL records, and generic interface, Ethernet and
+//! processor counters use sflowtool's key names. Every other record prints
+//! its field names as they appear in this crate's types, with nested fields
+//! joined by `_`. With the `packet` feature, raw packet headers are decoded
+//! into the same MAC, IP and port keys as sflowtool, and `FLOW` lines are
+//! filled from them; without it only sampled Ethernet/IPv4/IPv6 records
+//! contribute to `FLOW` lines.
+//!
+//! ```
+//! use flowparser_sflow::SflowParser;
+//! use flowparser_sflow::sflowtool::{DatagramInfo, datagram_lines, datagram_text};
+//! use std::net::{IpAddr, Ipv4Addr};
+//! use std::time::SystemTime;
+//!
+//! let parser = SflowParser::default();
+//! let bytes: &[u8] = &[/* sflow datagram bytes */];
+//! let result = parser.parse_bytes(bytes);
+//! for datagram in &result.datagrams {
+//!     let info = DatagramInfo {
+//!         source: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+//!         size: bytes.len(),
+//!         received: SystemTime::now(),
+//!     };
+//!     print!("{}", datagram_text(datagram, &info));
+//!     print!("{}", datagram_lines(datagram));
+//! }
+//! ```
+
+use std::fmt::{Display, Write};
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+use mac_address::MacAddress;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::counter_records::{CounterRecord, GenericInterface};
+use crate::datagram::SflowDatagram;
+use crate::flow_records::FlowRecord;
+use crate::format::{Ordered, civil_date, reparse, unwrap_variant};
+use crate::samples::{Interface, NormalizedFlowSample, SflowSample};
+
+const DATAGRAM_MARKER: &str = "=================================";
+const SAMPLE_MARKER: &str = "----------------------";
+
+/// Receive details printed in the datagram header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatagramInfo {
+    /// Source address of the UDP packet (`datagramSourceIP`).
+    pub source: IpAddr,
+    /// Size of the UDP payload in bytes (`datagramSize`).
+    pub size: usize,
+    /// Receive time (`unixSecondsUTC` and `localtime`, printed in UTC).
+    pub received: SystemTime,
+}
+
+/// Render `datagram` in sflowtool's verbose key/value format.
+pub fn datagram_text(datagram: &SflowDatagram, info: &DatagramInfo) -> String {
+    let mut out = String::new();
+    let secs = info
+        .received
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    kv(&mut out, "startDatagram", DATAGRAM_MARKER);
+    kv(&mut out, "datagramSourceIP", info.source);
+    kv(&mut out, "datagramSize", info.size);
+    kv(&mut out, "unixSecondsUTC", secs);
+    kv(&mut out, "localtime", utc_time(secs));
+    kv(&mut out, "datagramVersion", datagram.version);
+    kv(&mut out, "agentSubId", datagram.sub_agent_id);
+    kv(&mut out, "agent", &datagram.agent_address);
+    kv(&mut out, "packetSequenceNo", datagram.sequence_number);
+    kv(&mut out, "sysUpTime", datagram.uptime);
+    kv(&mut out, "samplesInPacket", datagram.samples.len());
+    for sample in &datagram.samples {
+        sample_text(&mut out, sample);
+    }
+    out.push_str("endDatagram   ");
+    out.push_str(DATAGRAM_MARKER);
+    out.push('\n');
+    out
+}
+
+/// Render `datagram` in the `sflowtool -l` line format.
+///
+/// Flow samples produce
+/// `FLOW,agent,inputPort,outputPort,srcMAC,dstMAC,ethernetType,in_vlan,out_vlan,srcIP,dstIP,IPProtocol,ipTos,ipTTL,srcPort,dstPort,tcpFlags,packetSize,IPSize,samplingRate`
+/// and generic interface counters produce
+/// `CNTR,agent,ifIndex,ifType,ifSpeed,ifDirection,ifStatus,ifInOctets,ifInUcastPkts,ifInMulticastPkts,ifInBroadcastPkts,ifInDiscards,ifInErrors,ifInUnknownProtos,ifOutOctets,ifOutUcastPkts,ifOutMulticastPkts,ifOutBroadcastPkts,ifOutDiscards,ifOutErrors,ifPromiscuousMode`.
+/// Fields that are not known are printed as zero.
+pub fn datagram_lines(datagram: &SflowDatagram) -> String {
+    let agent = datagram.agent_address.to_string();
+    let mut out = String::new();
+    for sample in &datagram.samples {
+        if let Some(flow) = sample.normalized_flow() {
+            out.push_str(&FlowLine::from_sample(&flow).render(&agent));
+            out.push('\n');
+        } else if let Some(counters) = sample.normalized_counter() {
+            for record in counters.records {
+                if let CounterRecord::GenericInterface(g) = record {
+                    out.push_str(&counter_line(&agent, g));
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Render one flow record as sflowtool key/value lines, starting with its
+/// `flowBlock_tag`.
+pub fn flow_record_text(record: &FlowRecord) -> String {
+    let mut out = String::new();
+    let (enterprise, format) = record.enterprise_format();
+    kv(
+        &mut out,
+        "flowBlock_tag",
+        format_args!("{enterprise}:{format}"),
+    );
+    match record {
+        FlowRecord::RawPacketHeader(r) => {
+            kv(&mut out, "flowSampleType", "HEADER");
+            kv(&mut out, "headerProtocol", r.header_protocol);
+            kv(&mut out, "sampledPacketSize", r.frame_length);
+            kv(&mut out, "strippedBytes", r.stripped);
+            kv(&mut out, "headerLen", r.header_length);
+            let len = (r.header_length as usize).min(r.header.len());
+            kv(&mut out, "headerBytes", hex(&r.header[..len]));
+            #[cfg(feature = "packet")]
+            decoded_header_text(&mut out, r);
+        }
+        FlowRecord::SampledEthernet(r) => {
+            kv(&mut out, "flowSampleType", "ETHERNET");
+            kv(&mut out, "ethernet_type", r.eth_type);
+            kv(&mut out, "ethernet_len", r.length);
+            kv(&mut out, "ethernet_src", mac(&r.src_mac));
+            kv(&mut out, "ethernet_dst", mac(&r.dst_mac));
+        }
+        FlowRecord::SampledIpv4(r) => {
+            kv(&mut out, "flowSampleType", "IPV4");
+            kv(&mut out, "sampledPacketSize", r.length);
+            kv(&mut out, "srcIP", r.src_ip);
+            kv(&mut out, "dstIP", r.dst_ip);
+            kv(&mut out, "IPProtocol", r.protocol);
+            kv(&mut out, "IPTOS", r.tos);
+            transport_text(&mut out, r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+        }
+        FlowRecord::SampledIpv6(r) => {
+            kv(&mut out, "flowSampleType", "IPV6");
+            kv(&mut out, "sampledPacketSize", r.length);
+            kv(&mut out, "srcIP", r.src_ip);
+            kv(&mut out, "dstIP", r.dst_ip);
+            kv(&mut out, "IPProtocol", r.protocol);
+            kv(&mut out, "IPPriority", r.priority);
+            transport_text(&mut out, r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+        }
+        FlowRecord::ExtendedSwitch(r) => {
+            kv(&mut out, "extendedType", "SWITCH");
+            kv(&mut out, "in_vlan", r.src_vlan);
+            kv(&mut out, "in_priority", r.src_priority);
+            kv(&mut out, "out_vlan", r.dst_vlan);
+            kv(&mut out, "out_priority", r.dst_priority);
+        }
+        FlowRecord::ExtendedRouter(r) => {
+            kv(&mut out, "extendedType", "ROUTER");
+            kv(&mut out, "nextHop", &r.next_hop);
+            kv(&mut out, "srcSubnetMask", r.src_mask_len);
+            kv(&mut out, "dstSubnetMask", r.dst_mask_len);
+        }
+        FlowRecord::ExtendedGateway(r) => {
+            kv(&mut out, "extendedType", "GATEWAY");
+            kv(&mut out, "nextHop", &r.next_hop);
+            kv(&mut out, "my_as", r.as_number);
+            kv(&mut out, "src_as", r.src_as);
+            kv(&mut out, "src_peer_as", r.src_peer_as);
+            let path: Vec<u32> = r
+                .as_path_segments
+                .iter()
+                .flat_map(|s| s.values.iter().copied())
+                .collect();
+            kv(&mut out, "dst_as_path_len", path.len());
+            if !path.is_empty() {
+                kv(&mut out, "dst_as_path", join(&path, "-"));
+            }
+            if !r.communities.is_empty() {
+                let communities: Vec<String> = r
+                    .communities
+                    .iter()
+                    .map(|c| format!("{}:{}", c >> 16, c & 0xffff))
+                    .collect();
+                kv(&mut out, "BGP_communities", communities.join("-"));
+            }
+            kv(&mut out, "dst_peer_as", path.first().copied().unwrap_or(0));
+            kv(&mut out, "dst_as", r.dst_as());
+        }
+        FlowRecord::ExtendedUser(r) => {
+            kv(&mut out, "extendedType", "USER");
+            kv(&mut out, "src_user_charset", r.src_charset);
+            kv(&mut out, "src_user", &r.src_user);
+            kv(&mut out, "dst_user_charset", r.dst_charset);
+            kv(&mut out, "dst_user", &r.dst_user);
+        }
+        FlowRecord::ExtendedUrl(r) => {
+            kv(&mut out, "extendedType", "URL");
+            kv(&mut out, "url_direction", r.direction);
+            kv(&mut out, "url", &r.url);
+            kv(&mut out, "host", &r.host);
+        }
+        FlowRecord::Unknown { data, .. } => kv(&mut out, "unknownBytes", hex(data)),
+        FlowRecord::Custom(c) => generic_text(&mut out, &c.value().to_json()),
+        other => generic_text(&mut out, other),
+    }
+    out
+}
+
+/// Render one counter record as sflowtool key/value lines, starting with its
+/// `counterBlock_tag`.
+pub fn counter_record_text(record: &CounterRecord) -> String {
+    let mut out = String::new();
+    let (enterprise, format) = record.enterprise_format();
+    kv(
+        &mut out,
+        "counterBlock_tag",
+        format_args!("{enterprise}:{format}"),
+    );
+    match record {
+        CounterRecord::GenericInterface(r) => {
+            kv(&mut out, "ifIndex", r.if_index);
+            kv(&mut out, "networkType", r.if_type);
+            kv(&mut out, "ifSpeed", r.if_speed);
+            kv(&mut out, "ifDirection", r.if_direction);
+            kv(&mut out, "ifStatus", r.if_status);
+            kv(&mut out, "ifInOctets", r.if_in_octets);
+            kv(&mut out, "ifInUcastPkts", r.if_in_ucast_pkts);
+            kv(&mut out, "ifInMulticastPkts", r.if_in_multicast_pkts);
+            kv(&mut out, "ifInBroadcastPkts", r.if_in_broadcast_pkts);
+            kv(&mut out, "ifInDiscards", r.if_in_discards);
+            kv(&mut out, "ifInErrors", r.if_in_errors);
+            kv(&mut out, "ifInUnknownProtos", r.if_in_unknown_protos);
+            kv(&mut out, "ifOutOctets", r.if_out_octets);
+            kv(&mut out, "ifOutUcastPkts", r.if_out_ucast_pkts);
+            kv(&mut out, "ifOutMulticastPkts", r.if_out_multicast_pkts);
+            kv(&mut out, "ifOutBroadcastPkts", r.if_out_broadcast_pkts);
+            kv(&mut out, "ifOutDiscards", r.if_out_discards);
+            kv(&mut out, "ifOutErrors", r.if_out_errors);
+            kv(&mut out, "ifPromiscuousMode", r.if_promiscuous_mode);
+        }
+        CounterRecord::EthernetInterface(r) => {
+            kv(
+                &mut out,
+                "dot3StatsAlignmentErrors",
+                r.dot3_stats_alignment_errors,
+            );
+            kv(&mut out, "dot3StatsFCSErrors", r.dot3_stats_fcs_errors);
+            kv(
+                &mut out,
+                "dot3StatsSingleCollisionFrames",
+                r.dot3_stats_single_collision_frames,
+            );
+            kv(
+                &mut out,
+                "dot3StatsMultipleCollisionFrames",
+                r.dot3_stats_multiple_collision_frames,
+            );
+            kv(
+                &mut out,
+                "dot3StatsSQETestErrors",
+                r.dot3_stats_sqe_test_errors,
+            );
+            kv(
+                &mut out,
+                "dot3StatsDeferredTransmissions",
+                r.dot3_stats_deferred_transmissions,
+            );
+            kv(
+                &mut out,
+                "dot3StatsLateCollisions",
+                r.dot3_stats_late_collisions,
+            );
+            kv(
+                &mut out,
+                "dot3StatsExcessiveCollisions",
+                r.dot3_stats_excessive_collisions,
+            );
+            kv(
+                &mut out,
+                "dot3StatsInternalMacTransmitErrors",
+                r.dot3_stats_internal_mac_transmit_errors,
+            );
+            kv(
+                &mut out,
+                "dot3StatsCarrierSenseErrors",
+                r.dot3_stats_carrier_sense_errors,
+            );
+            kv(
+                &mut out,
+                "dot3StatsFrameTooLongs",
+                r.dot3_stats_frame_too_longs,
+            );
+            kv(
+                &mut out,
+                "dot3StatsInternalMacReceiveErrors",
+                r.dot3_stats_internal_mac_receive_errors,
+            );
+            kv(
+                &mut out,
+                "dot3StatsSymbolErrors",
+                r.dot3_stats_symbol_errors,
+            );
+        }
+        CounterRecord::Processor(r) => {
+            kv(&mut out, "5s_cpu", r.cpu_5s);
+            kv(&mut out, "1m_cpu", r.cpu_1m);
+            kv(&mut out, "5m_cpu", r.cpu_5m);
+            kv(&mut out, "total_memory_bytes", r.total_memory);
+            kv(&mut out, "free_memory_bytes", r.free_memory);
+        }
+        CounterRecord::Unknown { data, .. } => kv(&mut out, "unknownBytes", hex(data)),
+        CounterRecord::Custom(c) => generic_text(&mut out, &c.value().to_json()),
+        other => generic_text(&mut out, other),
+    }
+    out
+}
+
+fn sample_text(out: &mut String, sample: &SflowSample) {
+    kv(out, "startSample", SAMPLE_MARKER);
+    match sample {
+        SflowSample::Flow(_) | SflowSample::ExpandedFlow(_) => {
+            let flow = sample.normalized_flow().expect("flow sample");
+            let (enterprise, sample_type) = match sample {
+                SflowSample::Flow(_) => (1, "FLOWSAMPLE"),
+                _ => (3, "FLOWSAMPLE_EXPANDED"),
+            };
+            kv(out, "sampleType_tag", format_args!("0:{enterprise}"));
+            kv(out, "sampleType", sample_type);
+            kv(out, "sampleSequenceNo", flow.sequence_number);
+            kv(out, "sourceId", flow.data_source);
+            kv(out, "meanSkipCount", flow.sampling_rate);
+            kv(out, "samplePool", flow.sample_pool);
+            kv(out, "dropEvents", flow.drops);
+            kv(out, "inputPort", port(flow.input));
+            kv(out, "outputPort", port(flow.output));
+            for record in flow.records {
+                out.push_str(&flow_record_text(record));
+            }
+        }
+        SflowSample::Counter(_) | SflowSample::ExpandedCounter(_) => {
+            let counters = sample.normalized_counter().expect("counter sample");
+            let (format, sample_type) = match sample {
+                SflowSample::Counter(_) => (2, "COUNTERSSAMPLE"),
+                _ => (4, "COUNTERSSAMPLE_EXPANDED"),
+            };
+            kv(out, "sampleType_tag", format_args!("0:{format}"));
+            kv(out, "sampleType", sample_type);
+            kv(out, "sampleSequenceNo", counters.sequence_number);
+            kv(out, "sourceId", counters.data_source);
+            for record in counters.records {
+                out.push_str(&counter_record_text(record));
+            }
+        }
+        SflowSample::Discard(d) => {
+            kv(out, "sampleType_tag", "0:5");
+            kv(out, "sampleType", "EVENT_DISCARDED_PACKET");
+            kv(out, "sampleSequenceNo", d.sequence_number);
+            kv(out, "sourceId", d.data_source());
+            kv(out, "dropEvents", d.drops);
+            kv(out, "inputPort", d.input);
+            kv(out, "outputPort", d.output);
+            kv(out, "discardCode", u32::from(d.reason));
+            for record in &d.records {
+                out.push_str(&flow_record_text(record));
+            }
+        }
+        SflowSample::Unknown {
+            enterprise, format, ..
+        } => {
+            kv(out, "sampleType_tag", format_args!("{enterprise}:{format}"));
+            kv(out, "sampleType", "UNKNOWN");
+        }
+        SflowSample::Custom(c) => {
+            kv(
+                out,
+                "sampleType_tag",
+                format_args!("{}:{}", c.enterprise, c.format),
+            );
+            generic_text(out, &c.value().to_json());
+        }
+    }
+    out.push_str("endSample   ");
+    out.push_str(SAMPLE_MARKER);
+    out.push('\n');
+}
+
+fn transport_text(out: &mut String, protocol: u32, src_port: u32, dst_port: u32, flags: u32) {
+    match protocol {
+        6 => {
+            kv(out, "TCPSrcPort", src_port);
+            kv(out, "TCPDstPort", dst_port);
+            kv(out, "TCPFlags", flags);
+        }
+        17 => {
+            kv(out, "UDPSrcPort", src_port);
+            kv(out, "UDPDstPort", dst_port);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "packet")]
+fn decoded_header_text(out: &mut String, header: &crate::flow_records::RawPacketHeader) {
+    let decoded = header.decode();
+    if let Some(dst) = decoded.dst_mac {
+        kv(out, "dstMAC", mac(&dst));
+    }
+    if let Some(src) = decoded.src_mac {
+        kv(out, "srcMAC", mac(&src));
+    }
+    if let Some(tag) = decoded.vlan_tags.first() {
+        kv(out, "decodedVLAN", tag.vid);
+        kv(out, "decodedPriority", tag.pcp);
+    }
+    if let Some(src) = decoded.src_ip {
+        kv(out, "srcIP", src);
+    }
+    if let Some(dst) = decoded.dst_ip {
+        kv(out, "dstIP", dst);
+    }
+    if let Some(protocol) = decoded.ip_protocol {
+        kv(out, "IPProtocol", protocol);
+    }
+    if let Some(tos) = decoded.tos {
+        kv(out, "IPTOS", tos);
+    }
+    if let Some(ttl) = decoded.ttl {
+        kv(out, "IPTTL", ttl);
+    }
+    if let (Some(protocol), Some(src_port), Some(dst_port)) =
+        (decoded.ip_protocol, decoded.src_port, decoded.dst_port)
+    {
+        let flags = decoded.tcp_flags.unwrap_or(0);
+        transport_text(
+            out,
+            u32::from(protocol),
+            u32::from(src_port),
+            u32::from(dst_port),
+            u32::from(flags),
+        );
+    }
+    if let (Some(icmp_type), Some(icmp_code)) = (decoded.icmp_type, decoded.icmp_code) {
+        kv(out, "ICMPType", icmp_type);
+        kv(out, "ICMPCode", icmp_code);
+    }
+}
+
+/// Fields of a `FLOW,...` line.
+#[derive(Debug, Default)]
+pub(crate) struct FlowLine {
+    pub(crate) input: u32,
+    pub(crate) output: u32,
+    pub(crate) src_mac: Option<MacAddress>,
+    pub(crate) dst_mac: Option<MacAddress>,
+    pub(crate) eth_type: u32,
+    pub(crate) in_vlan: u32,
+    pub(crate) out_vlan: u32,
+    pub(crate) src_ip: Option<IpAddr>,
+    pub(crate) dst_ip: Option<IpAddr>,
+    pub(crate) protocol: u32,
+    pub(crate) tos: u32,
+    pub(crate) ttl: u32,
+    pub(crate) src_port: u32,
+    pub(crate) dst_port: u32,
+    pub(crate) tcp_flags: u32,
+    pub(crate) packet_size: u32,
+    pub(crate) ip_size: u32,
+    pub(crate) sampling_rate: u32,
+}
+
+impl FlowLine {
+    pub(crate) fn from_sample(sample: &NormalizedFlowSample<'_>) -> Self {
+        let mut line = FlowLine {
+            input: sample.input.to_expanded().1,
+            output: sample.output.to_expanded().1,
+            sampling_rate: sample.sampling_rate,
+            packet_size: sample.frame_length().unwrap_or(0),
+            ..FlowLine::default()
+        };
+        for record in sample.records {
+            match record {
+                FlowRecord::SampledEthernet(r) => {
+                    line.src_mac = Some(r.src_mac);
+                    line.dst_mac = Some(r.dst_mac);
+                    line.eth_type = r.eth_type;
+                }
+                FlowRecord::SampledIpv4(r) if line.src_ip.is_none() => {
+                    line.src_ip = Some(IpAddr::V4(r.src_ip));
+                    line.dst_ip = Some(IpAddr::V4(r.dst_ip));
+                    line.set_transport(r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+                    line.tos = r.tos;
+                    line.ip_size = r.length;
+                }
+                FlowRecord::SampledIpv6(r) if line.src_ip.is_none() => {
+                    line.src_ip = Some(IpAddr::V6(r.src_ip));
+                    line.dst_ip = Some(IpAddr::V6(r.dst_ip));
+                    line.set_transport(r.protocol, r.src_port, r.dst_port, r.tcp_flags);
+                    line.tos = r.priority;
+                    line.ip_size = r.length;
+                }
+                FlowRecord::ExtendedSwitch(r) => {
+                    line.in_vlan = r.src_vlan;
+                    line.out_vlan = r.dst_vlan;
+                }
+                #[cfg(feature = "packet")]
+                FlowRecord::RawPacketHeader(r) => line.set_from_header(r),
+                _ => {}
+            }
+        }
+        line
+    }
+
+    fn set_transport(&mut self, protocol: u32, src_port: u32, dst_port: u32, tcp_flags: u32) {
+        self.protocol = protocol;
+        self.src_port = src_port;
+        self.dst_port = dst_port;
+        self.tcp_flags = tcp_flags;
+    }
+
+    #[cfg(feature = "packet")]
+    fn set_from_header(&mut self, header: &crate::flow_records::RawPacketHeader) {
+        use crate::flow_records::HeaderProtocol;
+
+        let decoded = header.decode();
+        self.src_mac = decoded.src_mac.or(self.src_mac);
+        self.dst_mac = decoded.dst_mac.or(self.dst_mac);
+        if let Some(eth_type) = decoded.ether_type {
+            self.eth_type = u32::from(eth_type);
+        }
+        if self.in_vlan == 0
+            && let Some(tag) = decoded.vlan_tags.first()
+        {
+            self.in_vlan = u32::from(tag.vid);
+        }
+        if decoded.src_ip.is_none() {
+            return;
+        }
+        self.src_ip = decoded.src_ip;
+        self.dst_ip = decoded.dst_ip;
+        self.set_transport(
+            decoded.ip_protocol.map_or(0, u32::from),
+            decoded.src_port.map_or(0, u32::from),
+            decoded.dst_port.map_or(0, u32::from),
+            decoded.tcp_flags.map_or(0, u32::from),
+        );
+        self.tos = decoded.tos.map_or(0, u32::from);
+        self.ttl = decoded.ttl.map_or(0, u32::from);
+        let labels = 4 * decoded.mpls_labels.len() as u32;
+        let offset = match header.protocol() {
+            HeaderProtocol::EthernetIso88023 => {
+                14 + 4 * decoded.vlan_tags.len() as u32 + labels
+            }
+            HeaderProtocol::Mpls => labels,
+            _ => 0,
+        };
+        self.ip_size = header
+            .frame_length
+            .saturating_sub(header.stripped)
+            .saturating_sub(offset);
+    }
+
+    fn render(&self, agent: &str) -> String {
+        format!(
+            "FLOW,{},{},{},{},{},0x{:04x},{},{},{},{},{},0x{:02x},{},{},{},0x{:02x},{},{},{}",
+            agent,
+            self.input,
+            self.output,
+            self.src_mac
+                .as_ref()
+                .map_or_else(|| "000000000000".into(), mac),
+            self.dst_mac
+                .as_ref()
+                .map_or_else(|| "000000000000".into(), mac),
+            self.eth_type,
+            self.in_vlan,
+            self.out_vlan,
+            self.src_ip
+                .map_or_else(|| "0.0.0.0".into(), |ip| ip.to_string()),
+            self.dst_ip
+                .map_or_else(|| "0.0.0.0".into(), |ip| ip.to_string()),
+            self.protocol,
+            self.tos,
+            self.ttl,
+            self.src_port,
+            self.dst_port,
+            self.tcp_flags,
+            self.packet_size,
+            self.ip_size,
+            self.sampling_rate,
+        )
+    }
+}
+
+fn counter_line(agent: &str, g: &GenericInterface) -> String {
+    format!(
+        "CNTR,{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        agent,
+        g.if_index,
+        g.if_type,
+        g.if_speed,
+        g.if_direction,
+        g.if_status,
+        g.if_in_octets,
+        g.if_in_ucast_pkts,
+        g.if_in_multicast_pkts,
+        g.if_in_broadcast_pkts,
+        g.if_in_discards,
+        g.if_in_errors,
+        g.if_in_unknown_protos,
+        g.if_out_octets,
+        g.if_out_ucast_pkts,
+        g.if_out_multicast_pkts,
+        g.if_out_broadcast_pkts,
+        g.if_out_discards,
+        g.if_out_errors,
+        g.if_promiscuous_mode,
+    )
+}
+
+fn kv(out: &mut String, key: &str, value: impl Display) {
+    let _ = writeln!(out, "{key} {value}");
+}
+
+/// Print the fields of a record without a dedicated sflowtool layout.
+fn generic_text(out: &mut String, record: &impl Serialize) {
+    // Walk the serialized text rather than a `Value`, whose maps sort their
+    // keys, so fields print in declaration order.
+    let value = reparse(record).unwrap_or(Ordered::Scalar(Value::Null));
+    flatten(out, "", &value);
+}
+
+fn flatten(out: &mut String, prefix: &str, value: &Ordered) {
+    let key = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}_{name}")
+        }
+    };
+    match unwrap_variant(value) {
+        Ordered::Object(members) => {
+            for (name, field) in members {
+                flatten(out, &key(name), field);
+            }
+        }
+        Ordered::Array(items) if items.iter().all(|v| matches!(v, Ordered::Scalar(_))) => {
+            let items: Vec<String> = items
+                .iter()
+                .filter_map(|v| match v {
+                    Ordered::Scalar(v) => Some(scalar(v)),
+                    _ => None,
+                })
+                .collect();
+            kv(out, prefix, items.join(","));
+        }
+        Ordered::Array(items) => {
+            kv(out, &key("count"), items.len());
+            for (i, item) in items.iter().enumerate() {
+                flatten(out, &key(&i.to_string()), item);
+            }
+        }
+        Ordered::Scalar(Value::Null) => {}
+        Ordered::Scalar(other) => kv(out, prefix, scalar(other)),
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn port(interface: Interface) -> String {
+    match interface {
+        Interface::Single(index) => index.to_string(),
+        Interface::Unknown => "0".to_string(),
+        Interface::Internal => "internal".to_string(),
+        Interface::Multiple(count) => format!("multiple {count}"),
+        Interface::Discarded(reason) => format!("discard {}", u32::from(reason)),
+    }
+}
+
+fn mac(mac: &MacAddress) -> String {
+    mac.bytes().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn join(values: &[u32], separator: &str) -> String {
+    values
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// `YYYY-MM-DDTHH:MM:SS+0000` for `secs` since the Unix epoch.
+fn utc_time(secs: u64) -> String {
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}+0000",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
@@ -78,9 +78,9 @@ fn test_parse_datagram_ipv6_agent() {
 
 #[test]
 fn test_parse_datagram_bad_version() {
-    // version=4 (unsupported)
+    // version=3 (unsupported)
     let data = h("\
-        0000   00 00 00 04 00 00 00 01 0a 00 00 01 00 00 00 00\n\
+        0000   00 00 00 03 00 00 00 01 0a 00 00 01 00 00 00 00\n\
         0010   00 00 00 01 00 00 03 e8 00 00 00 00\
     ");
     let parser = SflowParser::default();
@@ -88,7 +88,7 @@ fn test_parse_datagram_bad_version() {
     assert!(result.error.is_some());
     match result.error.unwrap() {
         SflowError::UnsupportedVersion { version } => {
-            assert_eq!(version, 4)
+            assert_eq!(version, 3)
         }
         other => panic!("Expected UnsupportedVersion, got {:?}", other),
     }
@@ -136,6 +136,8 @@ fn test_parse_flow_sample_with_extended_switch() {
             assert_eq!(fs.source_id_type, 0);
             assert_eq!(fs.source_id_index, 3);
             assert_eq!(fs.sampling_rate, 256);
+            assert_eq!(fs.input_interface(), Interface::Single(1));
+            assert_eq!(fs.output_interface(), Interface::Single(2));
             assert_eq!(fs.records.len(), 1);
             match &fs.records[0] {
                 FlowRecord::ExtendedSwitch(es) => {
@@ -181,6 +183,225 @@ fn test_parse_expanded_flow_sample() {
     }
 }
 
+#[test]
+fn test_parse_discarded_packet_sample() {
+    let data = h("\
+        0000   00 00 00 05 00 00 00 01 0a 00 00 01 00 00 00 00\n\
+        0010   00 00 00 01 00 00 03 e8 00 00 00 01 00 00 00 05\n\
+        0020   00 00 00 38 00 00 00 01 00 00 00 00 00 00 00 03\n\
+        0030   00 00 00 00 00 00 00 01 00 00 00 00 00 00 01 02\n\
+        0040   00 00 00 01 00 00 03 e9 00 00 00 10 00 00 00 64\n\
+        0050   00 00 00 00 00 00 00 c8 00 00 00 00\
+    ");
+    //  sample: enterprise=0 format=5(discarded packet), len=56
+    //  seq=1 src_type=0 src_idx=3 drops=0
+    //  in=1 out=0 reason=258(acl) records=1
+    //  record: enterprise=0 format=1001(switch), len=16
+    //        src_vlan=100 src_pri=0 dst_vlan=200 dst_pri=0
+
+    let parser = SflowParser::default();
+    let result = parser.parse_bytes(&data);
+    assert!(result.error.is_none());
+
+    match &result.datagrams[0].samples[0] {
+        SflowSample::Discard(dp) => {
+            assert_eq!(dp.sequence_number, 1);
+            assert_eq!(dp.source_id_type, 0);
+            assert_eq!(dp.source_id_index, 3);
+            assert_eq!(dp.drops, 0);
+            assert_eq!(dp.input, 1);
+            assert_eq!(dp.output, 0);
+            assert_eq!(dp.reason, DropReason::Acl);
+            assert_eq!(dp.records.len(), 1);
+            assert!(matches!(&dp.records[0], FlowRecord::ExtendedSwitch(_)));
+        }
+        other => panic!("Expected Discard sample, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_drop_reason_from_u32() {
+    assert_eq!(DropReason::from(0), DropReason::NetUnreachable);
+    assert_eq!(DropReason::from(256), DropReason::Unknown);
+    assert_eq!(DropReason::from(301), DropReason::MtuExceeded);
+    assert_eq!(DropReason::from(1000), DropReason::Unrecognized(1000));
+}
+
+#[test]
+fn test_data_source() {
+    assert_eq!(DataSource::new(0, 17), DataSource::IfIndex(17));
+    assert_eq!(DataSource::new(1, 100), DataSource::SmonVlan(100));
+    assert_eq!(DataSource::new(2, 3), DataSource::EntPhysical(3));
+    let other = DataSource::new(9, 4);
+    assert_eq!(
+        other,
+        DataSource::Other {
+            source_id_type: 9,
+            index: 4
+        }
+    );
+    assert_eq!((other.source_id_type(), other.index()), (9, 4));
+
+    assert_eq!(DataSource::IfIndex(17).to_string(), "0:17");
+    assert_eq!(DataSource::SmonVlan(100).to_string(), "1:100");
+    assert_eq!(other.to_string(), "9:4");
+
+    let mut sources = vec![
+        other,
+        DataSource::SmonVlan(1),
+        DataSource::IfIndex(20),
+        DataSource::IfIndex(3),
+    ];
+    sources.sort();
+    assert_eq!(
+        sources,
+        vec![
+            DataSource::IfIndex(3),
+            DataSource::IfIndex(20),
+            DataSource::SmonVlan(1),
+            other,
+        ]
+    );
+
+    let sample = SflowSample::Counter(CounterSample {
+        sequence_number: 1,
+        source_id_type: 0,
+        source_id_index: 5,
+        records: vec![],
+    });
+    assert_eq!(sample.data_source(), Some(DataSource::IfIndex(5)));
+    let unknown = SflowSample::Unknown {
+        enterprise: 1,
+        format: 1,
+        data: vec![],
+    };
+    assert_eq!(unknown.data_source(), None);
+}
+
+#[test]
+fn test_normalized_flow_sample_ignores_encoding() {
+    let compact = FlowSample {
+        sequence_number: 9,
+        source_id_type: 0,
+        source_id_index: 4,
+        sampling_rate: 512,
+        sample_pool: 5120,
+        drops: 1,
+        input: 4,
+        output: 0x4000_0102,
+        records: vec![FlowRecord::ExtendedSwitch(ExtendedSwitch {
+            src_vlan: 10,
+            src_priority: 0,
+            dst_vlan: 20,
+            dst_priority: 0,
+        })],
+    };
+    let expanded = ExpandedFlowSample::from(compact.clone());
+    assert_eq!((expanded.output_format, expanded.output_value), (1, 258));
+
+    let compact = SflowSample::Flow(compact);
+    let expanded = SflowSample::ExpandedFlow(expanded);
+    let view = compact.normalized_flow().unwrap();
+    assert_eq!(expanded.normalized_flow(), Some(view.clone()));
+    assert_eq!(view.data_source, DataSource::IfIndex(4));
+    assert_eq!(view.input, Interface::Single(4));
+    assert_eq!(view.output, Interface::Discarded(DropReason::Acl));
+    assert_eq!(view.records.len(), 1);
+    assert!(compact.normalized_counter().is_none());
+
+    let bytes = SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+        sub_agent_id: 0,
+        sequence_number: 1,
+        uptime: 1,
+        samples: vec![compact.clone()],
+    }
+    .to_bytes();
+    let borrowed = SflowParser::default().parse_bytes_ref(&bytes);
+    let borrowed_view = borrowed.datagrams[0].samples[0].normalized_flow().unwrap();
+    assert_eq!(borrowed_view.output, view.output);
+    assert_eq!(borrowed_view.records.len(), 1);
+}
+
+#[test]
+fn test_normalized_counter_sample_ignores_encoding() {
+    let compact = CounterSample {
+        sequence_number: 3,
+        source_id_type: 1,
+        source_id_index: 100,
+        records: vec![],
+    };
+    let expanded = SflowSample::ExpandedCounter(compact.clone().into());
+    let compact = SflowSample::Counter(compact);
+    let view = compact.normalized_counter().unwrap();
+    assert_eq!(expanded.normalized_counter(), Some(view.clone()));
+    assert_eq!(view.data_source, DataSource::SmonVlan(100));
+    assert!(expanded.normalized_flow().is_none());
+}
+
+#[test]
+fn test_interface_from_compact() {
+    assert_eq!(Interface::from_compact(0), Interface::Unknown);
+    assert_eq!(Interface::from_compact(7), Interface::Single(7));
+    assert_eq!(Interface::from_compact(0x3FFF_FFFF), Interface::Internal);
+    assert_eq!(
+        Interface::from_compact(0x4000_0102),
+        Interface::Discarded(DropReason::Acl)
+    );
+    assert_eq!(
+        Interface::from_compact(0x4000_0003),
+        Interface::Discarded(DropReason::PortUnreachable)
+    );
+    assert_eq!(Interface::from_compact(0x8000_0004), Interface::Multiple(4));
+    assert_eq!(Interface::from_compact(0xC000_0001), Interface::Unknown);
+
+    for v in [7, 0x3FFF_FFFF, 0x4000_0102, 0x8000_0004, 0x8000_0000] {
+        assert_eq!(Interface::from_compact(v).to_compact(), v);
+    }
+}
+
+#[test]
+fn test_interface_from_expanded() {
+    assert_eq!(
+        Interface::from_expanded(0, 0x0100_0000),
+        Interface::Single(0x0100_0000)
+    );
+    assert_eq!(
+        Interface::from_expanded(0, 0x3FFF_FFFF),
+        Interface::Internal
+    );
+    assert_eq!(
+        Interface::from_expanded(1, 258),
+        Interface::Discarded(DropReason::Acl)
+    );
+    assert_eq!(Interface::from_expanded(2, 0), Interface::Multiple(0));
+    assert_eq!(Interface::from_expanded(3, 1), Interface::Unknown);
+    assert_eq!(
+        Interface::Discarded(DropReason::Acl).to_expanded(),
+        (1, 258)
+    );
+
+    let efs = ExpandedFlowSample {
+        sequence_number: 1,
+        source_id_type: 0,
+        source_id_index: 3,
+        sampling_rate: 100,
+        sample_pool: 100,
+        drops: 0,
+        input_format: 0,
+        input_value: 3,
+        output_format: 1,
+        output_value: 0,
+        records: vec![],
+    };
+    assert_eq!(efs.input_interface(), Interface::Single(3));
+    assert_eq!(
+        efs.output_interface(),
+        Interface::Discarded(DropReason::NetUnreachable)
+    );
+}
+
 // === Counter Sample Tests ===
 
 #[test]
@@ -281,6 +502,27 @@ fn test_parse_raw_packet_header() {
     }
 }
 
+#[test]
+fn test_header_protocol_from_u32() {
+    assert_eq!(HeaderProtocol::from(1), HeaderProtocol::EthernetIso88023);
+    assert_eq!(HeaderProtocol::from(11), HeaderProtocol::Ipv4);
+    assert_eq!(HeaderProtocol::from(15), HeaderProtocol::Ieee80211Mac);
+    assert_eq!(HeaderProtocol::from(99), HeaderProtocol::Unrecognized(99));
+    for v in 1..=18 {
+        assert_eq!(u32::from(HeaderProtocol::from(v)), v);
+    }
+}
+
+#[test]
+fn test_if_direction_from_u32() {
+    assert_eq!(IfDirection::from(1), IfDirection::FullDuplex);
+    assert_eq!(IfDirection::from(2), IfDirection::HalfDuplex);
+    assert_eq!(IfDirection::from(7), IfDirection::Unrecognized(7));
+    for v in 0..=5 {
+        assert_eq!(u32::from(IfDirection::from(v)), v);
+    }
+}
+
 #[test]
 fn test_parse_sampled_ipv4() {
     let data = h("\
@@ -424,6 +666,7 @@ fn test_parse_extended_gateway() {
             assert_eq!(eg.as_path_segments[0].segment_type, 2);
             assert_eq!(eg.as_path_segments[0].values, vec![65001, 65002]);
             assert_eq!(eg.communities, vec![0xFFFF0001]);
+            assert_eq!(eg.dst_as(), 65002);
         }
         other => panic!("Expected ExtendedGateway, got {:?}", other),
     }
@@ -876,6 +1119,7 @@ fn test_extended_gateway_empty_segments() {
             assert_eq!(eg.as_number, 65000);
             assert_eq!(eg.as_path_segments.len(), 0);
             assert_eq!(eg.communities.len(), 0);
+            assert_eq!(eg.dst_as(), 65000);
         }
         other => panic!("Expected ExtendedGateway, got {:?}", other),
     }
@@ -0,0 +1,194 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::agent::{AgentEvent, AgentRegistry};
+use flowparser_sflow::sequence::AgentKey;
+use flowparser_sflow::*;
+use std::time::Duration;
+
+fn from_sub_agent(sub_agent_id: u32, sequence_number: u32, uptime: u32) -> SflowDatagram {
+    SflowDatagram {
+        sub_agent_id,
+        sequence_number,
+        uptime,
+        ..datagram(vec![])
+    }
+}
+
+#[test]
+fn test_discovery_and_steady_state() {
+    let mut registry = AgentRegistry::default();
+    assert_eq!(
+        registry.observe(&from_sub_agent(0, 10, 60_000), at(0)),
+        vec![
+            AgentEvent::AgentDiscovered {
+                agent_address: agent_address()
+            },
+            AgentEvent::SubAgentDiscovered {
+                agent: AgentKey {
+                    agent_address: agent_address(),
+                    sub_agent_id: 0
+                }
+            },
+        ]
+    );
+    assert_eq!(
+        registry.observe(&from_sub_agent(1, 1, 61_000), at(1)),
+        vec![AgentEvent::SubAgentDiscovered {
+            agent: AgentKey {
+                agent_address: agent_address(),
+                sub_agent_id: 1
+            }
+        }]
+    );
+    assert!(
+        registry
+            .observe(&from_sub_agent(0, 11, 62_000), at(2))
+            .is_empty()
+    );
+    // A datagram delayed behind the previous one is not a restart.
+    assert!(
+        registry
+            .observe(&from_sub_agent(0, 10, 60_000), at(2))
+            .is_empty()
+    );
+
+    assert_eq!(
+        registry.boot_time(&agent_address()),
+        Some(at(0) - Duration::from_secs(60))
+    );
+    assert_eq!(registry.timestamp(&agent_address(), 70_000), Some(at(10)));
+    assert_eq!(registry.agents().count(), 1);
+}
+
+#[test]
+fn test_reboot_resets_sub_agents_quietly() {
+    let mut registry = AgentRegistry::default();
+    registry.observe(&from_sub_agent(0, 5000, 3_600_000), at(0));
+    registry.observe(&from_sub_agent(1, 7000, 3_600_000), at(0));
+
+    let events = registry.observe(&from_sub_agent(0, 1, 30_000), at(60));
+    assert_eq!(
+        events,
+        vec![AgentEvent::AgentRestarted {
+            agent_address: agent_address(),
+            previous_uptime: 3_600_000,
+            uptime: 30_000,
+        }]
+    );
+    // The other sub-agent's sequence restart is explained by the reboot.
+    assert!(
+        registry
+            .observe(&from_sub_agent(1, 1, 31_000), at(61))
+            .is_empty()
+    );
+
+    let state = registry.agent(&agent_address()).unwrap();
+    assert_eq!(state.restarts, 1);
+    assert_eq!(state.boot_time, at(30));
+}
+
+#[test]
+fn test_reboot_while_silent() {
+    let mut registry = AgentRegistry::default();
+    registry.observe(&from_sub_agent(0, 5000, 3_600_000), at(0));
+    // Two hours later the uptime has advanced by only 100 seconds.
+    let events = registry.observe(&from_sub_agent(0, 20, 3_700_000), at(7200));
+    assert!(matches!(events[..], [AgentEvent::AgentRestarted { .. }]));
+}
+
+#[test]
+fn test_uptime_wraparound_is_not_a_reboot() {
+    let mut registry = AgentRegistry::default();
+    let before_wrap = u32::MAX - 4_999;
+    registry.observe(&from_sub_agent(0, 100, before_wrap), at(0));
+    let boot = registry.boot_time(&agent_address()).unwrap();
+
+    let events = registry.observe(&from_sub_agent(0, 101, 5_000), at(10));
+    assert_eq!(
+        events,
+        vec![AgentEvent::UptimeWrapped {
+            agent_address: agent_address()
+        }]
+    );
+    let state = registry.agent(&agent_address()).unwrap();
+    assert_eq!(state.wraps, 1);
+    assert_eq!(state.restarts, 0);
+    assert_eq!(state.boot_time, boot);
+    assert_eq!(state.uptime(), Duration::from_millis((1 << 32) + 5_000));
+    assert_eq!(registry.timestamp(&agent_address(), 5_000), Some(at(10)));
+}
+
+#[test]
+fn test_sub_agent_reset_without_reboot() {
+    let mut registry = AgentRegistry::default();
+    registry.observe(&from_sub_agent(2, 9000, 100_000), at(0));
+    let events = registry.observe(&from_sub_agent(2, 1, 105_000), at(5));
+    assert_eq!(
+        events,
+        vec![AgentEvent::SubAgentReset {
+            agent: AgentKey {
+                agent_address: agent_address(),
+                sub_agent_id: 2
+            },
+            previous_sequence: 9000,
+            sequence_number: 1,
+        }]
+    );
+    assert!(
+        registry
+            .observe(&from_sub_agent(2, 2, 106_000), at(6))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_sub_agent_reset_to_low_sequence_number() {
+    let mut registry = AgentRegistry::default();
+    for seq in 1..=50 {
+        registry.observe(&from_sub_agent(2, seq, 100_000 + seq), at(0));
+    }
+    // A repeated number could be a duplicate datagram.
+    assert!(
+        registry
+            .observe(&from_sub_agent(2, 1, 101_000), at(1))
+            .is_empty()
+    );
+    assert_eq!(
+        registry.observe(&from_sub_agent(2, 2, 102_000), at(2)),
+        vec![AgentEvent::SubAgentReset {
+            agent: AgentKey {
+                agent_address: agent_address(),
+                sub_agent_id: 2
+            },
+            previous_sequence: 50,
+            sequence_number: 2,
+        }]
+    );
+    assert!(
+        registry
+            .observe(&from_sub_agent(2, 3, 103_000), at(3))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_expire_and_tolerance() {
+    let mut registry = AgentRegistry::builder()
+        .with_tolerance(Duration::from_secs(1))
+        .build();
+    registry.observe(&from_sub_agent(0, 1, 10_000), at(0));
+    // Uptime 5 seconds behind wall clock exceeds the 1 second tolerance.
+    let events = registry.observe(&from_sub_agent(0, 2, 15_000), at(10));
+    assert!(matches!(events[..], [AgentEvent::AgentRestarted { .. }]));
+
+    registry.expire(at(5));
+    assert!(registry.agent(&agent_address()).is_some());
+    registry.expire(at(11));
+    assert!(registry.agent(&agent_address()).is_none());
+    assert!(
+        !registry
+            .observe(&from_sub_agent(0, 3, 16_000), at(12))
+            .is_empty()
+    );
+}
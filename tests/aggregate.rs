@@ -0,0 +1,212 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::aggregate::{FlowAggregator, FlowField, FlowKey};
+use flowparser_sflow::flow_records::{
+    ExtendedGateway, ExtendedSwitch, FlowRecord, SampledIpv4, extended_gateway::AsPathSegment,
+};
+use flowparser_sflow::samples::FlowSample;
+use flowparser_sflow::*;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, SystemTime};
+
+fn packet(src: u8, dst_port: u32, length: u32) -> FlowRecord {
+    FlowRecord::SampledIpv4(SampledIpv4 {
+        length,
+        src_ip: Ipv4Addr::new(192, 168, 1, src),
+        dst_port,
+        ..sampled_ipv4()
+    })
+}
+
+fn gateway(src_as: u32, path: Vec<u32>) -> FlowRecord {
+    FlowRecord::ExtendedGateway(ExtendedGateway {
+        next_hop: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 254)),
+        as_number: 65000,
+        src_as,
+        src_peer_as: src_as,
+        as_path_segments: vec![AsPathSegment {
+            segment_type: 2,
+            values: path,
+        }],
+        communities: vec![],
+    })
+}
+
+fn flow(sequence_number: u32, input: u32, records: Vec<FlowRecord>) -> SflowSample {
+    SflowSample::Flow(FlowSample {
+        sequence_number,
+        source_id_index: input,
+        sampling_rate: 1000,
+        sample_pool: 1000 * sequence_number,
+        input,
+        output: 7,
+        ..flow_sample(records)
+    })
+}
+
+#[test]
+fn test_flow_key_from_sample() {
+    let sample = flow(
+        1,
+        3,
+        vec![
+            packet(5, 443, 1500),
+            FlowRecord::ExtendedSwitch(ExtendedSwitch {
+                src_vlan: 10,
+                src_priority: 0,
+                dst_vlan: 20,
+                dst_priority: 0,
+            }),
+            gateway(65001, vec![65002, 65003]),
+        ],
+    );
+    let agent = agent_address();
+    let key = FlowKey::from_sample(&agent, &sample.normalized_flow().unwrap());
+    assert_eq!(key.agent_address, Some(agent));
+    assert_eq!(key.input, Some(3));
+    assert_eq!(key.output, Some(7));
+    assert_eq!(
+        key.src_addr,
+        Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)))
+    );
+    assert_eq!(key.protocol, Some(6));
+    assert_eq!(key.src_port, Some(40000));
+    assert_eq!(key.dst_port, Some(443));
+    assert_eq!(key.tos, Some(0));
+    assert_eq!((key.src_vlan, key.dst_vlan), (Some(10), Some(20)));
+    assert_eq!((key.src_as, key.dst_as), (Some(65001), Some(65003)));
+
+    let selected = key.select(&[FlowField::DstPort, FlowField::DstAs]);
+    assert_eq!(
+        selected,
+        FlowKey {
+            dst_port: Some(443),
+            dst_as: Some(65003),
+            ..FlowKey::default()
+        }
+    );
+}
+
+#[test]
+fn test_windows_close_and_scale() {
+    let mut aggregator = FlowAggregator::builder()
+        .with_window(Duration::from_secs(60))
+        .with_fields(&[FlowField::DstPort])
+        .build();
+
+    let first = numbered(
+        1,
+        vec![
+            flow(1, 3, vec![packet(1, 443, 1000)]),
+            flow(1, 4, vec![packet(2, 80, 100)]),
+        ],
+    );
+    assert!(aggregator.observe(&first, at(45)).is_empty());
+    let second = numbered(2, vec![flow(2, 3, vec![packet(3, 443, 500)])]);
+    assert!(aggregator.observe(&second, at(70)).is_empty());
+    assert_eq!(aggregator.len(), 2);
+
+    // The next datagram arrives in the following window.
+    let third = numbered(3, vec![flow(3, 3, vec![packet(1, 443, 1000)])]);
+    let closed = aggregator.observe(&third, at(110));
+    assert_eq!(closed.len(), 2);
+    let https = &closed[0];
+    assert_eq!(https.key.dst_port, Some(443));
+    assert_eq!(https.key.src_addr, None);
+    assert_eq!(https.estimate.samples, 2);
+    assert_eq!(https.estimate.packets, 2000.0);
+    assert_eq!(https.estimate.bytes, 1_500_000.0);
+    assert_eq!(closed[1].key.dst_port, Some(80));
+    assert_eq!(closed[1].estimate.bytes, 100_000.0);
+
+    let start = https.window_start;
+    assert_eq!(https.window_end, start + Duration::from_secs(60));
+    assert_eq!(
+        start
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            % 60,
+        0
+    );
+    assert!(start <= at(45) && at(70) < https.window_end);
+
+    assert!(aggregator.close_expired(at(140)).is_empty());
+    let last = aggregator.close_expired(at(240));
+    assert_eq!(last.len(), 1);
+    assert_eq!(last[0].window_start, https.window_end);
+    assert!(aggregator.is_empty());
+    assert!(aggregator.flush().is_empty());
+}
+
+#[test]
+fn test_max_flows_overflow_bucket() {
+    let mut aggregator = FlowAggregator::builder().with_max_flows(2).build();
+    let samples = (1..=4)
+        .map(|i| flow(1, u32::from(i), vec![packet(i, 443, 100)]))
+        .collect();
+    aggregator.observe(&numbered(1, samples), at(0));
+    assert_eq!(aggregator.len(), 2);
+    let flows = aggregator.flush();
+    assert_eq!(flows.len(), 3);
+    let overflow: Vec<_> = flows.iter().filter(|f| f.overflow).collect();
+    assert_eq!(overflow.len(), 1);
+    assert_eq!(overflow[0].key, FlowKey::default());
+    assert_eq!(overflow[0].estimate.samples, 2);
+}
+
+#[test]
+fn test_overflow_is_separate_from_empty_key() {
+    // Keyed on AS numbers, which these samples do not carry.
+    let mut aggregator = FlowAggregator::builder()
+        .with_fields(&[FlowField::SrcAs])
+        .with_max_flows(1)
+        .build();
+    let samples = vec![
+        flow(1, 3, vec![packet(1, 443, 100)]),
+        flow(
+            2,
+            3,
+            vec![packet(1, 443, 100), gateway(64_512, vec![64_512])],
+        ),
+    ];
+    aggregator.observe(&numbered(1, samples), at(0));
+    let flows = aggregator.flush();
+    assert_eq!(flows.len(), 2);
+    let real = flows.iter().find(|f| !f.overflow).unwrap();
+    assert_eq!(real.key, FlowKey::default());
+    assert_eq!(real.estimate.samples, 1);
+}
+
+#[cfg(feature = "packet")]
+#[test]
+fn test_flow_key_from_raw_packet_header() {
+    use flowparser_sflow::flow_records::RawPacketHeader;
+
+    // Ethernet / IPv4 / UDP 10.1.1.1:5353 -> 10.1.1.2:53
+    let mut header = vec![
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0x08, 0x00,
+    ];
+    header.extend_from_slice(&[
+        0x45, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 10, 1, 1, 1,
+        10, 1, 1, 2,
+    ]);
+    header.extend_from_slice(&[0x14, 0xe9, 0x00, 0x35, 0x00, 0x08, 0x00, 0x00]);
+    let sample = flow(
+        1,
+        3,
+        vec![FlowRecord::RawPacketHeader(RawPacketHeader {
+            header_protocol: 1,
+            frame_length: 46,
+            stripped: 4,
+            header_length: header.len() as u32,
+            header,
+        })],
+    );
+    let agent = agent_address();
+    let key = FlowKey::from_sample(&agent, &sample.normalized_flow().unwrap());
+    assert_eq!(key.dst_addr, Some(IpAddr::V4(Ipv4Addr::new(10, 1, 1, 2))));
+    assert_eq!(key.protocol, Some(17));
+    assert_eq!((key.src_port, key.dst_port), (Some(5353), Some(53)));
+}
@@ -0,0 +1,271 @@
+#![cfg(feature = "arrow")]
+
+mod common;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float64Type, Int64Type, TimestampMillisecondType, UInt32Type, UInt64Type,
+};
+use arrow_array::{Array, RecordBatch};
+use arrow_schema::DataType;
+use common::*;
+use flowparser_sflow::arrow::{
+    CounterBatchBuilder, FlowBatchBuilder, ParquetWriter, counter_schema, flow_schema,
+};
+use flowparser_sflow::counter_records::{PortName, Temperature};
+use flowparser_sflow::custom::Custom;
+use flowparser_sflow::flow_records::{ExtendedSwitch, FlowRecord};
+use flowparser_sflow::*;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::Serialize;
+use std::fs::File;
+
+fn temperature() -> CounterRecord {
+    CounterRecord::Temperature(Temperature {
+        minimum: -5,
+        maximum: 40,
+        errors: 0,
+    })
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> &'a dyn Array {
+    batch.column_by_name(name).unwrap().as_ref()
+}
+
+#[test]
+fn test_flow_batch() {
+    let mut builder = FlowBatchBuilder::new();
+    let dg = datagram(vec![
+        flow(vec![
+            ipv4(),
+            FlowRecord::ExtendedSwitch(ExtendedSwitch {
+                src_vlan: 10,
+                src_priority: 0,
+                dst_vlan: 20,
+                dst_priority: 0,
+            }),
+        ]),
+        flow(vec![]),
+        counters(2, 1, vec![cpu()]),
+    ]);
+    assert_eq!(builder.append_datagram(&dg, received()), 2);
+    assert_eq!(builder.len(), 2);
+
+    let batch = builder.finish();
+    assert!(builder.is_empty());
+    assert_eq!(batch.schema(), flow_schema());
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(
+        column(&batch, "time")
+            .as_primitive::<TimestampMillisecondType>()
+            .value(0),
+        1_700_000_000_250
+    );
+    assert_eq!(
+        column(&batch, "agent").as_string::<i32>().value(0),
+        "10.0.0.254"
+    );
+    assert_eq!(
+        column(&batch, "source_id").as_string::<i32>().value(1),
+        "0:3"
+    );
+    assert_eq!(
+        column(&batch, "input")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        3
+    );
+    assert_eq!(
+        column(&batch, "output_type").as_string::<i32>().value(0),
+        "multiple"
+    );
+    assert_eq!(
+        column(&batch, "output_count")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        2
+    );
+    assert!(column(&batch, "output").is_null(0));
+    assert_eq!(
+        column(&batch, "src_ip").as_string::<i32>().value(0),
+        "192.168.1.1"
+    );
+    assert_eq!(
+        column(&batch, "dst_port")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        443
+    );
+    assert_eq!(
+        column(&batch, "frame_length")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        1500
+    );
+    assert_eq!(
+        column(&batch, "out_vlan")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        20
+    );
+    assert!(column(&batch, "next_hop").is_null(0));
+    assert!(column(&batch, "src_ip").is_null(1));
+}
+
+#[test]
+fn test_counter_batches() {
+    let mut builder = CounterBatchBuilder::new();
+    let dg = datagram(vec![
+        counters(
+            2,
+            1,
+            vec![
+                cpu(),
+                temperature(),
+                CounterRecord::PortName(PortName {
+                    name: "eth0".to_string(),
+                }),
+            ],
+        ),
+        counters(2, 1, vec![cpu()]),
+        flow(vec![ipv4()]),
+    ]);
+    assert_eq!(builder.append_datagram(&dg, received()), 4);
+    assert_eq!(builder.len(), 4);
+    assert_eq!(builder.schema("host_cpu"), Some(counter_schema(&cpu())));
+
+    let schema = counter_schema(&temperature());
+    assert_eq!(
+        schema.field_with_name("minimum").unwrap().data_type(),
+        &DataType::Int64
+    );
+    assert_eq!(
+        schema.field_with_name("errors").unwrap().data_type(),
+        &DataType::UInt64
+    );
+
+    let batches = builder.finish();
+    assert!(builder.is_empty());
+    let names: Vec<_> = batches.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["host_cpu", "port_name", "temperature"]);
+
+    let cpu = &batches[0].1;
+    assert_eq!(cpu.num_rows(), 2);
+    assert_eq!(
+        column(cpu, "source_id_type")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        2
+    );
+    assert_eq!(
+        column(cpu, "load_five")
+            .as_primitive::<Float64Type>()
+            .value(0),
+        0.5
+    );
+    assert_eq!(
+        column(cpu, "cpu_user")
+            .as_primitive::<UInt64Type>()
+            .value(1),
+        1000
+    );
+    assert_eq!(
+        column(&batches[1].1, "name").as_string::<i32>().value(0),
+        "eth0"
+    );
+    assert_eq!(
+        column(&batches[2].1, "minimum")
+            .as_primitive::<Int64Type>()
+            .value(0),
+        -5
+    );
+    assert!(builder.finish().is_empty());
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Optics {
+    tx_power: i64,
+    rx_errors: u64,
+}
+
+fn optics(tx_power: i64, rx_errors: u64) -> CounterRecord {
+    CounterRecord::Custom(Custom::new(
+        9999,
+        2,
+        vec![],
+        Optics {
+            tx_power,
+            rx_errors,
+        },
+    ))
+}
+
+#[test]
+fn test_custom_counter_signedness() {
+    let mut builder = CounterBatchBuilder::new();
+    let dg = datagram(vec![counters(
+        0,
+        3,
+        vec![optics(3, 7), optics(-2, 8), optics(1, u64::MAX)],
+    )]);
+    assert_eq!(builder.append_datagram(&dg, received()), 3);
+    // A later negative value still fits; only u64::MAX does not.
+    assert_eq!(builder.out_of_range(), 1);
+
+    let batches = builder.finish();
+    let (name, batch) = &batches[0];
+    assert_eq!(name, "custom_9999_2");
+    let tx_power = column(batch, "tx_power").as_primitive::<Int64Type>();
+    assert_eq!(tx_power.values().to_vec(), vec![3, -2, 1]);
+    let rx_errors = column(batch, "rx_errors").as_primitive::<Int64Type>();
+    assert_eq!(rx_errors.value(1), 8);
+    assert!(rx_errors.is_null(2));
+}
+
+#[test]
+fn test_parquet_writer() {
+    let dir = std::env::temp_dir().join(format!("sflow-parquet-{}", std::process::id()));
+    let mut writer = ParquetWriter::builder()
+        .with_batch_size(2)
+        .build(&dir)
+        .unwrap();
+    let dg = datagram(vec![
+        flow(vec![ipv4()]),
+        counters(2, 1, vec![cpu(), temperature()]),
+    ]);
+    for _ in 0..3 {
+        assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 3);
+    }
+    let mut files = writer.close().unwrap();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            dir.join("flows.parquet"),
+            dir.join("host_cpu.parquet"),
+            dir.join("temperature.parquet"),
+        ]
+    );
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&files[0]).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let batches: Vec<_> = reader.map(Result::unwrap).collect();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+    assert_eq!(batches[0].schema().fields(), flow_schema().fields());
+    assert_eq!(
+        column(&batches[0], "src_port")
+            .as_primitive::<UInt32Type>()
+            .value(0),
+        40000
+    );
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&files[2]).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(rows, 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,208 @@
+use flowparser_sflow::flow_records::{ExtendedUser, HttpRequest, RawPacketHeaderRef};
+use flowparser_sflow::samples::{DataSource, FlowSample};
+use flowparser_sflow::*;
+use std::borrow::Cow;
+use std::net::Ipv4Addr;
+
+/// Decode a Wireshark-style hex dump into bytes.
+/// Strips offset prefixes (e.g., "0000   ") and whitespace.
+fn h(hex: &str) -> Vec<u8> {
+    let mut out = String::new();
+    for line in hex.lines() {
+        let t = line.trim();
+        if t.is_empty() {
+            continue;
+        }
+        let data = match t.find("  ") {
+            Some(pos) if pos >= 4 && t[..pos].bytes().all(|b| b.is_ascii_hexdigit()) => {
+                &t[pos..]
+            }
+            _ => t,
+        };
+        out.push_str(data);
+    }
+    hex::decode(out.replace(' ', "")).unwrap()
+}
+
+fn points_into(buf: &[u8], slice: &[u8]) -> bool {
+    let range = buf.as_ptr_range();
+    range.contains(&slice.as_ptr()) && slice.len() <= buf.len()
+}
+
+fn string_datagram(records: Vec<FlowRecord>) -> Vec<u8> {
+    SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+        sub_agent_id: 0,
+        sequence_number: 1,
+        uptime: 1000,
+        samples: vec![SflowSample::Flow(FlowSample {
+            sequence_number: 1,
+            source_id_type: 0,
+            source_id_index: 1,
+            sampling_rate: 100,
+            sample_pool: 100,
+            drops: 0,
+            input: 1,
+            output: 2,
+            records,
+        })],
+    }
+    .to_bytes()
+}
+
+#[test]
+fn test_borrowed_raw_header_points_into_buffer() {
+    let data = h("\
+        0000   00 00 00 05 00 00 00 01 0a 00 00 14 00 00 00 00\n\
+        0010   00 00 89 40 04 27 24 10 00 00 00 02 00 00 00 02\n\
+        0020   00 00 00 6c 00 00 0d 9c 00 00 00 10 00 00 00 01\n\
+        0030   00 00 00 01 00 00 00 58 00 00 00 10 00 00 00 06\n\
+        0040   00 00 00 00 05 f5 e1 00 00 00 00 00 00 00 00 03\n\
+        0050   00 00 00 00 1a 22 f5 6e 00 21 fe e7 00 00 00 00\n\
+        0060   ff ff ff ff 00 00 00 00 00 00 00 00 ff ff ff ff\n\
+        0070   00 00 00 00 9d ad 17 05 02 3d 0d 17 ff ff ff ff\n\
+        0080   ff ff ff ff 00 00 02 ad 00 00 00 00 00 00 00 00\n\
+        0090   00 00 00 01 00 00 00 bc 00 00 1d ae 00 00 00 16\n\
+        00a0   00 00 01 90 00 2f 25 e3 00 00 00 00 00 00 00 16\n\
+        00b0   00 00 00 02 00 00 00 02 00 00 03 e9 00 00 00 10\n\
+        00c0   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+        00d0   00 00 00 01 00 00 00 7c 00 00 00 01 00 00 00 6d\n\
+        00e0   00 00 00 04 00 00 00 69 3e 5b 35 4b 3a 72 f2 29\n\
+        00f0   01 70 58 25 08 00 45 00 00 5b 55 3e 40 00 40 06\n\
+        0100   d0 32 0a 00 00 97 0a 00 00 96 2b cb c1 f3 47 e9\n\
+        0110   a7 d7 b0 5a a0 59 80 18 00 2e e3 9e 00 00 01 01\n\
+        0120   08 0a 3e d9 3b 26 3e d9 4a dc 56 41 4c 55 45 20\n\
+        0130   73 65 73 73 69 6f 6e 2e 74 69 6d 65 20 30 20 38\n\
+        0140   0d 0a 31 31 3a 34 38 3a 32 38 0d 0a 45 4e 44 0d\n\
+        0150   0a 56 80 18\
+    ");
+
+    let parser = SflowParser::default();
+    let borrowed = parser.parse_bytes_ref(&data);
+    assert!(borrowed.error.is_none());
+
+    let sources: Vec<_> = borrowed.datagrams[0]
+        .samples
+        .iter()
+        .map(SflowSampleRef::data_source)
+        .collect();
+    assert_eq!(
+        sources,
+        vec![Some(DataSource::IfIndex(16)), Some(DataSource::IfIndex(22))]
+    );
+
+    let SflowSampleRef::Flow(fs) = &borrowed.datagrams[0].samples[1] else {
+        panic!("Expected flow sample");
+    };
+    let header = fs
+        .records
+        .iter()
+        .find_map(|r| match r {
+            FlowRecordRef::RawPacketHeader(RawPacketHeaderRef { header, .. }) => Some(*header),
+            _ => None,
+        })
+        .expect("raw packet header");
+    assert_eq!(header.len(), 105);
+    assert!(points_into(&data, header));
+
+    assert_eq!(borrowed.into_owned(), parser.parse_bytes(&data));
+}
+
+#[test]
+fn test_borrowed_strings_and_unknown_data() {
+    let data = string_datagram(vec![
+        FlowRecord::ExtendedUser(ExtendedUser {
+            src_charset: 106,
+            src_user: "alice".into(),
+            dst_charset: 106,
+            dst_user: "bob".into(),
+        }),
+        FlowRecord::HttpRequest(HttpRequest {
+            method: 2,
+            protocol: 1001,
+            uri: "/index.html".into(),
+            host: "example.com".into(),
+            referer: String::new(),
+            useragent: "curl/8".into(),
+            xff: String::new(),
+            authuser: String::new(),
+            mime_type: "text/html".into(),
+            req_bytes: 10,
+            resp_bytes: 20,
+            duration_us: 30,
+            status: 200,
+        }),
+        FlowRecord::Unknown {
+            enterprise: 9,
+            format: 1,
+            data: vec![1, 2, 3, 4],
+        },
+    ]);
+
+    let parser = SflowParser::default();
+    let borrowed = parser.parse_bytes_ref(&data);
+    assert!(borrowed.error.is_none());
+
+    let SflowSampleRef::Flow(fs) = &borrowed.datagrams[0].samples[0] else {
+        panic!("Expected flow sample");
+    };
+    match &fs.records[0] {
+        FlowRecordRef::ExtendedUser(u) => {
+            assert!(matches!(u.src_user, Cow::Borrowed("alice")));
+            assert!(points_into(&data, u.dst_user.as_bytes()));
+        }
+        other => panic!("Expected ExtendedUser, got {other:?}"),
+    }
+    match &fs.records[1] {
+        FlowRecordRef::HttpRequest(r) => {
+            assert!(matches!(r.host, Cow::Borrowed("example.com")));
+            assert!(matches!(r.referer, Cow::Borrowed("")));
+        }
+        other => panic!("Expected HttpRequest, got {other:?}"),
+    }
+    match &fs.records[2] {
+        FlowRecordRef::Unknown { data: body, .. } => {
+            assert_eq!(*body, &[1, 2, 3, 4]);
+            assert!(points_into(&data, body));
+        }
+        other => panic!("Expected Unknown, got {other:?}"),
+    }
+
+    assert_eq!(borrowed.into_owned(), parser.parse_bytes(&data));
+}
+
+#[test]
+fn test_borrowed_invalid_utf8_is_owned() {
+    let mut data = string_datagram(vec![FlowRecord::ExtendedUser(ExtendedUser {
+        src_charset: 106,
+        src_user: "abc".into(),
+        dst_charset: 106,
+        dst_user: String::new(),
+    })]);
+    let pos = data.windows(3).position(|w| w == b"abc").unwrap();
+    data[pos + 1] = 0xFF;
+
+    let borrowed = SflowParser::default().parse_bytes_ref(&data);
+    let SflowSampleRef::Flow(fs) = &borrowed.datagrams[0].samples[0] else {
+        panic!("Expected flow sample");
+    };
+    let FlowRecordRef::ExtendedUser(u) = &fs.records[0] else {
+        panic!("Expected ExtendedUser");
+    };
+    assert!(matches!(u.src_user, Cow::Owned(_)));
+    assert_eq!(u.src_user, "a\u{FFFD}c");
+}
+
+#[test]
+fn test_borrowed_partial_result_matches_owned() {
+    let mut data = string_datagram(vec![]);
+    let first_len = data.len();
+    data.extend_from_slice(&data.clone()[..first_len - 4]);
+
+    let parser = SflowParser::default();
+    let borrowed = parser.parse_bytes_ref(&data);
+    assert_eq!(borrowed.datagrams.len(), 1);
+    assert!(borrowed.error.is_some());
+    assert_eq!(borrowed.into_owned(), parser.parse_bytes(&data));
+}
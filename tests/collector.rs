@@ -0,0 +1,237 @@
+#![cfg(feature = "collector")]
+
+use flowparser_sflow::collector::{Collector, CollectorStats, OutputFormat};
+use flowparser_sflow::counter_records::HostMemory;
+use flowparser_sflow::prometheus::MetricsExporter;
+use flowparser_sflow::samples::CounterSample;
+use flowparser_sflow::*;
+use std::io::{BufWriter, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Shared {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn sflow(agent: [u8; 4], sequence_number: u32) -> Vec<u8> {
+    SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::from(agent)),
+        sub_agent_id: 0,
+        sequence_number,
+        uptime: 1000,
+        samples: vec![],
+    }
+    .to_bytes()
+}
+
+fn localhost() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+#[test]
+fn test_output_format_from_str() {
+    assert_eq!("json".parse(), Ok(OutputFormat::Json));
+    assert_eq!("lines".parse(), Ok(OutputFormat::SflowtoolLines));
+    assert_eq!(
+        "influx-flows".parse(),
+        Ok(OutputFormat::Influx { flows: true })
+    );
+    assert_eq!("pcapng".parse(), Ok(OutputFormat::PcapNg));
+    assert!("csv".parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_filter_forward_and_json_output() {
+    let target = UdpSocket::bind(localhost()).unwrap();
+    target
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let out = Shared::default();
+    let mut collector = Collector::builder()
+        .with_bind(localhost())
+        .with_recv_buffer(1 << 20)
+        .with_agents(&[IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))])
+        .with_forward(target.local_addr().unwrap())
+        .with_output(OutputFormat::Json, out.clone())
+        .build()
+        .unwrap();
+    assert_eq!(collector.local_addrs().unwrap().len(), 1);
+
+    let source: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+    let accepted = sflow([10, 0, 0, 1], 1);
+    collector
+        .handle(&accepted, source, SystemTime::now())
+        .unwrap();
+    collector
+        .handle(&sflow([10, 0, 0, 2], 1), source, SystemTime::now())
+        .unwrap();
+    collector
+        .handle(&[0xff; 12], source, SystemTime::now())
+        .unwrap();
+
+    assert_eq!(
+        collector.stats(),
+        CollectorStats {
+            received: 3,
+            parse_errors: 1,
+            filtered: 1,
+            written: 1,
+            forwarded: 1,
+            forward_errors: 0,
+        }
+    );
+    let text = out.text();
+    assert_eq!(text.lines().count(), 1);
+    let json: serde_json::Value = serde_json::from_str(text.trim_end()).unwrap();
+    assert_eq!(json["sequence_number"], 1);
+
+    let mut buf = [0u8; 1500];
+    let (size, _) = target.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..size], &accepted[..]);
+}
+
+#[test]
+fn test_run_receives_from_socket() {
+    let out = Shared::default();
+    let collector = Collector::builder()
+        .with_bind(localhost())
+        .with_bind(localhost())
+        .with_excluded_agents(&[IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))])
+        .with_output(OutputFormat::Sflowtool, out.clone())
+        .build()
+        .unwrap();
+    let addrs = collector.local_addrs().unwrap();
+    std::thread::spawn(move || collector.run());
+
+    let sender = UdpSocket::bind(localhost()).unwrap();
+    sender.send_to(&sflow([10, 0, 0, 2], 1), addrs[0]).unwrap();
+    sender.send_to(&sflow([10, 0, 0, 3], 2), addrs[1]).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !out.text().contains("endDatagram") && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let text = out.text();
+    assert!(text.contains("agent 10.0.0.3\npacketSequenceNo 2\n"));
+    assert!(text.contains("datagramSourceIP 127.0.0.1\n"));
+    assert!(!text.contains("10.0.0.2"));
+}
+
+#[test]
+fn test_output_flushed_on_interval() {
+    let out = Shared::default();
+    let mut collector = Collector::builder()
+        .with_bind(localhost())
+        .with_flush_interval(Duration::from_secs(3600))
+        .with_output(OutputFormat::Json, BufWriter::new(out.clone()))
+        .build()
+        .unwrap();
+    for seq in 1..=3 {
+        collector
+            .handle(&sflow([10, 0, 0, 1], seq), localhost(), SystemTime::now())
+            .unwrap();
+    }
+    assert!(out.text().is_empty());
+    collector.flush().unwrap();
+    assert_eq!(out.text().lines().count(), 3);
+
+    // An idle collector flushes once the interval has passed.
+    let out = Shared::default();
+    let collector = Collector::builder()
+        .with_bind(localhost())
+        .with_flush_interval(Duration::from_millis(50))
+        .with_output(OutputFormat::Json, BufWriter::new(out.clone()))
+        .build()
+        .unwrap();
+    let addr = collector.local_addrs().unwrap()[0];
+    std::thread::spawn(move || collector.run());
+    let sender = UdpSocket::bind(localhost()).unwrap();
+    sender.send_to(&sflow([10, 0, 0, 1], 1), addr).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while out.text().is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(out.text().lines().count(), 1);
+}
+
+#[test]
+fn test_metrics_endpoint() {
+    let mut collector = Collector::builder()
+        .with_bind(localhost())
+        .with_metrics(localhost(), MetricsExporter::default())
+        .build()
+        .unwrap();
+    let datagram = SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+        sub_agent_id: 0,
+        sequence_number: 1,
+        uptime: 1000,
+        samples: vec![SflowSample::Counter(CounterSample {
+            sequence_number: 1,
+            source_id_type: 2,
+            source_id_index: 1,
+            records: vec![CounterRecord::HostMemory(HostMemory {
+                mem_total: 1024,
+                mem_free: 512,
+                mem_shared: 0,
+                mem_buffers: 0,
+                mem_cached: 0,
+                swap_total: 0,
+                swap_free: 0,
+                page_in: 7,
+                page_out: 0,
+                swap_in: 0,
+                swap_out: 0,
+            })],
+        })],
+    };
+    collector
+        .handle(&datagram.to_bytes(), localhost(), SystemTime::now())
+        .unwrap();
+    let addr = collector.metrics_addr().unwrap();
+    std::thread::spawn(move || collector.run());
+
+    let get = |request: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let response = get("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+    assert!(
+        response
+            .contains("sflow_host_memory_page_in_total{agent=\"10.0.0.1\",source=\"2:1\"} 7\n")
+    );
+
+    let response = get(
+        "GET /metrics HTTP/1.1\r\nAccept: application/openmetrics-text; version=1.0.0\r\n\r\n",
+    );
+    assert!(response.contains("Content-Type: application/openmetrics-text"));
+    assert!(response.ends_with("# EOF\n"));
+
+    let response = get("GET / HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
@@ -0,0 +1,908 @@
+//! Datagram fixtures shared by the integration tests.
+//!
+//! Every test binary compiles this module but uses only part of it.
+#![allow(dead_code)]
+
+use flowparser_sflow::counter_records::*;
+use flowparser_sflow::flow_records::*;
+use flowparser_sflow::samples::*;
+use flowparser_sflow::sequence::AgentKey;
+use flowparser_sflow::*;
+use mac_address::MacAddress;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
+
+/// `secs` seconds after 2023-11-14T22:13:20Z.
+pub fn at(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+}
+
+/// Receive time of [`datagram`]: 2023-11-14T22:13:20.250Z.
+pub fn received() -> SystemTime {
+    at(0) + Duration::from_millis(250)
+}
+
+/// Sub-agent 1 of agent 10.0.0.`last_octet`.
+pub fn agent(last_octet: u8) -> AgentKey {
+    AgentKey {
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, last_octet)),
+        sub_agent_id: 1,
+    }
+}
+
+/// Address of the agent that sends [`datagram`].
+pub fn agent_address() -> AddressType {
+    AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 254))
+}
+
+/// Datagram from sub-agent 1 of [`agent_address`], sequence 42.
+pub fn datagram(samples: Vec<SflowSample>) -> SflowDatagram {
+    SflowDatagram {
+        version: 5,
+        agent_address: agent_address(),
+        sub_agent_id: 1,
+        sequence_number: 42,
+        uptime: 123_456,
+        samples,
+    }
+}
+
+/// [`datagram`] with the given sequence number, sent one second per
+/// sequence number after boot.
+pub fn numbered(sequence_number: u32, samples: Vec<SflowSample>) -> SflowDatagram {
+    SflowDatagram {
+        sequence_number,
+        uptime: 1000 * sequence_number,
+        ..datagram(samples)
+    }
+}
+
+/// Flow sample on ifIndex 3, 1 in 512, sent out of two interfaces.
+pub fn flow_sample(records: Vec<FlowRecord>) -> FlowSample {
+    FlowSample {
+        sequence_number: 7,
+        source_id_type: 0,
+        source_id_index: 3,
+        sampling_rate: 512,
+        sample_pool: 1024,
+        drops: 0,
+        input: 3,
+        output: 0x8000_0002,
+        records,
+    }
+}
+
+pub fn flow(records: Vec<FlowRecord>) -> SflowSample {
+    SflowSample::Flow(flow_sample(records))
+}
+
+pub fn counter_sample(
+    source_id_type: u32,
+    source_id_index: u32,
+    records: Vec<CounterRecord>,
+) -> CounterSample {
+    CounterSample {
+        sequence_number: 8,
+        source_id_type,
+        source_id_index,
+        records,
+    }
+}
+
+pub fn counters(
+    source_id_type: u32,
+    source_id_index: u32,
+    records: Vec<CounterRecord>,
+) -> SflowSample {
+    SflowSample::Counter(counter_sample(source_id_type, source_id_index, records))
+}
+
+/// 1500 byte TCP packet from 192.168.1.1:40000 to 10.0.0.1:443.
+pub fn sampled_ipv4() -> SampledIpv4 {
+    SampledIpv4 {
+        length: 1500,
+        protocol: 6,
+        src_ip: Ipv4Addr::new(192, 168, 1, 1),
+        dst_ip: Ipv4Addr::new(10, 0, 0, 1),
+        src_port: 40000,
+        dst_port: 443,
+        tcp_flags: 0x18,
+        tos: 0,
+    }
+}
+
+pub fn ipv4() -> FlowRecord {
+    FlowRecord::SampledIpv4(sampled_ipv4())
+}
+
+pub fn cpu() -> CounterRecord {
+    CounterRecord::HostCpu(HostCpu {
+        load_one: 0.6,
+        load_five: 0.5,
+        load_fifteen: 0.25,
+        proc_run: 1,
+        proc_total: 200,
+        cpu_num: 4,
+        cpu_speed: 2400,
+        uptime: 3600,
+        cpu_user: 1000,
+        cpu_nice: 0,
+        cpu_system: 500,
+        cpu_idle: 9000,
+        cpu_wio: 0,
+        cpu_intr: 0,
+        cpu_sintr: 0,
+        interrupts: 42,
+        contexts: 99,
+    })
+}
+
+/// MAC address 00:11:22:33:44:`last`.
+pub fn mac(last: u8) -> MacAddress {
+    MacAddress::new([0x00, 0x11, 0x22, 0x33, 0x44, last])
+}
+
+pub fn ipv6_address() -> Ipv6Addr {
+    "2001:db8::1".parse().unwrap()
+}
+
+/// One record of every [`FlowRecord`] variant.
+pub fn all_flow_records() -> Vec<FlowRecord> {
+    let eth = SampledEthernet {
+        length: 64,
+        src_mac: mac(1),
+        dst_mac: mac(2),
+        eth_type: 0x0800,
+    };
+    let ipv4 = SampledIpv4 {
+        length: 60,
+        protocol: 6,
+        src_ip: Ipv4Addr::new(192, 168, 1, 1),
+        dst_ip: Ipv4Addr::new(10, 0, 0, 1),
+        src_port: 80,
+        dst_port: 443,
+        tcp_flags: 0x12,
+        tos: 0,
+    };
+    let ipv6_rec = SampledIpv6 {
+        length: 80,
+        protocol: 17,
+        src_ip: ipv6_address(),
+        dst_ip: Ipv6Addr::LOCALHOST,
+        src_port: 53,
+        dst_port: 5353,
+        tcp_flags: 0,
+        priority: 1,
+    };
+    vec![
+        FlowRecord::RawPacketHeader(RawPacketHeader {
+            header_protocol: 1,
+            frame_length: 1514,
+            stripped: 4,
+            header_length: 5,
+            header: vec![1, 2, 3, 4, 5],
+        }),
+        FlowRecord::SampledEthernet(eth.clone()),
+        FlowRecord::SampledIpv4(ipv4.clone()),
+        FlowRecord::SampledIpv6(ipv6_rec.clone()),
+        FlowRecord::ExtendedSwitch(ExtendedSwitch {
+            src_vlan: 100,
+            src_priority: 1,
+            dst_vlan: 200,
+            dst_priority: 2,
+        }),
+        FlowRecord::ExtendedRouter(ExtendedRouter {
+            next_hop: AddressType::IPv6(ipv6_address()),
+            src_mask_len: 24,
+            dst_mask_len: 64,
+        }),
+        FlowRecord::ExtendedGateway(ExtendedGateway {
+            next_hop: AddressType::IPv4(Ipv4Addr::new(10, 1, 1, 1)),
+            as_number: 65000,
+            src_as: 65001,
+            src_peer_as: 65002,
+            as_path_segments: vec![extended_gateway::AsPathSegment {
+                segment_type: 2,
+                values: vec![65003, 65004],
+            }],
+            communities: vec![0xFFFF_0001],
+        }),
+        FlowRecord::ExtendedUser(ExtendedUser {
+            src_charset: 106,
+            src_user: "alice".into(),
+            dst_charset: 106,
+            dst_user: "bob".into(),
+        }),
+        FlowRecord::ExtendedUrl(ExtendedUrl {
+            direction: 1,
+            url: "/index.html".into(),
+            host: "example.com".into(),
+        }),
+        FlowRecord::ExtendedMpls(ExtendedMpls {
+            next_hop: AddressType::IPv4(Ipv4Addr::new(10, 2, 2, 2)),
+            in_label_stack: vec![16, 17],
+            out_label_stack: vec![18],
+        }),
+        FlowRecord::ExtendedNat(ExtendedNat {
+            src_address: AddressType::IPv4(Ipv4Addr::new(1, 2, 3, 4)),
+            dst_address: AddressType::IPv6(ipv6_address()),
+        }),
+        FlowRecord::ExtendedMplsTunnel(ExtendedMplsTunnel {
+            tunnel_lsp_name: "lsp0".into(),
+            tunnel_id: 7,
+            tunnel_cos: 3,
+        }),
+        FlowRecord::ExtendedMplsVc(ExtendedMplsVc {
+            vc_instance_name: "vc-a".into(),
+            vll_vc_id: 9,
+            vc_label_cos: 1,
+        }),
+        FlowRecord::ExtendedMplsFtn(ExtendedMplsFtn {
+            mpls_ftn_descr: "ftn".into(),
+            mpls_ftn_mask: 0xFFFF_FF00,
+        }),
+        FlowRecord::ExtendedMplsLdpFec(ExtendedMplsLdpFec {
+            mpls_fec_addr_prefix_length: 24,
+        }),
+        FlowRecord::ExtendedVlanTunnel(ExtendedVlanTunnel {
+            stack: vec![0x8100_0064, 0x8100_00c8],
+        }),
+        FlowRecord::Extended80211Payload(Extended80211Payload {
+            cipher_suite: 4,
+            data: vec![0xAA, 0xBB, 0xCC],
+        }),
+        FlowRecord::Extended80211Rx(Extended80211Rx {
+            ssid: "corp".into(),
+            bssid: mac(3),
+            version: 4,
+            channel: 36,
+            speed: 300_000_000,
+            rsni: 30,
+            rcpi: 100,
+            packet_duration_us: 50,
+        }),
+        FlowRecord::Extended80211Tx(Extended80211Tx {
+            ssid: "guest-net".into(),
+            bssid: mac(4),
+            version: 5,
+            transmissions: 2,
+            packet_duration_us: 40,
+            retrans_duration_us: 10,
+            channel: 11,
+            speed: 54_000_000,
+            power: 20,
+        }),
+        FlowRecord::ExtendedL2TunnelEgress(eth.clone()),
+        FlowRecord::ExtendedL2TunnelIngress(eth),
+        FlowRecord::ExtendedIpv4TunnelEgress(ipv4.clone()),
+        FlowRecord::ExtendedIpv4TunnelIngress(ipv4),
+        FlowRecord::ExtendedIpv6TunnelEgress(ipv6_rec.clone()),
+        FlowRecord::ExtendedIpv6TunnelIngress(ipv6_rec),
+        FlowRecord::ExtendedDecapsulateEgress(ExtendedDecapsulateEgress {
+            inner_header_offset: 14,
+        }),
+        FlowRecord::ExtendedDecapsulateIngress(ExtendedDecapsulateIngress {
+            inner_header_offset: 50,
+        }),
+        FlowRecord::ExtendedVniEgress(ExtendedVniEgress { vni: 5000 }),
+        FlowRecord::ExtendedVniIngress(ExtendedVniIngress { vni: 5001 }),
+        FlowRecord::ExtendedEgressQueue(ExtendedEgressQueue { queue: 3 }),
+        FlowRecord::ExtendedAcl(ExtendedAcl {
+            number: 10,
+            name: "deny-all".into(),
+            direction: 1,
+        }),
+        FlowRecord::ExtendedFunction(ExtendedFunction {
+            symbol: "ip_rcv".into(),
+        }),
+        FlowRecord::ExtendedTransit(ExtendedTransit {
+            transit_delay_ns: 1500,
+        }),
+        FlowRecord::ExtendedQueue(ExtendedQueue { queue_depth: 4096 }),
+        FlowRecord::ExtendedSocketIpv4(ExtendedSocketIpv4 {
+            protocol: 6,
+            local_ip: Ipv4Addr::new(10, 0, 0, 5),
+            remote_ip: Ipv4Addr::new(10, 0, 0, 6),
+            local_port: 8080,
+            remote_port: 40000,
+        }),
+        FlowRecord::ExtendedSocketIpv6(ExtendedSocketIpv6 {
+            protocol: 6,
+            local_ip: ipv6_address(),
+            remote_ip: Ipv6Addr::LOCALHOST,
+            local_port: 443,
+            remote_port: 50000,
+        }),
+        FlowRecord::ExtendedProxySocketIpv4(ExtendedProxySocketIpv4 {
+            protocol: 6,
+            local_ip: Ipv4Addr::new(172, 16, 0, 1),
+            remote_ip: Ipv4Addr::new(172, 16, 0, 2),
+            local_port: 3128,
+            remote_port: 80,
+        }),
+        FlowRecord::ExtendedProxySocketIpv6(ExtendedProxySocketIpv6 {
+            protocol: 6,
+            local_ip: Ipv6Addr::LOCALHOST,
+            remote_ip: ipv6_address(),
+            local_port: 3128,
+            remote_port: 443,
+        }),
+        FlowRecord::JvmRuntime(JvmRuntime {
+            vm_name: "OpenJDK".into(),
+            vm_vendor: "Eclipse".into(),
+            vm_version: "21".into(),
+        }),
+        FlowRecord::MemcacheOperation(MemcacheOperation {
+            protocol: 1,
+            cmd: 2,
+            key: "session:1".into(),
+            nkeys: 1,
+            value_bytes: 512,
+            duration_us: 90,
+            status: 1,
+        }),
+        FlowRecord::AppOperation(AppOperation {
+            context: "checkout".into(),
+            status_descr: "ok".into(),
+            req_bytes: 100,
+            resp_bytes: 2000,
+            duration_us: 1200,
+            status: 0,
+        }),
+        FlowRecord::HttpRequest(HttpRequest {
+            method: 2,
+            protocol: 1001,
+            uri: "/api".into(),
+            host: "example.com".into(),
+            referer: "".into(),
+            useragent: "curl/8".into(),
+            xff: "".into(),
+            authuser: "".into(),
+            mime_type: "application/json".into(),
+            req_bytes: 10,
+            resp_bytes: 20,
+            duration_us: 30,
+            status: 200,
+        }),
+        FlowRecord::ExtendedProxyRequest(ExtendedProxyRequest {
+            uri: "/proxied".into(),
+            host: "upstream".into(),
+        }),
+        FlowRecord::Unknown {
+            enterprise: 9,
+            format: 99,
+            data: vec![1, 2, 3, 4],
+        },
+    ]
+}
+
+/// One record of every [`CounterRecord`] variant.
+pub fn all_counter_records() -> Vec<CounterRecord> {
+    vec![
+        CounterRecord::GenericInterface(GenericInterface {
+            if_index: 1,
+            if_type: 6,
+            if_speed: 10_000_000_000,
+            if_direction: 1,
+            if_status: 3,
+            if_in_octets: 1 << 40,
+            if_in_ucast_pkts: 2,
+            if_in_multicast_pkts: 3,
+            if_in_broadcast_pkts: 4,
+            if_in_discards: 5,
+            if_in_errors: 6,
+            if_in_unknown_protos: 7,
+            if_out_octets: 1 << 41,
+            if_out_ucast_pkts: 8,
+            if_out_multicast_pkts: 9,
+            if_out_broadcast_pkts: 10,
+            if_out_discards: 11,
+            if_out_errors: 12,
+            if_promiscuous_mode: 0,
+        }),
+        CounterRecord::EthernetInterface(EthernetInterface {
+            dot3_stats_alignment_errors: 1,
+            dot3_stats_fcs_errors: 2,
+            dot3_stats_single_collision_frames: 3,
+            dot3_stats_multiple_collision_frames: 4,
+            dot3_stats_sqe_test_errors: 5,
+            dot3_stats_deferred_transmissions: 6,
+            dot3_stats_late_collisions: 7,
+            dot3_stats_excessive_collisions: 8,
+            dot3_stats_internal_mac_transmit_errors: 9,
+            dot3_stats_carrier_sense_errors: 10,
+            dot3_stats_frame_too_longs: 11,
+            dot3_stats_internal_mac_receive_errors: 12,
+            dot3_stats_symbol_errors: 13,
+        }),
+        CounterRecord::TokenRing(TokenRing {
+            dot5_stats_line_errors: 1,
+            dot5_stats_burst_errors: 2,
+            dot5_stats_ac_errors: 3,
+            dot5_stats_abort_trans_errors: 4,
+            dot5_stats_internal_errors: 5,
+            dot5_stats_lost_frame_errors: 6,
+            dot5_stats_receive_congestions: 7,
+            dot5_stats_frame_copied_errors: 8,
+            dot5_stats_token_errors: 9,
+            dot5_stats_soft_errors: 10,
+            dot5_stats_hard_errors: 11,
+            dot5_stats_signal_loss: 12,
+            dot5_stats_transmit_beacons: 13,
+            dot5_stats_recoverys: 14,
+            dot5_stats_lobe_wires: 15,
+            dot5_stats_removes: 16,
+            dot5_stats_singles: 17,
+            dot5_stats_freq_errors: 18,
+        }),
+        CounterRecord::VgCounters(VgCounters {
+            in_high_priority_frames: 1,
+            in_high_priority_octets: 2,
+            in_norm_priority_frames: 3,
+            in_norm_priority_octets: 4,
+            in_ipm_errors: 5,
+            in_oversize_frame_errors: 6,
+            in_data_errors: 7,
+            in_null_addressed_frames: 8,
+            out_high_priority_frames: 9,
+            out_high_priority_octets: 10,
+            out_norm_priority_frames: 11,
+            out_norm_priority_octets: 12,
+            in_hc_high_priority_octets: 13,
+            in_hc_norm_priority_octets: 14,
+            out_hc_high_priority_octets: 15,
+            out_hc_norm_priority_octets: 16,
+        }),
+        CounterRecord::Vlan(Vlan {
+            vlan_id: 100,
+            octets: 4096,
+            ucast_pkts: 50,
+            multicast_pkts: 5,
+            broadcast_pkts: 2,
+            discards: 0,
+        }),
+        CounterRecord::Ieee80211Counters(Ieee80211Counters {
+            transmitted_fragments: 1,
+            multicast_transmitted_frames: 2,
+            failures: 3,
+            retries: 4,
+            multiple_retries: 5,
+            frame_duplicates: 6,
+            rts_successes: 7,
+            rts_failures: 8,
+            ack_failures: 9,
+            received_fragments: 10,
+            multicast_received_frames: 11,
+            fcs_errors: 12,
+            transmitted_frames: 13,
+            wep_undecryptables: 14,
+            qos_discarded_fragments: 15,
+            associated_stations: 16,
+            qos_cf_polls_received: 17,
+            qos_cf_polls_unused: 18,
+            qos_cf_polls_unusable: 19,
+            qos_cf_polls_lost: 20,
+        }),
+        CounterRecord::LagPortStats(LagPortStats {
+            actor_system_id: mac(5),
+            partner_system_id: mac(6),
+            attachment_individual: 1,
+            lacpdu_rx: 2,
+            marker_pdu_rx: 3,
+            marker_response_pdu_rx: 4,
+            unknown_rx: 5,
+            illegal_rx: 6,
+            lacpdu_tx: 7,
+            marker_pdu_tx: 8,
+            marker_response_pdu_tx: 9,
+        }),
+        CounterRecord::SlowPathCounts(SlowPathCounts {
+            unknown: 1,
+            other: 2,
+            cam_miss: 3,
+            cam_full: 4,
+            no_hw_support: 5,
+            cntrl: 6,
+        }),
+        CounterRecord::IbCounters(IbCounters {
+            port_xmit_data: 1,
+            port_rcv_data: 2,
+            port_xmit_pkts: 3,
+            port_rcv_pkts: 4,
+            symbol_error_counter: 5,
+            link_error_recovery_counter: 6,
+            link_downed_counter: 7,
+            port_rcv_errors: 8,
+            port_rcv_remote_physical_errors: 9,
+            port_rcv_switch_relay_errors: 10,
+            port_xmit_discards: 11,
+            port_xmit_constraint_errors: 12,
+            port_rcv_constraint_errors: 13,
+            local_link_integrity_errors: 14,
+            excessive_buffer_overrun_errors: 15,
+            vl15_dropped: 16,
+        }),
+        CounterRecord::Sfp(Sfp {
+            module_id: 1,
+            module_num_lanes: 1,
+            module_supply_voltage: 3300,
+            module_temperature: -5000,
+            lanes: vec![sfp::SfpLane {
+                tx_bias_current: 1,
+                tx_power: 2,
+                tx_power_min: 3,
+                tx_power_max: 4,
+                tx_wavelength: 1310,
+                rx_power: 5,
+                rx_power_min: 6,
+                rx_power_max: 7,
+                rx_wavelength: 1310,
+                bias_current: 8,
+            }],
+        }),
+        CounterRecord::Processor(Processor {
+            cpu_5s: 10,
+            cpu_1m: 20,
+            cpu_5m: 30,
+            total_memory: 1 << 33,
+            free_memory: 1 << 32,
+        }),
+        CounterRecord::RadioUtilization(RadioUtilization {
+            elapsed_time: 1000,
+            on_channel_time: 900,
+            on_channel_busy_time: 300,
+        }),
+        CounterRecord::QueueLength(QueueLength {
+            queue_index: 1,
+            segment_size: 2,
+            queue_segments: 3,
+            queue_length_0: 4,
+            queue_length_1: 5,
+            queue_length_2: 6,
+            queue_length_4: 7,
+            queue_length_8: 8,
+            queue_length_32: 9,
+            queue_length_128: 10,
+            queue_length_1024: 11,
+            queue_length_more: 12,
+            dropped: 13,
+        }),
+        CounterRecord::OfPort(OfPort {
+            datapath_id: 0x0000_0011_2233_4455,
+            port_no: 7,
+        }),
+        CounterRecord::PortName(PortName {
+            name: "eth0".into(),
+        }),
+        CounterRecord::HostDescr(HostDescr {
+            hostname: "host-1".into(),
+            uuid: uuid::Uuid::from_bytes([7; 16]),
+            machine_type: MachineType::X86_64,
+            os_name: OsName::Unrecognized(99),
+            os_release: "6.1.0".into(),
+        }),
+        CounterRecord::HostAdapters(HostAdapters {
+            adapters: vec![host_adapters::HostAdapter {
+                if_index: 2,
+                mac_addresses: vec![mac(7), mac(8)],
+            }],
+        }),
+        CounterRecord::HostParent(HostParent {
+            container_type: 2,
+            container_index: 1,
+        }),
+        CounterRecord::HostCpu(HostCpu {
+            load_one: 0.5,
+            load_five: 0.25,
+            load_fifteen: 0.125,
+            proc_run: 1,
+            proc_total: 2,
+            cpu_num: 3,
+            cpu_speed: 4,
+            uptime: 5,
+            cpu_user: 6,
+            cpu_nice: 7,
+            cpu_system: 8,
+            cpu_idle: 9,
+            cpu_wio: 10,
+            cpu_intr: 11,
+            cpu_sintr: 12,
+            interrupts: 13,
+            contexts: 14,
+        }),
+        CounterRecord::HostMemory(HostMemory {
+            mem_total: 1,
+            mem_free: 2,
+            mem_shared: 3,
+            mem_buffers: 4,
+            mem_cached: 5,
+            swap_total: 6,
+            swap_free: 7,
+            page_in: 8,
+            page_out: 9,
+            swap_in: 10,
+            swap_out: 11,
+        }),
+        CounterRecord::HostDiskIo(HostDiskIo {
+            disk_total: 1,
+            disk_free: 2,
+            part_max_used: 3,
+            reads: 4,
+            bytes_read: 5,
+            read_time: 6,
+            writes: 7,
+            bytes_written: 8,
+            write_time: 9,
+        }),
+        CounterRecord::HostNetIo(HostNetIo {
+            bytes_in: 1,
+            packets_in: 2,
+            errs_in: 3,
+            drops_in: 4,
+            bytes_out: 5,
+            packets_out: 6,
+            errs_out: 7,
+            drops_out: 8,
+        }),
+        CounterRecord::VirtNode(VirtNode {
+            mhz: 2400,
+            cpus: 8,
+            memory: 1 << 34,
+            memory_free: 1 << 33,
+            num_domains: 4,
+        }),
+        CounterRecord::VirtCpu(VirtCpu {
+            state: VirtDomainState::Running,
+            cpu_time: 100,
+            nr_virt_cpu: 2,
+        }),
+        CounterRecord::VirtMemory(VirtMemory {
+            memory: 1 << 30,
+            max_memory: 1 << 31,
+        }),
+        CounterRecord::VirtDiskIo(VirtDiskIo {
+            capacity: 1,
+            allocation: 2,
+            available: 3,
+            rd_req: 4,
+            rd_bytes: 5,
+            wr_req: 6,
+            wr_bytes: 7,
+            errs: 8,
+        }),
+        CounterRecord::VirtNetIo(VirtNetIo {
+            rx_bytes: 1,
+            rx_packets: 2,
+            rx_errs: 3,
+            rx_drop: 4,
+            tx_bytes: 5,
+            tx_packets: 6,
+            tx_errs: 7,
+            tx_drop: 8,
+        }),
+        CounterRecord::Mib2IpGroup(Mib2IpGroup {
+            ip_forwarding: 1,
+            ip_default_ttl: 64,
+            ip_in_receives: 3,
+            ip_in_hdr_errors: 4,
+            ip_in_addr_errors: 5,
+            ip_forw_datagrams: 6,
+            ip_in_unknown_protos: 7,
+            ip_in_discards: 8,
+            ip_in_delivers: 9,
+            ip_out_requests: 10,
+            ip_out_discards: 11,
+            ip_out_no_routes: 12,
+            ip_reasm_timeout: 13,
+            ip_reasm_reqds: 14,
+            ip_reasm_oks: 15,
+            ip_reasm_fails: 16,
+            ip_frag_oks: 17,
+            ip_frag_fails: 18,
+            ip_frag_creates: 19,
+        }),
+        CounterRecord::Mib2IcmpGroup(Mib2IcmpGroup {
+            icmp_in_msgs: 1,
+            icmp_in_errors: 2,
+            icmp_in_dest_unreachs: 3,
+            icmp_in_time_excds: 4,
+            icmp_in_parm_probs: 5,
+            icmp_in_src_quenchs: 6,
+            icmp_in_redirects: 7,
+            icmp_in_echos: 8,
+            icmp_in_echo_reps: 9,
+            icmp_in_timestamps: 10,
+            icmp_in_timestamp_reps: 11,
+            icmp_in_addr_masks: 12,
+            icmp_in_addr_mask_reps: 13,
+            icmp_out_msgs: 14,
+            icmp_out_errors: 15,
+            icmp_out_dest_unreachs: 16,
+            icmp_out_time_excds: 17,
+            icmp_out_parm_probs: 18,
+            icmp_out_src_quenchs: 19,
+            icmp_out_redirects: 20,
+            icmp_out_echos: 21,
+            icmp_out_echo_reps: 22,
+            icmp_out_timestamps: 23,
+            icmp_out_timestamp_reps: 24,
+            icmp_out_addr_masks: 25,
+            icmp_out_addr_mask_reps: 26,
+        }),
+        CounterRecord::Mib2TcpGroup(Mib2TcpGroup {
+            tcp_rto_algorithm: 1,
+            tcp_rto_min: 2,
+            tcp_rto_max: 3,
+            tcp_max_conn: 4,
+            tcp_active_opens: 5,
+            tcp_passive_opens: 6,
+            tcp_attempt_fails: 7,
+            tcp_estab_resets: 8,
+            tcp_curr_estab: 9,
+            tcp_in_segs: 10,
+            tcp_out_segs: 11,
+            tcp_retrans_segs: 12,
+            tcp_in_errs: 13,
+            tcp_out_rsts: 14,
+            tcp_in_csum_errs: 15,
+        }),
+        CounterRecord::Mib2UdpGroup(Mib2UdpGroup {
+            udp_in_datagrams: 1,
+            udp_no_ports: 2,
+            udp_in_errors: 3,
+            udp_out_datagrams: 4,
+            udp_rcvbuf_errors: 5,
+            udp_sndbuf_errors: 6,
+            udp_in_csum_errors: 7,
+        }),
+        CounterRecord::JmxRuntime(JmxRuntime {
+            vm_name: "HotSpot".into(),
+            vm_vendor: "Oracle".into(),
+            vm_version: "17.0.2".into(),
+        }),
+        CounterRecord::JvmStatistics(JvmStatistics {
+            heap_initial: 1,
+            heap_used: 2,
+            heap_committed: 3,
+            heap_max: 4,
+            non_heap_initial: 5,
+            non_heap_used: 6,
+            non_heap_committed: 7,
+            non_heap_max: 8,
+            gc_count: 9,
+            gc_time: 10,
+            classes_loaded: 11,
+            classes_total: 12,
+            classes_unloaded: 13,
+            compilation_time: 14,
+            threads_live: 15,
+            threads_daemon: 16,
+            threads_started: 17,
+            fds_open: 18,
+            fds_max: 19,
+        }),
+        CounterRecord::HttpCounters(HttpCounters {
+            method_option_count: 1,
+            method_get_count: 2,
+            method_head_count: 3,
+            method_post_count: 4,
+            method_put_count: 5,
+            method_delete_count: 6,
+            method_trace_count: 7,
+            method_connect_count: 8,
+            method_other_count: 9,
+            status_1xx_count: 10,
+            status_2xx_count: 11,
+            status_3xx_count: 12,
+            status_4xx_count: 13,
+            status_5xx_count: 14,
+            status_other_count: 15,
+        }),
+        CounterRecord::AppOperations(AppOperations {
+            application: "shop".into(),
+            success: 1,
+            other: 2,
+            timeout: 3,
+            internal_error: 4,
+            bad_request: 5,
+            forbidden: 6,
+            too_large: 7,
+            not_implemented: 8,
+            not_found: 9,
+            unavailable: 10,
+            unauthorized: 11,
+            status_ok: 12,
+        }),
+        CounterRecord::AppResources(AppResources {
+            user_time: 1,
+            system_time: 2,
+            mem_used: 3,
+            mem_max: 4,
+            fd_open: 5,
+            fd_max: 6,
+            conn_open: 7,
+            conn_max: 8,
+        }),
+        CounterRecord::MemcacheCounters(MemcacheCounters {
+            cmd_set: 1,
+            cmd_touch: 2,
+            cmd_flush: 3,
+            get_hits: 4,
+            get_misses: 5,
+            delete_hits: 6,
+            delete_misses: 7,
+            incr_hits: 8,
+            incr_misses: 9,
+            decr_hits: 10,
+            decr_misses: 11,
+            cas_hits: 12,
+            cas_misses: 13,
+            cas_badval: 14,
+            auth_cmds: 15,
+            auth_errors: 16,
+            threads: 17,
+            conn_yields: 18,
+            listen_disabled_num: 19,
+            curr_connections: 20,
+            rejected_connections: 21,
+            total_connections: 22,
+            connection_structures: 23,
+            evictions: 24,
+            reclaimed: 25,
+            curr_items: 26,
+            total_items: 27,
+            bytes_read: 28,
+            bytes_written: 29,
+            bytes: 30,
+            limit_maxbytes: 31,
+        }),
+        CounterRecord::AppWorkers(AppWorkers {
+            workers_active: 1,
+            workers_idle: 2,
+            workers_max: 3,
+            req_delayed: 4,
+            req_dropped: 5,
+        }),
+        CounterRecord::OvsDpStats(OvsDpStats {
+            n_hit: 1,
+            n_missed: 2,
+            n_lost: 3,
+            n_mask_hit: 4,
+            n_flows: 5,
+            n_masks: 6,
+        }),
+        CounterRecord::Energy(Energy {
+            voltage: 12000,
+            current: 500,
+            real_power: 6000,
+            power_factor: 0,
+            energy: 1_000_000,
+            errors: 0,
+        }),
+        CounterRecord::Temperature(Temperature {
+            minimum: -10,
+            maximum: 45,
+            errors: 0,
+        }),
+        CounterRecord::Humidity(Humidity {
+            relative_humidity: 40,
+        }),
+        CounterRecord::Fans(Fans {
+            total: 4,
+            failed: 1,
+            speed: 60,
+        }),
+        CounterRecord::XenVif(XenVif {
+            vif_index: 1,
+            vm_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 160)),
+            domain_id: 5,
+            network_index: 0,
+            flags: 1,
+        }),
+        CounterRecord::Unknown {
+            enterprise: 5,
+            format: 42,
+            data: vec![9, 8, 7, 6],
+        },
+    ]
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::custom::Custom;
+use flowparser_sflow::flow_records::{ExtendedSwitch, FlowRecord};
+use flowparser_sflow::jsonl::{Granularity, json_lines};
+use flowparser_sflow::*;
+use serde::Serialize;
+use std::time::SystemTime;
+
+const VENDOR: u32 = 9999;
+
+#[derive(Debug, PartialEq, Serialize)]
+struct AppTag {
+    app_id: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Optics {
+    rx_power: u32,
+    tx_power: u32,
+}
+
+fn app_tag(data: &[u8]) -> Option<AppTag> {
+    let app_id = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+    Some(AppTag { app_id })
+}
+
+fn optics(data: &[u8]) -> Option<Optics> {
+    if data.len() != 8 {
+        return None;
+    }
+    Some(Optics {
+        rx_power: u32::from_be_bytes(data[..4].try_into().ok()?),
+        tx_power: u32::from_be_bytes(data[4..].try_into().ok()?),
+    })
+}
+
+fn parser() -> SflowParser {
+    SflowParser::builder()
+        .with_flow_decoder(VENDOR, 1, app_tag)
+        .with_counter_decoder(VENDOR, 2, optics)
+        .with_sample_decoder(VENDOR, 3, |data: &[u8]| Some(data.len() as u32))
+        // Built-in formats are always decoded by the crate
+        .with_flow_decoder(0, 1001, app_tag)
+        .build()
+}
+
+fn vendor_datagram(optics: Vec<u8>) -> SflowDatagram {
+    datagram(vec![
+        flow(vec![
+            FlowRecord::ExtendedSwitch(ExtendedSwitch {
+                src_vlan: 10,
+                src_priority: 0,
+                dst_vlan: 20,
+                dst_priority: 0,
+            }),
+            FlowRecord::Unknown {
+                enterprise: VENDOR,
+                format: 1,
+                data: vec![0, 0, 0, 42],
+            },
+        ]),
+        counters(
+            0,
+            3,
+            vec![CounterRecord::Unknown {
+                enterprise: VENDOR,
+                format: 2,
+                data: optics,
+            }],
+        ),
+        SflowSample::Unknown {
+            enterprise: VENDOR,
+            format: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        },
+    ])
+}
+
+#[test]
+fn test_custom_decoders() {
+    let bytes = vendor_datagram(vec![0, 0, 0, 7, 0, 0, 0, 9]).to_bytes();
+    let result = parser().parse_bytes(&bytes);
+    assert!(result.error.is_none());
+    let dg = &result.datagrams[0];
+
+    let SflowSample::Flow(flow) = &dg.samples[0] else {
+        panic!("Expected flow sample, got {:?}", dg.samples[0]);
+    };
+    assert!(matches!(flow.records[0], FlowRecord::ExtendedSwitch(_)));
+    let FlowRecord::Custom(tag) = &flow.records[1] else {
+        panic!("Expected custom record, got {:?}", flow.records[1]);
+    };
+    assert_eq!(tag.downcast_ref::<AppTag>(), Some(&AppTag { app_id: 42 }));
+    assert_eq!(flow.records[1].enterprise_format(), (VENDOR, 1));
+
+    let SflowSample::Counter(counters) = &dg.samples[1] else {
+        panic!("Expected counter sample, got {:?}", dg.samples[1]);
+    };
+    let CounterRecord::Custom(custom) = &counters.records[0] else {
+        panic!("Expected custom record, got {:?}", counters.records[0]);
+    };
+    assert_eq!(
+        custom.downcast_ref::<Optics>(),
+        Some(&Optics {
+            rx_power: 7,
+            tx_power: 9,
+        })
+    );
+    assert!(custom.downcast_ref::<AppTag>().is_none());
+
+    let SflowSample::Custom(sample) = &dg.samples[2] else {
+        panic!("Expected custom sample, got {:?}", dg.samples[2]);
+    };
+    assert_eq!(sample.downcast_ref::<u32>(), Some(&8));
+    assert_eq!(dg.samples[2].data_source(), None);
+
+    // Borrowed parsing applies the same decoders, and encoding writes the
+    // original bodies back
+    assert_eq!(parser().parse_bytes_ref(&bytes).into_owned(), result);
+    assert_eq!(dg.to_bytes(), bytes);
+
+    // Without decoders the data stays unknown
+    let plain = SflowParser::default().parse_bytes(&bytes);
+    assert_eq!(
+        plain.datagrams[0],
+        vendor_datagram(vec![0, 0, 0, 7, 0, 0, 0, 9])
+    );
+}
+
+#[test]
+fn test_rejected_and_registry() {
+    // The optics decoder rejects bodies that are not 8 bytes long
+    let bytes = vendor_datagram(vec![0, 0, 0, 7]).to_bytes();
+    let parser = parser();
+    let dg = &parser.parse_bytes(&bytes).datagrams[0];
+    assert!(matches!(
+        dg.samples[1].normalized_counter().unwrap().records[0],
+        CounterRecord::Unknown { .. }
+    ));
+
+    let decoders = parser.decoders();
+    assert!(!decoders.is_empty());
+    assert!(SflowParser::default().decoders().is_empty());
+    assert_eq!(
+        decoders.flow_record(VENDOR, 1, &[0, 0, 0, 5]),
+        Some(Custom::new(
+            VENDOR,
+            1,
+            vec![0, 0, 0, 5],
+            AppTag { app_id: 5 }
+        ))
+    );
+    assert_eq!(decoders.counter_record(VENDOR, 1, &[0, 0, 0, 5]), None);
+    assert_eq!(decoders.sample(VENDOR, 4, &[]), None);
+    assert!(format!("{decoders:?}").contains("counters: [(9999, 2)]"));
+}
+
+#[test]
+fn test_custom_serialization() {
+    let bytes = vendor_datagram(vec![0, 0, 0, 7, 0, 0, 0, 9]).to_bytes();
+    let result = parser().parse_bytes(&bytes);
+
+    let json = serde_json::to_value(&result.datagrams[0].samples[1]).unwrap();
+    assert_eq!(
+        json["Counter"]["records"][0]["Custom"]["value"],
+        serde_json::json!({ "rx_power": 7, "tx_power": 9 })
+    );
+
+    // Deserialized values are JSON, and compare equal by their JSON form
+    let text = serde_json::to_string(&result).unwrap();
+    let back: ParseResult = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, result);
+
+    let lines = json_lines(
+        &result.datagrams[0],
+        SystemTime::UNIX_EPOCH,
+        Granularity::Record,
+    );
+    let optics = lines
+        .iter()
+        .find(|line| line["record_type"] == "custom_9999_2")
+        .unwrap();
+    assert_eq!(optics["custom_9999_2.rx_power"], 7);
+    assert_eq!(optics["record_enterprise"], VENDOR);
+    let sample = lines
+        .iter()
+        .find(|line| line["sample_type"] == "custom")
+        .unwrap();
+    assert_eq!(sample["custom_9999_3"], 8);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::*;
+use flowparser_sflow::flow_records::*;
+use flowparser_sflow::samples::*;
+use flowparser_sflow::*;
+
+fn round_trip(dg: &SflowDatagram) {
+    let bytes = dg.to_bytes();
+    let result = SflowParser::default().parse_bytes(&bytes);
+    assert!(result.error.is_none(), "parse error: {:?}", result.error);
+    assert_eq!(result.datagrams.len(), 1);
+    assert_eq!(&result.datagrams[0], dg);
+}
+
+fn reencode(hex: &str) {
+    let data = hex::decode(hex).unwrap();
+    let result = SflowParser::default().parse_bytes(&data);
+    assert!(result.error.is_none(), "parse error: {:?}", result.error);
+    let mut out = Vec::new();
+    for dg in &result.datagrams {
+        dg.encode(&mut out);
+    }
+    assert_eq!(out, data);
+}
+
+#[test]
+fn test_round_trip_all_flow_records() {
+    let records = all_flow_records();
+    let dg = datagram(vec![
+        SflowSample::Flow(FlowSample {
+            sequence_number: 1,
+            source_id_type: 0,
+            source_id_index: 3,
+            sampling_rate: 256,
+            sample_pool: 1000,
+            drops: 0,
+            input: 1,
+            output: 2,
+            records: records.clone(),
+        }),
+        SflowSample::ExpandedFlow(ExpandedFlowSample {
+            sequence_number: 2,
+            source_id_type: 0,
+            source_id_index: 0x0100_0000,
+            sampling_rate: 512,
+            sample_pool: 2000,
+            drops: 1,
+            input_format: 0,
+            input_value: 1,
+            output_format: 2,
+            output_value: 3,
+            records: records.clone(),
+        }),
+        SflowSample::Discard(DiscardedPacket {
+            sequence_number: 3,
+            source_id_type: 0,
+            source_id_index: 7,
+            drops: 0,
+            input: 1,
+            output: 0,
+            reason: DropReason::Acl,
+            records,
+        }),
+    ]);
+    round_trip(&dg);
+}
+
+#[test]
+fn test_round_trip_all_counter_records() {
+    let records = all_counter_records();
+    let dg = datagram(vec![
+        SflowSample::Counter(CounterSample {
+            sequence_number: 1,
+            source_id_type: 0,
+            source_id_index: 1,
+            records: records.clone(),
+        }),
+        SflowSample::ExpandedCounter(ExpandedCounterSample {
+            sequence_number: 2,
+            source_id_type: 2,
+            source_id_index: 1,
+            records,
+        }),
+    ]);
+    round_trip(&dg);
+}
+
+#[test]
+fn test_round_trip_unknown_sample_and_ipv6_agent() {
+    let mut dg = datagram(vec![SflowSample::Unknown {
+        enterprise: 99,
+        format: 1,
+        data: vec![0xDE, 0xAD, 0xBE, 0xEF],
+    }]);
+    dg.agent_address = AddressType::IPv6(ipv6_address());
+    round_trip(&dg);
+}
+
+#[test]
+fn test_string_and_opaque_padding() {
+    let record = FlowRecord::ExtendedFunction(ExtendedFunction {
+        symbol: "abcde".into(),
+    });
+    // data_format(4) + length(4) + string length(4) + 5 bytes + 3 padding
+    let bytes = record.to_bytes();
+    assert_eq!(bytes.len(), 20);
+    assert_eq!(&bytes[..8], &[0, 0, 0x04, 0x0E, 0, 0, 0, 12]);
+    assert_eq!(&bytes[8..12], &[0, 0, 0, 5]);
+    assert_eq!(&bytes[12..17], b"abcde");
+    assert_eq!(&bytes[17..], &[0, 0, 0]);
+}
+
+#[test]
+fn test_enterprise_format() {
+    let records = all_counter_records();
+    assert_eq!(records[0].enterprise_format(), (0, 1));
+    assert_eq!(
+        records
+            .iter()
+            .find(|r| matches!(r, CounterRecord::XenVif(_)))
+            .unwrap()
+            .enterprise_format(),
+        (4300, 2)
+    );
+    let discard = SflowSample::Discard(DiscardedPacket {
+        sequence_number: 0,
+        source_id_type: 0,
+        source_id_index: 0,
+        drops: 0,
+        input: 0,
+        output: 0,
+        reason: DropReason::Unknown,
+        records: vec![],
+    });
+    assert_eq!(discard.enterprise_format(), (0, 5));
+}
+
+#[test]
+fn test_reencode_real_host_counters() {
+    reencode(
+        "00000005000000010a000097000186a0000163a36c87eb980000000100000002000001b0000163a30200000100000006000007d10000004400000004000000010000000100000000000000000000000200000001f229017058250000000000030000000162f6cff12d34000000000006000000010a00009700000000000007d50000003400000003606a380000000002b661780000000a9200026f0800000000854e1400004f60d400526a460000000a7c12e0000e5a0cd0000007d4000000480000000040000000000000000576a0000000000000000000000000000bc5300000000000213e10000000000081ffe0000000000081ff40000010c71c014f825c000000000000000b000007d3000000443ca3d70a3d23d70a000000000000000000000056000000010000051100405d990038dee800763d880097a07cf576a5e800766588000000000002a8823f33e5d6176a429a000007d60000002800000013beaa68e7340603ba0000000000000000000000144f55b3db2f4503500000000000000000000007d0000000480000001378656e766d322e73662e696e6d6f6e2e636f6d00e9d0e08751c7429e98414439db35f3be000000030000000200000011322e362e31382d3139342e656c3578656e000000",
+    );
+}
+
+#[test]
+fn test_reencode_real_jvm_counters() {
+    reencode(
+        "00000005000000010a0000a0000018c700000196007c51e400000001000000020000015000000196030018c700000006000007d000000034000000096a766d2d696e6d736600000000000000000000000000000000000000000000000000000d00000008312e362e305f3138000007d2000000080000000200000001000008350000000c000000010002bf7a000000020000083600000010000000000ef610000000000062a400000000083900000050000000214a61766120486f7453706f7428544d292036342d4269742053657276657220564d0000000000001553756e204d6963726f73797374656d7320496e632e0000000000000831362e302d6231330000083a0000006c00000000000000000000000001e562b80000000009461000000000005cf400000000000001730000000000000401f5e00000000005b0000000000000084000000000002f00000f4000001e210000206100000240000116110000002200000016000005490000004d00000400",
+    );
+}
+
+#[test]
+fn test_reencode_real_xen_vif() {
+    reencode(
+        "00000005000000010a0000efa000000a00000088007b94b800000009000000040000008c00000088000000000000000100000002010cc00200000014000000010a0000a0000000050000003c00000000000000010000005800000001000000750000000005f5e10000000000000000030000000097f3e7d2bbbd52e201d4386e000000000000000000000000000000000000000094bb2aaeccf57b42008f2b3800000000000000000000000000000000000000040000008c00000088000000000000000200000002010cc00200000014000000010a0000a0000000050000003c0000000000000001000000580000000200000075000000003b9aca00000000000000000300000000ed53580837af33c10014cc24000000000000000500000000000000000000000078eada8c33c4b17f01c3a02800000000000000000000000000000000000000040000008c00000088000000000000000300000002010cc00200000014000000010a0000a0000000050000003c0000000000000001000000580000000300000075000000003b9aca00000000000000000300000000a28c15cb955b444700783b490000000000000004000000000000000000000000b8795ec787f6978101eb24b300000000000000000000000000000000000000040000008c00000088000000000000000400000002010cc00200000014000000010a0000a0000000050000003c000000000000000100000058000000040000007500000000000000000000000000000001000000000000a5300000013700000035000000000000000500000000000000000000000000014720000001200000004f00000000000000000000000000000000000000040000008c00000088000000000000000500000002010cc00200000014000000010a0000a0000000050000003c00000000000000010000005800000005000000750000000000000000000000000000000100000000004dbcd100001c990000003b0000000000000005000000000000000000000000001b671700001ed300001ecf00000000000000000000000000000000000000040000008c00000088000000000000000600000002010cc00200000014000000010a0000a0000000050000003c000000000000000100000058000000060000007500000000000000000000000000000001000000000000103f0000000000000025000000000000000500000000000000000000000000000088000000000000000100000000000000000000000000000000000000040000008c00000088000000000000000700000002010cc00200000014000000010a0000a0000000050000003c00000000000000010000005800000007000000750000000000000000000000000000000100000000000042e80000000000000053000000000000000500000000000000000000000000000088000000000000000100000000000000000000000000000000000000040000008c00000088000000000000000800000002010cc00200000014000000010a0000a0000000050000003c000000000000000100000058000000080000007500000000000000000000000000000001000000000002cf25000002f0000001ce00000000000000090000000000000000000000000007daf4000002c70000000700000000000000000000000000000000000000040000008c00000088000000000000001900000002010cc00200000014000000010a0000a0000000050000003c000000000000000100000058000000190000000100000000000000000000000000000003000000009f18351e001758d2023791a90000000000000000000000000000000000000000318cfa2800175e430005fef900000000000000000000000000000000",
+    );
+}
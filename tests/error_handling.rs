@@ -33,9 +33,9 @@ fn test_truncated_header() {
 
 #[test]
 fn test_wrong_version() {
-    // version=4 (unsupported), rest is valid IPv4 header
+    // version=3 (unsupported), rest is valid IPv4 header
     let data = h("\
-        0000   00 00 00 04 00 00 00 01 0a 00 00 01 00 00 00 00\n\
+        0000   00 00 00 03 00 00 00 01 0a 00 00 01 00 00 00 00\n\
         0010   00 00 00 01 00 00 00 64 00 00 00 00\
     ");
 
@@ -44,7 +44,7 @@ fn test_wrong_version() {
     assert!(result.error.is_some());
     match result.error.unwrap() {
         SflowError::UnsupportedVersion { version } => {
-            assert_eq!(version, 4)
+            assert_eq!(version, 3)
         }
         other => panic!("Expected UnsupportedVersion, got {:?}", other),
     }
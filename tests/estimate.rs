@@ -0,0 +1,165 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::estimate::{SamplingEstimator, WeightSource, Z_95};
+use flowparser_sflow::flow_records::{FlowRecord, RawPacketHeader, SampledIpv4};
+use flowparser_sflow::samples::{DataSource, FlowSample};
+use flowparser_sflow::sequence::{SampleKey, SampleKind};
+use flowparser_sflow::*;
+
+fn key() -> SampleKey {
+    SampleKey {
+        agent: agent(254),
+        data_source: DataSource::IfIndex(3),
+        kind: SampleKind::Flow,
+    }
+}
+
+fn packet(length: u32) -> FlowRecord {
+    FlowRecord::SampledIpv4(SampledIpv4 {
+        length,
+        ..sampled_ipv4()
+    })
+}
+
+fn flow(sequence_number: u32, sample_pool: u32, drops: u32) -> SflowSample {
+    SflowSample::Flow(FlowSample {
+        sequence_number,
+        sampling_rate: 100,
+        sample_pool,
+        drops,
+        ..flow_sample(vec![packet(1000)])
+    })
+}
+
+#[test]
+fn test_sample_pool_weights_cover_lost_samples() {
+    let mut estimator = SamplingEstimator::default();
+    let first = estimator.observe(&numbered(1, vec![flow(1, 100, 0)]));
+    assert_eq!(first[0].0, key());
+    assert_eq!(first[0].1.weight, 100.0);
+    assert_eq!(first[0].1.source, WeightSource::SamplingRate);
+    assert_eq!(first[0].1.frame_length, Some(1000));
+
+    let second = estimator.observe(&numbered(2, vec![flow(2, 200, 0)]));
+    assert_eq!(second[0].1.weight, 100.0);
+    assert_eq!(second[0].1.source, WeightSource::SamplePool);
+
+    // Sample 3 was lost; sample 4 stands for the whole pool since sample 2.
+    let fourth = estimator.observe(&numbered(4, vec![flow(4, 450, 0)]));
+    assert_eq!(fourth[0].1.weight, 250.0);
+    assert_eq!(estimator.effective_rate(&key()), Some(125.0));
+
+    let estimate = estimator.estimate(&key()).unwrap();
+    assert_eq!(estimate.samples, 3);
+    assert_eq!(estimate.packets, 450.0);
+    assert_eq!(estimate.bytes, 450_000.0);
+    assert_eq!(estimator.total(), *estimate);
+
+    estimator.clear_estimates();
+    assert_eq!(estimator.total().samples, 0);
+    let fifth = estimator.observe(&numbered(5, vec![flow(5, 550, 0)]));
+    assert_eq!(fifth[0].1.source, WeightSource::SamplePool);
+}
+
+#[test]
+fn test_sampling_rate_weights_account_for_drops() {
+    let mut estimator = SamplingEstimator::builder().with_sample_pool(false).build();
+    estimator.observe(&numbered(1, vec![flow(1, 100, 0)]));
+    let weights = estimator.observe(&numbered(2, vec![flow(2, 400, 2)]));
+    assert_eq!(weights[0].1.weight, 300.0);
+    assert_eq!(weights[0].1.source, WeightSource::SamplingRate);
+    assert_eq!(weights[0].1.bytes(), 300_000.0);
+    // Three samples' worth of packets: one delivered, two dropped.
+    assert_eq!(estimator.effective_rate(&key()), Some(100.0));
+}
+
+#[test]
+fn test_duplicate_late_and_reset_samples() {
+    let mut pool = SamplingEstimator::default();
+    let mut nominal = SamplingEstimator::builder().with_sample_pool(false).build();
+    for estimator in [&mut pool, &mut nominal] {
+        estimator.observe(&numbered(1, vec![flow(50_010, 1000, 0)]));
+        estimator.observe(&numbered(2, vec![flow(50_012, 1200, 0)]));
+        assert!(
+            estimator
+                .observe(&numbered(3, vec![flow(50_012, 1200, 0)]))
+                .is_empty()
+        );
+    }
+
+    // Sample 50011 arrives late: the pool of sample 50012 already covered it.
+    assert!(
+        pool.observe(&numbered(4, vec![flow(50_011, 1100, 0)]))
+            .is_empty()
+    );
+    assert_eq!(
+        nominal.observe(&numbered(4, vec![flow(50_011, 1100, 0)]))[0]
+            .1
+            .weight,
+        100.0
+    );
+
+    // The agent restarted its sample sequence.
+    let reset = pool.observe(&numbered(5, vec![flow(1, 100, 0)]));
+    assert_eq!(reset[0].1.source, WeightSource::SamplingRate);
+    assert_eq!(pool.effective_rate(&key()), None);
+    let next = pool.observe(&numbered(6, vec![flow(2, 150, 0)]));
+    assert_eq!(next[0].1.weight, 50.0);
+
+    pool.remove_agent(&agent_address());
+    assert_eq!(pool.estimates().count(), 0);
+}
+
+#[test]
+fn test_confidence_interval() {
+    let mut estimator = SamplingEstimator::builder().with_sample_pool(false).build();
+    assert!(estimator.total().relative_error(Z_95).is_infinite());
+    for seq in 1..=100 {
+        estimator.observe(&numbered(seq, vec![flow(seq, seq * 100, 0)]));
+    }
+    let total = estimator.total();
+    assert_eq!(total.packets, 10_000.0);
+    assert!((total.relative_error(Z_95) - Z_95 / 10.0).abs() < 1e-12);
+    let (low, high) = total.packets_interval(Z_95);
+    assert!((low - 8_040.0).abs() < 0.1);
+    assert!((high - 11_960.0).abs() < 0.1);
+    let (low, high) = total.bytes_interval(Z_95);
+    assert!(low < total.bytes && total.bytes < high);
+}
+
+#[test]
+fn test_frame_length_prefers_raw_packet_header() {
+    let mut sample = flow(1, 100, 0);
+    if let SflowSample::Flow(s) = &mut sample {
+        s.records.push(FlowRecord::RawPacketHeader(RawPacketHeader {
+            header_protocol: 1,
+            frame_length: 1018,
+            stripped: 4,
+            header_length: 0,
+            header: vec![],
+        }));
+    }
+    assert_eq!(sample.normalized_flow().unwrap().frame_length(), Some(1018));
+    assert_eq!(packet(64).frame_length(), Some(64));
+}
+
+#[test]
+fn test_observe_ref_matches_observe() {
+    let first = numbered(1, vec![flow(1, 100, 0)]);
+    let second = numbered(2, vec![flow(3, 350, 1)]);
+
+    let mut owned = SamplingEstimator::default();
+    owned.observe(&first);
+    let expected = owned.observe(&second);
+
+    let parser = SflowParser::default();
+    let (a, b) = (first.to_bytes(), second.to_bytes());
+    let mut borrowed = SamplingEstimator::default();
+    borrowed.observe_ref(&parser.parse_bytes_ref(&a).datagrams[0]);
+    assert_eq!(
+        borrowed.observe_ref(&parser.parse_bytes_ref(&b).datagrams[0]),
+        expected
+    );
+    assert_eq!(expected[0].1.weight, 250.0);
+}
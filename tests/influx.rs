@@ -0,0 +1,119 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::{HostMemory, PortName};
+use flowparser_sflow::influx::{InfluxWriter, counter_lines, flow_lines};
+use flowparser_sflow::*;
+
+#[test]
+fn test_counter_lines() {
+    let dg = datagram(vec![
+        counters(2, 1, vec![cpu()]),
+        counters(
+            0,
+            3,
+            vec![CounterRecord::PortName(PortName {
+                name: "Gi 0/1,uplink".to_string(),
+            })],
+        ),
+        flow(vec![ipv4()]),
+    ]);
+    let lines = counter_lines(&dg, received());
+    assert_eq!(
+        lines,
+        vec![
+            "sflow_host_cpu,agent=10.0.0.254,source=2:1,sub_agent=1 \
+             contexts=99i,cpu_idle=9000i,cpu_intr=0i,cpu_nice=0i,cpu_num=4i,\
+             cpu_sintr=0i,cpu_speed=2400i,cpu_system=500i,cpu_user=1000i,cpu_wio=0i,\
+             interrupts=42i,load_fifteen=0.25,load_five=0.5,load_one=0.6,proc_run=1i,\
+             proc_total=200i,uptime=3600i 1700000000250000000"
+        ]
+    );
+
+    // The port name tags the other records of its sample.
+    let dg = datagram(vec![counters(
+        0,
+        3,
+        vec![
+            cpu(),
+            CounterRecord::PortName(PortName {
+                name: "Gi 0/1,uplink".to_string(),
+            }),
+        ],
+    )]);
+    let lines = counter_lines(&dg, received());
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with(
+        "sflow_host_cpu,agent=10.0.0.254,ifindex=3,ifname=Gi\\ 0/1\\,uplink,source=0:3,\
+         sub_agent=1 contexts=99i,"
+    ));
+}
+
+#[test]
+fn test_unknown_u64_counter_keeps_field_type() {
+    let memory = |mem_free: u64| {
+        CounterRecord::HostMemory(HostMemory {
+            mem_total: 8_000_000_000,
+            mem_free,
+            mem_shared: 0,
+            mem_buffers: 0,
+            mem_cached: 0,
+            swap_total: 0,
+            swap_free: 0,
+            page_in: 0,
+            page_out: 0,
+            swap_in: 0,
+            swap_out: 0,
+        })
+    };
+
+    let known = counter_lines(
+        &datagram(vec![counters(2, 1, vec![memory(1024)])]),
+        received(),
+    );
+    assert!(known[0].contains(",mem_free=1024i,"));
+
+    // Agents report unknown 64-bit counters as all ones. Writing them with
+    // another suffix would conflict with the earlier integer points.
+    let unknown = counter_lines(
+        &datagram(vec![counters(2, 1, vec![memory(u64::MAX)])]),
+        received(),
+    );
+    assert!(!unknown[0].contains("mem_free"));
+    assert!(unknown[0].contains(" mem_buffers=0i,mem_cached=0i,mem_shared=0i,"));
+    let fields = unknown[0].split(' ').nth(1).unwrap();
+    assert!(fields.split(',').all(|field| field.ends_with('i')));
+}
+
+#[test]
+fn test_flow_lines() {
+    let dg = datagram(vec![flow(vec![ipv4()]), counters(2, 1, vec![cpu()])]);
+    let lines = flow_lines(&dg, received());
+    assert_eq!(
+        lines,
+        vec![
+            "sflow_flow,agent=10.0.0.254,ifindex=3,source=0:3,sub_agent=1 \
+             sampling_rate=512i,sample_pool=1024i,drops=0i,input=3i,output=2i,packets=512i,\
+             frame_length=1500i,bytes=768000i,protocol=6i,src_port=40000i,dst_port=443i,\
+             tcp_flags=24i,tos=0i,ttl=0i,ip_size=1500i,\
+             src_ip=\"192.168.1.1\",dst_ip=\"10.0.0.1\" 1700000000250000000"
+        ]
+    );
+}
+
+#[test]
+fn test_writer() {
+    let dg = datagram(vec![flow(vec![ipv4()]), counters(2, 1, vec![cpu()])]);
+    let mut writer = InfluxWriter::new(Vec::new());
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 1);
+    let mut writer = InfluxWriter::new(writer.into_inner()).with_flows(true);
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 2);
+    assert_eq!(writer.lines(), 2);
+
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+    let measurements: Vec<_> = text.lines().map(|l| l.split(',').next().unwrap()).collect();
+    assert_eq!(
+        measurements,
+        vec!["sflow_host_cpu", "sflow_host_cpu", "sflow_flow"]
+    );
+}
@@ -0,0 +1,257 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::flow_records::{
+    ExtendedGateway, ExtendedRouter, ExtendedSwitch, FlowRecord, SampledIpv4, SampledIpv6,
+    extended_gateway::AsPathSegment,
+};
+use flowparser_sflow::ipfix::{ExportFormat, FlowExporter, IPV4_TEMPLATE_ID, IPV6_TEMPLATE_ID};
+use flowparser_sflow::*;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    u16::from_be_bytes([b[i], b[i + 1]])
+}
+
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u32::from_be_bytes(b[i..i + 4].try_into().unwrap())
+}
+
+/// Minimal decoder: returns templates seen and data records as IE -> bytes.
+struct Decoded {
+    templates: Vec<u16>,
+    records: Vec<(u16, HashMap<u16, Vec<u8>>)>,
+}
+
+fn decode(
+    message: &[u8],
+    header_len: usize,
+    templates: &mut HashMap<u16, Vec<(u16, u16)>>,
+) -> Decoded {
+    let template_set = if header_len == 16 { 2 } else { 0 };
+    let mut decoded = Decoded {
+        templates: vec![],
+        records: vec![],
+    };
+    let mut pos = header_len;
+    while pos < message.len() {
+        let set_id = u16_at(message, pos);
+        let set_len = usize::from(u16_at(message, pos + 2));
+        let body = &message[pos + 4..pos + set_len];
+        if set_id == template_set {
+            let mut i = 0;
+            while i < body.len() {
+                let id = u16_at(body, i);
+                let count = usize::from(u16_at(body, i + 2));
+                let fields = (0..count)
+                    .map(|n| (u16_at(body, i + 4 + 4 * n), u16_at(body, i + 6 + 4 * n)))
+                    .collect();
+                templates.insert(id, fields);
+                decoded.templates.push(id);
+                i += 4 + 4 * count;
+            }
+        } else {
+            let fields = &templates[&set_id];
+            let record_len: usize = fields.iter().map(|&(_, l)| usize::from(l)).sum();
+            let mut i = 0;
+            while i + record_len <= body.len() {
+                let mut record = HashMap::new();
+                for &(id, len) in fields {
+                    record.insert(id, body[i..i + usize::from(len)].to_vec());
+                    i += usize::from(len);
+                }
+                decoded.records.push((set_id, record));
+            }
+        }
+        pos += set_len;
+    }
+    decoded
+}
+
+fn to_port(dst_port: u32) -> FlowRecord {
+    FlowRecord::SampledIpv4(SampledIpv4 {
+        dst_port,
+        ..sampled_ipv4()
+    })
+}
+
+fn ipv6() -> FlowRecord {
+    FlowRecord::SampledIpv6(SampledIpv6 {
+        length: 100,
+        protocol: 17,
+        src_ip: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+        dst_ip: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2),
+        src_port: 5353,
+        dst_port: 53,
+        tcp_flags: 0,
+        priority: 0,
+    })
+}
+
+#[test]
+fn test_ipfix_message() {
+    let dg = datagram(vec![
+        flow(vec![
+            FlowRecord::SampledIpv4(SampledIpv4 {
+                tos: 0x10,
+                ..sampled_ipv4()
+            }),
+            FlowRecord::ExtendedSwitch(ExtendedSwitch {
+                src_vlan: 100,
+                src_priority: 0,
+                dst_vlan: 200,
+                dst_priority: 0,
+            }),
+            FlowRecord::ExtendedRouter(ExtendedRouter {
+                next_hop: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 2)),
+                src_mask_len: 24,
+                dst_mask_len: 16,
+            }),
+            FlowRecord::ExtendedGateway(ExtendedGateway {
+                next_hop: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 2)),
+                as_number: 65000,
+                src_as: 65001,
+                src_peer_as: 65001,
+                as_path_segments: vec![AsPathSegment {
+                    segment_type: 2,
+                    values: vec![65010, 65020],
+                }],
+                communities: vec![],
+            }),
+        ]),
+        flow(vec![ipv6()]),
+        // No IP information: skipped.
+        flow(vec![]),
+    ]);
+    let mut exporter = FlowExporter::builder()
+        .with_observation_domain_id(7)
+        .build();
+    let messages = exporter.export(&dg, received());
+    assert_eq!(messages.len(), 1);
+    let m = &messages[0];
+    assert_eq!(u16_at(m, 0), 10);
+    assert_eq!(usize::from(u16_at(m, 2)), m.len());
+    assert_eq!(u32_at(m, 4), 1_700_000_000);
+    assert_eq!(u32_at(m, 8), 0);
+    assert_eq!(u32_at(m, 12), 7);
+
+    let mut templates = HashMap::new();
+    let decoded = decode(m, 16, &mut templates);
+    assert_eq!(decoded.templates, vec![IPV4_TEMPLATE_ID, IPV6_TEMPLATE_ID]);
+    assert_eq!(decoded.records.len(), 2);
+
+    let (set, v4) = &decoded.records[0];
+    assert_eq!(*set, IPV4_TEMPLATE_ID);
+    assert_eq!(v4[&8], vec![192, 168, 1, 1]);
+    assert_eq!(v4[&12], vec![10, 0, 0, 1]);
+    assert_eq!(v4[&15], vec![10, 0, 0, 2]);
+    assert_eq!((v4[&9][0], v4[&13][0]), (24, 16));
+    assert_eq!(v4[&4], vec![6]);
+    assert_eq!(v4[&5], vec![0x10]);
+    assert_eq!(u16_at(&v4[&6], 0), 0x18);
+    assert_eq!(u16_at(&v4[&11], 0), 443);
+    // Sent out of several interfaces: no single egressInterface
+    assert_eq!((u32_at(&v4[&10], 0), u32_at(&v4[&14], 0)), (3, 0));
+    assert_eq!((u32_at(&v4[&16], 0), u32_at(&v4[&17], 0)), (65001, 65020));
+    assert_eq!((u16_at(&v4[&58], 0), u16_at(&v4[&59], 0)), (100, 200));
+    assert_eq!(u64::from_be_bytes(v4[&1][..].try_into().unwrap()), 1500);
+    assert_eq!(u64::from_be_bytes(v4[&2][..].try_into().unwrap()), 1);
+    assert_eq!(u32_at(&v4[&34], 0), 512);
+    assert_eq!(v4[&35], vec![2]);
+    assert_eq!(u64::from_be_bytes(v4[&302][..].try_into().unwrap()), 3);
+    assert_eq!(
+        u64::from_be_bytes(v4[&152][..].try_into().unwrap()),
+        1_700_000_000_250
+    );
+
+    let (set, v6) = &decoded.records[1];
+    assert_eq!(*set, IPV6_TEMPLATE_ID);
+    assert_eq!(
+        v6[&28],
+        Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2).octets()
+    );
+    assert_eq!(u16_at(&v6[&11], 0), 53);
+
+    // The next message continues the sequence and omits templates.
+    let next = exporter.export(&dg, received());
+    assert_eq!(u32_at(&next[0], 8), 2);
+    let decoded = decode(&next[0], 16, &mut templates);
+    assert!(decoded.templates.is_empty());
+    assert_eq!(decoded.records.len(), 2);
+
+    assert!(
+        exporter
+            .export(&datagram(vec![flow(vec![])]), received())
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_netflow_v9_message() {
+    let dg = datagram(vec![flow(vec![to_port(80)]), flow(vec![to_port(81)])]);
+    let mut exporter = FlowExporter::builder()
+        .with_format(ExportFormat::NetflowV9)
+        .with_observation_domain_id(42)
+        .with_template_refresh(2)
+        .build();
+
+    let mut templates = HashMap::new();
+    let mut template_counts = vec![];
+    for expected_sequence in 0..4 {
+        let messages = exporter.export(&dg, received());
+        let m = &messages[0];
+        assert_eq!(u16_at(m, 0), 9);
+        assert_eq!(u32_at(m, 4), 123_456);
+        assert_eq!(u32_at(m, 8), 1_700_000_000);
+        assert_eq!(u32_at(m, 12), expected_sequence);
+        assert_eq!(u32_at(m, 16), 42);
+        // Flowsets are padded to 32-bit boundaries.
+        assert_eq!(m.len() % 4, 0);
+        let decoded = decode(m, 20, &mut templates);
+        assert_eq!(
+            usize::from(u16_at(m, 2)),
+            decoded.templates.len() + decoded.records.len()
+        );
+        assert_eq!(decoded.records.len(), 2);
+        let record = &decoded.records[1].1;
+        assert_eq!(u16_at(&record[&11], 0), 81);
+        assert_eq!(u32_at(&record[&22], 0), 123_456);
+        assert!(!record.contains_key(&302));
+        template_counts.push(decoded.templates.len());
+    }
+    assert_eq!(template_counts, vec![2, 0, 2, 0]);
+}
+
+#[test]
+fn test_messages_split_at_max_size() {
+    let samples = (1..=20).map(|seq| flow(vec![to_port(seq)])).collect();
+    let mut exporter = FlowExporter::builder().with_max_message_size(512).build();
+    let messages = exporter.export(&datagram(samples), received());
+    assert!(messages.len() > 1);
+
+    let mut templates = HashMap::new();
+    let mut ports = vec![];
+    let mut sequence = 0;
+    for m in &messages {
+        assert!(m.len() <= 512);
+        assert_eq!(u32_at(m, 8), sequence);
+        let decoded = decode(m, 16, &mut templates);
+        sequence += decoded.records.len() as u32;
+        ports.extend(decoded.records.iter().map(|(_, r)| u16_at(&r[&11], 0)));
+    }
+    assert_eq!(ports, (1..=20).collect::<Vec<u16>>());
+}
+
+#[test]
+fn test_max_message_size_fits_length_field() {
+    let samples = (1..=1000).map(|seq| flow(vec![to_port(seq)])).collect();
+    let mut exporter = FlowExporter::builder()
+        .with_max_message_size(1 << 20)
+        .build();
+    let messages = exporter.export(&datagram(samples), received());
+    assert!(messages.len() > 1);
+    for m in &messages {
+        assert_eq!(usize::from(u16_at(m, 2)), m.len());
+    }
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::HostMemory;
+use flowparser_sflow::flow_records::{
+    ExtendedRouter, FlowRecord, RawPacketHeader, SampledEthernet, SampledIpv4,
+};
+use flowparser_sflow::jsonl::{Granularity, JsonlWriter, SCHEMA_VERSION, json_lines};
+use flowparser_sflow::samples::{DiscardedPacket, DropReason};
+use flowparser_sflow::*;
+use mac_address::MacAddress;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+fn from_port(src_port: u32) -> FlowRecord {
+    FlowRecord::SampledIpv4(SampledIpv4 {
+        src_port,
+        ..sampled_ipv4()
+    })
+}
+
+#[test]
+fn test_record_lines() {
+    let dg = datagram(vec![flow(vec![
+        ipv4(),
+        FlowRecord::SampledEthernet(SampledEthernet {
+            length: 1518,
+            src_mac: MacAddress::new([0x00, 0x11, 0x22, 0xaa, 0xbb, 0xcc]),
+            dst_mac: MacAddress::new([0xff; 6]),
+            eth_type: 0x0800,
+        }),
+        FlowRecord::ExtendedRouter(ExtendedRouter {
+            next_hop: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 2)),
+            src_mask_len: 24,
+            dst_mask_len: 16,
+        }),
+        FlowRecord::RawPacketHeader(RawPacketHeader {
+            header_protocol: 1,
+            frame_length: 64,
+            stripped: 4,
+            header_length: 2,
+            header: vec![0xde, 0xad],
+        }),
+    ])]);
+    let lines = json_lines(&dg, received(), Granularity::Record);
+    assert_eq!(lines.len(), 4);
+
+    let first = Value::Object(lines[0].clone());
+    assert_eq!(
+        first,
+        json!({
+            "schema_version": SCHEMA_VERSION,
+            "time": "2023-11-14T22:13:20.250Z",
+            "agent": "10.0.0.254",
+            "sub_agent_id": 1,
+            "datagram_sequence": 42,
+            "uptime_ms": 123456,
+            "sample_type": "flow",
+            "sample_sequence": 7,
+            "source_id": "0:3",
+            "source_id_type": 0,
+            "source_id_index": 3,
+            "sampling_rate": 512,
+            "sample_pool": 1024,
+            "drops": 0,
+            "input": 3,
+            "input_type": "ifindex",
+            "output_count": 2,
+            "output_type": "multiple",
+            "record_index": 0,
+            "record_type": "sampled_ipv4",
+            "record_enterprise": 0,
+            "record_format": 3,
+            "sampled_ipv4.length": 1500,
+            "sampled_ipv4.protocol": 6,
+            "sampled_ipv4.src_ip": "192.168.1.1",
+            "sampled_ipv4.dst_ip": "10.0.0.1",
+            "sampled_ipv4.src_port": 40000,
+            "sampled_ipv4.dst_port": 443,
+            "sampled_ipv4.tcp_flags": 24,
+            "sampled_ipv4.tos": 0,
+        })
+    );
+    assert_eq!(lines[1]["sampled_ethernet.src_mac"], "00:11:22:aa:bb:cc");
+    assert_eq!(lines[2]["extended_router.next_hop"], "10.0.0.2");
+    assert_eq!(lines[3]["record_type"], "raw_packet_header");
+    assert_eq!(lines[3]["raw_packet_header.header"], "dead");
+    assert_eq!(
+        lines[3]["raw_packet_header.header_protocol_name"],
+        "ethernet_iso88023"
+    );
+}
+
+#[test]
+fn test_sample_lines_and_other_samples() {
+    let dg = datagram(vec![
+        flow(vec![from_port(1), from_port(2)]),
+        counters(
+            2,
+            1,
+            vec![CounterRecord::HostMemory(HostMemory {
+                mem_total: 1024,
+                mem_free: 512,
+                mem_shared: 0,
+                mem_buffers: 0,
+                mem_cached: 0,
+                swap_total: 0,
+                swap_free: 0,
+                page_in: 0,
+                page_out: 0,
+                swap_in: 0,
+                swap_out: 0,
+            })],
+        ),
+        SflowSample::Discard(DiscardedPacket {
+            sequence_number: 9,
+            source_id_type: 0,
+            source_id_index: 3,
+            drops: 0,
+            input: 3,
+            output: 0,
+            reason: DropReason::Acl,
+            records: vec![],
+        }),
+        SflowSample::Unknown {
+            enterprise: 9,
+            format: 1,
+            data: vec![1, 2],
+        },
+    ]);
+    let lines = json_lines(&dg, received(), Granularity::Sample);
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0]["record_types"],
+        json!(["sampled_ipv4", "sampled_ipv4"])
+    );
+    assert_eq!(lines[0]["sampled_ipv4.src_port"], 1);
+    assert_eq!(lines[0]["sampled_ipv4_2.src_port"], 2);
+    assert_eq!(lines[1]["sample_type"], "counter");
+    assert_eq!(lines[1]["source_id"], "2:1");
+    assert_eq!(lines[1]["host_memory.mem_free"], 512);
+    assert_eq!(lines[2]["sample_type"], "discard");
+    assert_eq!(lines[2]["drop_reason"], "acl");
+    assert_eq!(lines[2]["output_type"], "unknown");
+    assert!(!lines[2].contains_key("output"));
+    assert_eq!(lines[3]["sample_type"], "unknown");
+    assert_eq!(lines[3]["sample_data"], "0102");
+
+    // Samples without records still produce a line per record granularity.
+    let lines = json_lines(&dg, received(), Granularity::Record);
+    assert_eq!(lines.len(), 5);
+    assert!(!lines[3].contains_key("record_type"));
+}
+
+#[test]
+fn test_writer() {
+    let dg = datagram(vec![flow(vec![from_port(1), from_port(2)])]);
+    let mut writer = JsonlWriter::new(Vec::new());
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 2);
+    assert_eq!(writer.lines(), 2);
+    let mut writer =
+        JsonlWriter::new(writer.into_inner()).with_granularity(Granularity::Sample);
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 1);
+
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+    let lines: Vec<Value> = text
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1]["record_index"], 1);
+    assert_eq!(lines[2]["sampled_ipv4_2.src_port"], 2);
+}
+
+/// Keys are part of schema v1: a renamed struct field must fail here.
+#[test]
+fn test_record_keys_snapshot() {
+    let dg = datagram(vec![
+        flow(all_flow_records()),
+        counters(0, 3, all_counter_records()),
+    ]);
+    let mut keys = BTreeMap::<String, Vec<String>>::new();
+    for line in json_lines(&dg, received(), Granularity::Record) {
+        let record_type = line["record_type"].as_str().unwrap().to_string();
+        let prefix = format!("{record_type}.");
+        let fields = line.keys().filter(|key| key.starts_with(&prefix)).cloned();
+        keys.entry(record_type).or_default().extend(fields);
+    }
+    for names in keys.values_mut() {
+        names.sort();
+        names.dedup();
+    }
+    insta::assert_yaml_snapshot!(keys);
+}
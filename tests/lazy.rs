@@ -0,0 +1,218 @@
+use flowparser_sflow::samples::FlowSample;
+use flowparser_sflow::*;
+use std::net::Ipv4Addr;
+
+/// Decode a Wireshark-style hex dump into bytes.
+/// Strips offset prefixes (e.g., "0000   ") and whitespace.
+fn h(hex: &str) -> Vec<u8> {
+    let mut out = String::new();
+    for line in hex.lines() {
+        let t = line.trim();
+        if t.is_empty() {
+            continue;
+        }
+        let data = match t.find("  ") {
+            Some(pos) if pos >= 4 && t[..pos].bytes().all(|b| b.is_ascii_hexdigit()) => {
+                &t[pos..]
+            }
+            _ => t,
+        };
+        out.push_str(data);
+    }
+    hex::decode(out.replace(' ', "")).unwrap()
+}
+
+/// Real pcap: agent=10.0.0.20, Counter(GenericInterface) + Flow(ExtendedSwitch, RawPacketHeader)
+fn real_datagram() -> Vec<u8> {
+    h("\
+        0000   00 00 00 05 00 00 00 01 0a 00 00 14 00 00 00 00\n\
+        0010   00 00 89 40 04 27 24 10 00 00 00 02 00 00 00 02\n\
+        0020   00 00 00 6c 00 00 0d 9c 00 00 00 10 00 00 00 01\n\
+        0030   00 00 00 01 00 00 00 58 00 00 00 10 00 00 00 06\n\
+        0040   00 00 00 00 05 f5 e1 00 00 00 00 00 00 00 00 03\n\
+        0050   00 00 00 00 1a 22 f5 6e 00 21 fe e7 00 00 00 00\n\
+        0060   ff ff ff ff 00 00 00 00 00 00 00 00 ff ff ff ff\n\
+        0070   00 00 00 00 9d ad 17 05 02 3d 0d 17 ff ff ff ff\n\
+        0080   ff ff ff ff 00 00 02 ad 00 00 00 00 00 00 00 00\n\
+        0090   00 00 00 01 00 00 00 bc 00 00 1d ae 00 00 00 16\n\
+        00a0   00 00 01 90 00 2f 25 e3 00 00 00 00 00 00 00 16\n\
+        00b0   00 00 00 02 00 00 00 02 00 00 03 e9 00 00 00 10\n\
+        00c0   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+        00d0   00 00 00 01 00 00 00 7c 00 00 00 01 00 00 00 6d\n\
+        00e0   00 00 00 04 00 00 00 69 3e 5b 35 4b 3a 72 f2 29\n\
+        00f0   01 70 58 25 08 00 45 00 00 5b 55 3e 40 00 40 06\n\
+        0100   d0 32 0a 00 00 97 0a 00 00 96 2b cb c1 f3 47 e9\n\
+        0110   a7 d7 b0 5a a0 59 80 18 00 2e e3 9e 00 00 01 01\n\
+        0120   08 0a 3e d9 3b 26 3e d9 4a dc 56 41 4c 55 45 20\n\
+        0130   73 65 73 73 69 6f 6e 2e 74 69 6d 65 20 30 20 38\n\
+        0140   0d 0a 31 31 3a 34 38 3a 32 38 0d 0a 45 4e 44 0d\n\
+        0150   0a 56 80 18\
+    ")
+}
+
+fn flow_datagram(records: Vec<FlowRecord>) -> Vec<u8> {
+    SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+        sub_agent_id: 0,
+        sequence_number: 7,
+        uptime: 1000,
+        samples: vec![SflowSample::Flow(FlowSample {
+            sequence_number: 1,
+            source_id_type: 0,
+            source_id_index: 1,
+            sampling_rate: 100,
+            sample_pool: 100,
+            drops: 0,
+            input: 1,
+            output: 2,
+            records,
+        })],
+    }
+    .to_bytes()
+}
+
+#[test]
+fn test_lazy_header_and_decode_match_eager() {
+    let data = real_datagram();
+    let parser = SflowParser::default();
+
+    let datagrams: Vec<_> = parser.parse_lazy(&data).collect::<Result<_, _>>().unwrap();
+    assert_eq!(datagrams.len(), 1);
+    let dg = &datagrams[0];
+    assert_eq!(
+        dg.agent_address,
+        AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 20))
+    );
+    assert_eq!(dg.sequence_number, 35136);
+    assert_eq!(dg.num_samples, 2);
+
+    let eager = parser.parse_bytes_ref(&data);
+    assert_eq!(dg.decode().unwrap(), eager.datagrams[0]);
+
+    let samples: Vec<_> = dg.samples().map(|s| s.unwrap().decode().unwrap()).collect();
+    assert_eq!(samples, eager.datagrams[0].samples);
+}
+
+#[test]
+fn test_lazy_multiple_datagrams() {
+    let mut data = real_datagram();
+    data.extend_from_slice(&flow_datagram(vec![]));
+
+    let parser = SflowParser::default();
+    let seqs: Vec<u32> = parser
+        .parse_lazy(&data)
+        .map(|dg| dg.unwrap().sequence_number)
+        .collect();
+    assert_eq!(seqs, vec![35136, 7]);
+}
+
+#[test]
+fn test_lazy_record_iteration() {
+    let data = real_datagram();
+    let dg = SflowParser::default()
+        .parse_lazy(&data)
+        .next()
+        .unwrap()
+        .unwrap();
+    let samples: Vec<_> = dg.samples().collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(samples[0].enterprise_format(), (0, 2));
+    assert!(samples[0].flow_records().is_none());
+    let counters: Vec<_> = samples[0]
+        .counter_records()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(counters.len(), 1);
+    assert_eq!(counters[0].enterprise_format(), (0, 1));
+    match counters[0].decode().unwrap() {
+        CounterRecordRef::GenericInterface(gi) => assert_eq!(gi.if_index, 16),
+        other => panic!("Expected GenericInterface, got {other:?}"),
+    }
+
+    assert_eq!(samples[1].enterprise_format(), (0, 1));
+    assert!(samples[1].counter_records().is_none());
+    let formats: Vec<_> = samples[1]
+        .flow_records()
+        .unwrap()
+        .map(|r| r.unwrap().enterprise_format())
+        .collect();
+    assert_eq!(formats, vec![(0, 1001), (0, 1)]);
+}
+
+#[test]
+fn test_lazy_skips_undecoded_malformed_record() {
+    // An extended switch record whose body is too short to decode
+    let data = flow_datagram(vec![
+        FlowRecord::Unknown {
+            enterprise: 0,
+            format: 1001,
+            data: vec![0, 0, 0, 1],
+        },
+        FlowRecord::ExtendedEgressQueue(flow_records::ExtendedEgressQueue { queue: 3 }),
+    ]);
+
+    let parser = SflowParser::default();
+    assert!(parser.parse_bytes(&data).error.is_some());
+
+    let dg = parser.parse_lazy(&data).next().unwrap().unwrap();
+    let sample = dg.samples().next().unwrap().unwrap();
+    let records: Vec<_> = sample
+        .flow_records()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(records[0].decode().is_err());
+    assert_eq!(
+        records[1].decode().unwrap(),
+        FlowRecordRef::ExtendedEgressQueue(flow_records::ExtendedEgressQueue { queue: 3 })
+    );
+
+    // A sample body too short to hold its record count reports an error
+    let truncated = lazy::LazySample {
+        enterprise: 0,
+        format: 1,
+        data: &[0; 8],
+    };
+    let mut records = truncated.flow_records().unwrap();
+    assert!(matches!(
+        records.next(),
+        Some(Err(SflowError::ParseError {
+            context: ParseContext::FlowSample,
+            ..
+        }))
+    ));
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn test_lazy_errors() {
+    let data = real_datagram();
+    let parser = SflowParser::default();
+
+    // Truncated sample framing is reported when the datagram is split off
+    let mut results = parser.parse_lazy(&data[..data.len() - 8]);
+    assert!(matches!(
+        results.next(),
+        Some(Err(SflowError::Incomplete {
+            context: ParseContext::SampleData,
+            ..
+        }))
+    ));
+    assert!(results.next().is_none());
+
+    let limited = SflowParser::builder().with_max_samples(1).build();
+    assert!(matches!(
+        limited.parse_lazy(&data).next(),
+        Some(Err(SflowError::TooManySamples { count: 2, max: 1 }))
+    ));
+
+    assert!(matches!(
+        parser.parse_lazy(&[0, 0]).next(),
+        Some(Err(SflowError::Incomplete {
+            context: ParseContext::DatagramHeader,
+            ..
+        }))
+    ));
+}
@@ -0,0 +1,256 @@
+use flowparser_sflow::counter_records::CounterRecord;
+use flowparser_sflow::flow_records::extended_gateway::AsPathSegment;
+use flowparser_sflow::flow_records::{
+    ExtendedGateway, ExtendedSwitch, ExtendedUrl, ExtendedUser, FlowRecord, SampledIpv4,
+};
+use flowparser_sflow::samples::DataSource;
+use flowparser_sflow::*;
+use std::net::Ipv4Addr;
+
+/// Decode a Wireshark-style hex dump into bytes.
+/// Strips offset prefixes (e.g., "0000   ") and whitespace.
+fn h(hex: &str) -> Vec<u8> {
+    let mut out = String::new();
+    for line in hex.lines() {
+        let t = line.trim();
+        if t.is_empty() {
+            continue;
+        }
+        let data = match t.find("  ") {
+            Some(pos) if pos >= 4 && t[..pos].bytes().all(|b| b.is_ascii_hexdigit()) => {
+                &t[pos..]
+            }
+            _ => t,
+        };
+        out.push_str(data);
+    }
+    hex::decode(out.replace(' ', "")).unwrap()
+}
+
+/// v4 datagram: a flow sample with a sampled header (6 bytes, padded),
+/// switch, gateway and URL extended data, then an Ethernet counter sample.
+fn v4_datagram() -> Vec<u8> {
+    h("\
+        0000   00 00 00 04 00 00 00 01 0a 00 00 01 00 00 00 01\n\
+        0010   00 00 03 e8 00 00 00 02 00 00 00 01 00 00 00 05\n\
+        0020   00 00 00 03 00 00 01 00 00 00 04 00 00 00 00 00\n\
+        0030   00 00 00 03 00 00 00 05 00 00 00 01 00 00 00 01\n\
+        0040   00 00 00 40 00 00 00 06 00 11 22 33 44 55 00 00\n\
+        0050   00 00 00 03 00 00 00 01 00 00 00 0a 00 00 00 00\n\
+        0060   00 00 00 14 00 00 00 00 00 00 00 03 00 00 fd e8\n\
+        0070   00 00 fd e9 00 00 fd ea 00 00 00 01 00 00 00 02\n\
+        0080   00 00 00 02 00 00 fd eb 00 00 fd ec 00 00 00 01\n\
+        0090   fd e8 00 64 00 00 00 64 00 00 00 05 00 00 00 01\n\
+        00a0   00 00 00 02 2f 61 00 00 00 00 00 02 00 00 00 06\n\
+        00b0   00 00 00 03 00 00 00 14 00 00 00 02 00 00 00 03\n\
+        00c0   00 00 00 06 00 00 00 00 3b 9a ca 00 00 00 00 01\n\
+        00d0   00 00 00 03 00 00 00 00 00 00 03 e8 00 00 00 0a\n\
+        00e0   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+        00f0   00 00 00 00 00 00 00 00 00 00 07 d0 00 00 00 14\n\
+        0100   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+        0110   00 00 00 00 00 00 00 00 00 00 00 01 00 00 00 02\n\
+        0120   00 00 00 03 00 00 00 04 00 00 00 05 00 00 00 06\n\
+        0130   00 00 00 07 00 00 00 08 00 00 00 09 00 00 00 0a\n\
+        0140   00 00 00 0b 00 00 00 0c\
+    ")
+}
+
+#[test]
+fn test_parse_v4_datagram() {
+    let result = SflowParser::default().parse_bytes(&v4_datagram());
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(result.datagrams.len(), 1);
+
+    let dg = &result.datagrams[0];
+    assert_eq!(dg.version, 4);
+    assert_eq!(
+        dg.agent_address,
+        AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1))
+    );
+    assert_eq!(dg.sub_agent_id, 0);
+    assert_eq!(dg.sequence_number, 1);
+    assert_eq!(dg.uptime, 1000);
+    assert_eq!(dg.samples.len(), 2);
+
+    let SflowSample::Flow(fs) = &dg.samples[0] else {
+        panic!("Expected flow sample, got {:?}", dg.samples[0]);
+    };
+    assert_eq!(fs.sequence_number, 5);
+    assert_eq!(fs.data_source(), DataSource::IfIndex(3));
+    assert_eq!(fs.sampling_rate, 256);
+    assert_eq!(fs.sample_pool, 1024);
+    assert_eq!((fs.input, fs.output), (3, 5));
+    assert_eq!(fs.records.len(), 4);
+    match &fs.records[0] {
+        FlowRecord::RawPacketHeader(r) => {
+            assert_eq!(r.header_protocol, 1);
+            assert_eq!(r.frame_length, 64);
+            assert_eq!(r.stripped, 0);
+            assert_eq!(r.header, vec![0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        }
+        other => panic!("Expected RawPacketHeader, got {:?}", other),
+    }
+    assert_eq!(
+        fs.records[1],
+        FlowRecord::ExtendedSwitch(ExtendedSwitch {
+            src_vlan: 10,
+            src_priority: 0,
+            dst_vlan: 20,
+            dst_priority: 0,
+        })
+    );
+    assert_eq!(
+        fs.records[2],
+        FlowRecord::ExtendedGateway(ExtendedGateway {
+            next_hop: AddressType::IPv4(Ipv4Addr::UNSPECIFIED),
+            as_number: 65000,
+            src_as: 65001,
+            src_peer_as: 65002,
+            as_path_segments: vec![AsPathSegment {
+                segment_type: 2,
+                values: vec![65003, 65004],
+            }],
+            communities: vec![0xFDE8_0064],
+        })
+    );
+    assert_eq!(
+        fs.records[3],
+        FlowRecord::ExtendedUrl(ExtendedUrl {
+            direction: 1,
+            url: "/a".to_string(),
+            host: String::new(),
+        })
+    );
+
+    let SflowSample::Counter(cs) = &dg.samples[1] else {
+        panic!("Expected counter sample, got {:?}", dg.samples[1]);
+    };
+    assert_eq!(cs.sequence_number, 6);
+    assert_eq!(cs.records.len(), 2);
+    match &cs.records[0] {
+        CounterRecord::GenericInterface(gi) => {
+            assert_eq!(gi.if_index, 3);
+            assert_eq!(gi.if_speed, 1_000_000_000);
+            assert_eq!(gi.if_in_octets, 1000);
+            assert_eq!(gi.if_out_octets, 2000);
+        }
+        other => panic!("Expected GenericInterface, got {:?}", other),
+    }
+    match &cs.records[1] {
+        CounterRecord::EthernetInterface(eth) => {
+            assert_eq!(eth.dot3_stats_alignment_errors, 0);
+            assert_eq!(eth.dot3_stats_fcs_errors, 1);
+        }
+        other => panic!("Expected EthernetInterface, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_v2_datagram() {
+    // Sampled IPv4 with a flat v2 AS path and user data without charsets
+    let data = h("\
+        0000   00 00 00 02 00 00 00 01 0a 00 00 01 00 00 00 07\n\
+        0010   00 00 07 d0 00 00 00 01 00 00 00 01 00 00 00 08\n\
+        0020   00 00 00 03 00 00 02 00 00 00 08 00 00 00 00 00\n\
+        0030   00 00 00 03 00 00 00 05 00 00 00 02 00 00 05 dc\n\
+        0040   00 00 00 06 c0 a8 01 01 0a 00 00 01 00 00 9c 40\n\
+        0050   00 00 01 bb 00 00 00 18 00 00 00 00 00 00 00 02\n\
+        0060   00 00 00 03 00 00 fd e8 00 00 fd e9 00 00 fd ea\n\
+        0070   00 00 00 02 00 00 fd eb 00 00 fd ec 00 00 00 04\n\
+        0080   00 00 00 05 61 6c 69 63 65 00 00 00 00 00 00 00\
+    ");
+    let result = SflowParser::default().parse_bytes(&data);
+    assert!(result.error.is_none(), "{:?}", result.error);
+
+    let dg = &result.datagrams[0];
+    assert_eq!(dg.version, 2);
+    assert_eq!(dg.sequence_number, 7);
+    let flow = dg.samples[0].normalized_flow().unwrap();
+    assert_eq!(flow.sampling_rate, 512);
+    assert_eq!(flow.frame_length(), Some(1500));
+    assert_eq!(
+        flow.records,
+        &[
+            FlowRecord::SampledIpv4(SampledIpv4 {
+                length: 1500,
+                protocol: 6,
+                src_ip: Ipv4Addr::new(192, 168, 1, 1),
+                dst_ip: Ipv4Addr::new(10, 0, 0, 1),
+                src_port: 40000,
+                dst_port: 443,
+                tcp_flags: 0x18,
+                tos: 0,
+            }),
+            FlowRecord::ExtendedGateway(ExtendedGateway {
+                next_hop: AddressType::IPv4(Ipv4Addr::UNSPECIFIED),
+                as_number: 65000,
+                src_as: 65001,
+                src_peer_as: 65002,
+                as_path_segments: vec![AsPathSegment {
+                    segment_type: 2,
+                    values: vec![65003, 65004],
+                }],
+                communities: vec![],
+            }),
+            FlowRecord::ExtendedUser(ExtendedUser {
+                src_charset: 0,
+                src_user: "alice".to_string(),
+                dst_charset: 0,
+                dst_user: String::new(),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_v4_borrowed_and_encoded() {
+    let data = v4_datagram();
+    let parser = SflowParser::default();
+    let owned = parser.parse_bytes(&data);
+    assert_eq!(parser.parse_bytes_ref(&data).into_owned(), owned);
+
+    // Legacy datagrams are re-encoded as v5
+    let dg = &owned.datagrams[0];
+    let reparsed = parser.parse_bytes(&dg.to_bytes());
+    assert!(reparsed.error.is_none());
+    assert_eq!(reparsed.datagrams[0].version, 5);
+    assert_eq!(reparsed.datagrams[0].samples, dg.samples);
+}
+
+#[test]
+fn test_v4_errors() {
+    let data = v4_datagram();
+    let parser = SflowParser::default();
+
+    // Lazy decoding needs length-framed v5 samples
+    let mut lazy = parser.parse_lazy(&data);
+    let err = lazy.next().unwrap().unwrap_err();
+    assert_eq!(err, SflowError::LazyUnsupportedVersion { version: 4 });
+    assert_eq!(
+        err.to_string(),
+        "sFlow version 4 cannot be decoded lazily (expected 5)"
+    );
+
+    // Unknown counters version (8) in the counter sample
+    let mut bad = data.clone();
+    bad[0xbb] = 8;
+    let result = parser.parse_bytes(&bad);
+    assert!(result.datagrams.is_empty());
+    assert!(matches!(
+        result.error,
+        Some(SflowError::ParseError {
+            context: ParseContext::CounterSample,
+            ..
+        })
+    ));
+
+    // Truncated flow sample
+    let result = parser.parse_bytes(&data[..0x60]);
+    assert!(matches!(
+        result.error,
+        Some(SflowError::ParseError {
+            context: ParseContext::FlowSample,
+            ..
+        })
+    ));
+}
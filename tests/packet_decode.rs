@@ -0,0 +1,282 @@
+#![cfg(feature = "packet")]
+
+use etherparse::{NetSlice, PacketBuilder, SlicedPacket, TransportSlice, VlanId};
+use flowparser_sflow::flow_records::{HeaderProtocol, RawPacketHeader};
+use flowparser_sflow::packet::{MplsLabel, decode_header};
+use flowparser_sflow::*;
+use mac_address::MacAddress;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Decode a Wireshark-style hex dump into bytes.
+/// Strips offset prefixes (e.g., "0000   ") and whitespace.
+fn h(hex: &str) -> Vec<u8> {
+    let mut out = String::new();
+    for line in hex.lines() {
+        let t = line.trim();
+        if t.is_empty() {
+            continue;
+        }
+        let data = match t.find("  ") {
+            Some(pos) if pos >= 4 && t[..pos].bytes().all(|b| b.is_ascii_hexdigit()) => {
+                &t[pos..]
+            }
+            _ => t,
+        };
+        out.push_str(data);
+    }
+    hex::decode(out.replace(' ', "")).unwrap()
+}
+
+#[test]
+fn test_decode_real_ethernet_tcp_matches_etherparse() {
+    // Real pcap (see real_data.rs): the second sample carries Ethernet + IPv4 +
+    // TCP 10.0.0.151:11211 -> 10.0.0.150:49651, header_length=105.
+    let data = h("\
+        0000   00 00 00 05 00 00 00 01 0a 00 00 14 00 00 00 00\n\
+        0010   00 00 89 40 04 27 24 10 00 00 00 02 00 00 00 02\n\
+        0020   00 00 00 6c 00 00 0d 9c 00 00 00 10 00 00 00 01\n\
+        0030   00 00 00 01 00 00 00 58 00 00 00 10 00 00 00 06\n\
+        0040   00 00 00 00 05 f5 e1 00 00 00 00 00 00 00 00 03\n\
+        0050   00 00 00 00 1a 22 f5 6e 00 21 fe e7 00 00 00 00\n\
+        0060   ff ff ff ff 00 00 00 00 00 00 00 00 ff ff ff ff\n\
+        0070   00 00 00 00 9d ad 17 05 02 3d 0d 17 ff ff ff ff\n\
+        0080   ff ff ff ff 00 00 02 ad 00 00 00 00 00 00 00 00\n\
+        0090   00 00 00 01 00 00 00 bc 00 00 1d ae 00 00 00 16\n\
+        00a0   00 00 01 90 00 2f 25 e3 00 00 00 00 00 00 00 16\n\
+        00b0   00 00 00 02 00 00 00 02 00 00 03 e9 00 00 00 10\n\
+        00c0   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+        00d0   00 00 00 01 00 00 00 7c 00 00 00 01 00 00 00 6d\n\
+        00e0   00 00 00 04 00 00 00 69 3e 5b 35 4b 3a 72 f2 29\n\
+        00f0   01 70 58 25 08 00 45 00 00 5b 55 3e 40 00 40 06\n\
+        0100   d0 32 0a 00 00 97 0a 00 00 96 2b cb c1 f3 47 e9\n\
+        0110   a7 d7 b0 5a a0 59 80 18 00 2e e3 9e 00 00 01 01\n\
+        0120   08 0a 3e d9 3b 26 3e d9 4a dc 56 41 4c 55 45 20\n\
+        0130   73 65 73 73 69 6f 6e 2e 74 69 6d 65 20 30 20 38\n\
+        0140   0d 0a 31 31 3a 34 38 3a 32 38 0d 0a 45 4e 44 0d\n\
+        0150   0a 56 80 18\
+    ");
+
+    let result = SflowParser::default().parse_bytes(&data);
+    assert!(result.error.is_none(), "{:?}", result.error);
+    let rph = match &result.datagrams[0].samples[1] {
+        SflowSample::Flow(fs) => match &fs.records[1] {
+            FlowRecord::RawPacketHeader(rph) => rph,
+            other => panic!("expected RawPacketHeader, got {other:?}"),
+        },
+        other => panic!("expected Flow, got {other:?}"),
+    };
+    assert_eq!(rph.protocol(), HeaderProtocol::EthernetIso88023);
+    assert_eq!(rph.header_length, 105);
+
+    let decoded = rph.decode();
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.ether_type, Some(0x0800));
+    assert!(decoded.vlan_tags.is_empty());
+    assert_eq!(decoded.ip_version, Some(4));
+    assert_eq!(decoded.ttl, Some(64));
+    assert_eq!(decoded.tcp_flags, Some(0x018));
+
+    let sliced = SlicedPacket::from_ethernet(&rph.header[..105]).unwrap();
+    let link = match &sliced.link {
+        Some(etherparse::LinkSlice::Ethernet2(eth)) => eth,
+        other => panic!("expected Ethernet2, got {other:?}"),
+    };
+    assert_eq!(decoded.src_mac, Some(MacAddress::new(link.source())));
+    assert_eq!(decoded.dst_mac, Some(MacAddress::new(link.destination())));
+    let ip = match &sliced.net {
+        Some(NetSlice::Ipv4(ip)) => ip.header(),
+        other => panic!("expected IPv4, got {other:?}"),
+    };
+    assert_eq!(decoded.src_ip, Some(IpAddr::V4(ip.source_addr())));
+    assert_eq!(decoded.dst_ip, Some(IpAddr::V4(ip.destination_addr())));
+    assert_eq!(decoded.ip_protocol, Some(ip.protocol().0));
+    let tcp = match &sliced.transport {
+        Some(TransportSlice::Tcp(tcp)) => tcp,
+        other => panic!("expected TCP, got {other:?}"),
+    };
+    assert_eq!(decoded.src_port, Some(tcp.source_port()));
+    assert_eq!(decoded.dst_port, Some(tcp.destination_port()));
+    assert_eq!(decoded.payload_offset, Some(105 - tcp.payload().len()));
+}
+
+#[test]
+fn test_decode_qinq_ipv6_udp() {
+    let builder = PacketBuilder::ethernet2([1, 2, 3, 4, 5, 6], [7, 8, 9, 10, 11, 12])
+        .double_vlan(VlanId::try_new(100).unwrap(), VlanId::try_new(200).unwrap())
+        .ipv6(
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            [0xff; 16],
+            32,
+        )
+        .udp(5353, 53);
+    let payload = [0u8; 10];
+    let mut frame = Vec::new();
+    builder.write(&mut frame, &payload).unwrap();
+
+    let decoded = decode_header(HeaderProtocol::EthernetIso88023, &frame);
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.src_mac, Some(MacAddress::new([1, 2, 3, 4, 5, 6])));
+    assert_eq!(
+        decoded.dst_mac,
+        Some(MacAddress::new([7, 8, 9, 10, 11, 12]))
+    );
+    let vids: Vec<u16> = decoded.vlan_tags.iter().map(|t| t.vid).collect();
+    assert_eq!(vids, vec![100, 200]);
+    assert_eq!(decoded.vlan_tags[1].tpid, 0x8100);
+    assert_eq!(decoded.ether_type, Some(0x86DD));
+    assert_eq!(decoded.ip_version, Some(6));
+    assert_eq!(
+        decoded.src_ip,
+        Some(IpAddr::V6("2001:db8::1".parse::<Ipv6Addr>().unwrap()))
+    );
+    assert_eq!(decoded.ttl, Some(32));
+    assert_eq!(decoded.ip_protocol, Some(17));
+    assert_eq!(decoded.src_port, Some(5353));
+    assert_eq!(decoded.dst_port, Some(53));
+    assert_eq!(decoded.payload_offset, Some(frame.len() - payload.len()));
+}
+
+#[test]
+fn test_decode_ipv6_extension_headers() {
+    // IPv6 with a hop-by-hop header followed by TCP.
+    let bytes = h("\
+        60 00 00 00 00 1c 00 40 00 00 00 00 00 00 00 00\n\
+        00 00 00 00 00 00 00 01 00 00 00 00 00 00 00 00\n\
+        00 00 00 00 00 00 00 02 06 00 01 04 00 00 00 00\n\
+        00 50 c0 00 00 00 00 01 00 00 00 00 50 02 ff ff\n\
+        00 00 00 00\
+    ");
+    let decoded = decode_header(HeaderProtocol::Ipv6, &bytes);
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.ip_protocol, Some(6));
+    assert_eq!(decoded.src_port, Some(80));
+    assert_eq!(decoded.dst_port, Some(0xC000));
+    assert_eq!(decoded.tcp_flags, Some(0x002));
+    assert_eq!(decoded.payload_offset, Some(68));
+}
+
+#[test]
+fn test_decode_truncated_tcp_keeps_partial_fields() {
+    // Ethernet + IPv4 + the first 4 bytes of a TCP header.
+    let bytes = h("\
+        ff ff ff ff ff ff 00 11 22 33 44 55 08 00 45 00\n\
+        00 28 00 01 00 00 40 06 00 00 c0 a8 00 01 c0 a8\n\
+        00 02 01 bb d4 31\
+    ");
+    let decoded = decode_header(HeaderProtocol::EthernetIso88023, &bytes);
+    assert!(decoded.truncated);
+    assert_eq!(
+        decoded.src_ip,
+        Some(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)))
+    );
+    assert_eq!(decoded.src_port, Some(443));
+    assert_eq!(decoded.dst_port, Some(54321));
+    assert_eq!(decoded.tcp_flags, None);
+    assert_eq!(decoded.payload_offset, Some(34));
+}
+
+#[test]
+fn test_decode_respects_header_length() {
+    // header_length=16 stops inside the IPv4 header even though the padded
+    // header buffer holds more bytes.
+    let mut header = h("\
+        00 00 00 00 00 01 00 00 00 00 00 02 08 00 45 00\n\
+        00 28 00 01 00 00 40 06 00 00 c0 a8 00 01 c0 a8\
+    ");
+    header.resize(32, 0);
+    let rph = RawPacketHeader {
+        header_protocol: 1,
+        frame_length: 64,
+        stripped: 4,
+        header_length: 16,
+        header,
+    };
+    let decoded = rph.decode();
+    assert!(decoded.truncated);
+    assert_eq!(decoded.ether_type, Some(0x0800));
+    assert_eq!(decoded.ip_version, None);
+    assert_eq!(decoded.payload_offset, Some(14));
+}
+
+#[test]
+fn test_decode_ipv4_non_initial_fragment() {
+    let bytes = h("\
+        45 00 00 1c 00 01 00 10 40 11 00 00 0a 00 00 01\n\
+        0a 00 00 02 00 35 00 35 00 08 00 00\
+    ");
+    let decoded = decode_header(HeaderProtocol::Ipv4, &bytes);
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.ip_protocol, Some(17));
+    assert_eq!(decoded.src_port, None);
+    assert_eq!(decoded.payload_offset, Some(20));
+}
+
+#[test]
+fn test_decode_mpls_icmp() {
+    // Two labels (16, 17 bottom-of-stack) followed by an IPv4 ICMP echo request.
+    let bytes = h("\
+        00 01 00 40 00 01 11 3f 45 00 00 1c 00 00 00 00\n\
+        40 01 00 00 0a 00 00 01 0a 00 00 02 08 00 00 00\n\
+        00 00 00 00\
+    ");
+    let decoded = decode_header(HeaderProtocol::Mpls, &bytes);
+    assert!(!decoded.truncated);
+    assert_eq!(
+        decoded.mpls_labels,
+        vec![
+            MplsLabel {
+                label: 16,
+                tc: 0,
+                bottom_of_stack: false,
+                ttl: 64,
+            },
+            MplsLabel {
+                label: 17,
+                tc: 0,
+                bottom_of_stack: true,
+                ttl: 63,
+            },
+        ]
+    );
+    assert_eq!(decoded.ip_protocol, Some(1));
+    assert_eq!(decoded.icmp_type, Some(8));
+    assert_eq!(decoded.icmp_code, Some(0));
+    assert_eq!(decoded.payload_offset, Some(36));
+}
+
+#[test]
+fn test_decode_ppp_ipv4() {
+    let bytes = h("\
+        ff 03 00 21 45 00 00 1c 00 00 00 00 40 11 00 00\n\
+        0a 00 00 01 0a 00 00 02 00 44 00 43 00 08 00 00\
+    ");
+    let decoded = decode_header(HeaderProtocol::Ppp, &bytes);
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.dst_ip, Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))));
+    assert_eq!(decoded.src_port, Some(68));
+    assert_eq!(decoded.dst_port, Some(67));
+}
+
+#[test]
+fn test_decode_80211_qos_data_from_ds() {
+    // QoS data, FromDS: addr1=DA, addr2=BSSID, addr3=SA, then LLC/SNAP IPv4.
+    let bytes = h("\
+        88 02 00 00 aa aa aa aa aa aa bb bb bb bb bb bb\n\
+        cc cc cc cc cc cc 00 00 00 00 aa aa 03 00 00 00\n\
+        08 00 45 00 00 1c 00 00 00 00 40 11 00 00 0a 00\n\
+        00 01 0a 00 00 02 00 35 c0 00 00 08 00 00\
+    ");
+    let decoded = decode_header(HeaderProtocol::Ieee80211Mac, &bytes);
+    assert!(!decoded.truncated);
+    assert_eq!(decoded.dst_mac, Some(MacAddress::new([0xaa; 6])));
+    assert_eq!(decoded.src_mac, Some(MacAddress::new([0xcc; 6])));
+    assert_eq!(decoded.ether_type, Some(0x0800));
+    assert_eq!(decoded.src_port, Some(53));
+    assert_eq!(decoded.payload_offset, Some(62));
+}
+
+#[test]
+fn test_decode_unsupported_protocol() {
+    let decoded = decode_header(HeaderProtocol::Fddi, &[0u8; 32]);
+    assert_eq!(decoded, Default::default());
+}
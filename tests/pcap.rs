@@ -0,0 +1,167 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::flow_records::{FlowRecord, RawPacketHeader};
+use flowparser_sflow::pcap::{
+    LINKTYPE_ETHERNET, LINKTYPE_IPV4, PcapNgWriter, PcapWriter, link_type, sampled_packets,
+};
+use flowparser_sflow::samples::{DiscardedPacket, DropReason, FlowSample, Interface};
+use flowparser_sflow::*;
+use pcap_parser::pcapng::Block;
+use pcap_parser::{PcapCapture, PcapNGCapture};
+
+fn raw(header_protocol: u32, frame_length: u32, header: Vec<u8>) -> FlowRecord {
+    FlowRecord::RawPacketHeader(RawPacketHeader {
+        header_protocol,
+        frame_length,
+        stripped: 4,
+        header_length: header.len() as u32,
+        header,
+    })
+}
+
+fn flow_on(source_id_index: u32, records: Vec<FlowRecord>) -> SflowSample {
+    SflowSample::Flow(FlowSample {
+        sequence_number: 1,
+        source_id_type: 0,
+        source_id_index,
+        sampling_rate: 512,
+        sample_pool: 512,
+        drops: 0,
+        input: source_id_index,
+        output: 9,
+        records,
+    })
+}
+
+fn sample_datagram() -> SflowDatagram {
+    datagram(vec![
+        flow_on(3, vec![raw(1, 1518, vec![0xaa; 128])]),
+        flow_on(
+            4,
+            vec![
+                raw(11, 60, vec![0x45; 20]),
+                // No header: not written.
+                ipv4(),
+            ],
+        ),
+        // AAL5 has no pcap link type.
+        flow_on(5, vec![raw(9, 100, vec![0; 48])]),
+        SflowSample::Discard(DiscardedPacket {
+            sequence_number: 1,
+            source_id_type: 0,
+            source_id_index: 3,
+            drops: 0,
+            input: 3,
+            output: 0,
+            reason: DropReason::Acl,
+            records: vec![raw(1, 64, vec![0xbb; 60])],
+        }),
+    ])
+}
+
+#[test]
+fn test_sampled_packets() {
+    assert_eq!(link_type(1.into()), Some(LINKTYPE_ETHERNET));
+    assert_eq!(link_type(12.into()), Some(229));
+    assert_eq!(link_type(9.into()), None);
+
+    let dg = sample_datagram();
+    let packets = sampled_packets(&dg);
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[0].original_length, 1514);
+    assert_eq!(packets[0].data.len(), 128);
+    assert_eq!(packets[0].sampling_rate, Some(512));
+    assert_eq!(packets[1].link_type, LINKTYPE_IPV4);
+    // Never shorter than the captured bytes.
+    assert_eq!(packets[1].original_length, 56);
+    assert_eq!(packets[2].sampling_rate, None);
+    assert_eq!(packets[2].drop_reason, Some(DropReason::Acl));
+    assert_eq!(packets[2].input, Interface::Single(3));
+    assert_eq!(packets[2].output, Interface::Unknown);
+
+    // Discard interfaces are plain ifIndexes without a format in the top bits.
+    let SflowSample::Discard(discard) = &dg.samples[3] else {
+        unreachable!()
+    };
+    let discard = DiscardedPacket {
+        output: 0x3FFF_FFFF,
+        ..discard.clone()
+    };
+    assert_eq!(discard.output_interface(), Interface::Single(0x3FFF_FFFF));
+}
+
+#[test]
+fn test_pcap_writer_filters_link_type() {
+    let mut writer = PcapWriter::new(Vec::new(), LINKTYPE_ETHERNET).unwrap();
+    assert_eq!(
+        writer
+            .write_datagram(&sample_datagram(), received())
+            .unwrap(),
+        2
+    );
+    let file = writer.into_inner();
+
+    let capture = PcapCapture::from_file(&file).unwrap();
+    assert_eq!(capture.header.network.0, 1);
+    assert_eq!(capture.blocks.len(), 2);
+    let first = &capture.blocks[0];
+    assert_eq!((first.ts_sec, first.ts_usec), (1_700_000_000, 250_000));
+    assert_eq!((first.caplen, first.origlen), (128, 1514));
+    assert_eq!(first.data, &[0xaa; 128][..]);
+    assert_eq!(capture.blocks[1].origlen, 60);
+}
+
+#[test]
+fn test_pcapng_writer_interfaces_and_comments() {
+    let mut writer = PcapNgWriter::new(Vec::new()).unwrap();
+    let dg = sample_datagram();
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 3);
+    assert_eq!(writer.write_datagram(&dg, received()).unwrap(), 3);
+    // Ethernet and IPv4 on different data sources; the discard shares 0:3.
+    assert_eq!(writer.interface_count(), 2);
+    let file = writer.into_inner();
+
+    let capture = PcapNGCapture::from_file(&file).unwrap();
+    let blocks = &capture.sections[0].blocks;
+    let mut interfaces = vec![];
+    let mut packets = vec![];
+    for block in blocks {
+        match block {
+            Block::InterfaceDescription(idb) => {
+                let name = idb.options.iter().find(|o| o.code.0 == 2).unwrap();
+                interfaces.push((idb.linktype.0, name.as_str().unwrap().to_string()));
+            }
+            Block::EnhancedPacket(epb) => {
+                let comment = epb.options.iter().find(|o| o.code.0 == 1).unwrap();
+                packets.push((epb, comment.as_str().unwrap().to_string()));
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(
+        interfaces,
+        vec![
+            (1, "10.0.0.254/0:3".to_string()),
+            (228, "10.0.0.254/0:4".to_string())
+        ]
+    );
+    assert_eq!(packets.len(), 6);
+
+    let (epb, comment) = &packets[0];
+    assert_eq!(epb.if_id, 0);
+    let micros = (u64::from(epb.ts_high) << 32) | u64::from(epb.ts_low);
+    assert_eq!(micros, 1_700_000_000_250_000);
+    assert_eq!((epb.caplen, epb.origlen), (128, 1514));
+    assert_eq!(
+        comment,
+        "agent=10.0.0.254 sub_agent=1 source=0:3 input=3 output=9 sampling_rate=512"
+    );
+    assert_eq!(packets[1].0.if_id, 1);
+    let (epb, comment) = &packets[2];
+    assert_eq!(epb.if_id, 0);
+    assert_eq!(
+        comment,
+        "agent=10.0.0.254 sub_agent=1 source=0:3 input=3 output=unknown drop_reason=Acl"
+    );
+}
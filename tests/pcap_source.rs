@@ -0,0 +1,227 @@
+#![cfg(feature = "pcap")]
+
+use etherparse::{
+    IpFragOffset, IpNumber, Ipv4Header, LinuxSllPacketType, PacketBuilder, UdpHeader, VlanId,
+};
+use flowparser_sflow::flow_records::{FlowRecord, SampledIpv4};
+use flowparser_sflow::pcap::{PcapSource, PcapWriter};
+use flowparser_sflow::samples::FlowSample;
+use flowparser_sflow::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime};
+
+fn sflow(sequence_number: u32, samples: u32) -> Vec<u8> {
+    let samples = (0..samples)
+        .map(|i| {
+            SflowSample::Flow(FlowSample {
+                sequence_number: i,
+                source_id_type: 0,
+                source_id_index: 1,
+                sampling_rate: 100,
+                sample_pool: 100 * i,
+                drops: 0,
+                input: 1,
+                output: 2,
+                records: vec![FlowRecord::SampledIpv4(SampledIpv4 {
+                    length: 1500,
+                    protocol: 6,
+                    src_ip: Ipv4Addr::new(192, 168, 1, 1),
+                    dst_ip: Ipv4Addr::new(10, 0, 0, 1),
+                    src_port: 80,
+                    dst_port: 443,
+                    tcp_flags: 0x10,
+                    tos: 0,
+                })],
+            })
+        })
+        .collect();
+    SflowDatagram {
+        version: 5,
+        agent_address: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 1)),
+        sub_agent_id: 0,
+        sequence_number,
+        uptime: 1000,
+        samples,
+    }
+    .to_bytes()
+}
+
+fn at(micros: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_000 + micros)
+}
+
+fn ethernet_ipv4(vlan: Option<u16>, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+    let builder = PacketBuilder::ethernet2([1; 6], [2; 6]);
+    let mut out = vec![];
+    match vlan {
+        Some(vlan) => {
+            let builder = builder
+                .single_vlan(VlanId::try_new(vlan).unwrap())
+                .ipv4([10, 0, 0, 1], [10, 0, 0, 100], 64)
+                .udp(50000, dst_port);
+            builder.write(&mut out, payload).unwrap();
+        }
+        None => {
+            let builder = builder
+                .ipv4([10, 0, 0, 1], [10, 0, 0, 100], 64)
+                .udp(50000, dst_port);
+            builder.write(&mut out, payload).unwrap();
+        }
+    }
+    out
+}
+
+fn pcap(link_type: u16, packets: &[(SystemTime, Vec<u8>)]) -> Vec<u8> {
+    let mut writer = PcapWriter::new(Vec::new(), link_type).unwrap();
+    for (ts, data) in packets {
+        writer.write_packet(*ts, data, data.len() as u32).unwrap();
+    }
+    writer.into_inner()
+}
+
+fn ng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let total = (12 + body.len()) as u32;
+    let mut out = block_type.to_le_bytes().to_vec();
+    out.extend_from_slice(&total.to_le_bytes());
+    out.extend_from_slice(body);
+    out.extend_from_slice(&total.to_le_bytes());
+    out
+}
+
+#[test]
+fn test_legacy_ethernet_vlan_and_ipv6() {
+    let mut ipv6 = vec![];
+    PacketBuilder::ethernet2([1; 6], [2; 6])
+        .ipv6(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).octets(),
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2).octets(),
+            64,
+        )
+        .udp(40000, 6343)
+        .write(&mut ipv6, &sflow(2, 1))
+        .unwrap();
+    let file = pcap(
+        1,
+        &[
+            (at(10), ethernet_ipv4(Some(10), 6343, &sflow(1, 2))),
+            // Not sFlow.
+            (at(20), ethernet_ipv4(None, 53, &[0; 12])),
+            (at(30), ipv6),
+            (at(40), ethernet_ipv4(None, 6343, &[0xff; 12])),
+        ],
+    );
+
+    let mut source = PcapSource::new(std::io::Cursor::new(file)).unwrap();
+    let (ts, src, dst, datagram) = source.next().unwrap().unwrap();
+    assert_eq!(ts, at(10));
+    assert_eq!(src, "10.0.0.1:50000".parse::<SocketAddr>().unwrap());
+    assert_eq!(dst, "10.0.0.100:6343".parse::<SocketAddr>().unwrap());
+    assert_eq!(datagram.sequence_number, 1);
+    assert_eq!(datagram.samples.len(), 2);
+
+    let (ts, src, _, datagram) = source.next().unwrap().unwrap();
+    assert_eq!(ts, at(30));
+    assert_eq!(
+        src.ip(),
+        IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))
+    );
+    assert_eq!(datagram.sequence_number, 2);
+
+    assert!(source.next().is_none());
+    assert_eq!(source.packets(), 4);
+    assert_eq!(source.parse_errors(), 1);
+}
+
+#[test]
+fn test_pcapng_linux_cooked_custom_port() {
+    let mut packet = vec![];
+    PacketBuilder::linux_sll(LinuxSllPacketType::HOST, 6, [1, 2, 3, 4, 5, 6, 0, 0])
+        .ipv4([10, 0, 0, 1], [10, 0, 0, 100], 64)
+        .udp(50000, 9999)
+        .write(&mut packet, &sflow(7, 1))
+        .unwrap();
+
+    let mut shb = 0x1a2b_3c4du32.to_le_bytes().to_vec();
+    shb.extend_from_slice(&[1, 0, 0, 0]);
+    shb.extend_from_slice(&(-1i64).to_le_bytes());
+    let mut idb = 113u16.to_le_bytes().to_vec();
+    idb.extend_from_slice(&[0, 0]);
+    idb.extend_from_slice(&0u32.to_le_bytes());
+    // if_tsresol: nanoseconds.
+    idb.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+    let nanos: u64 = 1_700_000_000_000_000_500;
+    let mut epb = 0u32.to_le_bytes().to_vec();
+    epb.extend_from_slice(&((nanos >> 32) as u32).to_le_bytes());
+    epb.extend_from_slice(&(nanos as u32).to_le_bytes());
+    epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    epb.extend_from_slice(&packet);
+    epb.resize(epb.len().next_multiple_of(4), 0);
+
+    let mut file = ng_block(0x0a0d_0d0a, &shb);
+    file.extend(ng_block(1, &idb));
+    file.extend(ng_block(6, &epb));
+
+    let default_port = PcapSource::new(std::io::Cursor::new(file.clone())).unwrap();
+    assert_eq!(default_port.count(), 0);
+
+    let source = PcapSource::builder()
+        .with_ports(&[9999])
+        .build(std::io::Cursor::new(file))
+        .unwrap();
+    let items: Vec<_> = source.map(Result::unwrap).collect();
+    assert_eq!(items.len(), 1);
+    let (ts, _, dst, datagram) = &items[0];
+    assert_eq!(
+        *ts,
+        SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_000_000_500)
+    );
+    assert_eq!(dst.port(), 9999);
+    assert_eq!(datagram.sequence_number, 7);
+}
+
+#[test]
+fn test_ipv4_fragments_reassembled() {
+    let payload = sflow(3, 60);
+    assert!(payload.len() > 3000);
+    let mut segment = UdpHeader::without_ipv4_checksum(50000, 6343, payload.len())
+        .unwrap()
+        .to_bytes()
+        .to_vec();
+    segment.extend_from_slice(&payload);
+
+    let mut fragments = vec![];
+    for (i, chunk) in segment.chunks(1480).enumerate() {
+        let mut ip = Ipv4Header::new(
+            chunk.len() as u16,
+            64,
+            IpNumber::UDP,
+            [10, 0, 0, 1],
+            [10, 0, 0, 100],
+        )
+        .unwrap();
+        ip.identification = 0x1234;
+        ip.more_fragments = (i + 1) * 1480 < segment.len();
+        ip.fragment_offset = IpFragOffset::try_new((i * 1480 / 8) as u16).unwrap();
+        ip.header_checksum = ip.calc_header_checksum();
+        let mut frame = vec![2; 6];
+        frame.extend_from_slice(&[1; 6]);
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(&ip.to_bytes());
+        frame.extend_from_slice(chunk);
+        fragments.push((at(i as u64), frame));
+    }
+    // Fragments may arrive out of order.
+    fragments.swap(0, 1);
+
+    let source = PcapSource::builder()
+        .with_ports(&[])
+        .build(std::io::Cursor::new(pcap(1, &fragments)))
+        .unwrap();
+    let items: Vec<_> = source.map(Result::unwrap).collect();
+    assert_eq!(items.len(), 1);
+    let (ts, src, _, datagram) = &items[0];
+    assert_eq!(*ts, fragments.last().unwrap().0);
+    assert_eq!(src.port(), 50000);
+    assert_eq!(datagram.samples.len(), 60);
+}
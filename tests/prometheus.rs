@@ -0,0 +1,128 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::{GenericInterface, PortName};
+use flowparser_sflow::prometheus::{ExpositionFormat, MetricsExporter};
+use flowparser_sflow::*;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+fn generic(if_index: u32, if_in_octets: u64) -> CounterRecord {
+    CounterRecord::GenericInterface(GenericInterface {
+        if_index,
+        if_type: 6,
+        if_speed: 1_000_000_000,
+        if_direction: 1,
+        if_status: 3,
+        if_in_octets,
+        if_in_ucast_pkts: 10,
+        if_in_multicast_pkts: 0,
+        if_in_broadcast_pkts: 0,
+        if_in_discards: 0,
+        if_in_errors: 0,
+        if_in_unknown_protos: 0,
+        if_out_octets: 2000,
+        if_out_ucast_pkts: 20,
+        if_out_multicast_pkts: 0,
+        if_out_broadcast_pkts: 0,
+        if_out_discards: 0,
+        if_out_errors: 0,
+        if_promiscuous_mode: 0,
+    })
+}
+
+fn from_agent(agent: [u8; 4], samples: Vec<(u32, u32, Vec<CounterRecord>)>) -> SflowDatagram {
+    let samples = samples
+        .into_iter()
+        .map(|(source_id_type, source_id_index, records)| {
+            counters(source_id_type, source_id_index, records)
+        })
+        .collect();
+    SflowDatagram {
+        agent_address: AddressType::IPv4(Ipv4Addr::from(agent)),
+        ..datagram(samples)
+    }
+}
+
+#[test]
+fn test_prometheus_text() {
+    let mut exporter = MetricsExporter::default();
+    exporter.observe(
+        &from_agent(
+            [10, 0, 0, 1],
+            vec![
+                (
+                    0,
+                    3,
+                    vec![
+                        generic(3, 1000),
+                        CounterRecord::PortName(PortName {
+                            name: "eth\"0".to_string(),
+                        }),
+                    ],
+                ),
+                (2, 1, vec![cpu()]),
+            ],
+        ),
+        at(0),
+    );
+    let text = exporter.render(ExpositionFormat::Prometheus);
+    let labels = r#"{agent="10.0.0.1",source="0:3",ifindex="3",ifname="eth\"0"}"#;
+    assert!(text.contains(&format!(
+        "# TYPE sflow_generic_interface_if_in_octets_total counter\n\
+         sflow_generic_interface_if_in_octets_total{labels} 1000\n"
+    )));
+    assert!(text.contains(&format!(
+        "# TYPE sflow_generic_interface_if_speed gauge\n\
+         sflow_generic_interface_if_speed{labels} 1000000000\n"
+    )));
+    assert!(!text.contains("sflow_generic_interface_if_index"));
+    let host = r#"{agent="10.0.0.1",source="2:1"}"#;
+    assert!(text.contains(&format!("sflow_host_cpu_load_one{host} 0.6\n")));
+    assert!(text.contains(&format!("sflow_host_cpu_cpu_user_total{host} 1000\n")));
+    assert!(text.contains("# TYPE sflow_host_cpu_proc_total gauge\n"));
+    assert!(text.contains(
+        "sflow_agent_last_seen_timestamp_seconds{agent=\"10.0.0.1\"} 1700000000.000\n"
+    ));
+    assert!(!text.contains("# EOF"));
+
+    let open = exporter.render(ExpositionFormat::OpenMetrics);
+    assert!(open.contains("# TYPE sflow_host_cpu_cpu_user counter\n"));
+    assert!(open.contains(&format!("sflow_host_cpu_cpu_user_total{host} 1000\n")));
+    assert!(open.ends_with("# EOF\n"));
+}
+
+#[test]
+fn test_updates_and_staleness() {
+    let mut exporter = MetricsExporter::builder()
+        .with_stale_after(Duration::from_secs(60))
+        .build();
+    exporter.observe(
+        &from_agent([10, 0, 0, 1], vec![(0, 3, vec![generic(3, 1)])]),
+        at(0),
+    );
+    exporter.observe(
+        &from_agent([10, 0, 0, 2], vec![(0, 3, vec![generic(3, 1)])]),
+        at(0),
+    );
+    let per_interface = exporter.series_count() / 2;
+    exporter.observe(
+        &from_agent([10, 0, 0, 1], vec![(0, 3, vec![generic(3, 5)])]),
+        at(50),
+    );
+    assert_eq!(exporter.series_count(), per_interface * 2);
+    assert!(
+        exporter.render(ExpositionFormat::Prometheus).contains(
+            "if_in_octets_total{agent=\"10.0.0.1\",source=\"0:3\",ifindex=\"3\"} 5\n"
+        )
+    );
+
+    exporter.expire(at(100));
+    assert_eq!(exporter.series_count(), per_interface);
+    let text = exporter.render(ExpositionFormat::Prometheus);
+    assert!(!text.contains("10.0.0.2"));
+
+    exporter.expire(at(200));
+    assert_eq!(exporter.series_count(), 0);
+    assert_eq!(exporter.render(ExpositionFormat::Prometheus), "");
+}
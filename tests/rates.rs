@@ -0,0 +1,270 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::IfDirection;
+use flowparser_sflow::counter_records::{CounterRecord, GenericInterface, HostNetIo};
+use flowparser_sflow::rates::{
+    CounterEngine, CounterUpdate, CounterValue, InterfaceMetrics, SkipReason,
+};
+use flowparser_sflow::samples::DataSource;
+use flowparser_sflow::*;
+use std::time::Duration;
+
+fn generic(if_in_octets: u64, if_in_ucast_pkts: u32) -> CounterRecord {
+    CounterRecord::GenericInterface(GenericInterface {
+        if_index: 3,
+        if_type: 6,
+        if_speed: 1_000_000_000,
+        if_direction: 1,
+        if_status: 3,
+        if_in_octets,
+        if_in_ucast_pkts,
+        if_in_multicast_pkts: 0,
+        if_in_broadcast_pkts: 0,
+        if_in_discards: 0,
+        if_in_errors: 0,
+        if_in_unknown_protos: 0,
+        if_out_octets: 0,
+        if_out_ucast_pkts: 0,
+        if_out_multicast_pkts: 0,
+        if_out_broadcast_pkts: 0,
+        if_out_discards: 0,
+        if_out_errors: 0,
+        if_promiscuous_mode: 0,
+    })
+}
+
+/// Counters of ifIndex 3 read `uptime` milliseconds after boot.
+fn reading(uptime: u32, records: Vec<CounterRecord>) -> SflowDatagram {
+    numbered(uptime / 1000, vec![counters(0, 3, records)])
+}
+
+fn expect_rates(update: &CounterUpdate) -> &flowparser_sflow::rates::CounterRates {
+    match update {
+        CounterUpdate::Rates(rates) => rates,
+        other => panic!("expected rates, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_cumulative_counters_exclude_gauges() {
+    let fields = generic(10, 20).cumulative_counters();
+    assert_eq!(fields.len(), 13);
+    assert_eq!(fields[0], ("if_in_octets", CounterValue::U64(10)));
+    assert_eq!(fields[1], ("if_in_ucast_pkts", CounterValue::U32(20)));
+    assert!(fields.iter().all(|(name, _)| *name != "if_speed"));
+    assert!(
+        CounterRecord::Unknown {
+            enterprise: 0,
+            format: 999,
+            data: vec![],
+        }
+        .cumulative_counters()
+        .is_empty()
+    );
+}
+
+#[test]
+fn test_rates_over_uptime_interval() {
+    let mut engine = CounterEngine::default();
+    let first = engine.observe(&reading(10_000, vec![generic(1_000, 100)]));
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].0.data_source, DataSource::IfIndex(3));
+    assert_eq!(first[0].0.record, (0, 1));
+    assert_eq!(first[0].1, CounterUpdate::Baseline);
+
+    let second = engine.observe(&reading(40_000, vec![generic(31_000, 400)]));
+    let rates = expect_rates(&second[0].1);
+    assert_eq!(rates.interval, Duration::from_secs(30));
+    let octets = rates.get("if_in_octets").unwrap();
+    assert_eq!(octets.delta, 30_000);
+    assert!((octets.rate - 1_000.0).abs() < 1e-9);
+    assert!(!octets.wrapped && !octets.implausible);
+    assert_eq!(rates.get("if_in_ucast_pkts").unwrap().delta, 300);
+    assert!(!rates.has_implausible());
+    assert_eq!(engine.len(), 1);
+}
+
+#[test]
+fn test_wrap_handling_per_field_width() {
+    let mut engine = CounterEngine::default();
+    engine.observe(&reading(10_000, vec![generic(5_000, u32::MAX - 9)]));
+
+    // The 32-bit packet counter wraps.
+    let update = engine.observe(&reading(20_000, vec![generic(6_000, 10)]));
+    let rates = expect_rates(&update[0].1);
+    let packets = rates.get("if_in_ucast_pkts").unwrap();
+    assert_eq!(packets.delta, 20);
+    assert!(packets.wrapped && !packets.implausible);
+    assert_eq!(rates.get("if_in_octets").unwrap().delta, 1_000);
+
+    // A 64-bit counter going backwards, or a 32-bit "wrap" of more than half
+    // the range, is a reset, not traffic.
+    let update = engine.observe(&reading(30_000, vec![generic(5_000, 20)]));
+    assert_eq!(
+        update[0].1,
+        CounterUpdate::Skipped(SkipReason::CounterReset)
+    );
+    let update = engine.observe(&reading(40_000, vec![generic(6_000, 5)]));
+    assert_eq!(
+        update[0].1,
+        CounterUpdate::Skipped(SkipReason::CounterReset)
+    );
+    let update = engine.observe(&reading(50_000, vec![generic(7_000, 15)]));
+    assert_eq!(expect_rates(&update[0].1).delta("if_in_ucast_pkts"), 10);
+}
+
+#[test]
+fn test_max_rate_flags_implausible_jump() {
+    let mut engine = CounterEngine::builder().with_max_rate(1e6).build();
+    engine.observe(&reading(10_000, vec![generic(0, 0)]));
+    let update = engine.observe(&reading(11_000, vec![generic(5_000_000, 10)]));
+    let rates = expect_rates(&update[0].1);
+    assert!(rates.get("if_in_octets").unwrap().implausible);
+    assert!(!rates.get("if_in_ucast_pkts").unwrap().implausible);
+}
+
+#[test]
+fn test_restart_stale_and_duplicate_rebaseline() {
+    let mut engine = CounterEngine::builder()
+        .with_max_interval(Duration::from_secs(300))
+        .build();
+    engine.observe(&reading(600_000, vec![generic(1_000, 100)]));
+
+    let duplicate = engine.observe(&reading(600_000, vec![generic(1_000, 100)]));
+    assert_eq!(
+        duplicate[0].1,
+        CounterUpdate::Skipped(SkipReason::Duplicate)
+    );
+
+    // Uptime went backwards: the agent restarted, counters start over.
+    let restarted = engine.observe(&reading(5_000, vec![generic(10, 1)]));
+    assert_eq!(
+        restarted[0].1,
+        CounterUpdate::Skipped(SkipReason::AgentRestarted)
+    );
+    let update = engine.observe(&reading(15_000, vec![generic(110, 11)]));
+    assert_eq!(
+        expect_rates(&update[0].1)
+            .get("if_in_octets")
+            .unwrap()
+            .delta,
+        100
+    );
+
+    let stale = engine.observe(&reading(1_000_000, vec![generic(500, 50)]));
+    assert_eq!(stale[0].1, CounterUpdate::Skipped(SkipReason::Stale));
+
+    engine.reset_agent(&agent_address());
+    assert!(engine.is_empty());
+}
+
+#[test]
+fn test_restart_while_silent_rebaselines() {
+    let mut engine = CounterEngine::default();
+    engine.observe(&reading(60_000, vec![generic(50_000, 500)]));
+
+    // Down for 10 minutes, back up for 2: the uptime still moved forward.
+    let update = engine.observe(&reading(120_000, vec![generic(2_000, 20)]));
+    assert_eq!(
+        update[0].1,
+        CounterUpdate::Skipped(SkipReason::CounterReset)
+    );
+    let update = engine.observe(&reading(150_000, vec![generic(5_000, 50)]));
+    let rates = expect_rates(&update[0].1);
+    assert_eq!(rates.interval, Duration::from_secs(30));
+    assert_eq!(rates.delta("if_in_octets"), 3_000);
+}
+
+#[test]
+fn test_streams_keyed_by_record_type() {
+    let host = CounterRecord::HostNetIo(HostNetIo {
+        bytes_in: 1,
+        packets_in: 1,
+        errs_in: 0,
+        drops_in: 0,
+        bytes_out: 1,
+        packets_out: 1,
+        errs_out: 0,
+        drops_out: 0,
+    });
+    let mut engine = CounterEngine::default();
+    let updates = engine.observe(&reading(1_000, vec![generic(0, 0), host]));
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[1].0.record, (0, 2006));
+    assert_eq!(engine.len(), 2);
+}
+
+fn port(if_in_octets: u64, if_out_octets: u64, packets: u32) -> GenericInterface {
+    let CounterRecord::GenericInterface(mut r) = generic(if_in_octets, packets) else {
+        unreachable!()
+    };
+    r.if_out_octets = if_out_octets;
+    r.if_out_ucast_pkts = packets;
+    r
+}
+
+#[test]
+fn test_interface_metrics_full_duplex() {
+    let previous = port(0, 0, 0);
+    let mut current = port(250_000_000, 125_000_000, 1_000);
+    current.if_in_multicast_pkts = 100;
+    current.if_in_errors = 10;
+    current.if_in_discards = 40;
+    current.if_out_discards = 50;
+
+    let m = InterfaceMetrics::between(&previous, &current, Duration::from_secs(10));
+    assert_eq!(m.direction, IfDirection::FullDuplex);
+    assert!(m.admin_up && m.oper_up);
+    assert!((m.in_bps - 200_000_000.0).abs() < 1e-6);
+    assert!((m.in_utilization.unwrap() - 20.0).abs() < 1e-9);
+    assert!((m.out_utilization.unwrap() - 10.0).abs() < 1e-9);
+    assert!((m.in_pps - 110.0).abs() < 1e-9);
+    assert!((m.out_pps - 100.0).abs() < 1e-9);
+    assert!((m.in_error_ratio.unwrap() - 10.0 / 1_150.0).abs() < 1e-12);
+    assert!((m.in_discard_ratio.unwrap() - 40.0 / 1_150.0).abs() < 1e-12);
+    assert!((m.out_discard_ratio.unwrap() - 0.05).abs() < 1e-12);
+    assert_eq!(m.out_error_ratio, Some(0.0));
+    assert!(!m.implausible);
+}
+
+#[test]
+fn test_interface_metrics_half_duplex_and_status() {
+    let previous = port(0, 0, 0);
+    let mut current = port(250_000_000, 125_000_000, 0);
+    current.if_direction = 2;
+    let m = InterfaceMetrics::between(&previous, &current, Duration::from_secs(10));
+    assert!((m.in_utilization.unwrap() - 30.0).abs() < 1e-9);
+    assert_eq!(m.in_utilization, m.out_utilization);
+    assert_eq!(m.in_error_ratio, None);
+
+    // Admin up, oper down: no utilization.
+    current.if_status = 1;
+    let m = InterfaceMetrics::between(&previous, &current, Duration::from_secs(10));
+    assert!(m.admin_up && !m.oper_up);
+    assert_eq!(m.in_utilization, None);
+
+    current.if_status = 3;
+    current.if_speed = 0;
+    let m = InterfaceMetrics::between(&previous, &current, Duration::from_secs(10));
+    assert_eq!(m.out_utilization, None);
+}
+
+#[test]
+fn test_observe_interfaces() {
+    let mut engine = CounterEngine::default();
+    assert!(
+        engine
+            .observe_interfaces(&reading(10_000, vec![generic(0, 0)]))
+            .is_empty()
+    );
+    let metrics =
+        engine.observe_interfaces(&reading(20_000, vec![generic(12_500_000, u32::MAX)]));
+    assert_eq!(metrics.len(), 1);
+    let (key, m) = &metrics[0];
+    assert_eq!(key.data_source, DataSource::IfIndex(3));
+    assert_eq!(m.if_index, 3);
+    assert_eq!(m.interval, Duration::from_secs(10));
+    assert!((m.in_utilization.unwrap() - 1.0).abs() < 1e-9);
+    assert!(m.implausible);
+}
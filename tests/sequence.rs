@@ -0,0 +1,241 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::samples::{CounterSample, DataSource, FlowSample};
+use flowparser_sflow::sequence::{SampleKey, SampleKind, SequenceStatus};
+use flowparser_sflow::*;
+
+fn flow(sequence_number: u32, if_index: u32) -> SflowSample {
+    SflowSample::Flow(FlowSample {
+        sequence_number,
+        source_id_index: if_index,
+        input: if_index,
+        ..flow_sample(vec![])
+    })
+}
+
+fn counter(sequence_number: u32, if_index: u32) -> SflowSample {
+    SflowSample::Counter(CounterSample {
+        sequence_number,
+        ..counter_sample(0, if_index, vec![])
+    })
+}
+
+#[test]
+fn test_datagram_sequence_statuses() {
+    let mut tracker = SequenceTracker::default();
+    let statuses: Vec<_> = [10, 11, 11, 14, 13, 15, 9_000_000, 9_000_001]
+        .into_iter()
+        .map(|seq| tracker.observe_datagram(agent(1), seq))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            SequenceStatus::First,
+            SequenceStatus::InOrder,
+            SequenceStatus::Duplicate,
+            SequenceStatus::Gap { missing: 2 },
+            SequenceStatus::Reordered,
+            SequenceStatus::InOrder,
+            SequenceStatus::Reset,
+            SequenceStatus::InOrder,
+        ]
+    );
+
+    let stats = tracker.datagram_stats(&agent(1)).unwrap();
+    assert_eq!(stats.received, 8);
+    assert_eq!(stats.lost, 1);
+    assert_eq!(stats.duplicates, 1);
+    assert_eq!(stats.reordered, 1);
+    assert_eq!(stats.resets, 1);
+    assert_eq!(stats.last, 9_000_001);
+    assert!((stats.loss_ratio() - 1.0 / 8.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_replayed_sequence_is_duplicate() {
+    let mut tracker = SequenceTracker::default();
+    let statuses: Vec<_> = [1, 2, 3, 7, 2, 5, 5, 8, 6]
+        .into_iter()
+        .map(|seq| tracker.observe_datagram(agent(1), seq))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            SequenceStatus::First,
+            SequenceStatus::InOrder,
+            SequenceStatus::InOrder,
+            SequenceStatus::Gap { missing: 3 },
+            // Received before the gap
+            SequenceStatus::Duplicate,
+            SequenceStatus::Reordered,
+            // Already recovered
+            SequenceStatus::Duplicate,
+            SequenceStatus::InOrder,
+            SequenceStatus::Reordered,
+        ]
+    );
+    let stats = tracker.datagram_stats(&agent(1)).unwrap();
+    assert_eq!(stats.lost, 1);
+    assert_eq!(stats.duplicates, 2);
+    assert_eq!(stats.reordered, 2);
+
+    // A replayed old number on an in-order stream is not a late arrival
+    tracker.observe_datagram(agent(2), 10);
+    tracker.observe_datagram(agent(2), 11);
+    assert_eq!(
+        tracker.observe_datagram(agent(2), 10),
+        SequenceStatus::Duplicate
+    );
+    let stats = tracker.datagram_stats(&agent(2)).unwrap();
+    assert_eq!((stats.reordered, stats.duplicates), (0, 1));
+}
+
+#[test]
+fn test_sequence_wraps_at_u32_max() {
+    let mut tracker = SequenceTracker::default();
+    tracker.observe_datagram(agent(1), u32::MAX - 1);
+    assert_eq!(
+        tracker.observe_datagram(agent(1), u32::MAX),
+        SequenceStatus::InOrder
+    );
+    assert_eq!(
+        tracker.observe_datagram(agent(1), 0),
+        SequenceStatus::InOrder
+    );
+    assert_eq!(
+        tracker.observe_datagram(agent(1), 3),
+        SequenceStatus::Gap { missing: 2 }
+    );
+}
+
+#[test]
+fn test_streams_are_independent() {
+    let mut tracker = SequenceTracker::builder().with_max_gap(100).build();
+    tracker.observe_datagram(agent(1), 1);
+    tracker.observe_datagram(agent(2), 500);
+    assert_eq!(
+        tracker.observe_datagram(agent(1), 2),
+        SequenceStatus::InOrder
+    );
+    assert_eq!(
+        tracker.observe_datagram(agent(2), 501),
+        SequenceStatus::InOrder
+    );
+    // Beyond the configured max gap.
+    assert_eq!(
+        tracker.observe_datagram(agent(2), 700),
+        SequenceStatus::Reset
+    );
+    assert_eq!(tracker.datagram_streams().count(), 2);
+
+    tracker.remove_agent(&agent(2));
+    assert!(tracker.datagram_stats(&agent(2)).is_none());
+    assert_eq!(tracker.datagram_totals().received, 2);
+}
+
+#[test]
+fn test_observe_tracks_samples_per_data_source_and_kind() {
+    let mut tracker = SequenceTracker::default();
+    let first = tracker.observe(&numbered(1, vec![flow(5, 1), counter(5, 1), flow(7, 2)]));
+    assert_eq!(first.datagram, SequenceStatus::First);
+    assert!(
+        first
+            .samples
+            .iter()
+            .all(|s| s.status == SequenceStatus::First)
+    );
+
+    // Datagram 2 lost; datagram 3 shows gaps on ifIndex 1 flows only.
+    let report = tracker.observe(&numbered(3, vec![flow(8, 1), counter(6, 1), flow(8, 2)]));
+    assert_eq!(report.datagram, SequenceStatus::Gap { missing: 1 });
+    let statuses: Vec<_> = report
+        .samples
+        .iter()
+        .map(|s| (s.data_source, s.kind, s.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (
+                DataSource::IfIndex(1),
+                SampleKind::Flow,
+                SequenceStatus::Gap { missing: 2 }
+            ),
+            (
+                DataSource::IfIndex(1),
+                SampleKind::Counter,
+                SequenceStatus::InOrder
+            ),
+            (
+                DataSource::IfIndex(2),
+                SampleKind::Flow,
+                SequenceStatus::InOrder
+            ),
+        ]
+    );
+
+    let key = SampleKey {
+        agent: agent(254),
+        data_source: DataSource::IfIndex(1),
+        kind: SampleKind::Flow,
+    };
+    assert_eq!(tracker.sample_stats(&key).unwrap().lost, 2);
+    assert_eq!(tracker.sample_totals().lost, 2);
+    assert_eq!(tracker.sample_streams().count(), 3);
+}
+
+#[test]
+fn test_observe_ref_matches_observe() {
+    let dg = numbered(1, vec![flow(1, 1), counter(1, 1)]);
+    let next = numbered(2, vec![flow(3, 1), counter(2, 1)]);
+
+    let mut owned = SequenceTracker::default();
+    owned.observe(&dg);
+    let expected = owned.observe(&next);
+
+    let parser = SflowParser::default();
+    let (first, second) = (dg.to_bytes(), next.to_bytes());
+    let mut borrowed = SequenceTracker::default();
+    borrowed.observe_ref(&parser.parse_bytes_ref(&first).datagrams[0]);
+    let report = borrowed.observe_ref(&parser.parse_bytes_ref(&second).datagrams[0]);
+    assert_eq!(report, expected);
+    assert_eq!(report.samples[0].status, SequenceStatus::Gap { missing: 1 });
+}
+
+#[test]
+fn test_restart_to_low_sequence_number_is_reset() {
+    let mut tracker = SequenceTracker::default();
+    for seq in 1..=50 {
+        tracker.observe_datagram(agent(1), seq);
+    }
+    let statuses: Vec<_> = [1, 2, 3, 5]
+        .into_iter()
+        .map(|seq| tracker.observe_datagram(agent(1), seq))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            // Indistinguishable from a replay until the next number arrives
+            SequenceStatus::Duplicate,
+            SequenceStatus::Reset,
+            SequenceStatus::InOrder,
+            SequenceStatus::Gap { missing: 1 },
+        ]
+    );
+    let stats = tracker.datagram_stats(&agent(1)).unwrap();
+    assert_eq!((stats.duplicates, stats.resets, stats.lost), (0, 1, 1));
+    assert_eq!(stats.last, 5);
+    assert_eq!(
+        tracker.observe_datagram(agent(1), 2),
+        SequenceStatus::Duplicate
+    );
+
+    // Numbers from before tracking started were never seen
+    tracker.observe_datagram(agent(2), 100);
+    tracker.observe_datagram(agent(2), 101);
+    assert_eq!(
+        tracker.observe_datagram(agent(2), 90),
+        SequenceStatus::Reset
+    );
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use common::*;
+use flowparser_sflow::counter_records::GenericInterface;
+use flowparser_sflow::flow_records::{
+    ExtendedGateway, ExtendedSwitch, FlowRecord, SampledIpv4, extended_gateway::AsPathSegment,
+};
+use flowparser_sflow::sflowtool::{
+    DatagramInfo, counter_record_text, datagram_lines, datagram_text, flow_record_text,
+};
+use flowparser_sflow::*;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, SystemTime};
+
+fn generic() -> GenericInterface {
+    GenericInterface {
+        if_index: 3,
+        if_type: 6,
+        if_speed: 1_000_000_000,
+        if_direction: 1,
+        if_status: 3,
+        if_in_octets: 1000,
+        if_in_ucast_pkts: 10,
+        if_in_multicast_pkts: 1,
+        if_in_broadcast_pkts: 2,
+        if_in_discards: 0,
+        if_in_errors: 0,
+        if_in_unknown_protos: 0,
+        if_out_octets: 2000,
+        if_out_ucast_pkts: 20,
+        if_out_multicast_pkts: 3,
+        if_out_broadcast_pkts: 4,
+        if_out_discards: 5,
+        if_out_errors: 6,
+        if_promiscuous_mode: 0,
+    }
+}
+
+fn sample_datagram() -> SflowDatagram {
+    datagram(vec![
+        flow(vec![
+            FlowRecord::SampledIpv4(SampledIpv4 {
+                tos: 0x10,
+                ..sampled_ipv4()
+            }),
+            FlowRecord::ExtendedSwitch(ExtendedSwitch {
+                src_vlan: 100,
+                src_priority: 0,
+                dst_vlan: 200,
+                dst_priority: 0,
+            }),
+        ]),
+        counters(0, 3, vec![CounterRecord::GenericInterface(generic())]),
+    ])
+}
+
+#[test]
+fn test_datagram_text() {
+    let info = DatagramInfo {
+        source: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 254)),
+        size: 244,
+        received: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    };
+    let text = datagram_text(&sample_datagram(), &info);
+    let expected = "\
+startDatagram =================================
+datagramSourceIP 10.0.0.254
+datagramSize 244
+unixSecondsUTC 1700000000
+localtime 2023-11-14T22:13:20+0000
+datagramVersion 5
+agentSubId 1
+agent 10.0.0.254
+packetSequenceNo 42
+sysUpTime 123456
+samplesInPacket 2
+startSample ----------------------
+sampleType_tag 0:1
+sampleType FLOWSAMPLE
+sampleSequenceNo 7
+sourceId 0:3
+meanSkipCount 512
+samplePool 1024
+dropEvents 0
+inputPort 3
+outputPort multiple 2
+flowBlock_tag 0:3
+flowSampleType IPV4
+sampledPacketSize 1500
+srcIP 192.168.1.1
+dstIP 10.0.0.1
+IPProtocol 6
+IPTOS 16
+TCPSrcPort 40000
+TCPDstPort 443
+TCPFlags 24
+flowBlock_tag 0:1001
+extendedType SWITCH
+in_vlan 100
+in_priority 0
+out_vlan 200
+out_priority 0
+endSample   ----------------------
+startSample ----------------------
+sampleType_tag 0:2
+sampleType COUNTERSSAMPLE
+sampleSequenceNo 8
+sourceId 0:3
+counterBlock_tag 0:1
+ifIndex 3
+networkType 6
+ifSpeed 1000000000
+ifDirection 1
+ifStatus 3
+ifInOctets 1000
+ifInUcastPkts 10
+ifInMulticastPkts 1
+ifInBroadcastPkts 2
+ifInDiscards 0
+ifInErrors 0
+ifInUnknownProtos 0
+ifOutOctets 2000
+ifOutUcastPkts 20
+ifOutMulticastPkts 3
+ifOutBroadcastPkts 4
+ifOutDiscards 5
+ifOutErrors 6
+ifPromiscuousMode 0
+endSample   ----------------------
+endDatagram   =================================
+";
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn test_datagram_lines() {
+    let lines = datagram_lines(&sample_datagram());
+    assert_eq!(
+        lines,
+        "FLOW,10.0.0.254,3,2,000000000000,000000000000,0x0000,100,200,192.168.1.1,10.0.0.1,6,0x10,0,40000,443,0x18,1500,1500,512\n\
+         CNTR,10.0.0.254,3,6,1000000000,1,3,1000,10,1,2,0,0,0,2000,20,3,4,5,6,0\n"
+    );
+}
+
+#[test]
+fn test_gateway_and_generic_records() {
+    let gateway = FlowRecord::ExtendedGateway(ExtendedGateway {
+        next_hop: AddressType::IPv4(Ipv4Addr::new(10, 0, 0, 2)),
+        as_number: 65000,
+        src_as: 65001,
+        src_peer_as: 65002,
+        as_path_segments: vec![AsPathSegment {
+            segment_type: 2,
+            values: vec![65010, 65020],
+        }],
+        communities: vec![(65000 << 16) | 100],
+    });
+    assert_eq!(
+        flow_record_text(&gateway),
+        "flowBlock_tag 0:1003\nextendedType GATEWAY\nnextHop 10.0.0.2\nmy_as 65000\n\
+         src_as 65001\nsrc_peer_as 65002\ndst_as_path_len 2\ndst_as_path 65010-65020\n\
+         BGP_communities 65000:100\ndst_peer_as 65010\ndst_as 65020\n"
+    );
+
+    let text = counter_record_text(&cpu());
+    assert!(text.starts_with("counterBlock_tag 0:2003\nload_one 0.6\nload_five 0.5\n"));
+    assert!(text.ends_with("interrupts 42\ncontexts 99\n"));
+
+    let unknown = FlowRecord::Unknown {
+        enterprise: 9,
+        format: 1,
+        data: vec![0xde, 0xad],
+    };
+    assert_eq!(
+        flow_record_text(&unknown),
+        "flowBlock_tag 9:1\nunknownBytes DE-AD\n"
+    );
+}
+
+#[cfg(feature = "packet")]
+#[test]
+fn test_raw_header_flow_line() {
+    use flowparser_sflow::flow_records::RawPacketHeader;
+
+    // Ethernet / 802.1Q 10 / IPv4 / UDP 10.1.1.1:5353 -> 10.1.1.2:53, TTL 64
+    let mut header = vec![
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0x81, 0x00,
+        0x00, 0x0a, 0x08, 0x00,
+    ];
+    header.extend_from_slice(&[
+        0x45, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 10, 1, 1, 1,
+        10, 1, 1, 2,
+    ]);
+    header.extend_from_slice(&[0x14, 0xe9, 0x00, 0x35, 0x00, 0x08, 0x00, 0x00]);
+    let mut dg = sample_datagram();
+    dg.samples.truncate(1);
+    if let SflowSample::Flow(s) = &mut dg.samples[0] {
+        s.records = vec![FlowRecord::RawPacketHeader(RawPacketHeader {
+            header_protocol: 1,
+            frame_length: 68,
+            stripped: 4,
+            header_length: header.len() as u32,
+            header,
+        })];
+    }
+    assert_eq!(
+        datagram_lines(&dg),
+        "FLOW,10.0.0.254,3,2,66778899aabb,001122334455,0x0800,10,0,10.1.1.1,10.1.1.2,17,0x00,64,5353,53,0x00,68,46,512\n"
+    );
+    let text = flow_record_text(&match &dg.samples[0] {
+        SflowSample::Flow(s) => s.records[0].clone(),
+        _ => unreachable!(),
+    });
+    assert!(text.contains("dstMAC 001122334455\nsrcMAC 66778899aabb\ndecodedVLAN 10\n"));
+    assert!(text.contains("IPTTL 64\nUDPSrcPort 5353\nUDPDstPort 53\n"));
+}